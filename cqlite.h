@@ -27,6 +27,10 @@ enum CQLiteStatus {
   CQLITE_OPEN_TRANSACTION = 116,
   CQLITE_OPEN_STATEMENT = 117,
   CQLITE_MISUSE = 118,
+  CQLITE_MERGE_NULL_PROPERTY = 119,
+  CQLITE_INVALID_REGEX = 120,
+  CQLITE_UNION_COLUMN_MISMATCH = 121,
+  CQLITE_MISPLACED_AGGREGATE = 122,
 };
 typedef uint8_t CQLiteStatus;
 
@@ -38,21 +42,42 @@ enum CQLiteType {
   CQLITE_TEXT = 4,
   CQLITE_BLOB = 5,
   CQLITE_NULL = 6,
+  CQLITE_LIST = 7,
+  CQLITE_PATH = 8,
+  CQLITE_MAP = 9,
+  CQLITE_DATE = 10,
+  CQLITE_DATETIME = 11,
+  CQLITE_DURATION = 12,
 };
 typedef uint8_t CQLiteType;
 
+typedef struct CQLiteContext CQLiteContext;
+
 typedef struct CQLiteGraph CQLiteGraph;
 
 typedef struct CQLiteStatement CQLiteStatement;
 
 typedef struct CQLiteTxn CQLiteTxn;
 
+/**
+ * A user-defined function. The function reads its arguments from
+ * and stores its result in `ctx`, which is only valid for the duration
+ * of the call. Returning anything but `CQLITE_OK` aborts the query.
+ */
+typedef CQLiteStatus (*CQLiteFunction)(struct CQLiteContext *ctx, void *user_data);
+
 CQLiteStatus cqlite_open(const char *path, struct CQLiteGraph **graph);
 
 CQLiteStatus cqlite_open_anon(struct CQLiteGraph **graph);
 
 CQLiteStatus cqlite_close(struct CQLiteGraph *graph);
 
+CQLiteStatus cqlite_create_function(struct CQLiteGraph *graph,
+                                    const char *name,
+                                    uintptr_t arity,
+                                    CQLiteFunction function,
+                                    void *user_data);
+
 CQLiteStatus cqlite_txn(const struct CQLiteGraph *graph, struct CQLiteTxn **txn);
 
 CQLiteStatus cqlite_mut_txn(const struct CQLiteGraph *graph, struct CQLiteTxn **txn);
@@ -90,6 +115,8 @@ CQLiteStatus cqlite_bind_null(struct CQLiteStatement *stmt, const char *name);
 
 uintptr_t cqlite_return_count(struct CQLiteStatement *stmt);
 
+const char *cqlite_return_name(struct CQLiteStatement *stmt, uintptr_t idx);
+
 CQLiteType cqlite_return_type(struct CQLiteStatement *stmt, uintptr_t idx);
 
 uint64_t cqlite_return_id(struct CQLiteStatement *stmt, uintptr_t idx);
@@ -106,4 +133,36 @@ const void *cqlite_return_blob(struct CQLiteStatement *stmt, uintptr_t idx);
 
 uintptr_t cqlite_return_bytes(struct CQLiteStatement *stmt, uintptr_t idx);
 
+uintptr_t cqlite_arg_count(struct CQLiteContext *ctx);
+
+CQLiteType cqlite_arg_type(struct CQLiteContext *ctx, uintptr_t idx);
+
+uint64_t cqlite_arg_id(struct CQLiteContext *ctx, uintptr_t idx);
+
+int64_t cqlite_arg_integer(struct CQLiteContext *ctx, uintptr_t idx);
+
+double cqlite_arg_real(struct CQLiteContext *ctx, uintptr_t idx);
+
+bool cqlite_arg_boolean(struct CQLiteContext *ctx, uintptr_t idx);
+
+const char *cqlite_arg_text(struct CQLiteContext *ctx, uintptr_t idx);
+
+const void *cqlite_arg_blob(struct CQLiteContext *ctx, uintptr_t idx);
+
+uintptr_t cqlite_arg_bytes(struct CQLiteContext *ctx, uintptr_t idx);
+
+void cqlite_result_id(struct CQLiteContext *ctx, uint64_t value);
+
+void cqlite_result_integer(struct CQLiteContext *ctx, int64_t value);
+
+void cqlite_result_real(struct CQLiteContext *ctx, double value);
+
+void cqlite_result_boolean(struct CQLiteContext *ctx, bool value);
+
+CQLiteStatus cqlite_result_text(struct CQLiteContext *ctx, const char *value);
+
+void cqlite_result_blob(struct CQLiteContext *ctx, const void *value, uintptr_t length);
+
+void cqlite_result_null(struct CQLiteContext *ctx);
+
 #endif /* CQLITE_H */
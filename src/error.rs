@@ -51,10 +51,22 @@ pub enum Error {
     /// The given identifier does not exists.
     #[error("Identifier {0} does not exists")]
     UnknownIdentifier(String),
+    /// The queries combined by a `UNION` do
+    /// not return the same columns.
+    #[error("All queries in a union must return the same columns")]
+    UnionColumnMismatch,
+    /// A user defined aggregate was used
+    /// other than as a returned value.
+    #[error("Aggregate {0} may only be used as a returned value")]
+    MisplacedAggregate(String),
 
     /// Attempted type conversion failed.
     #[error("Type mismatch")]
     TypeMismatch,
+    /// The pattern of a `=~` predicate is
+    /// not a valid regular expression.
+    #[error("Invalid regular expression: {0}")]
+    InvalidRegex(String),
     /// The return index was out of bounds.
     #[error("Index out of bounds")]
     IndexOutOfBounds,
@@ -70,6 +82,10 @@ pub enum Error {
     /// graph.
     #[error("Attempt to delete connected node")]
     DeleteConnected,
+    /// Attempted to merge a node or edge
+    /// using a `NULL` property value.
+    #[error("Cannot merge using null property value for {0}")]
+    MergeNullProperty(String),
 }
 
 impl From<SanakirjaError> for Error {
@@ -1,13 +1,14 @@
 use crate::parser;
 use crate::planner::QueryPlan;
-use crate::runtime::{Program, Status, VirtualMachine};
+use crate::runtime::{Functions, Program, Status, VirtualMachine};
 use crate::store::{PropOwned, Store, StoreTxn};
 use crate::Error;
 use std::collections::HashMap;
-use std::ffi::{c_void, CStr};
+use std::ffi::{c_void, CStr, CString};
 use std::os::raw::c_char;
 use std::ptr::read;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 #[repr(u8)]
 #[allow(non_camel_case_types)]
@@ -38,6 +39,11 @@ pub enum CQLiteStatus {
     CQLITE_OPEN_TRANSACTION = 116,
     CQLITE_OPEN_STATEMENT = 117,
     CQLITE_MISUSE = 118,
+
+    CQLITE_MERGE_NULL_PROPERTY = 119,
+    CQLITE_INVALID_REGEX = 120,
+    CQLITE_UNION_COLUMN_MISMATCH = 121,
+    CQLITE_MISPLACED_AGGREGATE = 122,
 }
 
 #[repr(u8)]
@@ -50,10 +56,17 @@ pub enum CQLiteType {
     CQLITE_TEXT = 4,
     CQLITE_BLOB = 5,
     CQLITE_NULL = 6,
+    CQLITE_LIST = 7,
+    CQLITE_PATH = 8,
+    CQLITE_MAP = 9,
+    CQLITE_DATE = 10,
+    CQLITE_DATETIME = 11,
+    CQLITE_DURATION = 12,
 }
 
 pub struct CQLiteGraph {
     store: Store,
+    functions: Functions,
     txn_count: AtomicUsize,
     stmt_count: AtomicUsize,
 }
@@ -66,6 +79,7 @@ pub struct CQLiteTxn {
 pub struct CQLiteStatement {
     graph: *const CQLiteGraph,
     program: *mut Program,
+    columns: Vec<CString>,
     parameters: HashMap<String, PropOwned>,
     runtime: Option<(
         VirtualMachine<'static, 'static, 'static>,
@@ -73,6 +87,25 @@ pub struct CQLiteStatement {
     )>,
 }
 
+pub struct CQLiteContext {
+    arguments: Vec<PropOwned>,
+    buffers: Vec<Option<Vec<u8>>>,
+    result: PropOwned,
+}
+
+/// A user-defined function. The function reads its arguments from
+/// and stores its result in `ctx`, which is only valid for the duration
+/// of the call. Returning anything but `CQLITE_OK` aborts the query.
+pub type CQLiteFunction =
+    unsafe extern "C" fn(ctx: *mut CQLiteContext, user_data: *mut c_void) -> CQLiteStatus;
+
+struct UserData(*mut c_void);
+
+// Like SQLite, we leave it to the caller to ensure that the user data
+// can be used from any thread running a query.
+unsafe impl Send for UserData {}
+unsafe impl Sync for UserData {}
+
 #[no_mangle]
 pub unsafe extern "C" fn cqlite_open(
     path: *const c_char,
@@ -84,6 +117,7 @@ pub unsafe extern "C" fn cqlite_open(
             .map_err(|_| CQLiteStatus::CQLITE_INVALID_STRING)?;
         Ok(CQLiteGraph {
             store: Store::open(path)?,
+            functions: Functions::new(),
             txn_count: AtomicUsize::new(0),
             stmt_count: AtomicUsize::new(0),
         })
@@ -102,6 +136,7 @@ pub unsafe extern "C" fn cqlite_open_anon(graph: *mut *mut CQLiteGraph) -> CQLit
     let inner = || -> Result<CQLiteGraph, CQLiteStatus> {
         Ok(CQLiteGraph {
             store: Store::open_anon()?,
+            functions: Functions::new(),
             txn_count: AtomicUsize::new(0),
             stmt_count: AtomicUsize::new(0),
         })
@@ -127,6 +162,47 @@ pub unsafe extern "C" fn cqlite_close(graph: *mut CQLiteGraph) -> CQLiteStatus {
     }
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn cqlite_create_function(
+    graph: *mut CQLiteGraph,
+    name: *const c_char,
+    arity: usize,
+    function: CQLiteFunction,
+    user_data: *mut c_void,
+) -> CQLiteStatus {
+    let inner = || -> Result<(), CQLiteStatus> {
+        if (*graph).txn_count.load(Ordering::SeqCst) > 0 {
+            return Err(CQLiteStatus::CQLITE_OPEN_TRANSACTION);
+        } else if (*graph).stmt_count.load(Ordering::SeqCst) > 0 {
+            return Err(CQLiteStatus::CQLITE_OPEN_STATEMENT);
+        }
+        let name = CStr::from_ptr(name)
+            .to_str()
+            .map_err(|_| CQLiteStatus::CQLITE_INVALID_STRING)?;
+        let user_data = UserData(user_data);
+        (*graph).functions.register(
+            name,
+            arity,
+            Arc::new(move |args: &[PropOwned]| {
+                let mut ctx = CQLiteContext {
+                    arguments: args.to_vec(),
+                    buffers: args.iter().map(|_| None).collect(),
+                    result: PropOwned::Null,
+                };
+                match function(&mut ctx, user_data.0) {
+                    CQLiteStatus::CQLITE_OK => Ok(ctx.result),
+                    status => Err(status.into()),
+                }
+            }),
+        );
+        Ok(())
+    };
+    match inner() {
+        Err(err) => err,
+        Ok(()) => CQLiteStatus::CQLITE_OK,
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn cqlite_txn(
     graph: *const CQLiteGraph,
@@ -199,12 +275,22 @@ pub unsafe extern "C" fn cqlite_prepare(
             .to_str()
             .map_err(|_| CQLiteStatus::CQLITE_INVALID_STRING)?;
         let ast = parser::parse(query).map_err(|_| CQLiteStatus::CQLITE_SYNTAX)?;
-        let plan = QueryPlan::new(&ast)?.optimize()?;
-        let program = Box::into_raw(Box::new(Program::new(&plan)?));
+        let programs = QueryPlan::union(&ast, &(*graph).functions)?
+            .into_iter()
+            .map(|plan| Program::new(&plan.optimize()?, &(*graph).functions))
+            .collect::<Result<_, Error>>()?;
+        let columns = ast
+            .column_names()
+            .into_iter()
+            .map(CString::new)
+            .collect::<Result<_, _>>()
+            .map_err(|_| CQLiteStatus::CQLITE_INVALID_STRING)?;
+        let program = Box::into_raw(Box::new(Program::union(programs, !ast.all)));
         (*graph).stmt_count.fetch_add(1, Ordering::SeqCst);
         Ok(CQLiteStatement {
             graph,
             program,
+            columns,
             parameters: HashMap::new(),
             runtime: None,
         })
@@ -427,10 +513,22 @@ pub unsafe extern "C" fn cqlite_return_count(stmt: *mut CQLiteStatement) -> usiz
     (*(*stmt).program).returns.len()
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn cqlite_return_name(
+    stmt: *mut CQLiteStatement,
+    idx: usize,
+) -> *const c_char {
+    let columns = &(*stmt).columns;
+    match columns.get(idx) {
+        Some(name) => name.as_ptr(),
+        None => std::ptr::null(),
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn cqlite_return_type(stmt: *mut CQLiteStatement, idx: usize) -> CQLiteType {
     let (vm, _) = (*stmt).runtime.as_mut().unwrap();
-    match vm.access_return(idx).unwrap() {
+    match vm.access_return(idx).unwrap_or(&PropOwned::Null) {
         PropOwned::Id(_) => CQLiteType::CQLITE_ID,
         PropOwned::Integer(_) => CQLiteType::CQLITE_INTEGER,
         PropOwned::Real(_) => CQLiteType::CQLITE_REAL,
@@ -438,14 +536,20 @@ pub unsafe extern "C" fn cqlite_return_type(stmt: *mut CQLiteStatement, idx: usi
         PropOwned::Text(_) => CQLiteType::CQLITE_TEXT,
         PropOwned::Blob(_) => CQLiteType::CQLITE_BLOB,
         PropOwned::Null => CQLiteType::CQLITE_NULL,
+        PropOwned::List(_) => CQLiteType::CQLITE_LIST,
+        PropOwned::Map(_) => CQLiteType::CQLITE_MAP,
+        PropOwned::Path(_) => CQLiteType::CQLITE_PATH,
+        PropOwned::Date(_) => CQLiteType::CQLITE_DATE,
+        PropOwned::DateTime(_) => CQLiteType::CQLITE_DATETIME,
+        PropOwned::Duration(_) => CQLiteType::CQLITE_DURATION,
     }
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn cqlite_return_id(stmt: *mut CQLiteStatement, idx: usize) -> u64 {
     let (vm, _) = (*stmt).runtime.as_mut().unwrap();
-    match vm.access_return(idx).unwrap() {
-        PropOwned::Id(id) => id,
+    match vm.access_return(idx).unwrap_or(&PropOwned::Null) {
+        PropOwned::Id(id) => *id,
         _ => panic!(),
     }
 }
@@ -453,8 +557,8 @@ pub unsafe extern "C" fn cqlite_return_id(stmt: *mut CQLiteStatement, idx: usize
 #[no_mangle]
 pub unsafe extern "C" fn cqlite_return_integer(stmt: *mut CQLiteStatement, idx: usize) -> i64 {
     let (vm, _) = (*stmt).runtime.as_mut().unwrap();
-    match vm.access_return(idx).unwrap() {
-        PropOwned::Integer(num) => num,
+    match vm.access_return(idx).unwrap_or(&PropOwned::Null) {
+        PropOwned::Integer(num) => *num,
         _ => panic!(),
     }
 }
@@ -462,8 +566,8 @@ pub unsafe extern "C" fn cqlite_return_integer(stmt: *mut CQLiteStatement, idx:
 #[no_mangle]
 pub unsafe extern "C" fn cqlite_return_real(stmt: *mut CQLiteStatement, idx: usize) -> f64 {
     let (vm, _) = (*stmt).runtime.as_mut().unwrap();
-    match vm.access_return(idx).unwrap() {
-        PropOwned::Real(num) => num,
+    match vm.access_return(idx).unwrap_or(&PropOwned::Null) {
+        PropOwned::Real(num) => *num,
         _ => panic!(),
     }
 }
@@ -471,8 +575,8 @@ pub unsafe extern "C" fn cqlite_return_real(stmt: *mut CQLiteStatement, idx: usi
 #[no_mangle]
 pub unsafe extern "C" fn cqlite_return_boolean(stmt: *mut CQLiteStatement, idx: usize) -> bool {
     let (vm, _) = (*stmt).runtime.as_mut().unwrap();
-    match vm.access_return(idx).unwrap() {
-        PropOwned::Boolean(val) => val,
+    match vm.access_return(idx).unwrap_or(&PropOwned::Null) {
+        PropOwned::Boolean(val) => *val,
         _ => panic!(),
     }
 }
@@ -485,9 +589,9 @@ pub unsafe extern "C" fn cqlite_return_text(
     let (vm, buffers) = (*stmt).runtime.as_mut().unwrap();
     match &buffers[idx] {
         Some(buffer) => buffer.as_ptr() as *const c_char,
-        None => match vm.access_return(idx).unwrap() {
+        None => match vm.access_return(idx).unwrap_or(&PropOwned::Null) {
             PropOwned::Text(string) => {
-                let mut buf = string.into_bytes();
+                let mut buf = string.clone().into_bytes();
                 buf.push(0);
                 buffers[idx] = Some(buf);
                 buffers[idx].as_ref().unwrap().as_ptr() as *const c_char
@@ -505,9 +609,9 @@ pub unsafe extern "C" fn cqlite_return_blob(
     let (vm, buffers) = (*stmt).runtime.as_mut().unwrap();
     match &buffers[idx] {
         Some(buffer) => buffer.as_ptr() as *const c_void,
-        None => match vm.access_return(idx).unwrap() {
+        None => match vm.access_return(idx).unwrap_or(&PropOwned::Null) {
             PropOwned::Text(string) => {
-                let buf = string.into_bytes();
+                let buf = string.clone().into_bytes();
                 buffers[idx] = Some(buf);
                 buffers[idx].as_ref().unwrap().as_ptr() as *const c_void
             }
@@ -525,6 +629,172 @@ pub unsafe extern "C" fn cqlite_return_bytes(stmt: *mut CQLiteStatement, idx: us
     }
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn cqlite_arg_count(ctx: *mut CQLiteContext) -> usize {
+    (*ctx).arguments.len()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cqlite_arg_type(ctx: *mut CQLiteContext, idx: usize) -> CQLiteType {
+    let arguments = &(*ctx).arguments;
+    match &arguments[idx] {
+        PropOwned::Id(_) => CQLiteType::CQLITE_ID,
+        PropOwned::Integer(_) => CQLiteType::CQLITE_INTEGER,
+        PropOwned::Real(_) => CQLiteType::CQLITE_REAL,
+        PropOwned::Boolean(_) => CQLiteType::CQLITE_BOOLEAN,
+        PropOwned::Text(_) => CQLiteType::CQLITE_TEXT,
+        PropOwned::Blob(_) => CQLiteType::CQLITE_BLOB,
+        PropOwned::Null => CQLiteType::CQLITE_NULL,
+        PropOwned::List(_) => CQLiteType::CQLITE_LIST,
+        PropOwned::Map(_) => CQLiteType::CQLITE_MAP,
+        PropOwned::Path(_) => CQLiteType::CQLITE_PATH,
+        PropOwned::Date(_) => CQLiteType::CQLITE_DATE,
+        PropOwned::DateTime(_) => CQLiteType::CQLITE_DATETIME,
+        PropOwned::Duration(_) => CQLiteType::CQLITE_DURATION,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cqlite_arg_id(ctx: *mut CQLiteContext, idx: usize) -> u64 {
+    let arguments = &(*ctx).arguments;
+    match arguments[idx] {
+        PropOwned::Id(id) => id,
+        _ => panic!(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cqlite_arg_integer(ctx: *mut CQLiteContext, idx: usize) -> i64 {
+    let arguments = &(*ctx).arguments;
+    match arguments[idx] {
+        PropOwned::Integer(num) => num,
+        _ => panic!(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cqlite_arg_real(ctx: *mut CQLiteContext, idx: usize) -> f64 {
+    let arguments = &(*ctx).arguments;
+    match arguments[idx] {
+        PropOwned::Real(num) => num,
+        _ => panic!(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cqlite_arg_boolean(ctx: *mut CQLiteContext, idx: usize) -> bool {
+    let arguments = &(*ctx).arguments;
+    match arguments[idx] {
+        PropOwned::Boolean(val) => val,
+        _ => panic!(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cqlite_arg_text(ctx: *mut CQLiteContext, idx: usize) -> *const c_char {
+    let CQLiteContext {
+        arguments, buffers, ..
+    } = &mut *ctx;
+    let buffer = buffers[idx].get_or_insert_with(|| match &arguments[idx] {
+        PropOwned::Text(string) => {
+            let mut buf = string.clone().into_bytes();
+            buf.push(0);
+            buf
+        }
+        _ => panic!(),
+    });
+    buffer.as_ptr() as *const c_char
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cqlite_arg_blob(ctx: *mut CQLiteContext, idx: usize) -> *const c_void {
+    let arguments = &(*ctx).arguments;
+    match &arguments[idx] {
+        PropOwned::Blob(bytes) => bytes.as_ptr() as *const c_void,
+        PropOwned::Text(string) => string.as_ptr() as *const c_void,
+        _ => panic!(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cqlite_arg_bytes(ctx: *mut CQLiteContext, idx: usize) -> usize {
+    let arguments = &(*ctx).arguments;
+    match &arguments[idx] {
+        PropOwned::Blob(bytes) => bytes.len(),
+        PropOwned::Text(string) => string.len(),
+        _ => 0,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cqlite_result_id(ctx: *mut CQLiteContext, value: u64) {
+    (*ctx).result = PropOwned::Id(value);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cqlite_result_integer(ctx: *mut CQLiteContext, value: i64) {
+    (*ctx).result = PropOwned::Integer(value);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cqlite_result_real(ctx: *mut CQLiteContext, value: f64) {
+    (*ctx).result = PropOwned::Real(value);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cqlite_result_boolean(ctx: *mut CQLiteContext, value: bool) {
+    (*ctx).result = PropOwned::Boolean(value);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cqlite_result_text(
+    ctx: *mut CQLiteContext,
+    value: *const c_char,
+) -> CQLiteStatus {
+    match CStr::from_ptr(value).to_str() {
+        Ok(value) => {
+            (*ctx).result = PropOwned::Text(value.to_string());
+            CQLiteStatus::CQLITE_OK
+        }
+        Err(_) => CQLiteStatus::CQLITE_INVALID_STRING,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cqlite_result_blob(
+    ctx: *mut CQLiteContext,
+    value: *const c_void,
+    length: usize,
+) {
+    let value = std::slice::from_raw_parts(value as *const u8, length);
+    (*ctx).result = PropOwned::Blob(value.to_vec());
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cqlite_result_null(ctx: *mut CQLiteContext) {
+    (*ctx).result = PropOwned::Null;
+}
+
+impl From<CQLiteStatus> for Error {
+    fn from(status: CQLiteStatus) -> Self {
+        match status {
+            CQLiteStatus::CQLITE_CORRUPTION => Error::Corruption,
+            CQLiteStatus::CQLITE_POISON => Error::Poison,
+            CQLiteStatus::CQLITE_READ_ONLY_WRITE => Error::ReadOnlyWrite,
+            CQLiteStatus::CQLITE_TYPE_MISMATCH => Error::TypeMismatch,
+            CQLiteStatus::CQLITE_INDEX_OUT_OF_BOUNDS => Error::IndexOutOfBounds,
+            CQLiteStatus::CQLITE_MISSING_NODE => Error::MissingNode,
+            CQLiteStatus::CQLITE_MISSING_EDGE => Error::MissingEdge,
+            CQLiteStatus::CQLITE_DELETE_CONNECTED => Error::DeleteConnected,
+            CQLiteStatus::CQLITE_MERGE_NULL_PROPERTY => Error::MergeNullProperty(String::new()),
+            CQLiteStatus::CQLITE_INVALID_REGEX => Error::InvalidRegex(String::new()),
+            CQLiteStatus::CQLITE_UNION_COLUMN_MISMATCH => Error::UnionColumnMismatch,
+            CQLiteStatus::CQLITE_MISPLACED_AGGREGATE => Error::MisplacedAggregate(String::new()),
+            _ => Error::Internal,
+        }
+    }
+}
+
 impl From<Error> for CQLiteStatus {
     fn from(err: Error) -> Self {
         match err {
@@ -538,11 +808,15 @@ impl From<Error> for CQLiteStatus {
             Error::IdentifierIsNotEdge(_) => CQLiteStatus::CQLITE_IDENTIFIER_IS_NOT_EDGE,
             Error::IdentifierExists(_) => CQLiteStatus::CQLITE_IDENTIGIER_EXISTS,
             Error::UnknownIdentifier(_) => CQLiteStatus::CQLITE_UNKNOWN_IDENTIFIER,
+            Error::UnionColumnMismatch => CQLiteStatus::CQLITE_UNION_COLUMN_MISMATCH,
+            Error::MisplacedAggregate(_) => CQLiteStatus::CQLITE_MISPLACED_AGGREGATE,
             Error::TypeMismatch => CQLiteStatus::CQLITE_TYPE_MISMATCH,
             Error::IndexOutOfBounds => CQLiteStatus::CQLITE_INDEX_OUT_OF_BOUNDS,
             Error::MissingNode => CQLiteStatus::CQLITE_MISSING_NODE,
             Error::MissingEdge => CQLiteStatus::CQLITE_MISSING_EDGE,
             Error::DeleteConnected => CQLiteStatus::CQLITE_DELETE_CONNECTED,
+            Error::MergeNullProperty(_) => CQLiteStatus::CQLITE_MERGE_NULL_PROPERTY,
+            Error::InvalidRegex(_) => CQLiteStatus::CQLITE_INVALID_REGEX,
         }
     }
 }
@@ -46,8 +46,8 @@
 //! ```
 
 use planner::QueryPlan;
-use runtime::{Program, Status, VirtualMachine};
-use std::{convert::TryInto, path::Path};
+use runtime::{Functions, Program, Status, UserAggregate, VirtualMachine};
+use std::{any::Any, convert::TryInto, marker::PhantomData, path, sync::Arc};
 use store::{Store, StoreTxn};
 
 pub(crate) mod error;
@@ -63,7 +63,7 @@ mod ffi;
 
 pub use error::Error;
 pub use params::Params;
-pub use property::Property;
+pub use property::{Path, Property};
 
 /// A graph is a collection of nodes and edges.
 ///
@@ -71,6 +71,7 @@ pub use property::Property;
 /// file and support ACID queries over the graph.
 pub struct Graph {
     store: Store,
+    functions: Functions,
 }
 
 /// An ongoing transaction.
@@ -86,6 +87,7 @@ pub struct Txn<'graph>(StoreTxn<'graph>);
 pub struct Statement<'graph> {
     _graph: &'graph Graph,
     program: Program,
+    columns: Vec<String>,
 }
 
 /// RAII guard which represents an ongoing query.
@@ -111,6 +113,15 @@ pub struct MappedQuery<'stmt, 'txn, F> {
     map: F,
 }
 
+/// The callbacks of an aggregate registered using
+/// [`create_aggregate`][Graph::create_aggregate].
+struct AggregateCallbacks<S, I, T, F> {
+    init: I,
+    step: T,
+    finalize: F,
+    state: PhantomData<fn() -> S>,
+}
+
 impl Graph {
     /// Opens the file at the given path. If the file does not exist,
     /// it will be created. A newly created graph will start out empty.
@@ -126,9 +137,12 @@ impl Graph {
     /// # }
     /// # test().unwrap();
     /// ```
-    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+    pub fn open<P: AsRef<path::Path>>(path: P) -> Result<Self, Error> {
         let store = Store::open(path)?;
-        Ok(Self { store })
+        Ok(Self {
+            store,
+            functions: Functions::new(),
+        })
     }
 
     /// Open an anonymous graph which is held in-memory.
@@ -146,7 +160,10 @@ impl Graph {
     /// ```
     pub fn open_anon() -> Result<Self, Error> {
         let store = Store::open_anon()?;
-        Ok(Self { store })
+        Ok(Self {
+            store,
+            functions: Functions::new(),
+        })
     }
 
     /// Prepare a statement given a query `&str`. Queries support
@@ -185,22 +202,151 @@ impl Graph {
     /// ```
     pub fn prepare<'graph>(&'graph self, query: &str) -> Result<Statement<'graph>, Error> {
         let ast = parser::parse(query)?;
-        let plan = QueryPlan::new(&ast)?.optimize()?;
+        let programs = QueryPlan::union(&ast, &self.functions)?
+            .into_iter()
+            .map(|plan| Program::new(&plan.optimize()?, &self.functions))
+            .collect::<Result<_, Error>>()?;
         Ok(Statement {
             _graph: self,
-            program: Program::new(&plan)?,
+            program: Program::union(programs, !ast.all),
+            columns: ast.column_names().into_iter().map(String::from).collect(),
         })
     }
 
+    /// Register a scalar function, which can be called by name
+    /// from any statement prepared afterwards. Function names are
+    /// case-insensitive, and a registered function replaces both
+    /// any previously registered and any built-in function of the
+    /// same name.
+    ///
+    /// Calls are resolved when a statement is prepared: calling the
+    /// function with a number of arguments different from `arity`
+    /// fails with [`TypeMismatch`][Error::TypeMismatch]. Any error
+    /// returned by the function aborts the running query, and is
+    /// returned when stepping it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn test() -> Result<(), cqlite::Error> {
+    /// use cqlite::{Error, Graph, Property};
+    ///
+    /// let mut graph = Graph::open_anon()?;
+    /// graph.create_function("double", 1, |args| match &args[0] {
+    ///     Property::Integer(num) => Ok(Property::Integer(2 * num)),
+    ///     Property::Null => Ok(Property::Null),
+    ///     _ => Err(Error::TypeMismatch),
+    /// });
+    ///
+    /// let value: i64 = graph
+    ///     .prepare("RETURN double($num)")?
+    ///     .query_map(&mut graph.txn()?, ("num", 21), |m| m.get(0))?
+    ///     .next()
+    ///     .unwrap()?;
+    /// assert_eq!(value, 42);
+    /// # Ok(())
+    /// # }
+    /// # test().unwrap();
+    /// ```
+    pub fn create_function<F>(&mut self, name: &str, arity: usize, function: F)
+    where
+        F: Fn(&[Property]) -> Result<Property, Error> + Send + Sync + 'static,
+    {
+        self.functions.register(
+            name,
+            arity,
+            Arc::new(move |args: &[store::PropOwned]| {
+                let args: Vec<Property> =
+                    args.iter().cloned().map(|arg| arg.to_external()).collect();
+                Ok(function(&args)?.to_internal())
+            }),
+        );
+    }
+
+    /// Register an aggregate function, which can be called by name
+    /// from any statement prepared afterwards. Like the built-in
+    /// aggregates, it is computed over all matches which share the
+    /// values of the non-aggregate returns.
+    ///
+    /// Each group starts with the state returned by `init`. Then
+    /// `step` is called with the arguments of every match in the
+    /// group, including any `NULL` values. Finally, `finalize`
+    /// turns the state into the returned value. If no match is
+    /// found and there are no grouping keys, `finalize` is called
+    /// on the initial state.
+    ///
+    /// Function names are case-insensitive, and a registered aggregate
+    /// replaces any previously registered function of the same name.
+    /// Built-in aggregates like `count` can not be replaced. Calling the
+    /// aggregate with a number of arguments different from `arity` fails
+    /// with [`TypeMismatch`][Error::TypeMismatch].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn test() -> Result<(), cqlite::Error> {
+    /// use cqlite::{Error, Graph, Property};
+    ///
+    /// let mut graph = Graph::open_anon()?;
+    /// graph.create_aggregate(
+    ///     "product",
+    ///     1,
+    ///     || 1,
+    ///     |product: &mut i64, args| match &args[0] {
+    ///         Property::Integer(num) => Ok(*product *= num),
+    ///         _ => Err(Error::TypeMismatch),
+    ///     },
+    ///     |product| Ok(Property::Integer(product)),
+    /// );
+    ///
+    /// let mut txn = graph.mut_txn()?;
+    /// graph
+    ///     .prepare("CREATE (:NUM { value: 2 }) CREATE (:NUM { value: 21 })")?
+    ///     .execute(&mut txn, ())?;
+    /// let value: i64 = graph
+    ///     .prepare("MATCH (n:NUM) RETURN product(n.value)")?
+    ///     .query_map(&mut txn, (), |m| m.get(0))?
+    ///     .next()
+    ///     .unwrap()?;
+    /// assert_eq!(value, 42);
+    /// # Ok(())
+    /// # }
+    /// # test().unwrap();
+    /// ```
+    pub fn create_aggregate<S, I, T, F>(
+        &mut self,
+        name: &str,
+        arity: usize,
+        init: I,
+        step: T,
+        finalize: F,
+    ) where
+        S: 'static,
+        I: Fn() -> S + Send + Sync + 'static,
+        T: Fn(&mut S, &[Property]) -> Result<(), Error> + Send + Sync + 'static,
+        F: Fn(S) -> Result<Property, Error> + Send + Sync + 'static,
+    {
+        self.functions.register_aggregate(
+            name,
+            arity,
+            Arc::new(AggregateCallbacks {
+                init,
+                step,
+                finalize,
+                state: PhantomData,
+            }),
+        );
+    }
+
     /// Start a new read-only transaction. There may be many simultaneous
     /// read-only transactions.
-    pub fn txn(&self) -> Result<Txn, Error> {
+    pub fn txn(&self) -> Result<Txn<'_>, Error> {
         Ok(Txn(self.store.txn()?))
     }
 
     /// Start a new write transaction. Queries executed within this transaction
     /// may modify the graph. Multiple write transactions exclude each other.
-    pub fn mut_txn(&self) -> Result<Txn, Error> {
+    pub fn mut_txn(&self) -> Result<Txn<'_>, Error> {
         Ok(Txn(self.store.mut_txn()?))
     }
 }
@@ -217,6 +363,27 @@ impl<'graph> Txn<'graph> {
 }
 
 impl<'graph> Statement<'graph> {
+    /// The names of the columns returned by this statement. Columns
+    /// are named by their alias, as in `RETURN a.name AS name`, or
+    /// otherwise by the returned expression as written.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn test() -> Result<(), cqlite::Error> {
+    /// use cqlite::Graph;
+    ///
+    /// let graph = Graph::open_anon()?;
+    /// let stmt = graph.prepare("MATCH (a:PERSON) RETURN ID(a), a.name AS name")?;
+    /// assert_eq!(stmt.column_names(), ["ID(a)", "name"]);
+    /// # Ok(())
+    /// # }
+    /// # test().unwrap();
+    /// ```
+    pub fn column_names(&self) -> Vec<&str> {
+        self.columns.iter().map(String::as_str).collect()
+    }
+
     /// Execute this statement. The returned `Query` RAII
     /// guard can be used to step through the produced matches.
     ///
@@ -367,7 +534,7 @@ impl<'stmt, 'txn> Query<'stmt, 'txn> {
     /// completed, returns `None`, otherwise the match is
     /// returned.
     #[inline]
-    pub fn step(&mut self) -> Result<Option<Match>, Error> {
+    pub fn step(&mut self) -> Result<Option<Match<'_>>, Error> {
         if self.stmt.program.returns.is_empty() {
             loop {
                 match self.vm.run()? {
@@ -414,7 +581,51 @@ impl<'query> Match<'query> {
         Property: TryInto<P, Error = E>,
         Error: From<E>,
     {
-        Ok(self.query.vm.access_return(idx)?.to_external().try_into()?)
+        Ok(self
+            .query
+            .vm
+            .access_return(idx)?
+            .clone()
+            .to_external()
+            .try_into()?)
+    }
+
+    /// Return the value of the column with the given name
+    /// from the queries `RETURN` statement. Unknown names
+    /// return an error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn test() -> Result<(), cqlite::Error> {
+    /// use cqlite::Graph;
+    ///
+    /// let graph = Graph::open_anon()?;
+    /// let stmt = graph.prepare("RETURN $first AS first, $second")?;
+    /// let mut txn = graph.txn()?;
+    /// let mut query = stmt.query(&mut txn, (("first", "one"), ("second", 2)))?;
+    ///
+    /// let m = query.step()?.unwrap();
+    /// assert_eq!(m.get_by_name::<String, _>("first")?, "one");
+    /// assert_eq!(m.get_by_name::<i64, _>("$second")?, 2);
+    /// assert!(m.get_by_name::<i64, _>("third").is_err());
+    /// # Ok(())
+    /// # }
+    /// # test().unwrap();
+    /// ```
+    pub fn get_by_name<P, E>(&self, name: &str) -> Result<P, Error>
+    where
+        Property: TryInto<P, Error = E>,
+        Error: From<E>,
+    {
+        let idx = self
+            .query
+            .stmt
+            .columns
+            .iter()
+            .position(|column| column == name)
+            .ok_or_else(|| Error::UnknownIdentifier(name.to_string()))?;
+        self.get(idx)
     }
 
     /// Return the number of properties returned
@@ -436,3 +647,30 @@ where
         query.step().transpose().map(|res| res.and_then(map))
     }
 }
+
+impl<S, I, T, F> UserAggregate for AggregateCallbacks<S, I, T, F>
+where
+    S: 'static,
+    I: Fn() -> S + Send + Sync,
+    T: Fn(&mut S, &[Property]) -> Result<(), Error> + Send + Sync,
+    F: Fn(S) -> Result<Property, Error> + Send + Sync,
+{
+    fn init(&self) -> Box<dyn Any> {
+        Box::new((self.init)())
+    }
+
+    fn step(&self, state: &mut dyn Any, arguments: &[store::PropOwned]) -> Result<(), Error> {
+        let state = state.downcast_mut().ok_or(Error::Internal)?;
+        let arguments: Vec<Property> = arguments
+            .iter()
+            .cloned()
+            .map(|arg| arg.to_external())
+            .collect();
+        (self.step)(state, &arguments)
+    }
+
+    fn finish(&self, state: Box<dyn Any>) -> Result<store::PropOwned, Error> {
+        let state = state.downcast().map_err(|_| Error::Internal)?;
+        Ok((self.finalize)(*state)?.to_internal())
+    }
+}
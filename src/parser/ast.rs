@@ -1,31 +1,184 @@
+/// One or more queries combined by `UNION`, whose
+/// rows are returned one after the other.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement<'src> {
+    pub queries: Vec<Query<'src>>,
+    /// Keep duplicate rows, as in `UNION ALL`.
+    pub all: bool,
+}
+
+impl<'src> Statement<'src> {
+    /// The names of the columns returned by the
+    /// statement, which are those of the first query.
+    pub fn column_names(&self) -> Vec<&'src str> {
+        self.queries[0].column_names()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Query<'src> {
     pub match_clauses: Vec<MatchClause<'src>>,
     pub where_clauses: Vec<Condition<'src>>,
+    pub optional_match_clauses: Vec<OptionalMatchClause<'src>>,
     pub create_clauses: Vec<CreateClause<'src>>,
+    pub merge_clauses: Vec<MergeClause<'src>>,
     pub set_clauses: Vec<SetClause<'src>>,
-    pub delete_clauses: Vec<&'src str>,
-    pub return_clause: Vec<Expression<'src>>,
+    pub remove_clauses: Vec<RemoveClause<'src>>,
+    pub delete_clauses: Vec<DeleteClause<'src>>,
+    /// Continues the query in a new scope. The return
+    /// clause is empty if this is present.
+    pub with_clause: Option<WithClause<'src>>,
+    /// Continues the query once for each element of a
+    /// list. The return clause is empty if this is present.
+    pub unwind_clause: Option<UnwindClause<'src>>,
+    pub return_clause: Projection<'src>,
+}
+
+impl<'src> Query<'src> {
+    /// The names of the columns returned at the
+    /// end of the query, following any `WITH`.
+    pub fn column_names(&self) -> Vec<&'src str> {
+        if let Some(clause) = &self.with_clause {
+            clause.query.column_names()
+        } else if let Some(clause) = &self.unwind_clause {
+            clause.query.column_names()
+        } else {
+            self.return_clause
+                .items
+                .iter()
+                .map(|(name, _)| *name)
+                .collect()
+        }
+    }
 }
 
+/// Projects the named items into a new scope, in which
+/// `query` is evaluated. Only matches for which the
+/// condition holds are passed on. If `distinct` is set,
+/// only the first of any equal projections is passed on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WithClause<'src> {
+    pub distinct: bool,
+    pub items: Vec<(&'src str, Expression<'src>)>,
+    pub where_clause: Option<Condition<'src>>,
+    pub query: Box<Query<'src>>,
+}
+
+/// Binds each element of the list `expression` to `name`
+/// in turn and evaluates `query` for each of them. All
+/// other names remain in scope.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnwindClause<'src> {
+    pub expression: Expression<'src>,
+    pub name: &'src str,
+    pub query: Box<Query<'src>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Projection<'src> {
+    /// Only returns the first of any equal rows.
+    pub distinct: bool,
+    /// The returned expressions and their column names. Columns are
+    /// named by their alias, as in `a.name AS name`, or otherwise by
+    /// the expression as written.
+    pub items: Vec<(&'src str, Expression<'src>)>,
+    pub order_by: Vec<OrderBy<'src>>,
+    pub skip: Option<Expression<'src>>,
+    pub limit: Option<Expression<'src>>,
+}
+
+impl<'src> Projection<'src> {
+    pub fn new(items: Vec<(&'src str, Expression<'src>)>) -> Self {
+        Self {
+            distinct: false,
+            items,
+            order_by: Vec::new(),
+            skip: None,
+            limit: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderBy<'src> {
+    pub expression: Expression<'src>,
+    pub descending: bool,
+}
+
+/// The comma separated patterns of a `MATCH`, as in
+/// `MATCH (a) -> (b), (a) -> (c)`. No edge is matched
+/// more than once across all patterns.
 #[derive(Debug, Clone, PartialEq)]
 pub struct MatchClause<'src> {
+    pub patterns: Vec<Pattern<'src>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern<'src> {
+    /// The name to which the whole pattern is bound, as
+    /// in `p = (a) -> (b)`.
+    pub path: Option<&'src str>,
+    /// Only match the shortest paths of a pattern with
+    /// a single variable length edge.
+    pub shortest: Option<Shortest>,
     pub start: Node<'src>,
     pub edges: Vec<(Edge<'src>, Node<'src>)>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub struct SetClause<'src> {
-    pub name: &'src str,
-    pub key: &'src str,
-    pub value: Expression<'src>,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shortest {
+    /// `shortestPath(...)`, matches one shortest path.
+    One,
+    /// `allShortestPaths(...)`, matches every shortest path.
+    All,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptionalMatchClause<'src> {
+    pub pattern: MatchClause<'src>,
+    pub where_clause: Option<Condition<'src>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SetClause<'src> {
+    /// Sets a single property, as in `SET a.key = value`.
+    Property {
+        name: &'src str,
+        key: &'src str,
+        value: Expression<'src>,
+    },
+    /// Sets the properties from a map, as in `SET a += value`.
+    /// If `replace` is set, as in `SET a = value`, all other
+    /// properties are removed.
+    Properties {
+        name: &'src str,
+        value: Expression<'src>,
+        replace: bool,
+    },
+    /// Adds labels to a node, as in `SET a:LABEL:OTHER`.
+    Labels {
+        name: &'src str,
+        labels: Vec<&'src str>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RemoveClause<'src> {
+    /// Removes property `key` from the node or edge `name`.
+    Property { name: &'src str, key: &'src str },
+    /// Removes labels from a node, as in `REMOVE a:LABEL`.
+    Labels {
+        name: &'src str,
+        labels: Vec<&'src str>,
+    },
 }
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Clone, PartialEq)]
 pub enum CreateClause<'src> {
     CreateNode {
         name: Option<&'src str>,
-        label: &'src str,
+        labels: Vec<&'src str>,
         properties: Vec<(&'src str, Expression<'src>)>,
     },
     CreateEdge {
@@ -35,12 +188,46 @@ pub enum CreateClause<'src> {
         target: &'src str,
         properties: Vec<(&'src str, Expression<'src>)>,
     },
+    /// Creates the nodes and edges along a path, as in
+    /// `CREATE (a:PERSON) -[:KNOWS]-> (b:PERSON) <-[:KNOWS]- (c)`.
+    /// Nodes without labels refer to existing nodes.
+    CreatePath {
+        start: Node<'src>,
+        edges: Vec<(Edge<'src>, Node<'src>)>,
+    },
 }
 
+/// Deletes the node or edge `name`. Nodes with edges can
+/// only be deleted if `detach` is set, which also deletes
+/// all edges connected to the node.
 #[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeleteClause<'src> {
+    pub name: &'src str,
+    pub detach: bool,
+}
+
+/// Matches `pattern`, or creates it if there is no match. The
+/// `on_create` sets are applied if the pattern was created, the
+/// `on_match` sets are applied to every existing match.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeClause<'src> {
+    pub pattern: CreateClause<'src>,
+    pub on_create: Vec<SetClause<'src>>,
+    pub on_match: Vec<SetClause<'src>>,
+}
+
+/// The labels of a pattern. `:A:B` matches entities which
+/// have all labels, `:A|B` entities which have any of them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Labels<'src> {
+    All(Vec<&'src str>),
+    Any(Vec<&'src str>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Annotation<'src> {
     pub name: Option<&'src str>,
-    pub label: Option<&'src str>,
+    pub labels: Labels<'src>,
 }
 
 impl<'src> Annotation<'src> {
@@ -48,7 +235,7 @@ impl<'src> Annotation<'src> {
     pub fn new(name: &'src str, kind: &'src str) -> Self {
         Self {
             name: Some(name),
-            label: Some(kind),
+            labels: Labels::All(vec![kind]),
         }
     }
 
@@ -56,7 +243,7 @@ impl<'src> Annotation<'src> {
     pub fn with_name(name: &'src str) -> Self {
         Self {
             name: Some(name),
-            label: None,
+            labels: Labels::All(vec![]),
         }
     }
 
@@ -64,7 +251,7 @@ impl<'src> Annotation<'src> {
     pub fn with_label(label: &'src str) -> Self {
         Self {
             name: None,
-            label: Some(label),
+            labels: Labels::All(vec![label]),
         }
     }
 
@@ -72,7 +259,16 @@ impl<'src> Annotation<'src> {
     pub fn empty() -> Self {
         Self {
             name: None,
-            label: None,
+            labels: Labels::All(vec![]),
+        }
+    }
+
+    /// The label of an edge pattern, edges
+    /// have at most a single label.
+    pub fn label(&self) -> Option<&'src str> {
+        match &self.labels {
+            Labels::All(labels) if labels.len() == 1 => Some(labels[0]),
+            _ => None,
         }
     }
 }
@@ -112,7 +308,7 @@ impl<'src> Node<'src> {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Direction {
     Left,
     Right,
@@ -124,6 +320,16 @@ pub struct Edge<'src> {
     pub direction: Direction,
     pub annotation: Annotation<'src>,
     pub properties: Vec<(&'src str, Expression<'src>)>,
+    /// The number of hops for variable length edges.
+    pub length: Option<Length>,
+}
+
+/// An inclusive range of hops, which is unbounded if
+/// `max` is `None`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Length {
+    pub min: usize,
+    pub max: Option<usize>,
 }
 
 impl<'src> Edge<'src> {
@@ -135,6 +341,7 @@ impl<'src> Edge<'src> {
             direction: Direction::Either,
             annotation,
             properties,
+            length: None,
         }
     }
 
@@ -146,6 +353,7 @@ impl<'src> Edge<'src> {
             direction: Direction::Left,
             annotation,
             properties,
+            length: None,
         }
     }
 
@@ -157,6 +365,7 @@ impl<'src> Edge<'src> {
             direction: Direction::Right,
             annotation,
             properties,
+            length: None,
         }
     }
 }
@@ -170,13 +379,105 @@ pub enum Literal<'src> {
     Null,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+    Collect,
+}
+
+/// Constructors for temporal values, like `date('2024-01-01')`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Temporal {
+    Date,
+    DateTime,
+    Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Expression<'src> {
     Literal(Literal<'src>),
-    IdOf { name: &'src str },
-    LabelOf { name: &'src str },
-    Property { name: &'src str, key: &'src str },
+    IdOf {
+        name: &'src str,
+    },
+    LabelOf {
+        name: &'src str,
+    },
+    /// The list of node ids along a named path.
+    NodesOf {
+        name: &'src str,
+    },
+    /// The list of edge ids along a named path.
+    RelationshipsOf {
+        name: &'src str,
+    },
+    /// The number of edges in a named path.
+    LengthOf {
+        name: &'src str,
+    },
+    Property {
+        name: &'src str,
+        key: &'src str,
+    },
     Parameter(&'src str),
+    /// A list literal like `[1, 2, 3]`.
+    List(Vec<Expression<'src>>),
+    /// A map literal like `{ name: 'Alice', age: 42 }`.
+    Map(Vec<(&'src str, Expression<'src>)>),
+    /// A named node, edge, or value. Nodes and edges
+    /// evaluate to their id.
+    Variable(&'src str),
+    /// An aggregation over all matches of a group. The
+    /// argument is `None` for `count(*)`.
+    Aggregate {
+        function: Aggregate,
+        argument: Option<Box<Expression<'src>>>,
+    },
+    /// Constructs a temporal value. Without an argument,
+    /// `date()` and `datetime()` give the current time.
+    Temporal {
+        function: Temporal,
+        argument: Option<Box<Expression<'src>>>,
+    },
+    Binary {
+        op: BinaryOp,
+        lhs: Box<Expression<'src>>,
+        rhs: Box<Expression<'src>>,
+    },
+    /// A simple `CASE value WHEN .. THEN .. ELSE .. END`, which gives
+    /// the result of the first branch equal to `value`, or `default`
+    /// (`NULL` if there is none).
+    SimpleCase {
+        value: Box<Expression<'src>>,
+        branches: Vec<(Expression<'src>, Expression<'src>)>,
+        default: Option<Box<Expression<'src>>>,
+    },
+    /// A searched `CASE WHEN .. THEN .. ELSE .. END`, which gives the
+    /// result of the first branch whose condition holds, or `default`.
+    Case {
+        branches: Vec<(Condition<'src>, Expression<'src>)>,
+        default: Option<Box<Expression<'src>>>,
+    },
+    /// The first of the values which is not `NULL`.
+    Coalesce(Vec<Expression<'src>>),
+    /// A call of a scalar function, like `toUpper(n.name)`.
+    Function {
+        name: &'src str,
+        arguments: Vec<Expression<'src>>,
+    },
 }
 
 impl<'src> Expression<'src> {
@@ -191,6 +492,75 @@ impl<'src> Expression<'src> {
     pub fn property(name: &'src str, key: &'src str) -> Self {
         Self::Property { name, key }
     }
+
+    pub fn aggregate(function: Aggregate, argument: Self) -> Self {
+        Self::Aggregate {
+            function,
+            argument: Some(Box::new(argument)),
+        }
+    }
+
+    pub fn temporal(function: Temporal, argument: Option<Self>) -> Self {
+        Self::Temporal {
+            function,
+            argument: argument.map(Box::new),
+        }
+    }
+
+    pub fn binary(op: BinaryOp, lhs: Self, rhs: Self) -> Self {
+        Self::Binary {
+            op,
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+        }
+    }
+
+    pub fn count_all() -> Self {
+        Self::Aggregate {
+            function: Aggregate::Count,
+            argument: None,
+        }
+    }
+
+    /// The first name the expression refers to, if any.
+    pub fn first_name(&self) -> Option<&'src str> {
+        match self {
+            Self::IdOf { name }
+            | Self::LabelOf { name }
+            | Self::NodesOf { name }
+            | Self::RelationshipsOf { name }
+            | Self::LengthOf { name }
+            | Self::Property { name, .. }
+            | Self::Variable(name) => Some(name),
+            Self::Literal(_) | Self::Parameter(_) => None,
+            Self::List(items) => items.iter().find_map(Self::first_name),
+            Self::Map(entries) => entries.iter().find_map(|(_, value)| value.first_name()),
+            Self::Aggregate { argument, .. } | Self::Temporal { argument, .. } => {
+                argument.as_ref().and_then(|argument| argument.first_name())
+            }
+            Self::Binary { lhs, rhs, .. } => lhs.first_name().or_else(|| rhs.first_name()),
+            Self::SimpleCase {
+                value,
+                branches,
+                default,
+            } => value
+                .first_name()
+                .or_else(|| {
+                    branches
+                        .iter()
+                        .find_map(|(when, then)| when.first_name().or_else(|| then.first_name()))
+                })
+                .or_else(|| default.as_ref().and_then(|default| default.first_name())),
+            Self::Case { branches, default } => branches
+                .iter()
+                .find_map(|(when, then)| when.first_name().or_else(|| then.first_name()))
+                .or_else(|| default.as_ref().and_then(|default| default.first_name())),
+            Self::Coalesce(values)
+            | Self::Function {
+                arguments: values, ..
+            } => values.iter().find_map(Self::first_name),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -210,6 +580,16 @@ pub enum Condition<'src> {
     Gt(Expression<'src>, Expression<'src>),
     Ge(Expression<'src>, Expression<'src>),
 
+    StartsWith(Expression<'src>, Expression<'src>),
+    EndsWith(Expression<'src>, Expression<'src>),
+    Contains(Expression<'src>, Expression<'src>),
+    In(Expression<'src>, Expression<'src>),
+    IsNull(Expression<'src>),
+    IsNotNull(Expression<'src>),
+    /// Matches the whole text on the left against
+    /// the regular expression on the right.
+    Regex(Expression<'src>, Expression<'src>),
+
     IdEq(&'src str, Expression<'src>),
 }
 
@@ -225,4 +605,25 @@ impl<'src> Condition<'src> {
     pub fn not(cond: Self) -> Self {
         Self::Not(Box::new(cond))
     }
+
+    /// The first name the condition refers to, if any.
+    pub fn first_name(&self) -> Option<&'src str> {
+        match self {
+            Self::And(a, b) | Self::Or(a, b) => a.first_name().or_else(|| b.first_name()),
+            Self::Not(cond) => cond.first_name(),
+            Self::Expression(e) | Self::IsNull(e) | Self::IsNotNull(e) => e.first_name(),
+            Self::Eq(a, b)
+            | Self::Ne(a, b)
+            | Self::Lt(a, b)
+            | Self::Le(a, b)
+            | Self::Gt(a, b)
+            | Self::Ge(a, b)
+            | Self::StartsWith(a, b)
+            | Self::EndsWith(a, b)
+            | Self::Contains(a, b)
+            | Self::In(a, b)
+            | Self::Regex(a, b) => a.first_name().or_else(|| b.first_name()),
+            Self::IdEq(name, _) => Some(name),
+        }
+    }
 }
@@ -11,19 +11,61 @@ peg::parser! {
         use ast::*;
 
         rule kw_match()     = ("MATCH" / "match")
+        rule kw_optional()  = ("OPTIONAL" / "optional")
         rule kw_create()    = ("CREATE" / "create")
+        rule kw_merge()     = ("MERGE" / "merge")
+        rule kw_on()        = ("ON" / "on")
         rule kw_set()       = ("SET" / "set")
+        rule kw_remove()    = ("REMOVE" / "remove")
         rule kw_delete()    = ("DELETE" / "delete")
+        rule kw_detach()    = ("DETACH" / "detach")
         rule kw_where()     = ("WHERE" / "where")
+        rule kw_with()      = ("WITH" / "with")
+        rule kw_as()        = ("AS" / "as")
+        rule kw_distinct()  = ("DISTINCT" / "distinct")
+        rule kw_unwind()    = ("UNWIND" / "unwind")
+        rule kw_union()     = ("UNION" / "union")
+        rule kw_all()       = ("ALL" / "all")
         rule kw_return()    = ("RETURN" / "return")
+        rule kw_order()     = ("ORDER" / "order")
+        rule kw_by()        = ("BY" / "by")
+        rule kw_asc()       = ("ASCENDING" / "ascending" / "ASC" / "asc")
+        rule kw_desc()      = ("DESCENDING" / "descending" / "DESC" / "desc")
+        rule kw_skip()      = ("SKIP" / "skip")
+        rule kw_limit()     = ("LIMIT" / "limit")
         rule kw_true()      = ("TRUE" / "true")
         rule kw_false()     = ("FALSE" / "false")
         rule kw_null()      = ("NULL" / "null")
         rule kw_and()       = ("AND" / "and")
         rule kw_or()        = ("OR" / "or")
         rule kw_not()       = ("NOT" / "not")
+        rule kw_in()        = ("IN" / "in")
+        rule kw_is()        = ("IS" / "is")
         rule kw_id()        = ("ID" / "id")
         rule kw_label()     = ("LABEL" / "label")
+        rule kw_nodes()     = ("NODES" / "nodes")
+        rule kw_rels()      = ("RELATIONSHIPS" / "relationships")
+        rule kw_length()    = ("LENGTH" / "length")
+        rule kw_count()     = ("COUNT" / "count")
+        rule kw_sum()       = ("SUM" / "sum")
+        rule kw_avg()       = ("AVG" / "avg")
+        rule kw_min()       = ("MIN" / "min")
+        rule kw_max()       = ("MAX" / "max")
+        rule kw_collect()   = ("COLLECT" / "collect")
+        rule kw_starts()    = ("STARTS" / "starts")
+        rule kw_ends()      = ("ENDS" / "ends")
+        rule kw_contains()  = ("CONTAINS" / "contains")
+        rule kw_case()      = ("CASE" / "case")
+        rule kw_when()      = ("WHEN" / "when")
+        rule kw_then()      = ("THEN" / "then")
+        rule kw_else()      = ("ELSE" / "else")
+        rule kw_end()       = ("END" / "end")
+        rule kw_coalesce()  = ("COALESCE" / "coalesce")
+        rule kw_date()      = ("DATE" / "date")
+        rule kw_datetime()  = ("DATETIME" / "datetime" / "dateTime")
+        rule kw_duration()  = ("DURATION" / "duration")
+        rule kw_shortest()  = ("SHORTESTPATH" / "shortestPath" / "shortestpath")
+        rule kw_all_shortest() = ("ALLSHORTESTPATHS" / "allShortestPaths" / "allshortestpaths")
 
         rule _()
             = [' ']
@@ -65,21 +107,98 @@ peg::parser! {
             / t:text() { Literal::Text(t) }
             / kw_null() { Literal::Null }
 
-        rule expression() -> Expression<'input>
+        // e.g. 'date', 'datetime', 'duration'
+        rule temporal() -> Temporal
+            = kw_datetime() { Temporal::DateTime }
+            / kw_date() { Temporal::Date }
+            / kw_duration() { Temporal::Duration }
+
+        // e.g. 'a.age + 1', '(a.x + a.y) * 2', 'datetime() - $since'
+        rule expression() -> Expression<'input> = precedence!{
+            a:(@) _* "+" _* b:@ { Expression::binary(BinaryOp::Add, a, b) }
+            a:(@) _* "-" _* b:@ { Expression::binary(BinaryOp::Sub, a, b) }
+            --
+            a:(@) _* "*" _* b:@ { Expression::binary(BinaryOp::Mul, a, b) }
+            a:(@) _* "/" _* b:@ { Expression::binary(BinaryOp::Div, a, b) }
+            a:(@) _* "%" _* b:@ { Expression::binary(BinaryOp::Mod, a, b) }
+            --
+            a:(@) _* "^" _* b:@ { Expression::binary(BinaryOp::Pow, a, b) }
+            --
+            "(" __* e:expression() __* ")" { e }
+            e:atom() { e }
+        }
+
+        rule atom() -> Expression<'input>
             = "$" name:ident() { Expression::Parameter(name) }
             / l:literal() { Expression::Literal(l) }
+            / "[" __* items:( expression() ** (__* "," __*) ) __* "]" { Expression::List(items) }
+            / "{" __* "}" { Expression::Map(vec![]) }
+            / m:property_map() { Expression::Map(m) }
             / kw_id() _* "(" _* n:ident() _* ")" { Expression::id_of(n) }
             / kw_label() _* "(" _* n:ident() _* ")" { Expression::label_of(n) }
+            / kw_nodes() _* "(" _* n:ident() _* ")" { Expression::NodesOf { name: n } }
+            / kw_rels() _* "(" _* n:ident() _* ")" { Expression::RelationshipsOf { name: n } }
+            / kw_length() _* "(" _* n:ident() _* ")" { Expression::LengthOf { name: n } }
+            / f:temporal() _* "(" _* e:expression()? _* ")" { Expression::temporal(f, e) }
+            / kw_case() __+ branches:( kw_when() __+ c:condition() __+ kw_then() __+ e:expression() { (c, e) } ) ++ (__+)
+              default:case_default()? __+ kw_end() {
+                Expression::Case { branches, default: default.map(Box::new) }
+            }
+            / kw_case() __+ value:expression() __+ branches:( kw_when() __+ w:expression() __+ kw_then() __+ e:expression() { (w, e) } ) ++ (__+)
+              default:case_default()? __+ kw_end() {
+                Expression::SimpleCase { value: Box::new(value), branches, default: default.map(Box::new) }
+            }
+            / kw_coalesce() _* "(" __* values:( expression() ++ (__* "," __*) ) __* ")" { Expression::Coalesce(values) }
+            / !((aggregate() / kw_coalesce()) _* "(") name:ident() _* "(" __* arguments:( expression() ** (__* "," __*) ) __* ")" {
+                Expression::Function { name, arguments }
+            }
             / p:property() { Expression::property(p.0, p.1) }
+            / n:variable() { Expression::Variable(n) }
+
+        // e.g. 'ELSE 42' in a 'CASE' expression
+        rule case_default() -> Expression<'input>
+            = __+ kw_else() __+ e:expression() { e }
+
+        // e.g. 'count', 'avg', 'collect'
+        rule aggregate() -> Aggregate
+            = kw_count() { Aggregate::Count }
+            / kw_sum() { Aggregate::Sum }
+            / kw_avg() { Aggregate::Avg }
+            / kw_min() { Aggregate::Min }
+            / kw_max() { Aggregate::Max }
+            / kw_collect() { Aggregate::Collect }
+
+        // e.g. 'a.name', 'count(*)', 'max(a.age)'
+        rule projection() -> Expression<'input>
+            = kw_count() _* "(" _* "*" _* ")" { Expression::count_all() }
+            / f:aggregate() _* "(" _* e:expression() _* ")" { Expression::aggregate(f, e) }
+            / expression()
 
         // e.g. 'hello_world', 'Rust', 'HAS_PROPERTY'
         rule ident() -> &'input str
             = ident:$(alpha()alpha_num()*) { ident }
 
 
-        // e.g. 'a', 'a : PERSON', ': KNOWS'
+        // e.g. 'a', 'name', but not 'NOT'
+        rule variable() -> &'input str
+            = !((kw_and() / kw_or() / kw_not()) !alpha_num()) name:ident() { name }
+
+        // e.g. ':PERSON', ':PERSON:ADMIN', ':PERSON|ADMIN'
+        rule labels() -> Labels<'input>
+            = _* ":" _* first:ident() rest:( _* "|" _* ":"? _* k:ident() { k } )+ {
+                Labels::Any(std::iter::once(first).chain(rest).collect())
+            }
+            / labels:( _* ":" _* k:ident() { k } )* { Labels::All(labels) }
+
+        // e.g. 'a', 'a : PERSON', 'a:PERSON:ADMIN', ':PERSON|ADMIN'
         rule annotation() -> Annotation<'input>
-            = name:ident()? label:( _* ":" _* k:ident() { k } )? { Annotation { name, label } }
+            = name:ident()? labels:labels() { Annotation { name, labels } }
+
+        // e.g. 'a', 'a : KNOWS', ': KNOWS'
+        rule edge_annotation() -> Annotation<'input>
+            = name:ident()? label:( _* ":" _* k:ident() { k } )? {
+                Annotation { name, labels: Labels::All(label.into_iter().collect()) }
+            }
 
         // e.g. '{answer: 42, book: 'Hitchhikers Guide'}'
         rule property_map() -> Vec<(&'input str, Expression<'input>)>
@@ -91,16 +210,32 @@ peg::parser! {
                 Node::new(a, p.unwrap_or_else(Vec::new))
             }
 
-        // e.g. '-', '<-', '-[ name:KIND ]-', '<-[name]-'
+        // e.g. '42', '0'
+        rule hops() -> usize
+            = hops:$(num()+) {? hops.parse().or(Err("invalid number of hops")) }
+
+        // e.g. '*', '*3', '*2..', '*..5', '*1..3'
+        rule length() -> Length
+            = "*" _* min:hops()? _* ".." _* max:hops()? {?
+                let min = min.unwrap_or(1);
+                match max {
+                    Some(max) if max < min => Err("minimum hops exceed maximum hops"),
+                    _ => Ok(Length { min, max }),
+                }
+            }
+            / "*" _* hops:hops() { Length { min: hops, max: Some(hops) } }
+            / "*" { Length { min: 1, max: None } }
+
+        // e.g. '-', '<-', '-[ name:KIND ]-', '<-[name]-', '-[:KIND*1..3]->'
         rule edge() -> Edge<'input>
-            =  "-[" _* a:annotation() _* p:property_map()? _* "]->" {
-                Edge::right(a, p.unwrap_or_else(Vec::new))
+            =  "-[" _* a:edge_annotation() _* l:length()? _* p:property_map()? _* "]->" {
+                Edge { length: l, ..Edge::right(a, p.unwrap_or_else(Vec::new)) }
             }
-            /  "-[" _* a:annotation() _* p:property_map()? _* "]-"  {
-                Edge::either(a, p.unwrap_or_else(Vec::new))
+            /  "-[" _* a:edge_annotation() _* l:length()? _* p:property_map()? _* "]-"  {
+                Edge { length: l, ..Edge::either(a, p.unwrap_or_else(Vec::new)) }
             }
-            / "<-[" _* a:annotation() _* p:property_map()? _* "]-"  {
-                Edge::left(a, p.unwrap_or_else(Vec::new))
+            / "<-[" _* a:edge_annotation() _* l:length()? _* p:property_map()? _* "]-"  {
+                Edge { length: l, ..Edge::left(a, p.unwrap_or_else(Vec::new)) }
             }
             / "<-" { Edge::left(Annotation::empty(), Vec::new()) }
             / "->" { Edge::right(Annotation::empty(), Vec::new()) }
@@ -119,39 +254,115 @@ peg::parser! {
             kw_id() _* "(" _* n:ident() _* ")" _* "=" _* e:expression() { Condition::IdEq(n, e) }
             e:expression() _* "=" _* kw_id() _* "(" _* n:ident() _* ")" { Condition::IdEq(n, e) }
             --
+            a:expression() _* "=~" _* b:expression() { Condition::Regex(a, b) }
             a:expression() _* "="  _* b:expression() { Condition::Eq(a, b) }
             a:expression() _* ("<>" / "!=") _* b:expression() { Condition::Ne(a, b) }
             a:expression() _* "<"  _* b:expression() { Condition::Lt(a, b) }
             a:expression() _* "<=" _* b:expression() { Condition::Le(a, b) }
             a:expression() _* ">"  _* b:expression() { Condition::Gt(a, b) }
             a:expression() _* ">=" _* b:expression() { Condition::Ge(a, b) }
+            a:expression() __+ kw_starts() __+ kw_with() __+ b:expression() { Condition::StartsWith(a, b) }
+            a:expression() __+ kw_ends() __+ kw_with() __+ b:expression() { Condition::EndsWith(a, b) }
+            a:expression() __+ kw_contains() __+ b:expression() { Condition::Contains(a, b) }
+            a:expression() __+ kw_in() __+ b:expression() { Condition::In(a, b) }
+            e:expression() __+ kw_is() __+ kw_null() { Condition::IsNull(e) }
+            e:expression() __+ kw_is() __+ kw_not() __+ kw_null() { Condition::IsNotNull(e) }
             --
             e:expression() { Condition::Expression(e) }
             "(" __* c:condition() __* ")" { c }
         }
 
 
-        // e.g. 'MATCH (a)', 'MATCH (a) -> (b) <- (c)', ...
+        // e.g. 'shortestPath', 'allShortestPaths'
+        rule shortest() -> Shortest
+            = kw_shortest() { Shortest::One }
+            / kw_all_shortest() { Shortest::All }
+
+        // e.g. 'MATCH (a)', 'MATCH (a) -> (b) <- (c)', 'MATCH (a) -> (b), (a) -> (c)', ...
         rule match_clause() -> MatchClause<'input>
-            = kw_match() __+ start:node()
+            = kw_match() __+ patterns:( pattern() ++ (__* "," __*) ) { MatchClause { patterns } }
+
+        // e.g. '(a)', '(a) -> (b) <- (c)', 'p = (a) -[*]-> (b)', 'p = shortestPath((a) -[*]- (b))'
+        rule pattern() -> Pattern<'input>
+            = path:( p:ident() _* "=" _* { p } )?
+              s:shortest() _* "(" _* start:node() __* e:edge() __* n:node() _* ")" {?
+                match e.length {
+                    Some(length) if length.min <= 1 => Ok(Pattern {
+                        path,
+                        shortest: Some(s),
+                        start,
+                        edges: vec![(e, n)],
+                    }),
+                    Some(_) => Err("shortest path must have a minimum length of 0 or 1"),
+                    None => Err("shortest path must have a variable length"),
+                }
+            }
+            / path:( p:ident() _* "=" _* { p } )? start:node()
               edges:( (__* e:edge() __* n:node() { (e, n) }) ** "" ) {
-                MatchClause { start, edges }
+                Pattern { path, shortest: None, start, edges }
             }
 
         // e.g. 'WHERE a.name <> b.name', 'WHERE a.age > b.age AND a.age <= 42'
         rule where_clause() -> Condition<'input>
             = kw_where() __+ c:condition() { c }
 
-        // e.g. 'CREATE (node:LABEL { name: 'hello', answer: 42.0 })', 'CREATE (a) -[:LABEL]-> (b)'
-        rule create_clause() -> CreateClause<'input>
-            = kw_create() __+ n:node() {?
+        // e.g. 'OPTIONAL MATCH (a) -> (b)', 'OPTIONAL MATCH (a) -> (b) WHERE b.age > 42'
+        rule optional_match_clause() -> OptionalMatchClause<'input>
+            = kw_optional() __+ pattern:match_clause() where_clause:( __+ c:where_clause() { c } )? {
+                OptionalMatchClause { pattern, where_clause }
+            }
+
+        // e.g. 'CREATE (node:LABEL { name: 'hello', answer: 42.0 })', 'CREATE (a) -[:LABEL]-> (b)',
+        // 'CREATE (a:LABEL) -[:LABEL]-> (b:LABEL), (c:LABEL)'
+        rule create_clause() -> Vec<CreateClause<'input>>
+            = kw_create() __+ patterns:( create_item() ++ (__* "," __*) ) { patterns }
+
+        rule create_item() -> CreateClause<'input>
+            = p:create_pattern() !(__* edge()) { p }
+            / create_path()
+
+        // e.g. '(a:LABEL) -[:LABEL]-> (b:LABEL) <-[:LABEL]- (c)'
+        rule create_path() -> CreateClause<'input>
+            = start:node() edges:( __* e:edge() __* n:node() { (e, n) } )+ {?
+                let nodes = std::iter::once(&start).chain(edges.iter().map(|(_, node)| node));
+                for node in nodes {
+                    match &node.annotation.labels {
+                        Labels::All(labels) if !labels.is_empty() => (),
+                        Labels::All(_) if node.annotation.name.is_some()
+                            && node.properties.is_empty() => (),
+                        Labels::All(_) => return Err("a label is required"),
+                        Labels::Any(_) => return Err("labels must not be alternatives"),
+                    }
+                }
+                for (edge, _) in &edges {
+                    if edge.length.is_some() {
+                        return Err("edge must have a fixed length");
+                    }
+                    if edge.direction == Direction::Either {
+                        return Err("edge must be directed");
+                    }
+                    edge.annotation.label().ok_or("a label is required")?;
+                }
+                Ok(CreateClause::CreatePath { start, edges })
+            }
+
+        // e.g. '(node:LABEL { name: 'hello' })', '(a) -[:LABEL]-> (b)'
+        rule create_pattern() -> CreateClause<'input>
+            = n:node() {?
                 let name = n.annotation.name;
-                let label = n.annotation.label.ok_or("a label is required")?;
-                Ok(CreateClause::CreateNode { name, label, properties: n.properties })
+                let labels = match n.annotation.labels {
+                    Labels::All(labels) if !labels.is_empty() => labels,
+                    Labels::All(_) => return Err("a label is required"),
+                    Labels::Any(_) => return Err("labels must not be alternatives"),
+                };
+                Ok(CreateClause::CreateNode { name, labels, properties: n.properties })
             }
-            / kw_create() __+ "(" _* lhs:ident() _* ")" __* e:edge() __* "(" _* rhs:ident() _* ")" {?
+            / "(" _* lhs:ident() _* ")" __* e:edge() __* "(" _* rhs:ident() _* ")" {?
+                if e.length.is_some() {
+                    return Err("edge must have a fixed length");
+                }
                 let name = e.annotation.name;
-                let label = e.annotation.label.ok_or("a label is required")?;
+                let label = e.annotation.label().ok_or("a label is required")?;
                 let (origin, target) = match e.direction {
                     Direction::Left => (rhs, lhs),
                     Direction::Right => (lhs, rhs),
@@ -166,41 +377,153 @@ peg::parser! {
                 })
             }
 
+        // e.g. 'MERGE (a:LABEL { name: 'hello' }) ON CREATE SET a.answer = 42'
+        rule merge_clause() -> MergeClause<'input>
+            = kw_merge() __+ pattern:create_pattern() actions:( __+ a:merge_action() { a } )* {
+                let mut clause = MergeClause { pattern, on_create: vec![], on_match: vec![] };
+                for (on_create, mut sets) in actions {
+                    if on_create {
+                        clause.on_create.append(&mut sets);
+                    } else {
+                        clause.on_match.append(&mut sets);
+                    }
+                }
+                clause
+            }
+
+        // e.g. 'ON CREATE SET a.name = 'hello'', 'ON MATCH SET a.seen = true, a.count = 2'
+        rule merge_action() -> (bool, Vec<SetClause<'input>>)
+            = kw_on() __+ on_create:( kw_create() { true } / kw_match() { false } ) __+
+              kw_set() __+ sets:( set_item() ++ (__* "," __*) ) {
+                (on_create, sets)
+            }
+
         // e.g. 'SET a.name = 'Peter Parker''
         rule set_clause() -> SetClause<'input>
-            = kw_set() __+ p:property() _* "=" _* e:expression() {
-                SetClause { name: p.0, key: p.1, value: e }
+            = kw_set() __+ s:set_item() { s }
+
+        // e.g. ':PERSON', ':PERSON:ADMIN'
+        rule label_list() -> Vec<&'input str>
+            = labels:( _* ":" _* k:ident() { k } )+ { labels }
+
+        // e.g. 'a.name = 'Peter Parker'', 'a += $properties', 'a = $properties', 'a:ADMIN'
+        rule set_item() -> SetClause<'input>
+            = p:property() _* "=" _* e:expression() {
+                SetClause::Property { name: p.0, key: p.1, value: e }
+            }
+            / name:ident() _* replace:( "+=" { false } / "=" { true } ) _* e:expression() {
+                SetClause::Properties { name, value: e, replace }
+            }
+            / name:ident() labels:label_list() { SetClause::Labels { name, labels } }
+
+        // e.g. 'REMOVE a.name', 'REMOVE a:ADMIN'
+        rule remove_clause() -> RemoveClause<'input>
+            = kw_remove() __+ p:property() {
+                RemoveClause::Property { name: p.0, key: p.1 }
+            }
+            / kw_remove() __+ name:ident() labels:label_list() {
+                RemoveClause::Labels { name, labels }
+            }
+
+        // e.g. 'DELETE a', 'DETACH DELETE a'
+        rule delete_clause() -> DeleteClause<'input>
+            = detach:( kw_detach() __+ )? kw_delete() __+ name:ident() {
+                DeleteClause { name, detach: detach.is_some() }
+            }
+
+        // e.g. 'a', 'a.name AS name', '42 AS answer'
+        rule with_item() -> (&'input str, Expression<'input>)
+            = e:expression() __+ kw_as() __+ n:ident() { (n, e) }
+            / n:ident() { (n, Expression::Variable(n)) }
+
+        // e.g. 'WITH a, b.name AS name WHERE name <> 'Peter' MATCH (a) -> (c) RETURN c.name'
+        rule with_clause() -> WithClause<'input>
+            = kw_with() __+ distinct:( kw_distinct() __+ )? items:( with_item() ++ (__* "," __*) )
+              where_clause:( __+ c:where_clause() { c } )?
+              query:query() {
+                WithClause { distinct: distinct.is_some(), items, where_clause, query: Box::new(query) }
             }
 
-        // e.g. 'DELETE a'
-        rule delete_clause() -> &'input str
-            = kw_delete() __+ name:ident() { name }
+        // e.g. 'UNWIND $names AS name CREATE (:PERSON { name: name })'
+        rule unwind_clause() -> UnwindClause<'input>
+            = kw_unwind() __+ expression:expression() __+ kw_as() __+ name:ident()
+              query:query() {
+                UnwindClause { expression, name, query: Box::new(query) }
+            }
 
-        // e.g. 'RETURN a, b'
-        rule return_clause() -> Vec<Expression<'input>>
-            = kw_return() __+ items:( expression() ++ (__* "," __*) ) { items }
+        // e.g. 'a.name', 'a.age DESC'
+        rule order_by() -> OrderBy<'input>
+            = expression:projection() descending:( __+ kw_asc() { false } / __+ kw_desc() { true } )? {
+                OrderBy { expression, descending: descending.unwrap_or(false) }
+            }
+
+        // e.g. 'a.name', 'a.name AS name', 'count(*) AS total'
+        rule return_item() -> (&'input str, Expression<'input>)
+            = text:&(t:$(projection()) { t }) e:projection() alias:( __+ kw_as() __+ n:ident() { n } )? {
+                (alias.unwrap_or(text), e)
+            }
+
+        // e.g. 'RETURN a, b', 'RETURN DISTINCT a.name', 'RETURN a.name ORDER BY a.age DESC SKIP 10 LIMIT 5'
+        rule return_clause() -> Projection<'input>
+            = kw_return() __+ distinct:( kw_distinct() __+ )? items:( return_item() ++ (__* "," __*) )
+              order_by:( __+ kw_order() __+ kw_by() __+ o:( order_by() ++ (__* "," __*) ) { o } )?
+              skip:( __+ kw_skip() __+ e:expression() { e } )?
+              limit:( __+ kw_limit() __+ e:expression() { e } )? {
+                Projection {
+                    distinct: distinct.is_some(),
+                    items,
+                    order_by: order_by.unwrap_or_else(Vec::new),
+                    skip,
+                    limit,
+                }
+            }
 
         pub rule query() -> Query<'input>
             = __*
               match_clauses:( match_clause() ** (__+) )
               where_clauses:( __* w:( where_clause() ** (__+) )? { w.unwrap_or_else(Vec::new) } )
-              create_clauses:( __* c:(create_clause() ** (__+) )? { c.unwrap_or_else(Vec::new) } )
+              optional_match_clauses:( __* o:(optional_match_clause() ** (__+) )? { o.unwrap_or_else(Vec::new) } )
+              create_clauses:( __* c:(create_clause() ** (__+) )? {
+                  c.map(|c| c.into_iter().flatten().collect()).unwrap_or_else(Vec::new)
+              } )
+              merge_clauses:( __* m:(merge_clause() ** (__+) )? { m.unwrap_or_else(Vec::new) } )
               set_clauses:( __* s:(set_clause() ** (__+) )? { s.unwrap_or_else(Vec::new) } )
+              remove_clauses:( __* r:(remove_clause() ** (__+) )? { r.unwrap_or_else(Vec::new) } )
               delete_clauses:( __* d:(delete_clause() ** (__+) )? { d.unwrap_or_else(Vec::new) } )
-              return_clause:( __* r:return_clause()? { r.unwrap_or_else(Vec::new) })
+              with_clause:( __* w:with_clause()? { w } )
+              unwind_clause:( __* u:unwind_clause()? { u } )
+              return_clause:( __* r:return_clause()? { r.unwrap_or_else(|| Projection::new(Vec::new())) })
               __* {
                 Query {
                     match_clauses,
                     where_clauses,
+                    optional_match_clauses,
                     create_clauses,
+                    merge_clauses,
                     set_clauses,
+                    remove_clauses,
                     delete_clauses,
+                    with_clause,
+                    unwind_clause,
                     return_clause,
                 }
             }
+
+        // e.g. 'MATCH (a:PERSON) RETURN a.name AS name UNION ALL MATCH (b:BOOK) RETURN b.title AS name'
+        pub rule statement() -> Statement<'input>
+            = first:query() rest:( kw_union() all:( __+ kw_all() )? __+ q:query() { (all.is_some(), q) } )* {?
+                #[allow(clippy::unnecessary_map_or)]
+                let all = rest.first().map_or(false, |(all, _)| *all);
+                if rest.iter().any(|(other, _)| *other != all) {
+                    return Err("UNION and UNION ALL can not be mixed");
+                }
+                let mut queries = vec![first];
+                queries.extend(rest.into_iter().map(|(_, query)| query));
+                Ok(Statement { queries, all })
+            }
     }
 }
 
-pub fn parse(input: &str) -> Result<ast::Query<'_>, ParseError<LineCol>> {
-    cypher::query(input)
+pub fn parse(input: &str) -> Result<ast::Statement<'_>, ParseError<LineCol>> {
+    cypher::statement(input)
 }
@@ -7,51 +7,81 @@ fn match_clauses_work() {
         cypher::query("MATCH (a) - (b) RETURN a.name, LABEL(a) "),
         Ok(Query {
             match_clauses: vec![MatchClause {
-                start: Node::with_annotation(Annotation::with_name("a")),
-                edges: vec![(
-                    Edge::either(Annotation::empty(), vec![]),
-                    Node::with_annotation(Annotation::with_name("b"))
-                )],
+                patterns: vec![Pattern {
+                    path: None,
+                    shortest: None,
+                    start: Node::with_annotation(Annotation::with_name("a")),
+                    edges: vec![(
+                        Edge::either(Annotation::empty(), vec![]),
+                        Node::with_annotation(Annotation::with_name("b"))
+                    )],
+                }]
             }],
             where_clauses: vec![],
+            optional_match_clauses: vec![],
             create_clauses: vec![],
+            merge_clauses: vec![],
             set_clauses: vec![],
+            remove_clauses: vec![],
             delete_clauses: vec![],
-            return_clause: vec![Expression::property("a", "name"), Expression::label_of("a")],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection::new(vec![
+                ("a.name", Expression::property("a", "name")),
+                ("LABEL(a)", Expression::label_of("a"))
+            ]),
         })
     );
     assert_eq!(
         cypher::query("MATCH (a:LABEL) <- ( )\nRETURN ID(a)"),
         Ok(Query {
             match_clauses: vec![MatchClause {
-                start: Node::with_annotation(Annotation::new("a", "LABEL")),
-                edges: vec![(
-                    Edge::left(Annotation::empty(), vec![]),
-                    Node::with_annotation(Annotation::empty())
-                )],
+                patterns: vec![Pattern {
+                    path: None,
+                    shortest: None,
+                    start: Node::with_annotation(Annotation::new("a", "LABEL")),
+                    edges: vec![(
+                        Edge::left(Annotation::empty(), vec![]),
+                        Node::with_annotation(Annotation::empty())
+                    )],
+                }]
             }],
             where_clauses: vec![],
+            optional_match_clauses: vec![],
             create_clauses: vec![],
+            merge_clauses: vec![],
             set_clauses: vec![],
+            remove_clauses: vec![],
             delete_clauses: vec![],
-            return_clause: vec![Expression::id_of("a")],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection::new(vec![("ID(a)", Expression::id_of("a"))]),
         })
     );
     assert_eq!(
         cypher::query(" MATCH () -> (:LABEL_ONLY) RETURN a.test"),
         Ok(Query {
             match_clauses: vec![MatchClause {
-                start: Node::with_annotation(Annotation::empty()),
-                edges: vec![(
-                    Edge::right(Annotation::empty(), vec![]),
-                    Node::with_annotation(Annotation::with_label("LABEL_ONLY"))
-                )],
+                patterns: vec![Pattern {
+                    path: None,
+                    shortest: None,
+                    start: Node::with_annotation(Annotation::empty()),
+                    edges: vec![(
+                        Edge::right(Annotation::empty(), vec![]),
+                        Node::with_annotation(Annotation::with_label("LABEL_ONLY"))
+                    )],
+                }]
             }],
             where_clauses: vec![],
+            optional_match_clauses: vec![],
             create_clauses: vec![],
+            merge_clauses: vec![],
             set_clauses: vec![],
+            remove_clauses: vec![],
             delete_clauses: vec![],
-            return_clause: vec![Expression::property("a", "test")],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection::new(vec![("a.test", Expression::property("a", "test"))]),
         })
     );
 
@@ -59,54 +89,84 @@ fn match_clauses_work() {
         cypher::query("MATCH \n (a)  -[edge]->  (b) RETURN ID(edge)"),
         Ok(Query {
             match_clauses: vec![MatchClause {
-                start: Node::with_annotation(Annotation::with_name("a")),
-                edges: vec![(
-                    Edge::right(Annotation::with_name("edge"), vec![]),
-                    Node::with_annotation(Annotation::with_name("b"))
-                )],
+                patterns: vec![Pattern {
+                    path: None,
+                    shortest: None,
+                    start: Node::with_annotation(Annotation::with_name("a")),
+                    edges: vec![(
+                        Edge::right(Annotation::with_name("edge"), vec![]),
+                        Node::with_annotation(Annotation::with_name("b"))
+                    )],
+                }]
             }],
             where_clauses: vec![],
+            optional_match_clauses: vec![],
             create_clauses: vec![],
+            merge_clauses: vec![],
             set_clauses: vec![],
+            remove_clauses: vec![],
             delete_clauses: vec![],
-            return_clause: vec![Expression::id_of("edge")],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection::new(vec![("ID(edge)", Expression::id_of("edge"))]),
         })
     );
     assert_eq!(
         cypher::query("MATCH (a) <-[e:KNOWS]- (b) RETURN e.since, b.name"),
         Ok(Query {
             match_clauses: vec![MatchClause {
-                start: Node::with_annotation(Annotation::with_name("a")),
-                edges: vec![(
-                    Edge::left(Annotation::new("e", "KNOWS"), vec![]),
-                    Node::with_annotation(Annotation::with_name("b"))
-                )],
+                patterns: vec![Pattern {
+                    path: None,
+                    shortest: None,
+                    start: Node::with_annotation(Annotation::with_name("a")),
+                    edges: vec![(
+                        Edge::left(Annotation::new("e", "KNOWS"), vec![]),
+                        Node::with_annotation(Annotation::with_name("b"))
+                    )],
+                }]
             }],
             where_clauses: vec![],
+            optional_match_clauses: vec![],
             create_clauses: vec![],
+            merge_clauses: vec![],
             set_clauses: vec![],
+            remove_clauses: vec![],
             delete_clauses: vec![],
-            return_clause: vec![
-                Expression::property("e", "since"),
-                Expression::property("b", "name"),
-            ],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection::new(vec![
+                ("e.since", Expression::property("e", "since")),
+                ("b.name", Expression::property("b", "name")),
+            ]),
         })
     );
     assert_eq!(
         cypher::query("MATCH (a) -[]- (b) RETURN ID(a), $test"),
         Ok(Query {
             match_clauses: vec![MatchClause {
-                start: Node::with_annotation(Annotation::with_name("a")),
-                edges: vec![(
-                    Edge::either(Annotation::empty(), vec![]),
-                    Node::with_annotation(Annotation::with_name("b"))
-                )],
+                patterns: vec![Pattern {
+                    path: None,
+                    shortest: None,
+                    start: Node::with_annotation(Annotation::with_name("a")),
+                    edges: vec![(
+                        Edge::either(Annotation::empty(), vec![]),
+                        Node::with_annotation(Annotation::with_name("b"))
+                    )],
+                }]
             }],
             where_clauses: vec![],
+            optional_match_clauses: vec![],
             create_clauses: vec![],
+            merge_clauses: vec![],
             set_clauses: vec![],
+            remove_clauses: vec![],
             delete_clauses: vec![],
-            return_clause: vec![Expression::id_of("a"), Expression::Parameter("test")],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection::new(vec![
+                ("ID(a)", Expression::id_of("a")),
+                ("$test", Expression::Parameter("test"))
+            ]),
         })
     );
 
@@ -114,27 +174,36 @@ fn match_clauses_work() {
         cypher::query("MATCH (a) -> (b) - (c) RETURN a.a , b.b, c.c"),
         Ok(Query {
             match_clauses: vec![MatchClause {
-                start: Node::with_annotation(Annotation::with_name("a")),
-                edges: vec![
-                    (
-                        Edge::right(Annotation::empty(), vec![]),
-                        Node::with_annotation(Annotation::with_name("b"))
-                    ),
-                    (
-                        Edge::either(Annotation::empty(), vec![]),
-                        Node::with_annotation(Annotation::with_name("c"))
-                    )
-                ],
+                patterns: vec![Pattern {
+                    path: None,
+                    shortest: None,
+                    start: Node::with_annotation(Annotation::with_name("a")),
+                    edges: vec![
+                        (
+                            Edge::right(Annotation::empty(), vec![]),
+                            Node::with_annotation(Annotation::with_name("b"))
+                        ),
+                        (
+                            Edge::either(Annotation::empty(), vec![]),
+                            Node::with_annotation(Annotation::with_name("c"))
+                        )
+                    ],
+                }]
             }],
             where_clauses: vec![],
+            optional_match_clauses: vec![],
             create_clauses: vec![],
+            merge_clauses: vec![],
             set_clauses: vec![],
+            remove_clauses: vec![],
             delete_clauses: vec![],
-            return_clause: vec![
-                Expression::property("a", "a"),
-                Expression::property("b", "b"),
-                Expression::property("c", "c"),
-            ],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection::new(vec![
+                ("a.a", Expression::property("a", "a")),
+                ("b.b", Expression::property("b", "b")),
+                ("c.c", Expression::property("c", "c")),
+            ]),
         })
     );
     assert_eq!(
@@ -142,56 +211,129 @@ fn match_clauses_work() {
         Ok(Query {
             match_clauses: vec![
                 MatchClause {
-                    start: Node::with_annotation(Annotation::with_name("a")),
-                    edges: vec![(
-                        Edge::right(Annotation::empty(), vec![]),
-                        Node::with_annotation(Annotation::with_name("b"))
-                    )],
+                    patterns: vec![Pattern {
+                        path: None,
+                        shortest: None,
+                        start: Node::with_annotation(Annotation::with_name("a")),
+                        edges: vec![(
+                            Edge::right(Annotation::empty(), vec![]),
+                            Node::with_annotation(Annotation::with_name("b"))
+                        )],
+                    }]
                 },
                 MatchClause {
-                    start: Node::with_annotation(Annotation::with_name("b")),
-                    edges: vec![(
-                        Edge::right(Annotation::empty(), vec![]),
-                        Node::with_annotation(Annotation::with_name("c"))
-                    )],
+                    patterns: vec![Pattern {
+                        path: None,
+                        shortest: None,
+                        start: Node::with_annotation(Annotation::with_name("b")),
+                        edges: vec![(
+                            Edge::right(Annotation::empty(), vec![]),
+                            Node::with_annotation(Annotation::with_name("c"))
+                        )],
+                    }]
                 }
             ],
             where_clauses: vec![],
+            optional_match_clauses: vec![],
             create_clauses: vec![],
+            merge_clauses: vec![],
             set_clauses: vec![],
+            remove_clauses: vec![],
             delete_clauses: vec![],
-            return_clause: vec![
-                Expression::property("a", "a"),
-                Expression::property("b", "b"),
-                Expression::property("c", "c"),
-            ],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection::new(vec![
+                ("a.a", Expression::property("a", "a")),
+                ("b.b", Expression::property("b", "b")),
+                ("c.c", Expression::property("c", "c")),
+            ]),
         })
     );
 }
 
+#[test]
+fn comma_separated_patterns_work() {
+    assert_eq!(
+        cypher::query("MATCH (a) -> (b), (a) <-[e]- (c:PERSON) RETURN a")
+            .map(|query| query.match_clauses),
+        Ok(vec![MatchClause {
+            patterns: vec![
+                Pattern {
+                    path: None,
+                    shortest: None,
+                    start: Node::with_annotation(Annotation::with_name("a")),
+                    edges: vec![(
+                        Edge::right(Annotation::empty(), vec![]),
+                        Node::with_annotation(Annotation::with_name("b"))
+                    )],
+                },
+                Pattern {
+                    path: None,
+                    shortest: None,
+                    start: Node::with_annotation(Annotation::with_name("a")),
+                    edges: vec![(
+                        Edge::left(Annotation::with_name("e"), vec![]),
+                        Node::with_annotation(Annotation::new("c", "PERSON"))
+                    )],
+                },
+            ]
+        }])
+    );
+    assert_eq!(
+        cypher::query("MATCH p = (a) -> (b),q=(b)->(c) , shortestPath((a) -[*]-> (c)) RETURN a")
+            .map(|query| query.match_clauses[0]
+                .patterns
+                .iter()
+                .map(|pattern| (pattern.path, pattern.shortest))
+                .collect::<Vec<_>>()),
+        Ok(vec![
+            (Some("p"), None),
+            (Some("q"), None),
+            (None, Some(Shortest::One))
+        ])
+    );
+    assert_eq!(
+        cypher::query("MATCH (a) OPTIONAL MATCH (a) -> (b), (b) -> (c) RETURN c")
+            .map(|query| query.optional_match_clauses[0].pattern.patterns.len()),
+        Ok(2)
+    );
+
+    assert!(cypher::query("MATCH (a), RETURN a").is_err());
+    assert!(cypher::query("MATCH , (a) RETURN a").is_err());
+}
+
 #[test]
 fn property_maps_work() {
     assert_eq!(
         cypher::query("MATCH (a { answer: 42, book: $book}) - (b) RETURN ID(a)"),
         Ok(Query {
             match_clauses: vec![MatchClause {
-                start: Node::new(
-                    Annotation::with_name("a"),
-                    vec![
-                        ("answer", Expression::Literal(Literal::Integer(42))),
-                        ("book", Expression::Parameter("book")),
-                    ]
-                ),
-                edges: vec![(
-                    Edge::either(Annotation::empty(), vec![]),
-                    Node::with_annotation(Annotation::with_name("b"))
-                )],
+                patterns: vec![Pattern {
+                    path: None,
+                    shortest: None,
+                    start: Node::new(
+                        Annotation::with_name("a"),
+                        vec![
+                            ("answer", Expression::Literal(Literal::Integer(42))),
+                            ("book", Expression::Parameter("book")),
+                        ]
+                    ),
+                    edges: vec![(
+                        Edge::either(Annotation::empty(), vec![]),
+                        Node::with_annotation(Annotation::with_name("b"))
+                    )],
+                }]
             }],
             where_clauses: vec![],
+            optional_match_clauses: vec![],
             create_clauses: vec![],
+            merge_clauses: vec![],
             set_clauses: vec![],
+            remove_clauses: vec![],
             delete_clauses: vec![],
-            return_clause: vec![Expression::id_of("a")],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection::new(vec![("ID(a)", Expression::id_of("a"))]),
         })
     );
 
@@ -199,20 +341,29 @@ fn property_maps_work() {
         cypher::query("MATCH (a) -[:KNOWS{since: 'February' } ]- (b)"),
         Ok(Query {
             match_clauses: vec![MatchClause {
-                start: Node::with_annotation(Annotation::with_name("a"),),
-                edges: vec![(
-                    Edge::either(
-                        Annotation::with_label("KNOWS"),
-                        vec![("since", Expression::Literal(Literal::Text("February"))),]
-                    ),
-                    Node::with_annotation(Annotation::with_name("b"))
-                )],
+                patterns: vec![Pattern {
+                    path: None,
+                    shortest: None,
+                    start: Node::with_annotation(Annotation::with_name("a"),),
+                    edges: vec![(
+                        Edge::either(
+                            Annotation::with_label("KNOWS"),
+                            vec![("since", Expression::Literal(Literal::Text("February"))),]
+                        ),
+                        Node::with_annotation(Annotation::with_name("b"))
+                    )],
+                }]
             }],
             where_clauses: vec![],
+            optional_match_clauses: vec![],
             create_clauses: vec![],
+            merge_clauses: vec![],
             set_clauses: vec![],
+            remove_clauses: vec![],
             delete_clauses: vec![],
-            return_clause: vec![],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection::new(vec![]),
         })
     );
 }
@@ -223,17 +374,26 @@ fn where_clauses_work() {
         cypher::query("MATCH (a) WHERE ID(a) = 42 RETURN a.name"),
         Ok(Query {
             match_clauses: vec![MatchClause {
-                start: Node::with_annotation(Annotation::with_name("a")),
-                edges: vec![],
+                patterns: vec![Pattern {
+                    path: None,
+                    shortest: None,
+                    start: Node::with_annotation(Annotation::with_name("a")),
+                    edges: vec![],
+                }]
             }],
             where_clauses: vec![Condition::IdEq(
                 "a",
                 Expression::Literal(Literal::Integer(42))
             )],
+            optional_match_clauses: vec![],
             create_clauses: vec![],
+            merge_clauses: vec![],
             set_clauses: vec![],
+            remove_clauses: vec![],
             delete_clauses: vec![],
-            return_clause: vec![Expression::property("a", "name")],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection::new(vec![("a.name", Expression::property("a", "name"))]),
         })
     );
 
@@ -241,8 +401,12 @@ fn where_clauses_work() {
         cypher::query("MATCH (a) WHERE a.age >= $min_age RETURN a.age"),
         Ok(Query {
             match_clauses: vec![MatchClause {
-                start: Node::with_annotation(Annotation::with_name("a")),
-                edges: vec![],
+                patterns: vec![Pattern {
+                    path: None,
+                    shortest: None,
+                    start: Node::with_annotation(Annotation::with_name("a")),
+                    edges: vec![],
+                }]
             }],
             where_clauses: vec![Condition::Ge(
                 Expression::Property {
@@ -251,10 +415,15 @@ fn where_clauses_work() {
                 },
                 Expression::Parameter("min_age"),
             )],
+            optional_match_clauses: vec![],
             create_clauses: vec![],
+            merge_clauses: vec![],
             set_clauses: vec![],
+            remove_clauses: vec![],
             delete_clauses: vec![],
-            return_clause: vec![Expression::property("a", "age")],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection::new(vec![("a.age", Expression::property("a", "age"))]),
         })
     );
 
@@ -268,11 +437,15 @@ fn where_clauses_work() {
         ),
         Ok(Query {
             match_clauses: vec![MatchClause {
-                start: Node::with_annotation(Annotation::with_name("a")),
-                edges: vec![(
-                    Edge::right(Annotation::new("e", "KNOWS"), vec![]),
-                    Node::with_annotation(Annotation::with_name("b"))
-                )],
+                patterns: vec![Pattern {
+                    path: None,
+                    shortest: None,
+                    start: Node::with_annotation(Annotation::with_name("a")),
+                    edges: vec![(
+                        Edge::right(Annotation::new("e", "KNOWS"), vec![]),
+                        Node::with_annotation(Annotation::with_name("b"))
+                    )],
+                }]
             }],
             where_clauses: vec![Condition::or(
                 Condition::and(
@@ -296,10 +469,15 @@ fn where_clauses_work() {
                     key: "fake",
                 })),
             )],
+            optional_match_clauses: vec![],
             create_clauses: vec![],
+            merge_clauses: vec![],
             set_clauses: vec![],
+            remove_clauses: vec![],
             delete_clauses: vec![],
-            return_clause: vec![Expression::property("e", "since")],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection::new(vec![("e.since", Expression::property("e", "since"))]),
         })
     );
 }
@@ -311,17 +489,22 @@ fn create_clauses_work() {
         Ok(Query {
             match_clauses: vec![],
             where_clauses: vec![],
+            optional_match_clauses: vec![],
             create_clauses: vec![CreateClause::CreateNode {
                 name: Some("node"),
-                label: "PERSON",
+                labels: vec!["PERSON"],
                 properties: vec![
                     ("name", Expression::Literal(Literal::Text("Peter Parker"))),
                     ("answer", Expression::Literal(Literal::Integer(42))),
                 ],
             }],
+            merge_clauses: vec![],
             set_clauses: vec![],
+            remove_clauses: vec![],
             delete_clauses: vec![],
-            return_clause: vec![Expression::id_of("node")],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection::new(vec![("ID(node)", Expression::id_of("node"))]),
         })
     );
 
@@ -330,15 +513,24 @@ fn create_clauses_work() {
         Ok(Query {
             match_clauses: vec![
                 MatchClause {
-                    start: Node::with_annotation(Annotation::with_name("a")),
-                    edges: vec![],
+                    patterns: vec![Pattern {
+                        path: None,
+                        shortest: None,
+                        start: Node::with_annotation(Annotation::with_name("a")),
+                        edges: vec![],
+                    }]
                 },
                 MatchClause {
-                    start: Node::with_annotation(Annotation::with_name("b")),
-                    edges: vec![],
+                    patterns: vec![Pattern {
+                        path: None,
+                        shortest: None,
+                        start: Node::with_annotation(Annotation::with_name("b")),
+                        edges: vec![],
+                    }]
                 }
             ],
             where_clauses: vec![Condition::IdEq("a", Expression::Parameter("id"))],
+            optional_match_clauses: vec![],
             create_clauses: vec![CreateClause::CreateEdge {
                 name: None,
                 label: "KNOWS",
@@ -346,11 +538,149 @@ fn create_clauses_work() {
                 target: "b",
                 properties: vec![],
             }],
+            merge_clauses: vec![],
+            set_clauses: vec![],
+            remove_clauses: vec![],
+            delete_clauses: vec![],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection::new(vec![]),
+        })
+    );
+}
+
+#[test]
+fn create_paths_work() {
+    assert_eq!(
+        cypher::query("CREATE (a:PERSON { name: 'Peter' }) -[:KNOWS]-> (:PERSON) <-[e:LIKES]- (c)")
+            .map(|query| query.create_clauses),
+        Ok(vec![CreateClause::CreatePath {
+            start: Node::new(
+                Annotation::new("a", "PERSON"),
+                vec![("name", Expression::Literal(Literal::Text("Peter")))],
+            ),
+            edges: vec![
+                (
+                    Edge::right(Annotation::with_label("KNOWS"), vec![]),
+                    Node::with_annotation(Annotation::with_label("PERSON")),
+                ),
+                (
+                    Edge::left(Annotation::new("e", "LIKES"), vec![]),
+                    Node::with_annotation(Annotation::with_name("c")),
+                ),
+            ],
+        }])
+    );
+    assert_eq!(
+        cypher::query("CREATE (a:PERSON), (b:PERSON), (a) -[:KNOWS]-> (b) CREATE (c:PERSON)")
+            .map(|query| query.create_clauses.len()),
+        Ok(4)
+    );
+    assert_eq!(
+        cypher::query("MATCH (a) MATCH (b) CREATE (a) -[:KNOWS]-> (b) -[:KNOWS]-> (a)")
+            .map(|query| matches!(query.create_clauses[..], [CreateClause::CreatePath { .. }])),
+        Ok(true)
+    );
+
+    assert!(cypher::query("CREATE (a:PERSON) -[:KNOWS]- (b:PERSON)").is_err());
+    assert!(cypher::query("CREATE (a:PERSON) -[]-> (b:PERSON)").is_err());
+    assert!(cypher::query("CREATE (a:PERSON) -[:KNOWS*2]-> (b:PERSON)").is_err());
+    assert!(cypher::query("CREATE (a:PERSON) -[:KNOWS]-> ()").is_err());
+    assert!(cypher::query("CREATE (a:PERSON) -[:KNOWS]-> (b { name: 'Bob' })").is_err());
+    assert!(cypher::query("CREATE (a:PERSON) -[:KNOWS]-> (b:A|B)").is_err());
+    assert!(cypher::query("CREATE (a:PERSON),").is_err());
+}
+
+#[test]
+fn merge_clauses_work() {
+    assert_eq!(
+        cypher::query(
+            "MERGE (c:CITY { name: 'Zurich' }) ON CREATE SET c.visits = 1 ON MATCH SET c.visits = 2, c.seen = true"
+        ),
+        Ok(Query {
+            match_clauses: vec![],
+            where_clauses: vec![],
+            optional_match_clauses: vec![],
+            create_clauses: vec![],
+            merge_clauses: vec![MergeClause {
+                pattern: CreateClause::CreateNode {
+                    name: Some("c"),
+                    labels: vec!["CITY"],
+                    properties: vec![("name", Expression::Literal(Literal::Text("Zurich")))],
+                },
+                on_create: vec![SetClause::Property {
+                    name: "c",
+                    key: "visits",
+                    value: Expression::Literal(Literal::Integer(1)),
+                }],
+                on_match: vec![
+                    SetClause::Property {
+                        name: "c",
+                        key: "visits",
+                        value: Expression::Literal(Literal::Integer(2)),
+                    },
+                    SetClause::Property {
+                        name: "c",
+                        key: "seen",
+                        value: Expression::Literal(Literal::Boolean(true)),
+                    },
+                ],
+            }],
+            set_clauses: vec![],
+            remove_clauses: vec![],
+            delete_clauses: vec![],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection::new(vec![]),
+        })
+    );
+
+    assert_eq!(
+        cypher::query("MATCH (a) MATCH (b) MERGE (a) <-[e:KNOWS]- (b) RETURN ID(e)"),
+        Ok(Query {
+            match_clauses: vec![
+                MatchClause {
+                    patterns: vec![Pattern {
+                        path: None,
+                        shortest: None,
+                        start: Node::with_annotation(Annotation::with_name("a")),
+                        edges: vec![],
+                    }]
+                },
+                MatchClause {
+                    patterns: vec![Pattern {
+                        path: None,
+                        shortest: None,
+                        start: Node::with_annotation(Annotation::with_name("b")),
+                        edges: vec![],
+                    }]
+                }
+            ],
+            where_clauses: vec![],
+            optional_match_clauses: vec![],
+            create_clauses: vec![],
+            merge_clauses: vec![MergeClause {
+                pattern: CreateClause::CreateEdge {
+                    name: Some("e"),
+                    label: "KNOWS",
+                    origin: "b",
+                    target: "a",
+                    properties: vec![],
+                },
+                on_create: vec![],
+                on_match: vec![],
+            }],
             set_clauses: vec![],
+            remove_clauses: vec![],
             delete_clauses: vec![],
-            return_clause: vec![],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection::new(vec![("ID(e)", Expression::id_of("e"))]),
         })
     );
+
+    assert!(cypher::query("MERGE (a)").is_err());
+    assert!(cypher::query("MATCH (a) MATCH (b) MERGE (a) -[:KNOWS]- (b)").is_err());
 }
 
 #[test]
@@ -359,18 +689,27 @@ fn set_clauses_work() {
         cypher::query("MATCH (a) SET a.answer = 42"),
         Ok(Query {
             match_clauses: vec![MatchClause {
-                start: Node::with_annotation(Annotation::with_name("a")),
-                edges: vec![],
+                patterns: vec![Pattern {
+                    path: None,
+                    shortest: None,
+                    start: Node::with_annotation(Annotation::with_name("a")),
+                    edges: vec![],
+                }]
             }],
             where_clauses: vec![],
+            optional_match_clauses: vec![],
             create_clauses: vec![],
-            set_clauses: vec![SetClause {
+            merge_clauses: vec![],
+            set_clauses: vec![SetClause::Property {
                 name: "a",
                 key: "answer",
                 value: Expression::Literal(Literal::Integer(42)),
             }],
+            remove_clauses: vec![],
             delete_clauses: vec![],
-            return_clause: vec![],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection::new(vec![]),
         })
     );
 
@@ -378,27 +717,91 @@ fn set_clauses_work() {
         cypher::query("MATCH (a:PERSON) SET a.first = 'Peter' SET a.last = $last_name"),
         Ok(Query {
             match_clauses: vec![MatchClause {
-                start: Node::with_annotation(Annotation::new("a", "PERSON")),
-                edges: vec![],
+                patterns: vec![Pattern {
+                    path: None,
+                    shortest: None,
+                    start: Node::with_annotation(Annotation::new("a", "PERSON")),
+                    edges: vec![],
+                }]
             }],
             where_clauses: vec![],
+            optional_match_clauses: vec![],
             create_clauses: vec![],
+            merge_clauses: vec![],
             set_clauses: vec![
-                SetClause {
+                SetClause::Property {
                     name: "a",
                     key: "first",
                     value: Expression::Literal(Literal::Text("Peter")),
                 },
-                SetClause {
+                SetClause::Property {
                     name: "a",
                     key: "last",
                     value: Expression::Parameter("last_name"),
                 }
             ],
+            remove_clauses: vec![],
+            delete_clauses: vec![],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection::new(vec![]),
+        })
+    );
+}
+
+#[test]
+fn set_properties_and_remove_clauses_work() {
+    assert_eq!(
+        cypher::query(
+            "MATCH (a) -[e]-> (b) SET a += $props SET e = $all REMOVE b.name REMOVE e.since"
+        ),
+        Ok(Query {
+            match_clauses: vec![MatchClause {
+                patterns: vec![Pattern {
+                    path: None,
+                    shortest: None,
+                    start: Node::with_annotation(Annotation::with_name("a")),
+                    edges: vec![(
+                        Edge::right(Annotation::with_name("e"), vec![]),
+                        Node::with_annotation(Annotation::with_name("b")),
+                    )],
+                }]
+            }],
+            where_clauses: vec![],
+            optional_match_clauses: vec![],
+            create_clauses: vec![],
+            merge_clauses: vec![],
+            set_clauses: vec![
+                SetClause::Properties {
+                    name: "a",
+                    value: Expression::Parameter("props"),
+                    replace: false,
+                },
+                SetClause::Properties {
+                    name: "e",
+                    value: Expression::Parameter("all"),
+                    replace: true,
+                },
+            ],
+            remove_clauses: vec![
+                RemoveClause::Property {
+                    name: "b",
+                    key: "name",
+                },
+                RemoveClause::Property {
+                    name: "e",
+                    key: "since",
+                },
+            ],
             delete_clauses: vec![],
-            return_clause: vec![],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection::new(vec![]),
         })
     );
+
+    assert!(cypher::query("MATCH (a) REMOVE a").is_err());
+    assert!(cypher::query("MATCH (a) SET a.name += 'Peter'").is_err());
 }
 
 #[test]
@@ -407,14 +810,26 @@ fn delete_clauses_work() {
         cypher::query("MATCH (a:DEATH_STAR) DELETE a RETURN ID(a)"),
         Ok(Query {
             match_clauses: vec![MatchClause {
-                start: Node::with_annotation(Annotation::new("a", "DEATH_STAR")),
-                edges: vec![],
+                patterns: vec![Pattern {
+                    path: None,
+                    shortest: None,
+                    start: Node::with_annotation(Annotation::new("a", "DEATH_STAR")),
+                    edges: vec![],
+                }]
             }],
             where_clauses: vec![],
+            optional_match_clauses: vec![],
             create_clauses: vec![],
+            merge_clauses: vec![],
             set_clauses: vec![],
-            delete_clauses: vec!["a"],
-            return_clause: vec![Expression::id_of("a")],
+            remove_clauses: vec![],
+            delete_clauses: vec![DeleteClause {
+                name: "a",
+                detach: false,
+            }],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection::new(vec![("ID(a)", Expression::id_of("a"))]),
         })
     );
 
@@ -422,17 +837,1038 @@ fn delete_clauses_work() {
         cypher::query("MATCH (a) -[e:KNOWS]-> (b) DELETE b DELETE e"),
         Ok(Query {
             match_clauses: vec![MatchClause {
-                start: Node::with_annotation(Annotation::with_name("a")),
-                edges: vec![(
-                    Edge::right(Annotation::new("e", "KNOWS"), vec![]),
-                    Node::with_annotation(Annotation::with_name("b")),
-                )],
+                patterns: vec![Pattern {
+                    path: None,
+                    shortest: None,
+                    start: Node::with_annotation(Annotation::with_name("a")),
+                    edges: vec![(
+                        Edge::right(Annotation::new("e", "KNOWS"), vec![]),
+                        Node::with_annotation(Annotation::with_name("b")),
+                    )],
+                }]
+            }],
+            where_clauses: vec![],
+            optional_match_clauses: vec![],
+            create_clauses: vec![],
+            merge_clauses: vec![],
+            set_clauses: vec![],
+            remove_clauses: vec![],
+            delete_clauses: vec![
+                DeleteClause {
+                    name: "b",
+                    detach: false,
+                },
+                DeleteClause {
+                    name: "e",
+                    detach: false,
+                },
+            ],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection::new(vec![]),
+        })
+    );
+
+    assert_eq!(
+        cypher::query("MATCH (a) DETACH DELETE a"),
+        Ok(Query {
+            match_clauses: vec![MatchClause {
+                patterns: vec![Pattern {
+                    path: None,
+                    shortest: None,
+                    start: Node::with_annotation(Annotation::with_name("a")),
+                    edges: vec![],
+                }]
+            }],
+            where_clauses: vec![],
+            optional_match_clauses: vec![],
+            create_clauses: vec![],
+            merge_clauses: vec![],
+            set_clauses: vec![],
+            remove_clauses: vec![],
+            delete_clauses: vec![DeleteClause {
+                name: "a",
+                detach: true,
+            }],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection::new(vec![]),
+        })
+    );
+}
+
+#[test]
+fn order_skip_limit_work() {
+    assert_eq!(
+        cypher::query("MATCH (a) RETURN a.name ORDER BY a.age DESC, a.name SKIP 5 LIMIT $limit"),
+        Ok(Query {
+            match_clauses: vec![MatchClause {
+                patterns: vec![Pattern {
+                    path: None,
+                    shortest: None,
+                    start: Node::with_annotation(Annotation::with_name("a")),
+                    edges: vec![],
+                }]
             }],
             where_clauses: vec![],
+            optional_match_clauses: vec![],
+            create_clauses: vec![],
+            merge_clauses: vec![],
+            set_clauses: vec![],
+            remove_clauses: vec![],
+            delete_clauses: vec![],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection {
+                distinct: false,
+                items: vec![("a.name", Expression::property("a", "name"))],
+                order_by: vec![
+                    OrderBy {
+                        expression: Expression::property("a", "age"),
+                        descending: true,
+                    },
+                    OrderBy {
+                        expression: Expression::property("a", "name"),
+                        descending: false,
+                    },
+                ],
+                skip: Some(Expression::Literal(Literal::Integer(5))),
+                limit: Some(Expression::Parameter("limit")),
+            },
+        })
+    );
+
+    assert_eq!(
+        cypher::query("RETURN $a limit 1"),
+        Ok(Query {
+            match_clauses: vec![],
+            where_clauses: vec![],
+            optional_match_clauses: vec![],
             create_clauses: vec![],
+            merge_clauses: vec![],
             set_clauses: vec![],
-            delete_clauses: vec!["b", "e"],
-            return_clause: vec![],
+            remove_clauses: vec![],
+            delete_clauses: vec![],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection {
+                distinct: false,
+                items: vec![("$a", Expression::Parameter("a"))],
+                order_by: vec![],
+                skip: None,
+                limit: Some(Expression::Literal(Literal::Integer(1))),
+            },
         })
     );
 }
+
+#[test]
+fn aggregates_work() {
+    assert_eq!(
+        cypher::query("MATCH (a) RETURN a.city, count(*), COUNT(a.age), sum(a.age), avg( a.age ), min(a.age), max(a.age), collect(a.name) ORDER BY count(*) DESC"),
+        Ok(Query {
+            match_clauses: vec![MatchClause { patterns: vec![Pattern {
+                path: None,
+                shortest: None,
+                start: Node::with_annotation(Annotation::with_name("a")),
+                edges: vec![],
+            }] }],
+            where_clauses: vec![],
+            optional_match_clauses: vec![],
+            create_clauses: vec![],
+            merge_clauses: vec![],
+            set_clauses: vec![],
+            remove_clauses: vec![],
+            delete_clauses: vec![],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection {
+                distinct: false,
+                items: vec![
+                    ("a.city", Expression::property("a", "city")),
+                    ("count(*)", Expression::count_all()),
+                    ("COUNT(a.age)", Expression::aggregate(Aggregate::Count, Expression::property("a", "age"))),
+                    ("sum(a.age)", Expression::aggregate(Aggregate::Sum, Expression::property("a", "age"))),
+                    ("avg( a.age )", Expression::aggregate(Aggregate::Avg, Expression::property("a", "age"))),
+                    ("min(a.age)", Expression::aggregate(Aggregate::Min, Expression::property("a", "age"))),
+                    ("max(a.age)", Expression::aggregate(Aggregate::Max, Expression::property("a", "age"))),
+                    ("collect(a.name)", Expression::aggregate(Aggregate::Collect, Expression::property("a", "name"))),
+                ],
+                order_by: vec![OrderBy {
+                    expression: Expression::count_all(),
+                    descending: true,
+                }],
+                skip: None,
+                limit: None,
+            },
+        })
+    );
+
+    assert!(cypher::query("MATCH (a) WHERE count(*) > 2 RETURN a").is_err());
+    assert!(cypher::query("MATCH (a) RETURN sum(count(a.age))").is_err());
+    assert!(cypher::query("MATCH (a) RETURN sum(*)").is_err());
+}
+
+#[test]
+fn optional_match_clauses_work() {
+    assert_eq!(
+        cypher::query("MATCH (a) OPTIONAL MATCH (a) -> (b:PERSON) WHERE b.age > 42 RETURN b.name"),
+        Ok(Query {
+            match_clauses: vec![MatchClause {
+                patterns: vec![Pattern {
+                    path: None,
+                    shortest: None,
+                    start: Node::with_annotation(Annotation::with_name("a")),
+                    edges: vec![],
+                }]
+            }],
+            where_clauses: vec![],
+            optional_match_clauses: vec![OptionalMatchClause {
+                pattern: MatchClause {
+                    patterns: vec![Pattern {
+                        path: None,
+                        shortest: None,
+                        start: Node::with_annotation(Annotation::with_name("a")),
+                        edges: vec![(
+                            Edge::right(Annotation::empty(), vec![]),
+                            Node::with_annotation(Annotation::new("b", "PERSON"))
+                        )],
+                    }]
+                },
+                where_clause: Some(Condition::Gt(
+                    Expression::property("b", "age"),
+                    Expression::Literal(Literal::Integer(42))
+                )),
+            }],
+            create_clauses: vec![],
+            merge_clauses: vec![],
+            set_clauses: vec![],
+            remove_clauses: vec![],
+            delete_clauses: vec![],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection::new(vec![("b.name", Expression::property("b", "name"))]),
+        })
+    );
+
+    assert!(cypher::query(
+        "MATCH (a) OPTIONAL MATCH (a) -> (b) OPTIONAL MATCH (b) -> (c) RETURN c.name"
+    )
+    .is_ok());
+    assert!(cypher::query("OPTIONAL MATCH (a) MATCH (a) -> (b) RETURN b.name").is_err());
+}
+
+#[test]
+fn with_clauses_work() {
+    assert_eq!(
+        cypher::query("MATCH (a) WITH a, a.name AS name WHERE name <> 'Peter' MATCH (a) -> (b) RETURN name, b.name"),
+        Ok(Query {
+            match_clauses: vec![MatchClause { patterns: vec![Pattern {
+                path: None,
+                shortest: None,
+                start: Node::with_annotation(Annotation::with_name("a")),
+                edges: vec![],
+            }] }],
+            where_clauses: vec![],
+            optional_match_clauses: vec![],
+            create_clauses: vec![],
+            merge_clauses: vec![],
+            set_clauses: vec![],
+            remove_clauses: vec![],
+            delete_clauses: vec![],
+            with_clause: Some(WithClause {
+                distinct: false,
+                items: vec![
+                    ("a", Expression::Variable("a")),
+                    ("name", Expression::property("a", "name")),
+                ],
+                where_clause: Some(Condition::Ne(
+                    Expression::Variable("name"),
+                    Expression::Literal(Literal::Text("Peter")),
+                )),
+                query: Box::new(Query {
+                    match_clauses: vec![MatchClause { patterns: vec![Pattern {
+                        path: None,
+                        shortest: None,
+                        start: Node::with_annotation(Annotation::with_name("a")),
+                        edges: vec![(
+                            Edge::right(Annotation::empty(), vec![]),
+                            Node::with_annotation(Annotation::with_name("b"))
+                        )],
+                    }] }],
+                    where_clauses: vec![],
+                    optional_match_clauses: vec![],
+                    create_clauses: vec![],
+                    merge_clauses: vec![],
+                    set_clauses: vec![],
+                    remove_clauses: vec![],
+                    delete_clauses: vec![],
+                    with_clause: None,
+                    unwind_clause: None,
+                    return_clause: Projection::new(vec![
+                        ("name", Expression::Variable("name")),
+                        ("b.name", Expression::property("b", "name")),
+                    ]),
+                }),
+            }),
+            unwind_clause: None,
+            return_clause: Projection::new(vec![]),
+        })
+    );
+
+    assert_eq!(
+        cypher::query("CREATE (a:PERSON) WITH a AS b, 42 AS answer MATCH (c) RETURN answer"),
+        Ok(Query {
+            match_clauses: vec![],
+            where_clauses: vec![],
+            optional_match_clauses: vec![],
+            create_clauses: vec![CreateClause::CreateNode {
+                name: Some("a"),
+                labels: vec!["PERSON"],
+                properties: vec![],
+            }],
+            merge_clauses: vec![],
+            set_clauses: vec![],
+            remove_clauses: vec![],
+            delete_clauses: vec![],
+            with_clause: Some(WithClause {
+                distinct: false,
+                items: vec![
+                    ("b", Expression::Variable("a")),
+                    ("answer", Expression::Literal(Literal::Integer(42))),
+                ],
+                where_clause: None,
+                query: Box::new(Query {
+                    match_clauses: vec![MatchClause {
+                        patterns: vec![Pattern {
+                            path: None,
+                            shortest: None,
+                            start: Node::with_annotation(Annotation::with_name("c")),
+                            edges: vec![],
+                        }]
+                    }],
+                    where_clauses: vec![],
+                    optional_match_clauses: vec![],
+                    create_clauses: vec![],
+                    merge_clauses: vec![],
+                    set_clauses: vec![],
+                    remove_clauses: vec![],
+                    delete_clauses: vec![],
+                    with_clause: None,
+                    unwind_clause: None,
+                    return_clause: Projection::new(vec![(
+                        "answer",
+                        Expression::Variable("answer")
+                    )]),
+                }),
+            }),
+            unwind_clause: None,
+            return_clause: Projection::new(vec![]),
+        })
+    );
+
+    assert!(cypher::query("MATCH (a) WITH a.name RETURN a").is_err());
+    assert!(cypher::query("MATCH (a) WITH count(*) AS c RETURN c").is_err());
+    assert!(cypher::query("MATCH (a) WITH RETURN a").is_err());
+    assert!(cypher::query("MATCH (a) WITH a.seen AS seen WHERE NOT seen RETURN a").is_ok());
+}
+
+#[test]
+fn variable_length_edges_work() {
+    let length = |src| -> Result<Option<Length>, _> {
+        cypher::query(src).map(|query| query.match_clauses[0].patterns[0].edges[0].0.length)
+    };
+
+    assert_eq!(
+        cypher::query("MATCH (a) -[r:KNOWS*1..3]-> (b) RETURN b"),
+        Ok(Query {
+            match_clauses: vec![MatchClause {
+                patterns: vec![Pattern {
+                    path: None,
+                    shortest: None,
+                    start: Node::with_annotation(Annotation::with_name("a")),
+                    edges: vec![(
+                        Edge {
+                            length: Some(Length {
+                                min: 1,
+                                max: Some(3)
+                            }),
+                            ..Edge::right(Annotation::new("r", "KNOWS"), vec![])
+                        },
+                        Node::with_annotation(Annotation::with_name("b"))
+                    )],
+                }]
+            }],
+            where_clauses: vec![],
+            optional_match_clauses: vec![],
+            create_clauses: vec![],
+            merge_clauses: vec![],
+            set_clauses: vec![],
+            remove_clauses: vec![],
+            delete_clauses: vec![],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection::new(vec![("b", Expression::Variable("b"))]),
+        })
+    );
+
+    assert_eq!(
+        length("MATCH (a) -[*]- (b) RETURN b"),
+        Ok(Some(Length { min: 1, max: None }))
+    );
+    assert_eq!(
+        length("MATCH (a) <-[*2]- (b) RETURN b"),
+        Ok(Some(Length {
+            min: 2,
+            max: Some(2)
+        }))
+    );
+    assert_eq!(
+        length("MATCH (a) -[:KNOWS *..2 { since: 2010 }]-> (b) RETURN b"),
+        Ok(Some(Length {
+            min: 1,
+            max: Some(2)
+        }))
+    );
+    assert_eq!(
+        length("MATCH (a) -[*0..]-> (b) RETURN b"),
+        Ok(Some(Length { min: 0, max: None }))
+    );
+    assert_eq!(length("MATCH (a) -[r]-> (b) RETURN b"), Ok(None));
+
+    assert!(cypher::query("MATCH (a) -[*3..1]-> (b) RETURN b").is_err());
+    assert!(cypher::query("MATCH (a) -[*-1]-> (b) RETURN b").is_err());
+    assert!(cypher::query("CREATE (a:A) CREATE (a) -[:KNOWS*2]-> (a)").is_err());
+}
+
+#[test]
+fn named_paths_work() {
+    assert_eq!(
+        cypher::query("MATCH p = (a) -[*]-> (b) RETURN nodes(p), relationships(p), length(p), p"),
+        Ok(Query {
+            match_clauses: vec![MatchClause {
+                patterns: vec![Pattern {
+                    path: Some("p"),
+                    shortest: None,
+                    start: Node::with_annotation(Annotation::with_name("a")),
+                    edges: vec![(
+                        Edge {
+                            length: Some(Length { min: 1, max: None }),
+                            ..Edge::right(Annotation::empty(), vec![])
+                        },
+                        Node::with_annotation(Annotation::with_name("b"))
+                    )],
+                }]
+            }],
+            where_clauses: vec![],
+            optional_match_clauses: vec![],
+            create_clauses: vec![],
+            merge_clauses: vec![],
+            set_clauses: vec![],
+            remove_clauses: vec![],
+            delete_clauses: vec![],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection::new(vec![
+                ("nodes(p)", Expression::NodesOf { name: "p" }),
+                (
+                    "relationships(p)",
+                    Expression::RelationshipsOf { name: "p" }
+                ),
+                ("length(p)", Expression::LengthOf { name: "p" }),
+                ("p", Expression::Variable("p")),
+            ]),
+        })
+    );
+
+    assert_eq!(
+        cypher::query("MATCH (a) OPTIONAL MATCH path=(a) -> (b) RETURN LENGTH(path)")
+            .map(|query| query.optional_match_clauses[0].pattern.patterns[0].path),
+        Ok(Some("path"))
+    );
+    assert!(cypher::query("MATCH p = RETURN p").is_err());
+    assert!(cypher::query("MATCH (a) = (b) RETURN a").is_err());
+}
+
+#[test]
+fn shortest_paths_work() {
+    assert_eq!(
+        cypher::query("MATCH p = shortestPath((a) -[:NEXT*]- (b)) RETURN p"),
+        Ok(Query {
+            match_clauses: vec![MatchClause {
+                patterns: vec![Pattern {
+                    path: Some("p"),
+                    shortest: Some(Shortest::One),
+                    start: Node::with_annotation(Annotation::with_name("a")),
+                    edges: vec![(
+                        Edge {
+                            length: Some(Length { min: 1, max: None }),
+                            ..Edge::either(Annotation::with_label("NEXT"), vec![])
+                        },
+                        Node::with_annotation(Annotation::with_name("b"))
+                    )],
+                }]
+            }],
+            where_clauses: vec![],
+            optional_match_clauses: vec![],
+            create_clauses: vec![],
+            merge_clauses: vec![],
+            set_clauses: vec![],
+            remove_clauses: vec![],
+            delete_clauses: vec![],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: Projection::new(vec![("p", Expression::Variable("p"))]),
+        })
+    );
+
+    assert_eq!(
+        cypher::query("MATCH allShortestPaths( (a) -[*0..5]-> (b) ) RETURN a")
+            .map(|query| query.match_clauses[0].patterns[0].shortest),
+        Ok(Some(Shortest::All))
+    );
+    assert!(cypher::query("MATCH shortestPath((a) -[*2..]-> (b)) RETURN a").is_err());
+    assert!(cypher::query("MATCH shortestPath((a) -[]-> (b)) RETURN a").is_err());
+    assert!(cypher::query("MATCH shortestPath((a) -> (b) -[*]-> (c)) RETURN a").is_err());
+}
+
+#[test]
+fn list_and_map_literals_work() {
+    assert_eq!(
+        cypher::query("MATCH (a) RETURN [1, a.name, $p], [], { name: a.name, tags: [ 'x' ] }, {}")
+            .map(|query| query.return_clause),
+        Ok(Projection::new(vec![
+            (
+                "[1, a.name, $p]",
+                Expression::List(vec![
+                    Expression::Literal(Literal::Integer(1)),
+                    Expression::property("a", "name"),
+                    Expression::Parameter("p"),
+                ])
+            ),
+            ("[]", Expression::List(vec![])),
+            (
+                "{ name: a.name, tags: [ 'x' ] }",
+                Expression::Map(vec![
+                    ("name", Expression::property("a", "name")),
+                    (
+                        "tags",
+                        Expression::List(vec![Expression::Literal(Literal::Text("x"))])
+                    ),
+                ])
+            ),
+            ("{}", Expression::Map(vec![])),
+        ]))
+    );
+
+    assert_eq!(
+        cypher::query("CREATE (a:PERSON { nicknames: ['Al'], address: { city: 'Bern' } })")
+            .map(|query| query.create_clauses),
+        Ok(vec![CreateClause::CreateNode {
+            name: Some("a"),
+            labels: vec!["PERSON"],
+            properties: vec![
+                (
+                    "nicknames",
+                    Expression::List(vec![Expression::Literal(Literal::Text("Al"))])
+                ),
+                (
+                    "address",
+                    Expression::Map(vec![("city", Expression::Literal(Literal::Text("Bern")))])
+                ),
+            ],
+        }])
+    );
+    assert!(cypher::query("MATCH (a) RETURN [1, 2").is_err());
+    assert!(cypher::query("MATCH (a) RETURN { name }").is_err());
+}
+
+#[test]
+fn temporal_and_binary_expressions_work() {
+    assert_eq!(
+        cypher::query(
+            "MATCH (e) WHERE e.at > datetime() - duration('P1D') RETURN date(), e.at + $offset - e.delay"
+        )
+        .map(|query| (query.where_clauses, query.return_clause)),
+        Ok((
+            vec![Condition::Gt(
+                Expression::property("e", "at"),
+                Expression::binary(
+                    BinaryOp::Sub,
+                    Expression::temporal(Temporal::DateTime, None),
+                    Expression::temporal(
+                        Temporal::Duration,
+                        Some(Expression::Literal(Literal::Text("P1D")))
+                    ),
+                ),
+            )],
+            Projection::new(vec![
+                ("date()", Expression::temporal(Temporal::Date, None)),
+                ("e.at + $offset - e.delay", Expression::binary(
+                    BinaryOp::Sub,
+                    Expression::binary(
+                        BinaryOp::Add,
+                        Expression::property("e", "at"),
+                        Expression::Parameter("offset"),
+                    ),
+                    Expression::property("e", "delay"),
+                )),
+            ]),
+        ))
+    );
+
+    assert_eq!(
+        cypher::query("MATCH (e) RETURN e.count-1, DATE({ year: 2024 })")
+            .map(|query| query.return_clause),
+        Ok(Projection::new(vec![
+            (
+                "e.count-1",
+                Expression::binary(
+                    BinaryOp::Sub,
+                    Expression::property("e", "count"),
+                    Expression::Literal(Literal::Integer(1)),
+                )
+            ),
+            (
+                "DATE({ year: 2024 })",
+                Expression::temporal(
+                    Temporal::Date,
+                    Some(Expression::Map(vec![(
+                        "year",
+                        Expression::Literal(Literal::Integer(2024))
+                    )]))
+                )
+            ),
+        ]))
+    );
+    assert!(cypher::query("MATCH (e) RETURN e.at +").is_err());
+    assert!(cypher::query("MATCH (e) RETURN duration(").is_err());
+}
+
+#[test]
+fn arithmetic_precedence_works() {
+    let int = |i| Expression::Literal(Literal::Integer(i));
+    assert_eq!(
+        cypher::query("RETURN 1 + 2 * 3 ^ 2 - 4 / 2 % 3").map(|query| query.return_clause),
+        Ok(Projection::new(vec![(
+            "1 + 2 * 3 ^ 2 - 4 / 2 % 3",
+            Expression::binary(
+                BinaryOp::Sub,
+                Expression::binary(
+                    BinaryOp::Add,
+                    int(1),
+                    Expression::binary(
+                        BinaryOp::Mul,
+                        int(2),
+                        Expression::binary(BinaryOp::Pow, int(3), int(2)),
+                    ),
+                ),
+                Expression::binary(
+                    BinaryOp::Mod,
+                    Expression::binary(BinaryOp::Div, int(4), int(2)),
+                    int(3),
+                ),
+            )
+        )]))
+    );
+
+    assert_eq!(
+        cypher::query("MATCH (a) WHERE (a.x + 1) * 2 = a.y RETURN a.first + ' ' + a.last")
+            .map(|query| (query.where_clauses, query.return_clause)),
+        Ok((
+            vec![Condition::Eq(
+                Expression::binary(
+                    BinaryOp::Mul,
+                    Expression::binary(BinaryOp::Add, Expression::property("a", "x"), int(1)),
+                    int(2),
+                ),
+                Expression::property("a", "y"),
+            )],
+            Projection::new(vec![(
+                "a.first + ' ' + a.last",
+                Expression::binary(
+                    BinaryOp::Add,
+                    Expression::binary(
+                        BinaryOp::Add,
+                        Expression::property("a", "first"),
+                        Expression::Literal(Literal::Text(" ")),
+                    ),
+                    Expression::property("a", "last"),
+                )
+            )]),
+        ))
+    );
+
+    // parenthesized conditions still work
+    assert!(cypher::query("MATCH (a) WHERE (a.x = 1 OR a.x = 2) AND (a.y) RETURN a").is_ok());
+    assert!(cypher::query("RETURN (1 + 2").is_err());
+    assert!(cypher::query("RETURN 1 * ").is_err());
+}
+
+#[test]
+fn string_predicates_work() {
+    assert_eq!(
+        cypher::query(
+            "MATCH (n) WHERE n.name STARTS WITH 'Al' AND n.name ENDS WITH $suffix RETURN n"
+        )
+        .map(|query| query.where_clauses),
+        Ok(vec![Condition::And(
+            Box::new(Condition::StartsWith(
+                Expression::property("n", "name"),
+                Expression::Literal(Literal::Text("Al")),
+            )),
+            Box::new(Condition::EndsWith(
+                Expression::property("n", "name"),
+                Expression::Parameter("suffix"),
+            )),
+        )])
+    );
+    assert_eq!(
+        cypher::query("MATCH (n) WHERE n.name contains 'ic' OR NOT n.name =~ 'A.*e' RETURN n")
+            .map(|query| query.where_clauses),
+        Ok(vec![Condition::Or(
+            Box::new(Condition::Contains(
+                Expression::property("n", "name"),
+                Expression::Literal(Literal::Text("ic")),
+            )),
+            Box::new(Condition::Not(Box::new(Condition::Regex(
+                Expression::property("n", "name"),
+                Expression::Literal(Literal::Text("A.*e")),
+            )))),
+        )])
+    );
+    assert!(cypher::query("MATCH (n) WHERE n.name STARTS 'A' RETURN n").is_err());
+    assert!(cypher::query("MATCH (n) WHERE n.name =~ RETURN n").is_err());
+}
+
+#[test]
+fn in_and_null_predicates_work() {
+    assert_eq!(
+        cypher::query("MATCH (n) WHERE ID(n) IN $ids AND n.age in [1, 2] RETURN n")
+            .map(|query| query.where_clauses),
+        Ok(vec![Condition::And(
+            Box::new(Condition::In(
+                Expression::IdOf { name: "n" },
+                Expression::Parameter("ids"),
+            )),
+            Box::new(Condition::In(
+                Expression::property("n", "age"),
+                Expression::List(vec![
+                    Expression::Literal(Literal::Integer(1)),
+                    Expression::Literal(Literal::Integer(2)),
+                ]),
+            )),
+        )])
+    );
+    assert_eq!(
+        cypher::query("MATCH (n) WHERE n.name IS NULL OR n.age is not null RETURN n")
+            .map(|query| query.where_clauses),
+        Ok(vec![Condition::Or(
+            Box::new(Condition::IsNull(Expression::property("n", "name"))),
+            Box::new(Condition::IsNotNull(Expression::property("n", "age"))),
+        )])
+    );
+    assert!(cypher::query("MATCH (n) WHERE n.name IS RETURN n").is_err());
+    assert!(cypher::query("MATCH (n) WHERE n.name IN RETURN n").is_err());
+}
+
+#[test]
+fn case_and_coalesce_work() {
+    let int = |i| Expression::Literal(Literal::Integer(i));
+    assert_eq!(
+        cypher::query(
+            "MATCH (n) RETURN CASE WHEN n.age < 18 THEN 'minor' WHEN n.age IS NULL THEN 'unknown' ELSE 'adult' END"
+        )
+        .map(|query| query.return_clause),
+        Ok(Projection::new(vec![(
+            "CASE WHEN n.age < 18 THEN 'minor' WHEN n.age IS NULL THEN 'unknown' ELSE 'adult' END",
+            Expression::Case {
+                branches: vec![
+                    (
+                        Condition::Lt(Expression::property("n", "age"), int(18)),
+                        Expression::Literal(Literal::Text("minor")),
+                    ),
+                    (
+                        Condition::IsNull(Expression::property("n", "age")),
+                        Expression::Literal(Literal::Text("unknown")),
+                    ),
+                ],
+                default: Some(Box::new(Expression::Literal(Literal::Text("adult")))),
+            }
+        )]))
+    );
+    assert_eq!(
+        cypher::query("MATCH (n) RETURN case n.rank when 1 then 'gold' when 2 then 'silver' end")
+            .map(|query| query.return_clause),
+        Ok(Projection::new(vec![(
+            "case n.rank when 1 then 'gold' when 2 then 'silver' end",
+            Expression::SimpleCase {
+                value: Box::new(Expression::property("n", "rank")),
+                branches: vec![
+                    (int(1), Expression::Literal(Literal::Text("gold"))),
+                    (int(2), Expression::Literal(Literal::Text("silver"))),
+                ],
+                default: None,
+            }
+        )]))
+    );
+    assert_eq!(
+        cypher::query("MATCH (n) RETURN coalesce(n.nick, n.name, 'anonymous') + '!'")
+            .map(|query| query.return_clause),
+        Ok(Projection::new(vec![(
+            "coalesce(n.nick, n.name, 'anonymous') + '!'",
+            Expression::binary(
+                BinaryOp::Add,
+                Expression::Coalesce(vec![
+                    Expression::property("n", "nick"),
+                    Expression::property("n", "name"),
+                    Expression::Literal(Literal::Text("anonymous")),
+                ]),
+                Expression::Literal(Literal::Text("!")),
+            )
+        )]))
+    );
+    assert!(cypher::query("RETURN CASE ELSE 1 END").is_err());
+    assert!(cypher::query("RETURN CASE WHEN true THEN 1").is_err());
+    assert!(cypher::query("RETURN coalesce()").is_err());
+}
+
+#[test]
+fn function_calls_work() {
+    assert_eq!(
+        cypher::query(
+            "MATCH (n) RETURN toUpper(n.name), substring( 'hello' , 1, size(n.name) - 1 ), rand()"
+        )
+        .map(|query| query.return_clause),
+        Ok(Projection::new(vec![
+            (
+                "toUpper(n.name)",
+                Expression::Function {
+                    name: "toUpper",
+                    arguments: vec![Expression::property("n", "name")],
+                }
+            ),
+            (
+                "substring( 'hello' , 1, size(n.name) - 1 )",
+                Expression::Function {
+                    name: "substring",
+                    arguments: vec![
+                        Expression::Literal(Literal::Text("hello")),
+                        Expression::Literal(Literal::Integer(1)),
+                        Expression::binary(
+                            BinaryOp::Sub,
+                            Expression::Function {
+                                name: "size",
+                                arguments: vec![Expression::property("n", "name")],
+                            },
+                            Expression::Literal(Literal::Integer(1)),
+                        ),
+                    ],
+                }
+            ),
+            (
+                "rand()",
+                Expression::Function {
+                    name: "rand",
+                    arguments: vec![],
+                }
+            ),
+        ]))
+    );
+    assert_eq!(
+        cypher::query("MATCH (n) -[e]-> (m) WHERE type(e) = 'KNOWS' RETURN n")
+            .map(|query| query.where_clauses),
+        Ok(vec![Condition::Eq(
+            Expression::Function {
+                name: "type",
+                arguments: vec![Expression::Variable("e")],
+            },
+            Expression::Literal(Literal::Text("KNOWS")),
+        )])
+    );
+    assert!(cypher::query("RETURN toUpper('a'").is_err());
+    assert!(cypher::query("RETURN toUpper('a',)").is_err());
+}
+
+#[test]
+fn return_aliases_work() {
+    assert_eq!(
+        cypher::query("MATCH (a) RETURN a.name AS name, count(*)  AS total, ID(a)")
+            .map(|query| query.return_clause),
+        Ok(Projection::new(vec![
+            ("name", Expression::property("a", "name")),
+            ("total", Expression::count_all()),
+            ("ID(a)", Expression::id_of("a")),
+        ]))
+    );
+    assert_eq!(
+        cypher::query("MATCH (a) RETURN a.name as name ORDER BY name DESC")
+            .map(|query| query.return_clause),
+        Ok(Projection {
+            distinct: false,
+            items: vec![("name", Expression::property("a", "name"))],
+            order_by: vec![OrderBy {
+                expression: Expression::Variable("name"),
+                descending: true,
+            }],
+            skip: None,
+            limit: None,
+        })
+    );
+    assert!(cypher::query("MATCH (a) RETURN a.name AS").is_err());
+    assert!(cypher::query("MATCH (a) RETURN a.name AS 'name'").is_err());
+}
+
+#[test]
+fn distinct_projections_work() {
+    assert_eq!(
+        cypher::query("MATCH (a) RETURN DISTINCT a.name").map(|query| query.return_clause),
+        Ok(Projection {
+            distinct: true,
+            ..Projection::new(vec![("a.name", Expression::property("a", "name"))])
+        })
+    );
+    assert_eq!(
+        cypher::query("MATCH (a) RETURN distinct").map(|query| query.return_clause),
+        Ok(Projection::new(vec![(
+            "distinct",
+            Expression::Variable("distinct")
+        )]))
+    );
+    assert_eq!(
+        cypher::query("MATCH (a) WITH DISTINCT a.city AS city RETURN city")
+            .map(|query| query.with_clause.unwrap().distinct),
+        Ok(true)
+    );
+}
+
+#[test]
+fn unwind_clauses_work() {
+    assert_eq!(
+        cypher::query("UNWIND $names AS name CREATE (:PERSON { name: name })"),
+        Ok(Query {
+            match_clauses: vec![],
+            where_clauses: vec![],
+            optional_match_clauses: vec![],
+            create_clauses: vec![],
+            merge_clauses: vec![],
+            set_clauses: vec![],
+            remove_clauses: vec![],
+            delete_clauses: vec![],
+            with_clause: None,
+            unwind_clause: Some(UnwindClause {
+                expression: Expression::Parameter("names"),
+                name: "name",
+                query: Box::new(Query {
+                    match_clauses: vec![],
+                    where_clauses: vec![],
+                    optional_match_clauses: vec![],
+                    create_clauses: vec![CreateClause::CreateNode {
+                        name: None,
+                        labels: vec!["PERSON"],
+                        properties: vec![("name", Expression::Variable("name"))],
+                    }],
+                    merge_clauses: vec![],
+                    set_clauses: vec![],
+                    remove_clauses: vec![],
+                    delete_clauses: vec![],
+                    with_clause: None,
+                    unwind_clause: None,
+                    return_clause: Projection::new(vec![]),
+                }),
+            }),
+            return_clause: Projection::new(vec![]),
+        })
+    );
+
+    assert!(cypher::query("MATCH (a) UNWIND [1, 2] AS x RETURN a, x").is_ok());
+    assert!(cypher::query("UNWIND $ids AS id MATCH (a) WHERE ID(a) = id RETURN a").is_ok());
+    assert!(cypher::query("UNWIND $names RETURN name").is_err());
+    assert!(cypher::query("UNWIND $names AS RETURN name").is_err());
+}
+
+#[test]
+fn union_statements_work() {
+    assert_eq!(
+        cypher::statement("MATCH (a) RETURN a.name AS name UNION MATCH (b) RETURN b.title AS name"),
+        Ok(Statement {
+            queries: vec![
+                cypher::query("MATCH (a) RETURN a.name AS name").unwrap(),
+                cypher::query("MATCH (b) RETURN b.title AS name").unwrap(),
+            ],
+            all: false,
+        })
+    );
+    assert_eq!(
+        cypher::statement("RETURN 1 AS x UNION ALL RETURN 2 AS x union all RETURN 3 AS x")
+            .map(|statement| (statement.queries.len(), statement.all)),
+        Ok((3, true))
+    );
+    assert_eq!(
+        cypher::statement("MATCH (a) RETURN a").map(|statement| statement.queries.len()),
+        Ok(1)
+    );
+    assert!(
+        cypher::statement("RETURN 1 AS x UNION RETURN 2 AS x UNION ALL RETURN 3 AS x").is_err()
+    );
+    assert!(cypher::statement("RETURN 1 AS x UNIONRETURN 2 AS x").is_err());
+}
+
+#[test]
+fn multiple_labels_work() {
+    assert_eq!(
+        cypher::query("MATCH (a:PERSON:ADMIN) - (b : PERSON | ADMIN) RETURN a")
+            .map(|query| query.match_clauses),
+        Ok(vec![MatchClause {
+            patterns: vec![Pattern {
+                path: None,
+                shortest: None,
+                start: Node::with_annotation(Annotation {
+                    name: Some("a"),
+                    labels: Labels::All(vec!["PERSON", "ADMIN"]),
+                }),
+                edges: vec![(
+                    Edge::either(Annotation::empty(), vec![]),
+                    Node::with_annotation(Annotation {
+                        name: Some("b"),
+                        labels: Labels::Any(vec!["PERSON", "ADMIN"]),
+                    })
+                )],
+            }]
+        }])
+    );
+    assert_eq!(
+        cypher::query("MATCH (a:A|:B) RETURN a").map(|query| query.match_clauses[0].patterns[0]
+            .start
+            .annotation
+            .labels
+            .clone()),
+        Ok(Labels::Any(vec!["A", "B"]))
+    );
+    assert_eq!(
+        cypher::query("CREATE (a:PERSON:ADMIN)").map(|query| query.create_clauses),
+        Ok(vec![CreateClause::CreateNode {
+            name: Some("a"),
+            labels: vec!["PERSON", "ADMIN"],
+            properties: vec![],
+        }])
+    );
+    assert_eq!(
+        cypher::query("MATCH (a) SET a:ADMIN:OWNER REMOVE a:PERSON")
+            .map(|query| (query.set_clauses, query.remove_clauses)),
+        Ok((
+            vec![SetClause::Labels {
+                name: "a",
+                labels: vec!["ADMIN", "OWNER"],
+            }],
+            vec![RemoveClause::Labels {
+                name: "a",
+                labels: vec!["PERSON"],
+            }],
+        ))
+    );
+
+    assert!(cypher::query("CREATE (a:PERSON|ADMIN)").is_err());
+    assert!(cypher::query("MATCH (a) -[:KNOWS:LIKES]-> (b) RETURN a").is_err());
+    assert!(cypher::query("MATCH (a:A|B:C) RETURN a").is_err());
+}
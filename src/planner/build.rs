@@ -1,25 +1,46 @@
-use super::plan::{Filter, LoadProperty, MatchStep, QueryPlan, UpdateStep};
+use super::plan::{
+    Filter, LoadProperty, MatchStep, PathSegment, QueryPart, QueryPlan, SortKey, UpdateStep,
+};
 use crate::parser::ast;
+use crate::runtime::Functions;
 use crate::store::PropRef;
 use crate::Error;
 use std::collections::HashMap;
 
-pub(crate) struct BuildEnv<'src> {
+pub(crate) struct BuildEnv<'src, 'f> {
     names: HashMap<&'src str, NamedEntity>,
     next_name: usize,
+    functions: &'f Functions,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum NamedEntity {
     Node(usize),
     Edge(usize),
+    Edges(usize),
+    Path(usize),
+    Value(usize),
 }
 
-impl<'src> BuildEnv<'src> {
-    fn new() -> Self {
+impl NamedEntity {
+    /// The value of a variable bound to this entity.
+    fn load<'src>(&self) -> LoadProperty<'src> {
+        match *self {
+            NamedEntity::Node(node) => LoadProperty::IdOfNode { node },
+            NamedEntity::Edge(edge) => LoadProperty::IdOfEdge { edge },
+            NamedEntity::Edges(path) => LoadProperty::EdgesOfPath { path },
+            NamedEntity::Path(path) => LoadProperty::Path { path },
+            NamedEntity::Value(name) => LoadProperty::Value { name },
+        }
+    }
+}
+
+impl<'src, 'f> BuildEnv<'src, 'f> {
+    fn new(functions: &'f Functions) -> Self {
         Self {
             names: HashMap::new(),
             next_name: 0,
+            functions,
         }
     }
 
@@ -31,14 +52,24 @@ impl<'src> BuildEnv<'src> {
     fn get_node(&self, name: &str) -> Result<Option<usize>, Error> {
         match self.names.get(&name) {
             Some(NamedEntity::Node(name)) => Ok(Some(*name)),
-            Some(NamedEntity::Edge(_)) => Err(Error::IdentifierIsNotNode(name.to_string())),
+            Some(
+                NamedEntity::Edge(_)
+                | NamedEntity::Edges(_)
+                | NamedEntity::Path(_)
+                | NamedEntity::Value(_),
+            ) => Err(Error::IdentifierIsNotNode(name.to_string())),
             None => Ok(None),
         }
     }
 
     fn get_edge(&self, name: &str) -> Result<Option<usize>, Error> {
         match self.names.get(&name) {
-            Some(NamedEntity::Node(_)) => Err(Error::IdentifierIsNotEdge(name.to_string())),
+            Some(
+                NamedEntity::Node(_)
+                | NamedEntity::Edges(_)
+                | NamedEntity::Path(_)
+                | NamedEntity::Value(_),
+            ) => Err(Error::IdentifierIsNotEdge(name.to_string())),
             Some(NamedEntity::Edge(name)) => Ok(Some(*name)),
             None => Ok(None),
         }
@@ -46,9 +77,7 @@ impl<'src> BuildEnv<'src> {
 
     fn create_node(&mut self, name: &'src str) -> Result<usize, Error> {
         match self.names.get(&name) {
-            Some(NamedEntity::Node(_)) | Some(NamedEntity::Edge(_)) => {
-                Err(Error::IdentifierExists(name.to_string()))
-            }
+            Some(_) => Err(Error::IdentifierExists(name.to_string())),
             None => {
                 let next_name = self.next_name();
                 self.names.insert(name, NamedEntity::Node(next_name));
@@ -59,9 +88,7 @@ impl<'src> BuildEnv<'src> {
 
     fn create_edge(&mut self, name: &'src str) -> Result<usize, Error> {
         match self.names.get(&name) {
-            Some(NamedEntity::Node(_)) | Some(NamedEntity::Edge(_)) => {
-                Err(Error::IdentifierExists(name.to_string()))
-            }
+            Some(_) => Err(Error::IdentifierExists(name.to_string())),
             None => {
                 let next_name = self.next_name();
                 self.names.insert(name, NamedEntity::Edge(next_name));
@@ -70,6 +97,29 @@ impl<'src> BuildEnv<'src> {
         }
     }
 
+    fn get_path(&self, name: &str) -> Result<usize, Error> {
+        match self.names.get(&name) {
+            Some(NamedEntity::Path(path)) => Ok(*path),
+            Some(_) => Err(Error::TypeMismatch),
+            None => Err(Error::UnknownIdentifier(name.to_string())),
+        }
+    }
+
+    fn create_path(
+        &mut self,
+        name: &'src str,
+        entity: fn(usize) -> NamedEntity,
+    ) -> Result<usize, Error> {
+        match self.names.get(&name) {
+            Some(_) => Err(Error::IdentifierExists(name.to_string())),
+            None => {
+                let next_name = self.next_name();
+                self.names.insert(name, entity(next_name));
+                Ok(next_name)
+            }
+        }
+    }
+
     fn build_load_property(
         &mut self,
         expr: &'src ast::Expression<'src>,
@@ -83,6 +133,18 @@ impl<'src> BuildEnv<'src> {
                 ast::Literal::Text(t) => PropRef::Text(t),
                 ast::Literal::Null => PropRef::Null,
             }),
+            ast::Expression::List(items) => LoadProperty::List(
+                items
+                    .iter()
+                    .map(|item| self.build_load_property(item))
+                    .collect::<Result<_, _>>()?,
+            ),
+            ast::Expression::Map(entries) => LoadProperty::Map(
+                entries
+                    .iter()
+                    .map(|(key, value)| Ok((*key, self.build_load_property(value)?)))
+                    .collect::<Result<_, Error>>()?,
+            ),
             ast::Expression::IdOf { name } => match self
                 .names
                 .get(name)
@@ -90,6 +152,10 @@ impl<'src> BuildEnv<'src> {
             {
                 NamedEntity::Node(node) => LoadProperty::IdOfNode { node: *node },
                 NamedEntity::Edge(edge) => LoadProperty::IdOfEdge { edge: *edge },
+                NamedEntity::Edges(_) => return Err(Error::IdentifierIsNotEdge(name.to_string())),
+                NamedEntity::Path(_) | NamedEntity::Value(_) => {
+                    return Err(Error::IdentifierIsNotNode(name.to_string()))
+                }
             },
             ast::Expression::LabelOf { name } => match self
                 .names
@@ -98,6 +164,10 @@ impl<'src> BuildEnv<'src> {
             {
                 NamedEntity::Node(node) => LoadProperty::LabelOfNode { node: *node },
                 NamedEntity::Edge(edge) => LoadProperty::LabelOfEdge { edge: *edge },
+                NamedEntity::Edges(_) => return Err(Error::IdentifierIsNotEdge(name.to_string())),
+                NamedEntity::Path(_) | NamedEntity::Value(_) => {
+                    return Err(Error::IdentifierIsNotNode(name.to_string()))
+                }
             },
             ast::Expression::Property { name, key } => {
                 match self
@@ -107,8 +177,164 @@ impl<'src> BuildEnv<'src> {
                 {
                     NamedEntity::Node(node) => LoadProperty::PropertyOfNode { node: *node, key },
                     NamedEntity::Edge(edge) => LoadProperty::PropertyOfEdge { edge: *edge, key },
+                    NamedEntity::Edges(_) => {
+                        return Err(Error::IdentifierIsNotEdge(name.to_string()))
+                    }
+                    NamedEntity::Path(_) | NamedEntity::Value(_) => {
+                        return Err(Error::IdentifierIsNotNode(name.to_string()))
+                    }
+                }
+            }
+            ast::Expression::Variable(name) => self
+                .names
+                .get(name)
+                .ok_or_else(|| Error::UnknownIdentifier(name.to_string()))?
+                .load(),
+            ast::Expression::NodesOf { name } => LoadProperty::NodesOfPath {
+                path: self.get_path(name)?,
+            },
+            ast::Expression::RelationshipsOf { name } => LoadProperty::EdgesOfPath {
+                path: self.get_path(name)?,
+            },
+            ast::Expression::LengthOf { name } => LoadProperty::LengthOfPath {
+                path: self.get_path(name)?,
+            },
+            ast::Expression::Aggregate { function, argument } => LoadProperty::Aggregate {
+                function: *function,
+                value: argument
+                    .as_ref()
+                    .map(|arg| self.build_load_property(arg).map(Box::new))
+                    .transpose()?,
+            },
+            ast::Expression::Temporal { function, argument } => LoadProperty::Temporal {
+                function: *function,
+                value: argument
+                    .as_ref()
+                    .map(|arg| self.build_load_property(arg).map(Box::new))
+                    .transpose()?,
+            },
+            ast::Expression::Binary { op, lhs, rhs } => LoadProperty::Binary {
+                op: *op,
+                lhs: Box::new(self.build_load_property(lhs)?),
+                rhs: Box::new(self.build_load_property(rhs)?),
+            },
+            ast::Expression::SimpleCase {
+                value,
+                branches,
+                default,
+            } => {
+                let value = self.build_load_property(value)?;
+                LoadProperty::Case {
+                    branches: branches
+                        .iter()
+                        .map(|(when, then)| {
+                            Ok((
+                                Filter::Eq(value.clone(), self.build_load_property(when)?),
+                                self.build_load_property(then)?,
+                            ))
+                        })
+                        .collect::<Result<_, Error>>()?,
+                    default: default
+                        .as_ref()
+                        .map(|default| self.build_load_property(default).map(Box::new))
+                        .transpose()?,
                 }
             }
+            ast::Expression::Case { branches, default } => LoadProperty::Case {
+                branches: branches
+                    .iter()
+                    .map(|(when, then)| {
+                        Ok((self.build_filter(when)?, self.build_load_property(then)?))
+                    })
+                    .collect::<Result<_, Error>>()?,
+                default: default
+                    .as_ref()
+                    .map(|default| self.build_load_property(default).map(Box::new))
+                    .transpose()?,
+            },
+            ast::Expression::Coalesce(values) => LoadProperty::Coalesce(
+                values
+                    .iter()
+                    .map(|value| self.build_load_property(value))
+                    .collect::<Result<_, _>>()?,
+            ),
+            ast::Expression::Function { name, arguments } => {
+                self.build_function(name, arguments)?
+            }
+        };
+        Ok(load)
+    }
+
+    /// Like the built-in aggregates, user defined aggregates
+    /// are only allowed as returned values or sort keys.
+    fn build_projection(
+        &mut self,
+        expression: &'src ast::Expression<'src>,
+    ) -> Result<LoadProperty<'src>, Error> {
+        match expression {
+            ast::Expression::Function { name, arguments } if self.functions.is_aggregate(name) => {
+                Ok(LoadProperty::UserAggregate {
+                    name,
+                    arguments: arguments
+                        .iter()
+                        .map(|argument| self.build_load_property(argument))
+                        .collect::<Result<_, _>>()?,
+                })
+            }
+            _ => self.build_load_property(expression),
+        }
+    }
+
+    /// Functions which inspect a node or edge are resolved here,
+    /// all others are left to the runtime's built-in functions.
+    fn build_function(
+        &mut self,
+        name: &'src str,
+        arguments: &'src [ast::Expression<'src>],
+    ) -> Result<LoadProperty<'src>, Error> {
+        let (argument, entity) = match arguments {
+            [ast::Expression::Variable(argument)] => (*argument, self.names.get(argument).copied()),
+            _ => ("", None),
+        };
+        if self.functions.is_aggregate(name) {
+            return Err(Error::MisplacedAggregate(name.to_string()));
+        }
+        let load = match (name.to_ascii_lowercase().as_str(), entity) {
+            ("properties", Some(NamedEntity::Node(node))) => {
+                LoadProperty::PropertiesOfNode { node }
+            }
+            ("properties", Some(NamedEntity::Edge(edge))) => {
+                LoadProperty::PropertiesOfEdge { edge }
+            }
+            ("keys", Some(NamedEntity::Node(node))) => LoadProperty::Function {
+                name,
+                arguments: vec![LoadProperty::PropertiesOfNode { node }],
+            },
+            ("keys", Some(NamedEntity::Edge(edge))) => LoadProperty::Function {
+                name,
+                arguments: vec![LoadProperty::PropertiesOfEdge { edge }],
+            },
+            ("labels", Some(NamedEntity::Node(node))) => LoadProperty::LabelsOfNode { node },
+            ("type", Some(NamedEntity::Edge(edge))) => LoadProperty::LabelOfEdge { edge },
+            ("startnode", Some(NamedEntity::Edge(edge))) => LoadProperty::OriginOfEdge { edge },
+            ("endnode", Some(NamedEntity::Edge(edge))) => LoadProperty::TargetOfEdge { edge },
+            ("labels", Some(_)) => return Err(Error::IdentifierIsNotNode(argument.to_string())),
+            ("type" | "startnode" | "endnode", Some(_)) => {
+                return Err(Error::IdentifierIsNotEdge(argument.to_string()))
+            }
+            ("labels" | "type" | "startnode" | "endnode", None) => {
+                return Err(match arguments {
+                    [ast::Expression::Variable(name)] => Error::UnknownIdentifier(name.to_string()),
+                    _ => Error::TypeMismatch,
+                })
+            }
+            _ => LoadProperty::Function {
+                name,
+                arguments: arguments
+                    .iter()
+                    .map(|argument| self.build_load_property(argument))
+                    .collect::<Result<_, _>>()?,
+            },
         };
         Ok(load)
     }
@@ -137,6 +363,26 @@ impl<'src> BuildEnv<'src> {
                 Filter::Eq(self.build_load_property(a)?, self.build_load_property(b)?),
             ),
 
+            ast::Condition::StartsWith(a, b) => {
+                Filter::StartsWith(self.build_load_property(a)?, self.build_load_property(b)?)
+            }
+            ast::Condition::EndsWith(a, b) => {
+                Filter::EndsWith(self.build_load_property(a)?, self.build_load_property(b)?)
+            }
+            ast::Condition::Contains(a, b) => {
+                Filter::Contains(self.build_load_property(a)?, self.build_load_property(b)?)
+            }
+            ast::Condition::In(a, b) => {
+                Filter::In(self.build_load_property(a)?, self.build_load_property(b)?)
+            }
+            ast::Condition::IsNull(a) => Filter::IsNull(self.build_load_property(a)?),
+            ast::Condition::IsNotNull(a) => {
+                Filter::not(Filter::IsNull(self.build_load_property(a)?))
+            }
+            ast::Condition::Regex(a, b) => {
+                Filter::Matches(self.build_load_property(a)?, self.build_load_property(b)?)
+            }
+
             ast::Condition::Gt(a, b) => {
                 Filter::Gt(self.build_load_property(a)?, self.build_load_property(b)?)
             }
@@ -158,6 +404,10 @@ impl<'src> BuildEnv<'src> {
                     edge: *edge,
                     id: self.build_load_property(value)?,
                 },
+                NamedEntity::Edges(_) => return Err(Error::IdentifierIsNotEdge(name.to_string())),
+                NamedEntity::Path(_) | NamedEntity::Value(_) => {
+                    return Err(Error::IdentifierIsNotNode(name.to_string()))
+                }
             },
         };
         Ok(filter)
@@ -180,19 +430,92 @@ impl<'src> BuildEnv<'src> {
                         LoadProperty::PropertyOfEdge { edge, key },
                         self.build_load_property(value)?,
                     ),
+                    NamedEntity::Edges(_) | NamedEntity::Path(_) | NamedEntity::Value(_) => {
+                        return Err(Error::Internal)
+                    }
                 }))
             })
             .collect()
     }
 
+    /// The filters which check that `node` has `labels`.
+    fn build_label_filters(node: usize, labels: &ast::Labels<'src>) -> Vec<MatchStep<'src>> {
+        match labels {
+            ast::Labels::All(labels) => labels
+                .iter()
+                .map(|&label| MatchStep::Filter(Filter::NodeHasLabel { node, label }))
+                .collect(),
+            ast::Labels::Any(labels) => labels
+                .iter()
+                .map(|&label| Filter::NodeHasLabel { node, label })
+                .reduce(Filter::or)
+                .map(MatchStep::Filter)
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// Build the filters which ensure that the edge or path `entity`
+    /// shares no edge with those `matched` before it.
+    fn build_unique_edge_filters(
+        matched: &mut Vec<NamedEntity>,
+        entity: NamedEntity,
+    ) -> Vec<MatchStep<'src>> {
+        if matched.contains(&entity) {
+            return vec![];
+        }
+        let steps = matched
+            .iter()
+            .map(|other| {
+                MatchStep::Filter(match (entity, *other) {
+                    (NamedEntity::Edge(edge), NamedEntity::Edge(other)) => {
+                        Filter::not(Filter::EdgeHasId {
+                            edge,
+                            id: LoadProperty::IdOfEdge { edge: other },
+                        })
+                    }
+                    (NamedEntity::Edge(edge), NamedEntity::Edges(path))
+                    | (NamedEntity::Edges(path), NamedEntity::Edge(edge)) => {
+                        Filter::not(Filter::In(
+                            LoadProperty::IdOfEdge { edge },
+                            LoadProperty::EdgesOfPath { path },
+                        ))
+                    }
+                    (NamedEntity::Edges(path), NamedEntity::Edges(other)) => Filter::Disjoint(
+                        LoadProperty::EdgesOfPath { path },
+                        LoadProperty::EdgesOfPath { path: other },
+                    ),
+                    _ => unreachable!("only edges and paths are matched"),
+                })
+            })
+            .collect();
+        matched.push(entity);
+        steps
+    }
+
+    /// Build the steps for the patterns of a `MATCH`. No edge
+    /// is matched more than once across all of its patterns.
     fn build_match(
         &mut self,
         clause: &'src ast::MatchClause<'src>,
     ) -> Result<Vec<MatchStep<'src>>, Error> {
         let mut steps = vec![];
+        let mut matched = vec![];
+        for pattern in &clause.patterns {
+            steps.append(&mut self.build_pattern(pattern, &mut matched)?);
+        }
+        Ok(steps)
+    }
+
+    fn build_pattern(
+        &mut self,
+        pattern: &'src ast::Pattern<'src>,
+        matched: &mut Vec<NamedEntity>,
+    ) -> Result<Vec<MatchStep<'src>>, Error> {
+        let mut steps = vec![];
 
         // FIXME: this is an eyesore ...
-        let mut prev_node_name = if let Some(name) = clause.start.annotation.name {
+        let mut prev_node_name = if let Some(name) = pattern.start.annotation.name {
             if let Some(name) = self.get_node(name)? {
                 name
             } else {
@@ -206,44 +529,82 @@ impl<'src> BuildEnv<'src> {
             name
         };
 
-        if let Some(label) = clause.start.annotation.label {
-            steps.push(MatchStep::Filter(Filter::NodeHasLabel {
-                node: prev_node_name,
-                label,
-            }));
-        }
+        steps.append(&mut Self::build_label_filters(
+            prev_node_name,
+            &pattern.start.annotation.labels,
+        ));
 
         steps.append(&mut self.build_filters_from_property_map(
             NamedEntity::Node(prev_node_name),
-            clause.start.properties.as_ref(),
+            pattern.start.properties.as_ref(),
         )?);
 
-        for (edge, node) in &clause.edges {
-            let edge_name = if let Some(name) = edge.annotation.name {
-                if let Some(name) = self.get_edge(name)? {
-                    match edge.direction {
-                        ast::Direction::Left => steps.push(MatchStep::Filter(Filter::IsTarget {
-                            node: prev_node_name,
-                            edge: name,
-                        })),
-                        ast::Direction::Right => steps.push(MatchStep::Filter(Filter::IsOrigin {
-                            node: prev_node_name,
-                            edge: name,
-                        })),
-                        ast::Direction::Either => steps.push(MatchStep::Filter(Filter::or(
-                            Filter::IsOrigin {
+        let start = prev_node_name;
+        let mut segments = vec![];
+        for (edge, node) in &pattern.edges {
+            if let Some(length) = edge.length {
+                let (path, end) = self.build_path(
+                    &mut steps,
+                    prev_node_name,
+                    edge,
+                    node,
+                    length,
+                    pattern.shortest,
+                )?;
+                steps.append(&mut Self::build_unique_edge_filters(
+                    matched,
+                    NamedEntity::Edges(path),
+                ));
+                segments.push(PathSegment::Path { path });
+                prev_node_name = end;
+            } else {
+                let edge_name = if let Some(name) = edge.annotation.name {
+                    if let Some(name) = self.get_edge(name)? {
+                        match edge.direction {
+                            ast::Direction::Left => {
+                                steps.push(MatchStep::Filter(Filter::IsTarget {
+                                    node: prev_node_name,
+                                    edge: name,
+                                }))
+                            }
+                            ast::Direction::Right => {
+                                steps.push(MatchStep::Filter(Filter::IsOrigin {
+                                    node: prev_node_name,
+                                    edge: name,
+                                }))
+                            }
+                            ast::Direction::Either => steps.push(MatchStep::Filter(Filter::or(
+                                Filter::IsOrigin {
+                                    node: prev_node_name,
+                                    edge: name,
+                                },
+                                Filter::IsTarget {
+                                    node: prev_node_name,
+                                    edge: name,
+                                },
+                            ))),
+                        }
+                        name
+                    } else {
+                        let name = self.create_edge(name)?;
+                        match edge.direction {
+                            ast::Direction::Left => steps.push(MatchStep::LoadTargetEdge {
+                                name,
+                                node: prev_node_name,
+                            }),
+                            ast::Direction::Right => steps.push(MatchStep::LoadOriginEdge {
+                                name,
                                 node: prev_node_name,
-                                edge: name,
-                            },
-                            Filter::IsTarget {
+                            }),
+                            ast::Direction::Either => steps.push(MatchStep::LoadEitherEdge {
+                                name,
                                 node: prev_node_name,
-                                edge: name,
-                            },
-                        ))),
+                            }),
+                        }
+                        name
                     }
-                    name
                 } else {
-                    let name = self.create_edge(name)?;
+                    let name = self.next_name();
                     match edge.direction {
                         ast::Direction::Left => steps.push(MatchStep::LoadTargetEdge {
                             name,
@@ -259,75 +620,85 @@ impl<'src> BuildEnv<'src> {
                         }),
                     }
                     name
+                };
+
+                if let Some(label) = edge.annotation.label() {
+                    steps.push(MatchStep::Filter(Filter::EdgeHasLabel {
+                        edge: edge_name,
+                        label,
+                    }));
                 }
-            } else {
-                let name = self.next_name();
-                match edge.direction {
-                    ast::Direction::Left => steps.push(MatchStep::LoadTargetEdge {
-                        name,
-                        node: prev_node_name,
-                    }),
-                    ast::Direction::Right => steps.push(MatchStep::LoadOriginEdge {
-                        name,
-                        node: prev_node_name,
-                    }),
-                    ast::Direction::Either => steps.push(MatchStep::LoadEitherEdge {
-                        name,
-                        node: prev_node_name,
-                    }),
-                }
-                name
-            };
 
-            if let Some(label) = edge.annotation.label {
-                steps.push(MatchStep::Filter(Filter::EdgeHasLabel {
-                    edge: edge_name,
-                    label,
-                }));
-            }
+                steps.append(&mut self.build_filters_from_property_map(
+                    NamedEntity::Edge(edge_name),
+                    edge.properties.as_ref(),
+                )?);
 
-            steps.append(&mut self.build_filters_from_property_map(
-                NamedEntity::Edge(edge_name),
-                edge.properties.as_ref(),
-            )?);
+                steps.append(&mut Self::build_unique_edge_filters(
+                    matched,
+                    NamedEntity::Edge(edge_name),
+                ));
 
-            prev_node_name = if let Some(name) = node.annotation.name {
-                if let Some(name) = self.get_node(name)? {
-                    match edge.direction {
-                        ast::Direction::Left => steps.push(MatchStep::Filter(Filter::IsOrigin {
-                            node: name,
-                            edge: edge_name,
-                        })),
-                        ast::Direction::Right => steps.push(MatchStep::Filter(Filter::IsTarget {
-                            node: name,
-                            edge: edge_name,
-                        })),
-                        ast::Direction::Either => steps.push(MatchStep::Filter(Filter::or(
-                            Filter::and(
-                                Filter::IsOrigin {
+                prev_node_name = if let Some(name) = node.annotation.name {
+                    if let Some(name) = self.get_node(name)? {
+                        match edge.direction {
+                            ast::Direction::Left => {
+                                steps.push(MatchStep::Filter(Filter::IsOrigin {
                                     node: name,
                                     edge: edge_name,
-                                },
-                                Filter::IsTarget {
-                                    node: prev_node_name,
-                                    edge: edge_name,
-                                },
-                            ),
-                            Filter::and(
-                                Filter::IsTarget {
+                                }))
+                            }
+                            ast::Direction::Right => {
+                                steps.push(MatchStep::Filter(Filter::IsTarget {
                                     node: name,
                                     edge: edge_name,
-                                },
-                                Filter::IsOrigin {
-                                    node: prev_node_name,
-                                    edge: edge_name,
-                                },
-                            ),
-                        ))),
+                                }))
+                            }
+                            ast::Direction::Either => steps.push(MatchStep::Filter(Filter::or(
+                                Filter::and(
+                                    Filter::IsOrigin {
+                                        node: name,
+                                        edge: edge_name,
+                                    },
+                                    Filter::IsTarget {
+                                        node: prev_node_name,
+                                        edge: edge_name,
+                                    },
+                                ),
+                                Filter::and(
+                                    Filter::IsTarget {
+                                        node: name,
+                                        edge: edge_name,
+                                    },
+                                    Filter::IsOrigin {
+                                        node: prev_node_name,
+                                        edge: edge_name,
+                                    },
+                                ),
+                            ))),
+                        }
+                        name
+                    } else {
+                        let name = self.create_node(name)?;
+                        match edge.direction {
+                            ast::Direction::Left => steps.push(MatchStep::LoadOriginNode {
+                                name,
+                                edge: edge_name,
+                            }),
+                            ast::Direction::Right => steps.push(MatchStep::LoadTargetNode {
+                                name,
+                                edge: edge_name,
+                            }),
+                            ast::Direction::Either => steps.push(MatchStep::LoadOtherNode {
+                                name,
+                                node: prev_node_name,
+                                edge: edge_name,
+                            }),
+                        }
+                        name
                     }
-                    name
                 } else {
-                    let name = self.create_node(name)?;
+                    let name = self.next_name();
                     match edge.direction {
                         ast::Direction::Left => steps.push(MatchStep::LoadOriginNode {
                             name,
@@ -344,90 +715,294 @@ impl<'src> BuildEnv<'src> {
                         }),
                     }
                     name
-                }
-            } else {
-                let name = self.next_name();
-                match edge.direction {
-                    ast::Direction::Left => steps.push(MatchStep::LoadOriginNode {
-                        name,
-                        edge: edge_name,
-                    }),
-                    ast::Direction::Right => steps.push(MatchStep::LoadTargetNode {
-                        name,
-                        edge: edge_name,
-                    }),
-                    ast::Direction::Either => steps.push(MatchStep::LoadOtherNode {
-                        name,
-                        node: prev_node_name,
-                        edge: edge_name,
-                    }),
-                }
-                name
-            };
-
-            if let Some(label) = node.annotation.label {
-                steps.push(MatchStep::Filter(Filter::NodeHasLabel {
+                };
+                segments.push(PathSegment::Edge {
+                    edge: edge_name,
                     node: prev_node_name,
-                    label,
-                }));
+                });
             }
 
+            steps.append(&mut Self::build_label_filters(
+                prev_node_name,
+                &node.annotation.labels,
+            ));
+
             steps.append(&mut self.build_filters_from_property_map(
                 NamedEntity::Node(prev_node_name),
                 node.properties.as_ref(),
             )?);
         }
 
+        if let Some(name) = pattern.path {
+            let name = self.create_path(name, NamedEntity::Path)?;
+            steps.push(MatchStep::LoadNamedPath {
+                name,
+                start,
+                segments,
+            });
+        }
+
         Ok(steps)
     }
 
+    /// Build the steps for a variable length `edge` which starts
+    /// at `prev_node_name`, and return the names of the path and
+    /// its end node. Shortest paths are searched once the end node
+    /// is loaded.
+    fn build_path(
+        &mut self,
+        steps: &mut Vec<MatchStep<'src>>,
+        prev_node_name: usize,
+        edge: &'src ast::Edge<'src>,
+        node: &'src ast::Node<'src>,
+        length: ast::Length,
+        shortest: Option<ast::Shortest>,
+    ) -> Result<(usize, usize), Error> {
+        let name = match edge.annotation.name {
+            Some(name) => self.create_path(name, NamedEntity::Edges)?,
+            None => self.next_name(),
+        };
+        let properties = edge
+            .properties
+            .iter()
+            .map(|(key, expr)| -> Result<_, Error> { Ok((*key, self.build_load_property(expr)?)) })
+            .collect::<Result<_, Error>>()?;
+
+        let existing = node
+            .annotation
+            .name
+            .map(|name| self.get_node(name))
+            .transpose()?
+            .flatten();
+        let end = match (node.annotation.name, existing) {
+            (Some(name), None) => self.create_node(name)?,
+            _ => self.next_name(),
+        };
+
+        if let Some(shortest) = shortest {
+            let end = existing.unwrap_or(end);
+            steps.push(MatchStep::LoadShortestPath {
+                name,
+                node: prev_node_name,
+                end,
+                load_end: existing.is_none(),
+                direction: edge.direction,
+                label: edge.annotation.label(),
+                properties,
+                min: length.min,
+                max: length.max,
+                all: shortest == ast::Shortest::All,
+            });
+            return Ok((name, end));
+        }
+
+        steps.push(MatchStep::LoadPath {
+            name,
+            node: prev_node_name,
+            end,
+            direction: edge.direction,
+            label: edge.annotation.label(),
+            properties,
+            min: length.min,
+            max: length.max,
+        });
+        if let Some(existing) = existing {
+            steps.push(MatchStep::Filter(Filter::NodeHasId {
+                node: end,
+                id: LoadProperty::IdOfNode { node: existing },
+            }));
+            Ok((name, existing))
+        } else {
+            Ok((name, end))
+        }
+    }
+
+    /// Build the updates creating the nodes and edges of `clause`.
+    /// Nodes are created before the edges which connect them.
     fn build_create_update(
         &mut self,
         clause: &'src ast::CreateClause<'src>,
-    ) -> Result<UpdateStep<'src>, Error> {
+    ) -> Result<Vec<UpdateStep<'src>>, Error> {
         match clause {
             ast::CreateClause::CreateNode {
                 name,
-                label,
+                labels,
                 properties,
-            } => Ok(UpdateStep::CreateNode {
-                name: name
+            } => {
+                let name = name
                     .map(|n| self.create_node(n))
                     .transpose()?
-                    .unwrap_or_else(|| self.next_name()),
-                label,
-                properties: properties
-                    .iter()
-                    .map(|(key, expr)| -> Result<_, Error> {
-                        Ok((*key, self.build_load_property(expr)?))
-                    })
-                    .collect::<Result<_, Error>>()?,
-            }),
+                    .unwrap_or_else(|| self.next_name());
+                Ok(vec![self.build_create_node(name, labels, properties)?])
+            }
             ast::CreateClause::CreateEdge {
                 name,
                 label,
                 origin,
                 target,
                 properties,
-            } => Ok(UpdateStep::CreateEdge {
-                name: name
+            } => {
+                let name = name
                     .map(|n| self.create_edge(n))
                     .transpose()?
-                    .unwrap_or_else(|| self.next_name()),
-                label,
-                origin: self
+                    .unwrap_or_else(|| self.next_name());
+                let origin = self
                     .get_node(origin)?
-                    .ok_or_else(|| Error::UnknownIdentifier(origin.to_string()))?,
-                target: self
+                    .ok_or_else(|| Error::UnknownIdentifier(origin.to_string()))?;
+                let target = self
                     .get_node(target)?
-                    .ok_or_else(|| Error::UnknownIdentifier(target.to_string()))?,
-                properties: properties
-                    .iter()
-                    .map(|(key, expr)| -> Result<_, Error> {
-                        Ok((*key, self.build_load_property(expr)?))
-                    })
-                    .collect::<Result<_, Error>>()?,
+                    .ok_or_else(|| Error::UnknownIdentifier(target.to_string()))?;
+                Ok(vec![self.build_create_edge(
+                    name, label, origin, target, properties,
+                )?])
+            }
+            ast::CreateClause::CreatePath { start, edges } => {
+                let mut nodes = vec![];
+                let mut prev_node_name = self.build_path_node(start, &mut nodes)?;
+                let mut path_edges = vec![];
+                for (edge, node) in edges {
+                    let name = edge
+                        .annotation
+                        .name
+                        .map(|n| self.create_edge(n))
+                        .transpose()?
+                        .unwrap_or_else(|| self.next_name());
+                    let node_name = self.build_path_node(node, &mut nodes)?;
+                    let (origin, target) = match edge.direction {
+                        ast::Direction::Left => (node_name, prev_node_name),
+                        ast::Direction::Right => (prev_node_name, node_name),
+                        ast::Direction::Either => return Err(Error::Internal),
+                    };
+                    let label = edge.annotation.label().ok_or(Error::Internal)?;
+                    path_edges.push(self.build_create_edge(
+                        name,
+                        label,
+                        origin,
+                        target,
+                        &edge.properties,
+                    )?);
+                    prev_node_name = node_name;
+                }
+                nodes.append(&mut path_edges);
+                Ok(nodes)
+            }
+        }
+    }
+
+    /// Return the name of `node` in a created path. Nodes
+    /// without labels refer to existing nodes, all others
+    /// are created by an update appended to `updates`.
+    fn build_path_node(
+        &mut self,
+        node: &'src ast::Node<'src>,
+        updates: &mut Vec<UpdateStep<'src>>,
+    ) -> Result<usize, Error> {
+        match (&node.annotation.labels, node.annotation.name) {
+            (ast::Labels::All(labels), Some(name)) if labels.is_empty() => self
+                .get_node(name)?
+                .ok_or_else(|| Error::UnknownIdentifier(name.to_string())),
+            (ast::Labels::All(labels), name) => {
+                let name = name
+                    .map(|n| self.create_node(n))
+                    .transpose()?
+                    .unwrap_or_else(|| self.next_name());
+                updates.push(self.build_create_node(name, labels, &node.properties)?);
+                Ok(name)
+            }
+            (ast::Labels::Any(_), _) => Err(Error::Internal),
+        }
+    }
+
+    fn build_create_node(
+        &mut self,
+        name: usize,
+        labels: &[&'src str],
+        properties: &'src [(&'src str, ast::Expression<'src>)],
+    ) -> Result<UpdateStep<'src>, Error> {
+        Ok(UpdateStep::CreateNode {
+            name,
+            // labels given more than once are only added once
+            labels: labels
+                .iter()
+                .enumerate()
+                .filter(|(idx, label)| !labels[..*idx].contains(label))
+                .map(|(_, label)| *label)
+                .collect(),
+            properties: properties
+                .iter()
+                .map(|(key, expr)| -> Result<_, Error> {
+                    Ok((*key, self.build_load_property(expr)?))
+                })
+                .collect::<Result<_, Error>>()?,
+        })
+    }
+
+    fn build_create_edge(
+        &mut self,
+        name: usize,
+        label: &'src str,
+        origin: usize,
+        target: usize,
+        properties: &'src [(&'src str, ast::Expression<'src>)],
+    ) -> Result<UpdateStep<'src>, Error> {
+        Ok(UpdateStep::CreateEdge {
+            name,
+            label,
+            origin,
+            target,
+            properties: properties
+                .iter()
+                .map(|(key, expr)| -> Result<_, Error> {
+                    Ok((*key, self.build_load_property(expr)?))
+                })
+                .collect::<Result<_, Error>>()?,
+        })
+    }
+
+    fn build_merge_update(
+        &mut self,
+        clause: &'src ast::MergeClause<'src>,
+    ) -> Result<UpdateStep<'src>, Error> {
+        // the pattern is bound first, so the sets can refer to it
+        let mut creates = self.build_create_update(&clause.pattern)?;
+        let on_create = clause
+            .on_create
+            .iter()
+            .map(|set| self.build_set_update(set))
+            .collect::<Result<_, Error>>()?;
+        let on_match = clause
+            .on_match
+            .iter()
+            .map(|set| self.build_set_update(set))
+            .collect::<Result<_, Error>>()?;
+        match creates.pop() {
+            Some(UpdateStep::CreateNode {
+                name,
+                labels,
+                properties,
+            }) => Ok(UpdateStep::MergeNode {
+                name,
+                labels,
+                properties,
+                on_create,
+                on_match,
             }),
+            Some(UpdateStep::CreateEdge {
+                name,
+                label,
+                origin,
+                target,
+                properties,
+            }) => Ok(UpdateStep::MergeEdge {
+                name,
+                label,
+                origin,
+                target,
+                properties,
+                on_create,
+                on_match,
+            }),
+            _ => unreachable!("merge patterns build a single create"),
         }
     }
 
@@ -435,64 +1010,345 @@ impl<'src> BuildEnv<'src> {
         &mut self,
         clause: &'src ast::SetClause<'src>,
     ) -> Result<UpdateStep<'src>, Error> {
-        match self.names.get(clause.name) {
-            Some(NamedEntity::Node(node)) => Ok(UpdateStep::SetNodeProperty {
-                node: *node,
-                key: clause.key,
-                value: self.build_load_property(&clause.value)?,
-            }),
-            Some(NamedEntity::Edge(edge)) => Ok(UpdateStep::SetEdgeProperty {
-                edge: *edge,
-                key: clause.key,
-                value: self.build_load_property(&clause.value)?,
-            }),
-            None => Err(Error::UnknownIdentifier(clause.name.to_string())),
+        let name = match clause {
+            ast::SetClause::Property { name, .. }
+            | ast::SetClause::Properties { name, .. }
+            | ast::SetClause::Labels { name, .. } => name,
+        };
+        match (self.names.get(name), clause) {
+            (Some(&NamedEntity::Node(node)), ast::SetClause::Property { key, value, .. }) => {
+                Ok(UpdateStep::SetNodeProperty {
+                    node,
+                    key,
+                    value: self.build_load_property(value)?,
+                })
+            }
+            (Some(&NamedEntity::Edge(edge)), ast::SetClause::Property { key, value, .. }) => {
+                Ok(UpdateStep::SetEdgeProperty {
+                    edge,
+                    key,
+                    value: self.build_load_property(value)?,
+                })
+            }
+            (Some(&NamedEntity::Node(node)), ast::SetClause::Properties { value, replace, .. }) => {
+                Ok(UpdateStep::SetNodeProperties {
+                    node,
+                    value: self.build_load_property(value)?,
+                    replace: *replace,
+                })
+            }
+            (Some(&NamedEntity::Edge(edge)), ast::SetClause::Properties { value, replace, .. }) => {
+                Ok(UpdateStep::SetEdgeProperties {
+                    edge,
+                    value: self.build_load_property(value)?,
+                    replace: *replace,
+                })
+            }
+            (Some(&NamedEntity::Node(node)), ast::SetClause::Labels { labels, .. }) => {
+                Ok(UpdateStep::SetNodeLabels {
+                    node,
+                    labels: labels.clone(),
+                })
+            }
+            (Some(NamedEntity::Edge(_)), ast::SetClause::Labels { .. })
+            | (Some(NamedEntity::Edges(_) | NamedEntity::Path(_) | NamedEntity::Value(_)), _) => {
+                Err(Error::IdentifierIsNotNode(name.to_string()))
+            }
+            (None, _) => Err(Error::UnknownIdentifier(name.to_string())),
         }
     }
 
-    fn build_delete_update(&mut self, name: &'src str) -> Result<UpdateStep<'src>, Error> {
-        match self.names.get(name) {
-            Some(&NamedEntity::Node(node)) => Ok(UpdateStep::DeleteNode { node }),
-            Some(&NamedEntity::Edge(edge)) => Ok(UpdateStep::DeleteEdge { edge }),
-            None => Err(Error::UnknownIdentifier(name.to_string())),
+    fn build_remove_update(
+        &mut self,
+        clause: &'src ast::RemoveClause<'src>,
+    ) -> Result<UpdateStep<'src>, Error> {
+        let name = match clause {
+            ast::RemoveClause::Property { name, .. } | ast::RemoveClause::Labels { name, .. } => {
+                name
+            }
+        };
+        match (self.names.get(name), clause) {
+            (Some(&NamedEntity::Node(node)), ast::RemoveClause::Property { key, .. }) => {
+                Ok(UpdateStep::RemoveNodeProperty { node, key })
+            }
+            (Some(&NamedEntity::Edge(edge)), ast::RemoveClause::Property { key, .. }) => {
+                Ok(UpdateStep::RemoveEdgeProperty { edge, key })
+            }
+            (Some(&NamedEntity::Node(node)), ast::RemoveClause::Labels { labels, .. }) => {
+                Ok(UpdateStep::RemoveNodeLabels {
+                    node,
+                    labels: labels.clone(),
+                })
+            }
+            (Some(NamedEntity::Edge(_)), ast::RemoveClause::Labels { .. })
+            | (Some(NamedEntity::Edges(_) | NamedEntity::Path(_) | NamedEntity::Value(_)), _) => {
+                Err(Error::IdentifierIsNotNode(name.to_string()))
+            }
+            (None, _) => Err(Error::UnknownIdentifier(name.to_string())),
         }
     }
-}
 
-impl<'src> QueryPlan<'src> {
-    pub fn new(query: &'src ast::Query<'src>) -> Result<Self, Error> {
-        let mut env = BuildEnv::new();
-        let mut steps = vec![];
+    /// Append the steps matching the clauses of `query` to `steps`,
+    /// and return the updates it performs.
+    fn build_part(
+        &mut self,
+        query: &'src ast::Query<'src>,
+        steps: &mut Vec<MatchStep<'src>>,
+    ) -> Result<Vec<UpdateStep<'src>>, Error> {
         let mut updates = vec![];
 
         for clause in &query.match_clauses {
-            steps.append(&mut env.build_match(clause)?);
+            steps.append(&mut self.build_match(clause)?);
         }
 
         for condition in &query.where_clauses {
-            steps.push(MatchStep::Filter(env.build_filter(condition)?));
+            steps.push(MatchStep::Filter(self.build_filter(condition)?));
+        }
+
+        for clause in &query.optional_match_clauses {
+            let mut optional_steps = self.build_match(&clause.pattern)?;
+            if let Some(condition) = &clause.where_clause {
+                optional_steps.push(MatchStep::Filter(self.build_filter(condition)?));
+            }
+            steps.push(MatchStep::Optional(optional_steps));
         }
 
         for clause in &query.create_clauses {
-            updates.push(env.build_create_update(clause)?);
+            updates.append(&mut self.build_create_update(clause)?);
+        }
+        for clause in &query.merge_clauses {
+            updates.push(self.build_merge_update(clause)?);
         }
         for clause in &query.set_clauses {
-            updates.push(env.build_set_update(clause)?);
+            updates.push(self.build_set_update(clause)?);
+        }
+        for clause in &query.remove_clauses {
+            updates.push(self.build_remove_update(clause)?);
         }
-        for name in &query.delete_clauses {
-            updates.push(env.build_delete_update(name)?);
+        for clause in &query.delete_clauses {
+            updates.push(self.build_delete_update(clause)?);
         }
         updates.sort_by(|a, b| a.partial_cmp(b).unwrap());
 
-        let mut returns = Vec::with_capacity(query.return_clause.len());
-        for expr in &query.return_clause {
-            returns.push(env.build_load_property(expr)?);
+        Ok(updates)
+    }
+
+    /// Replace the current scope with the items projected by
+    /// `clause`. Nodes and edges keep their names, any other
+    /// items are bound to new value names, which are returned
+    /// together with the values to bind to them. For `DISTINCT`
+    /// projections, the values which identify a row are returned
+    /// as well.
+    #[allow(clippy::type_complexity)]
+    fn build_with(
+        &mut self,
+        clause: &'src ast::WithClause<'src>,
+    ) -> Result<
+        (
+            Vec<(usize, LoadProperty<'src>)>,
+            Option<Vec<LoadProperty<'src>>>,
+        ),
+        Error,
+    > {
+        let mut names = HashMap::new();
+        let mut values = vec![];
+        let mut distinct = vec![];
+        for (name, expr) in &clause.items {
+            if names.contains_key(name) {
+                return Err(Error::IdentifierExists(name.to_string()));
+            }
+            let entity = match expr {
+                ast::Expression::Variable(variable) => *self
+                    .names
+                    .get(variable)
+                    .ok_or_else(|| Error::UnknownIdentifier(variable.to_string()))?,
+                expr => {
+                    let value = self.build_load_property(expr)?;
+                    let value_name = self.next_name();
+                    values.push((value_name, value));
+                    NamedEntity::Value(value_name)
+                }
+            };
+            if clause.distinct {
+                distinct.push(entity.load());
+            }
+            names.insert(*name, entity);
         }
+        self.names = names;
+        Ok((values, Some(distinct).filter(|_| clause.distinct)))
+    }
+
+    /// Bind the name introduced by `clause` to a new value
+    /// name, and return it together with the list to unwind.
+    fn build_unwind(
+        &mut self,
+        clause: &'src ast::UnwindClause<'src>,
+    ) -> Result<(usize, LoadProperty<'src>), Error> {
+        let list = self.build_load_property(&clause.expression)?;
+        if self.names.contains_key(clause.name) {
+            return Err(Error::IdentifierExists(clause.name.to_string()));
+        }
+        let name = self.next_name();
+        self.names.insert(clause.name, NamedEntity::Value(name));
+        Ok((name, list))
+    }
+
+    fn build_delete_update(
+        &mut self,
+        clause: &'src ast::DeleteClause<'src>,
+    ) -> Result<UpdateStep<'src>, Error> {
+        let name = clause.name;
+        match self.names.get(name) {
+            Some(&NamedEntity::Node(node)) => Ok(UpdateStep::DeleteNode {
+                node,
+                detach: clause.detach,
+            }),
+            Some(&NamedEntity::Edge(edge)) => Ok(UpdateStep::DeleteEdge { edge }),
+            Some(NamedEntity::Edges(_) | NamedEntity::Path(_) | NamedEntity::Value(_)) => {
+                Err(Error::IdentifierIsNotNode(name.to_string()))
+            }
+            None => Err(Error::UnknownIdentifier(name.to_string())),
+        }
+    }
+}
+
+impl<'src> QueryPlan<'src> {
+    pub fn new(query: &'src ast::Query<'src>, functions: &Functions) -> Result<Self, Error> {
+        let mut env = BuildEnv::new(functions);
+        let mut parts = vec![];
+        let mut steps = vec![];
+
+        let mut query = query;
+        loop {
+            if let Some(clause) = &query.with_clause {
+                let updates = env.build_part(query, &mut steps)?;
+                let (values, distinct) = env.build_with(clause)?;
+                parts.push(QueryPart {
+                    steps: std::mem::take(&mut steps),
+                    updates,
+                    values,
+                    distinct,
+                    unwind: None,
+                });
+                if let Some(condition) = &clause.where_clause {
+                    steps.push(MatchStep::Filter(env.build_filter(condition)?));
+                }
+                query = &clause.query;
+            } else if let Some(clause) = &query.unwind_clause {
+                let updates = env.build_part(query, &mut steps)?;
+                let unwind = env.build_unwind(clause)?;
+                parts.push(QueryPart {
+                    steps: std::mem::take(&mut steps),
+                    updates,
+                    values: vec![],
+                    distinct: None,
+                    unwind: Some(unwind),
+                });
+                query = &clause.query;
+            } else {
+                break;
+            }
+        }
+        let updates = env.build_part(query, &mut steps)?;
+
+        let mut returns = Vec::with_capacity(query.return_clause.items.len());
+        for (idx, (name, expr)) in query.return_clause.items.iter().enumerate() {
+            if query.return_clause.items[..idx]
+                .iter()
+                .any(|(other, _)| other == name)
+            {
+                return Err(Error::IdentifierExists(name.to_string()));
+            }
+            returns.push(env.build_projection(expr)?);
+        }
+
+        // once matches are aggregated or made distinct, only the
+        // returned values and further aggregates are available for
+        // sorting; sort keys may also refer to returned values by
+        // their name
+        let sort_values = query
+            .return_clause
+            .order_by
+            .iter()
+            .map(|key| {
+                let column = match key.expression {
+                    ast::Expression::Variable(name) => query
+                        .return_clause
+                        .items
+                        .iter()
+                        .position(|(column, _)| *column == name),
+                    _ => None,
+                };
+                match column {
+                    Some(column) => Ok(returns[column].clone()),
+                    None => env.build_projection(&key.expression),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let is_aggregate = returns
+            .iter()
+            .chain(sort_values.iter())
+            .any(LoadProperty::is_aggregate);
+        let distinct = query.return_clause.distinct;
+        let mut order_by = Vec::with_capacity(sort_values.len());
+        for (key, value) in query.return_clause.order_by.iter().zip(sort_values) {
+            if (is_aggregate || distinct) && !value.is_aggregate() && !returns.contains(&value) {
+                match key.expression.first_name() {
+                    Some(name) => return Err(Error::UnknownIdentifier(name.to_string())),
+                    // constant keys do not change the order
+                    None => continue,
+                }
+            }
+            order_by.push(SortKey {
+                value,
+                descending: key.descending,
+            });
+        }
+
+        // SKIP and LIMIT are evaluated once all rows are known,
+        // so they may not refer to any names
+        let skip = query
+            .return_clause
+            .skip
+            .as_ref()
+            .map(|expr| BuildEnv::new(functions).build_load_property(expr))
+            .transpose()?;
+        let limit = query
+            .return_clause
+            .limit
+            .as_ref()
+            .map(|expr| BuildEnv::new(functions).build_load_property(expr))
+            .transpose()?;
 
         Ok(QueryPlan {
+            parts,
             steps,
             updates,
             returns,
+            distinct,
+            order_by,
+            skip,
+            limit,
         })
     }
+
+    /// Build a plan for each query of a `UNION`. All queries
+    /// must return the same number of columns, with the same
+    /// names.
+    pub fn union(
+        statement: &'src ast::Statement<'src>,
+        functions: &Functions,
+    ) -> Result<Vec<Self>, Error> {
+        let columns = statement.column_names();
+        for query in &statement.queries[1..] {
+            if query.column_names() != columns {
+                return Err(Error::UnionColumnMismatch);
+            }
+        }
+        statement
+            .queries
+            .iter()
+            .map(|query| Self::new(query, functions))
+            .collect()
+    }
 }
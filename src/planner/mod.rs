@@ -2,12 +2,15 @@ mod build;
 mod optimize;
 mod plan;
 
-pub(crate) use plan::{Filter, LoadProperty, MatchStep, QueryPlan, UpdateStep};
+pub(crate) use plan::{
+    Filter, LoadProperty, MatchStep, PathSegment, QueryPart, QueryPlan, UpdateStep,
+};
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::parser::ast;
+    use crate::runtime::Functions;
     use plan::*;
 
     #[test]
@@ -15,23 +18,33 @@ mod tests {
         // (a) -> (b)
         let query = ast::Query {
             match_clauses: vec![ast::MatchClause {
-                start: ast::Node::with_annotation(ast::Annotation::with_name("a")),
-                edges: vec![(
-                    ast::Edge::right(ast::Annotation::empty(), vec![]),
-                    ast::Node::with_annotation(ast::Annotation::with_name("b")),
-                )],
+                patterns: vec![ast::Pattern {
+                    path: None,
+                    shortest: None,
+                    start: ast::Node::with_annotation(ast::Annotation::with_name("a")),
+                    edges: vec![(
+                        ast::Edge::right(ast::Annotation::empty(), vec![]),
+                        ast::Node::with_annotation(ast::Annotation::with_name("b")),
+                    )],
+                }],
             }],
             where_clauses: vec![],
+            optional_match_clauses: vec![],
             create_clauses: vec![],
+            merge_clauses: vec![],
             set_clauses: vec![],
+            remove_clauses: vec![],
             delete_clauses: vec![],
-            return_clause: vec![
-                ast::Expression::property("a", "name"),
-                ast::Expression::property("b", "name"),
-            ],
+            with_clause: None,
+            unwind_clause: None,
+            return_clause: ast::Projection::new(vec![
+                ("a.name", ast::Expression::property("a", "name")),
+                ("b.name", ast::Expression::property("b", "name")),
+            ]),
         };
 
         let plan = QueryPlan {
+            parts: vec![],
             steps: vec![
                 MatchStep::LoadAnyNode { name: 0 },
                 MatchStep::LoadOriginEdge { name: 1, node: 0 },
@@ -48,8 +61,12 @@ mod tests {
                     key: "name",
                 },
             ],
+            distinct: false,
+            order_by: vec![],
+            skip: None,
+            limit: None,
         };
 
-        assert_eq!(plan, QueryPlan::new(&query).unwrap());
+        assert_eq!(plan, QueryPlan::new(&query, &Functions::new()).unwrap());
     }
 }
@@ -17,8 +17,13 @@ impl Optimization for ReorderIdConstrainedFirst {
             .iter()
             .filter_map(|step| match step {
                 MatchStep::Filter(
-                    Filter::NodeHasId { node: name, .. } | Filter::EdgeHasId { edge: name, .. },
-                ) => Some(*name),
+                    Filter::NodeHasId { node: name, id } | Filter::EdgeHasId { edge: name, id },
+                ) if is_constant(id) => Some(*name),
+                MatchStep::Filter(Filter::In(LoadProperty::IdOfNode { node }, ids))
+                    if is_constant(ids) =>
+                {
+                    Some(*node)
+                }
                 _ => None,
             })
             .collect();
@@ -165,8 +170,30 @@ impl Optimization for ReorderIdConstrainedFirst {
     }
 }
 
+/// Check if a property does not depend on any nodes, edges,
+/// or paths loaded by the plan, such that it can be evaluated
+/// before any of them are loaded.
+fn is_constant(load: &LoadProperty) -> bool {
+    match load {
+        LoadProperty::Constant(_) | LoadProperty::Parameter { .. } | LoadProperty::Value { .. } => {
+            true
+        }
+        LoadProperty::List(items) => items.iter().all(is_constant),
+        LoadProperty::Map(entries) => entries.iter().all(|(_, value)| is_constant(value)),
+        #[allow(clippy::unnecessary_map_or)]
+        LoadProperty::Temporal { value, .. } => value.as_deref().map_or(true, is_constant),
+        LoadProperty::Binary { lhs, rhs, .. } => is_constant(lhs) && is_constant(rhs),
+        LoadProperty::Coalesce(values)
+        | LoadProperty::Function {
+            arguments: values, ..
+        } => values.iter().all(is_constant),
+        _ => false,
+    }
+}
+
 /// Transform pairs of `LoadAnyNode` and `NodeHasId` into
-/// `LoadExactNode`.
+/// `LoadExactNode`, and pairs of `LoadAnyNode` and `ID(n) IN`
+/// into `LoadExactNodes`.
 pub(crate) struct LoadAnyToLoadExact;
 
 impl Optimization for LoadAnyToLoadExact {
@@ -176,26 +203,60 @@ impl Optimization for LoadAnyToLoadExact {
             .steps
             .iter()
             .filter_map(|step| match step {
-                MatchStep::Filter(Filter::NodeHasId { node, id }) => Some((*node, id.clone())),
+                MatchStep::Filter(Filter::NodeHasId { node, id }) if is_constant(id) => {
+                    Some((*node, id.clone()))
+                }
                 _ => None,
             })
             .collect();
+        let mut node_ids_checks: HashMap<usize, LoadProperty> = plan
+            .steps
+            .iter()
+            .filter_map(|step| match step {
+                MatchStep::Filter(Filter::In(LoadProperty::IdOfNode { node }, ids))
+                    if is_constant(ids) && !node_id_checks.contains_key(node) =>
+                {
+                    Some((*node, ids.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+        let mut loaded_id = HashMap::new();
+        let mut loaded_ids = HashMap::new();
         plan.steps = plan
             .steps
             .drain(..)
             .filter_map(|step| match step {
-                MatchStep::LoadAnyNode { name } => node_id_checks
-                    .remove(&name)
-                    .map(|id| {
+                MatchStep::LoadAnyNode { name } => {
+                    if let Some(id) = node_id_checks.remove(&name) {
                         changed = true;
-                        MatchStep::LoadExactNode { name, id }
-                    })
-                    .or(Some(MatchStep::LoadAnyNode { name })),
+                        loaded_id.insert(name, id.clone());
+                        Some(MatchStep::LoadExactNode { name, id })
+                    } else if let Some(ids) = node_ids_checks.remove(&name) {
+                        changed = true;
+                        loaded_ids.insert(name, ids.clone());
+                        Some(MatchStep::LoadExactNodes { name, ids })
+                    } else {
+                        Some(MatchStep::LoadAnyNode { name })
+                    }
+                }
                 MatchStep::Filter(Filter::NodeHasId { node, id }) => {
-                    if node_id_checks.contains_key(&node) {
-                        Some(MatchStep::Filter(Filter::NodeHasId { node, id }))
+                    if loaded_id.get(&node) == Some(&id) {
+                        loaded_id.remove(&node);
+                        None
                     } else {
+                        Some(MatchStep::Filter(Filter::NodeHasId { node, id }))
+                    }
+                }
+                MatchStep::Filter(Filter::In(LoadProperty::IdOfNode { node }, ids)) => {
+                    if loaded_ids.get(&node) == Some(&ids) {
+                        loaded_ids.remove(&node);
                         None
+                    } else {
+                        Some(MatchStep::Filter(Filter::In(
+                            LoadProperty::IdOfNode { node },
+                            ids,
+                        )))
                     }
                 }
                 step => Some(step),
@@ -212,23 +273,21 @@ pub(crate) struct LoadAnyToLoadLabeled;
 impl Optimization for LoadAnyToLoadLabeled {
     fn apply(plan: &mut QueryPlan) -> Result<bool, Error> {
         let mut changed = false;
-        let mut node_label_checks: HashMap<usize, &str> = plan
-            .steps
-            .iter()
-            .filter_map(|step| match step {
-                MatchStep::Filter(Filter::NodeHasLabel { node, label }) => Some((*node, *label)),
-                _ => None,
-            })
-            .collect();
+        let mut node_label_checks: HashMap<usize, Vec<&str>> = HashMap::new();
+        for step in &plan.steps {
+            if let MatchStep::Filter(Filter::NodeHasLabel { node, label }) = step {
+                node_label_checks.entry(*node).or_default().push(*label);
+            }
+        }
         plan.steps = plan
             .steps
             .drain(..)
             .filter_map(|step| match step {
                 MatchStep::LoadAnyNode { name } => node_label_checks
                     .remove(&name)
-                    .map(|label| {
+                    .map(|labels| {
                         changed = true;
-                        MatchStep::LoadLabeledNode { name, label }
+                        MatchStep::LoadLabeledNode { name, labels }
                     })
                     .or(Some(MatchStep::LoadAnyNode { name })),
                 MatchStep::Filter(Filter::NodeHasLabel { node, label }) => {
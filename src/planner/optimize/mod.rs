@@ -1,4 +1,4 @@
-use super::QueryPlan;
+use super::{QueryPart, QueryPlan};
 use crate::Error;
 
 mod loads;
@@ -27,6 +27,30 @@ pub(crate) trait Optimization {
 
 impl<'src> QueryPlan<'src> {
     pub fn optimize(mut self) -> Result<Self, Error> {
+        // parts are optimized on their own, such that no
+        // steps are moved across a `WITH`
+        self.parts = std::mem::take(&mut self.parts)
+            .into_iter()
+            .map(|part| {
+                let plan = QueryPlan {
+                    parts: vec![],
+                    steps: part.steps,
+                    updates: part.updates,
+                    returns: vec![],
+                    distinct: false,
+                    order_by: vec![],
+                    skip: None,
+                    limit: None,
+                }
+                .optimize()?;
+                Ok(QueryPart {
+                    steps: plan.steps,
+                    updates: plan.updates,
+                    ..part
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+
         normalize::SplitTopLevelAnd::fix(&mut self)?;
         normalize::CanonicalizeCheckNodeLabel::apply(&mut self)?;
         normalize::MergeDuplicateUpdates::apply(&mut self)?;
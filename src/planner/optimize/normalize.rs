@@ -29,7 +29,7 @@ impl Optimization for SplitTopLevelAnd {
     }
 }
 
-/// Normalize `LABEL(node) = "text"` to a canonical representation
+/// Normalize `"text" IN labels(node)` to a canonical representation
 /// as `NodeHasLabel`.
 pub(crate) struct CanonicalizeCheckNodeLabel;
 
@@ -40,20 +40,13 @@ impl Optimization for CanonicalizeCheckNodeLabel {
             .steps
             .drain(..)
             .map(|step| match step {
-                MatchStep::Filter(ref filter) => match filter {
-                    Filter::Eq(
-                        LoadProperty::LabelOfNode { node },
-                        LoadProperty::Constant(PropRef::Text(label)),
-                    )
-                    | Filter::Eq(
-                        LoadProperty::Constant(PropRef::Text(label)),
-                        LoadProperty::LabelOfNode { node },
-                    ) => {
-                        changed = true;
-                        MatchStep::Filter(Filter::NodeHasLabel { node: *node, label })
-                    }
-                    _ => step,
-                },
+                MatchStep::Filter(Filter::In(
+                    LoadProperty::Constant(PropRef::Text(label)),
+                    LoadProperty::LabelsOfNode { node },
+                )) => {
+                    changed = true;
+                    MatchStep::Filter(Filter::NodeHasLabel { node, label })
+                }
                 _ => step,
             })
             .collect();
@@ -62,18 +55,28 @@ impl Optimization for CanonicalizeCheckNodeLabel {
 }
 
 /// Combine sets for the same node/ edge and property into a single
-/// set. Combine deletes for the same node/ edge into a single delete.
+/// set. Combine deletes for the same node/ edge into a single delete,
+/// which detaches the node if any of the deletes did.
 pub(crate) struct MergeDuplicateUpdates;
 
 impl Optimization for MergeDuplicateUpdates {
     fn apply(plan: &mut QueryPlan) -> Result<bool, Error> {
         let mut changed = false;
+        let detached: HashSet<usize> = plan
+            .updates
+            .iter()
+            .filter_map(|update| match update {
+                UpdateStep::DeleteNode { node, detach: true } => Some(*node),
+                _ => None,
+            })
+            .collect();
         let mut seen_deletes = HashSet::new();
         plan.updates = plan
             .updates
             .drain(..)
             .filter(|update| match update {
-                UpdateStep::DeleteNode { node: name } | UpdateStep::DeleteEdge { edge: name } => {
+                UpdateStep::DeleteNode { node: name, .. }
+                | UpdateStep::DeleteEdge { edge: name } => {
                     if seen_deletes.contains(name) {
                         changed = true;
                         false
@@ -84,6 +87,13 @@ impl Optimization for MergeDuplicateUpdates {
                 }
                 _ => true,
             })
+            .map(|update| match update {
+                UpdateStep::DeleteNode {
+                    node,
+                    detach: false,
+                } if detached.contains(&node) => UpdateStep::DeleteNode { node, detach: true },
+                update => update,
+            })
             .collect();
         let mut seen_sets = HashSet::new();
         plan.updates = plan
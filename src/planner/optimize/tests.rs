@@ -5,6 +5,7 @@ use crate::store::PropRef;
 #[test]
 fn simplify_top_level_and() {
     let mut plan_before = QueryPlan {
+        parts: vec![],
         steps: vec![
             MatchStep::Filter(Filter::and(
                 Filter::IsOrigin { edge: 0, node: 1 },
@@ -20,8 +21,13 @@ fn simplify_top_level_and() {
         ],
         updates: vec![],
         returns: vec![],
+        distinct: false,
+        order_by: vec![],
+        skip: None,
+        limit: None,
     };
     let plan_after = QueryPlan {
+        parts: vec![],
         steps: vec![
             MatchStep::Filter(Filter::IsOrigin { edge: 0, node: 1 }),
             MatchStep::Filter(Filter::IsOrigin { edge: 0, node: 1 }),
@@ -31,6 +37,10 @@ fn simplify_top_level_and() {
         ],
         updates: vec![],
         returns: vec![],
+        distinct: false,
+        order_by: vec![],
+        skip: None,
+        limit: None,
     };
 
     normalize::SplitTopLevelAnd::fix(&mut plan_before).unwrap();
@@ -40,11 +50,12 @@ fn simplify_top_level_and() {
 #[test]
 fn canonicalize_check_node_label() {
     let mut plan_before = QueryPlan {
+        parts: vec![],
         steps: vec![
             MatchStep::LoadAnyNode { name: 0 },
-            MatchStep::Filter(Filter::Eq(
+            MatchStep::Filter(Filter::In(
                 LoadProperty::Constant(PropRef::Text("LABEL")),
-                LoadProperty::LabelOfNode { node: 0 },
+                LoadProperty::LabelsOfNode { node: 0 },
             )),
             MatchStep::Filter(Filter::Eq(
                 LoadProperty::LabelOfNode { node: 0 },
@@ -53,21 +64,31 @@ fn canonicalize_check_node_label() {
         ],
         updates: vec![],
         returns: vec![],
+        distinct: false,
+        order_by: vec![],
+        skip: None,
+        limit: None,
     };
     let plan_after = QueryPlan {
+        parts: vec![],
         steps: vec![
             MatchStep::LoadAnyNode { name: 0 },
             MatchStep::Filter(Filter::NodeHasLabel {
                 node: 0,
                 label: "LABEL",
             }),
-            MatchStep::Filter(Filter::NodeHasLabel {
-                node: 0,
-                label: "LABEL",
-            }),
+            // only checks the first label of the node
+            MatchStep::Filter(Filter::Eq(
+                LoadProperty::LabelOfNode { node: 0 },
+                LoadProperty::Constant(PropRef::Text("LABEL")),
+            )),
         ],
         updates: vec![],
         returns: vec![],
+        distinct: false,
+        order_by: vec![],
+        skip: None,
+        limit: None,
     };
 
     normalize::CanonicalizeCheckNodeLabel::apply(&mut plan_before).unwrap();
@@ -77,6 +98,7 @@ fn canonicalize_check_node_label() {
 #[test]
 fn simplify_merge_sets() {
     let mut plan_before = QueryPlan {
+        parts: vec![],
         steps: vec![],
         updates: vec![
             UpdateStep::SetNodeProperty {
@@ -98,8 +120,13 @@ fn simplify_merge_sets() {
             UpdateStep::DeleteEdge { edge: 1 },
         ],
         returns: vec![],
+        distinct: false,
+        order_by: vec![],
+        skip: None,
+        limit: None,
     };
     let plan_after = QueryPlan {
+        parts: vec![],
         steps: vec![],
         updates: vec![
             UpdateStep::DeleteEdge { edge: 1 },
@@ -110,6 +137,59 @@ fn simplify_merge_sets() {
             },
         ],
         returns: vec![],
+        distinct: false,
+        order_by: vec![],
+        skip: None,
+        limit: None,
+    };
+
+    normalize::MergeDuplicateUpdates::apply(&mut plan_before).unwrap();
+    assert_eq!(plan_before, plan_after);
+}
+
+#[test]
+fn simplify_merge_detached_deletes() {
+    let mut plan_before = QueryPlan {
+        parts: vec![],
+        steps: vec![],
+        updates: vec![
+            UpdateStep::DeleteNode {
+                node: 0,
+                detach: false,
+            },
+            UpdateStep::DeleteNode {
+                node: 1,
+                detach: false,
+            },
+            UpdateStep::DeleteNode {
+                node: 0,
+                detach: true,
+            },
+        ],
+        returns: vec![],
+        distinct: false,
+        order_by: vec![],
+        skip: None,
+        limit: None,
+    };
+    let plan_after = QueryPlan {
+        parts: vec![],
+        steps: vec![],
+        updates: vec![
+            UpdateStep::DeleteNode {
+                node: 0,
+                detach: true,
+            },
+            UpdateStep::DeleteNode {
+                node: 1,
+                detach: false,
+            },
+        ],
+        returns: vec![],
+        distinct: false,
+        order_by: vec![],
+        skip: None,
+        limit: None,
     };
 
     normalize::MergeDuplicateUpdates::apply(&mut plan_before).unwrap();
@@ -119,6 +199,7 @@ fn simplify_merge_sets() {
 #[test]
 fn load_reorder_id_constrained_first() {
     let mut plan_before = QueryPlan {
+        parts: vec![],
         steps: vec![
             MatchStep::LoadAnyNode { name: 0 },
             MatchStep::LoadOriginEdge { name: 1, node: 0 },
@@ -139,8 +220,13 @@ fn load_reorder_id_constrained_first() {
         ],
         updates: vec![],
         returns: vec![],
+        distinct: false,
+        order_by: vec![],
+        skip: None,
+        limit: None,
     };
     let plan_after = QueryPlan {
+        parts: vec![],
         steps: vec![
             MatchStep::LoadAnyNode { name: 4 },
             MatchStep::LoadEitherEdge { name: 3, node: 4 },
@@ -161,6 +247,10 @@ fn load_reorder_id_constrained_first() {
         ],
         updates: vec![],
         returns: vec![],
+        distinct: false,
+        order_by: vec![],
+        skip: None,
+        limit: None,
     };
 
     loads::ReorderIdConstrainedFirst::apply(&mut plan_before).unwrap();
@@ -170,6 +260,7 @@ fn load_reorder_id_constrained_first() {
 #[test]
 fn load_reorder_id_constrained_first_no_unnecessary_flips() {
     let plan = QueryPlan {
+        parts: vec![],
         steps: vec![
             MatchStep::LoadAnyNode { name: 0 },
             MatchStep::LoadOriginEdge { name: 1, node: 0 },
@@ -185,6 +276,10 @@ fn load_reorder_id_constrained_first_no_unnecessary_flips() {
         ],
         updates: vec![],
         returns: vec![],
+        distinct: false,
+        order_by: vec![],
+        skip: None,
+        limit: None,
     };
 
     let mut plan_copy = plan.clone();
@@ -195,6 +290,7 @@ fn load_reorder_id_constrained_first_no_unnecessary_flips() {
 #[test]
 fn load_any_node_to_load_exact_node() {
     let mut plan_before = QueryPlan {
+        parts: vec![],
         steps: vec![
             MatchStep::LoadAnyNode { name: 0 },
             MatchStep::Filter(Filter::NodeHasId {
@@ -204,16 +300,195 @@ fn load_any_node_to_load_exact_node() {
         ],
         updates: vec![],
         returns: vec![],
+        distinct: false,
+        order_by: vec![],
+        skip: None,
+        limit: None,
     };
     let plan_after = QueryPlan {
+        parts: vec![],
         steps: vec![MatchStep::LoadExactNode {
             name: 0,
             id: LoadProperty::Parameter { name: "test" },
         }],
         updates: vec![],
         returns: vec![],
+        distinct: false,
+        order_by: vec![],
+        skip: None,
+        limit: None,
     };
 
     loads::LoadAnyToLoadExact::apply(&mut plan_before).unwrap();
     assert_eq!(plan_before, plan_after);
 }
+
+#[test]
+fn load_any_node_to_load_exact_nodes() {
+    let ids_in = |node| {
+        MatchStep::Filter(Filter::In(
+            LoadProperty::IdOfNode { node },
+            LoadProperty::Parameter { name: "ids" },
+        ))
+    };
+    let mut plan_before = QueryPlan {
+        parts: vec![],
+        steps: vec![
+            MatchStep::LoadAnyNode { name: 0 },
+            ids_in(0),
+            MatchStep::LoadAnyNode { name: 1 },
+            MatchStep::Filter(Filter::In(
+                LoadProperty::IdOfNode { node: 1 },
+                LoadProperty::PropertyOfNode {
+                    node: 0,
+                    key: "ids",
+                },
+            )),
+        ],
+        updates: vec![],
+        returns: vec![],
+        distinct: false,
+        order_by: vec![],
+        skip: None,
+        limit: None,
+    };
+    let plan_after = QueryPlan {
+        parts: vec![],
+        steps: vec![
+            MatchStep::LoadExactNodes {
+                name: 0,
+                ids: LoadProperty::Parameter { name: "ids" },
+            },
+            MatchStep::LoadAnyNode { name: 1 },
+            MatchStep::Filter(Filter::In(
+                LoadProperty::IdOfNode { node: 1 },
+                LoadProperty::PropertyOfNode {
+                    node: 0,
+                    key: "ids",
+                },
+            )),
+        ],
+        updates: vec![],
+        returns: vec![],
+        distinct: false,
+        order_by: vec![],
+        skip: None,
+        limit: None,
+    };
+
+    loads::LoadAnyToLoadExact::apply(&mut plan_before).unwrap();
+    assert_eq!(plan_before, plan_after);
+
+    // a single id is preferred, and the other check is kept
+    let mut plan_before = QueryPlan {
+        parts: vec![],
+        steps: vec![
+            MatchStep::LoadAnyNode { name: 0 },
+            ids_in(0),
+            MatchStep::Filter(Filter::NodeHasId {
+                node: 0,
+                id: LoadProperty::Parameter { name: "id" },
+            }),
+        ],
+        updates: vec![],
+        returns: vec![],
+        distinct: false,
+        order_by: vec![],
+        skip: None,
+        limit: None,
+    };
+    let plan_after = QueryPlan {
+        parts: vec![],
+        steps: vec![
+            MatchStep::LoadExactNode {
+                name: 0,
+                id: LoadProperty::Parameter { name: "id" },
+            },
+            ids_in(0),
+        ],
+        updates: vec![],
+        returns: vec![],
+        distinct: false,
+        order_by: vec![],
+        skip: None,
+        limit: None,
+    };
+
+    loads::LoadAnyToLoadExact::apply(&mut plan_before).unwrap();
+    assert_eq!(plan_before, plan_after);
+}
+
+#[test]
+fn load_exact_only_for_independent_ids() {
+    let plan = QueryPlan {
+        parts: vec![],
+        steps: vec![
+            MatchStep::LoadAnyNode { name: 0 },
+            MatchStep::LoadOriginEdge { name: 1, node: 0 },
+            MatchStep::LoadTargetNode { name: 2, edge: 1 },
+            MatchStep::LoadAnyNode { name: 3 },
+            MatchStep::Filter(Filter::NodeHasId {
+                node: 0,
+                id: LoadProperty::TargetOfEdge { edge: 1 },
+            }),
+            MatchStep::Filter(Filter::NodeHasId {
+                node: 3,
+                id: LoadProperty::PropertyOfNode {
+                    node: 2,
+                    key: "friend",
+                },
+            }),
+        ],
+        updates: vec![],
+        returns: vec![],
+        distinct: false,
+        order_by: vec![],
+        skip: None,
+        limit: None,
+    };
+
+    let mut plan_copy = plan.clone();
+    loads::ReorderIdConstrainedFirst::apply(&mut plan_copy).unwrap();
+    loads::LoadAnyToLoadExact::apply(&mut plan_copy).unwrap();
+    assert_eq!(plan, plan_copy);
+}
+
+#[test]
+fn load_any_node_to_load_labeled_node() {
+    let mut plan_before = QueryPlan {
+        parts: vec![],
+        steps: vec![
+            MatchStep::LoadAnyNode { name: 0 },
+            MatchStep::Filter(Filter::NodeHasLabel {
+                node: 0,
+                label: "PERSON",
+            }),
+            MatchStep::Filter(Filter::NodeHasLabel {
+                node: 0,
+                label: "ADMIN",
+            }),
+        ],
+        updates: vec![],
+        returns: vec![],
+        distinct: false,
+        order_by: vec![],
+        skip: None,
+        limit: None,
+    };
+    let plan_after = QueryPlan {
+        parts: vec![],
+        steps: vec![MatchStep::LoadLabeledNode {
+            name: 0,
+            labels: vec!["PERSON", "ADMIN"],
+        }],
+        updates: vec![],
+        returns: vec![],
+        distinct: false,
+        order_by: vec![],
+        skip: None,
+        limit: None,
+    };
+
+    loads::LoadAnyToLoadLabeled::apply(&mut plan_before).unwrap();
+    assert_eq!(plan_before, plan_after);
+}
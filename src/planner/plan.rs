@@ -1,11 +1,36 @@
+use crate::parser::ast::{Aggregate, BinaryOp, Direction, Temporal};
 use crate::store::PropRef;
 use std::cmp::{Ordering, PartialOrd};
 
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) struct QueryPlan<'src> {
+    pub parts: Vec<QueryPart<'src>>,
     pub steps: Vec<MatchStep<'src>>,
     pub updates: Vec<UpdateStep<'src>>,
     pub returns: Vec<LoadProperty<'src>>,
+    /// Only returns the first of any equal rows.
+    pub distinct: bool,
+    pub order_by: Vec<SortKey<'src>>,
+    pub skip: Option<LoadProperty<'src>>,
+    pub limit: Option<LoadProperty<'src>>,
+}
+
+/// A part of the query plan which is followed by `WITH` or
+/// `UNWIND`. Parts are matched in order, and once the steps and
+/// updates of a part are done, `values` are bound to their names
+/// before continuing with the next part.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct QueryPart<'src> {
+    pub steps: Vec<MatchStep<'src>>,
+    pub updates: Vec<UpdateStep<'src>>,
+    pub values: Vec<(usize, LoadProperty<'src>)>,
+    /// If set, the next part is only continued for the first
+    /// match with any given combination of these values.
+    pub distinct: Option<Vec<LoadProperty<'src>>>,
+    /// If set, each element of the list is bound to the
+    /// name in turn, and the next part is continued for
+    /// every one of them.
+    pub unwind: Option<(usize, LoadProperty<'src>)>,
 }
 
 /// A step in the logical query plan. The execution model
@@ -18,7 +43,8 @@ pub(crate) struct QueryPlan<'src> {
 pub(crate) enum MatchStep<'src> {
     LoadAnyNode { name: usize },
     LoadExactNode { name: usize, id: LoadProperty<'src> },
-    LoadLabeledNode { name: usize, label: &'src str },
+    LoadExactNodes { name: usize, ids: LoadProperty<'src> },
+    LoadLabeledNode { name: usize, labels: Vec<&'src str> },
     LoadOriginNode { name: usize, edge: usize },
     LoadTargetNode { name: usize, edge: usize },
     LoadOtherNode { name: usize, node: usize, edge: usize },
@@ -27,7 +53,54 @@ pub(crate) enum MatchStep<'src> {
     LoadTargetEdge { name: usize, node: usize },
     LoadEitherEdge { name: usize, node: usize },
 
+    /// Load every path of `min` to `max` edges which starts at `node`,
+    /// and the node `end` in which it ends. Every edge in the path must
+    /// have `label` and `properties`, if given.
+    LoadPath {
+        name: usize,
+        node: usize,
+        end: usize,
+        direction: Direction,
+        label: Option<&'src str>,
+        properties: Vec<(&'src str, LoadProperty<'src>)>,
+        min: usize,
+        max: Option<usize>,
+    },
+    /// Load the shortest paths of `min` to `max` edges from `node` to
+    /// `end`, which have both been loaded before. If `all` is false,
+    /// only one shortest path is loaded. If `load_end` is set, `end`
+    /// is not loaded before, and is loaded as each node reached from
+    /// `node` in turn.
+    LoadShortestPath {
+        name: usize,
+        node: usize,
+        end: usize,
+        load_end: bool,
+        direction: Direction,
+        label: Option<&'src str>,
+        properties: Vec<(&'src str, LoadProperty<'src>)>,
+        min: usize,
+        max: Option<usize>,
+        all: bool,
+    },
+    /// Bind the path formed by `start` and the nodes, edges,
+    /// and paths of `segments`, which must all be loaded.
+    LoadNamedPath { name: usize, start: usize, segments: Vec<PathSegment> },
+
     Filter(Filter<'src>),
+
+    /// Match the contained steps if possible, otherwise
+    /// continue once with any names they introduce set to
+    /// `NULL`.
+    Optional(Vec<MatchStep<'src>>),
+}
+
+/// A piece of a named path, which continues from the
+/// node at which the previous segment ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PathSegment {
+    Edge { edge: usize, node: usize },
+    Path { path: usize },
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -36,20 +109,52 @@ pub(crate) enum Filter<'src> {
     Or(Box<Filter<'src>>, Box<Filter<'src>>),
     Not(Box<Filter<'src>>),
 
-    IsOrigin { node: usize, edge: usize },
-    IsTarget { node: usize, edge: usize },
+    IsOrigin {
+        node: usize,
+        edge: usize,
+    },
+    IsTarget {
+        node: usize,
+        edge: usize,
+    },
 
-    NodeHasLabel { node: usize, label: &'src str },
-    EdgeHasLabel { edge: usize, label: &'src str },
+    NodeHasLabel {
+        node: usize,
+        label: &'src str,
+    },
+    EdgeHasLabel {
+        edge: usize,
+        label: &'src str,
+    },
 
-    NodeHasId { node: usize, id: LoadProperty<'src> },
-    EdgeHasId { edge: usize, id: LoadProperty<'src> },
+    NodeHasId {
+        node: usize,
+        id: LoadProperty<'src>,
+    },
+    EdgeHasId {
+        edge: usize,
+        id: LoadProperty<'src>,
+    },
 
     IsTruthy(LoadProperty<'src>),
 
     Eq(LoadProperty<'src>, LoadProperty<'src>),
     Lt(LoadProperty<'src>, LoadProperty<'src>),
     Gt(LoadProperty<'src>, LoadProperty<'src>),
+
+    StartsWith(LoadProperty<'src>, LoadProperty<'src>),
+    EndsWith(LoadProperty<'src>, LoadProperty<'src>),
+    Contains(LoadProperty<'src>, LoadProperty<'src>),
+    /// The value on the left is an element of
+    /// the list on the right.
+    In(LoadProperty<'src>, LoadProperty<'src>),
+    /// The lists on both sides have no
+    /// element in common.
+    Disjoint(LoadProperty<'src>, LoadProperty<'src>),
+    IsNull(LoadProperty<'src>),
+    /// Matches the text on the left against the
+    /// regular expression on the right.
+    Matches(LoadProperty<'src>, LoadProperty<'src>),
 }
 
 impl<'src> Filter<'src> {
@@ -69,20 +174,131 @@ impl<'src> Filter<'src> {
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) enum LoadProperty<'src> {
     Constant(PropRef<'src>),
-    IdOfNode { node: usize },
-    IdOfEdge { edge: usize },
-    LabelOfNode { node: usize },
-    LabelOfEdge { edge: usize },
-    PropertyOfNode { node: usize, key: &'src str },
-    PropertyOfEdge { edge: usize, key: &'src str },
-    Parameter { name: &'src str },
+    IdOfNode {
+        node: usize,
+    },
+    IdOfEdge {
+        edge: usize,
+    },
+    LabelOfNode {
+        node: usize,
+    },
+    LabelOfEdge {
+        edge: usize,
+    },
+    /// The list of all labels of `node`.
+    LabelsOfNode {
+        node: usize,
+    },
+    PropertyOfNode {
+        node: usize,
+        key: &'src str,
+    },
+    PropertyOfEdge {
+        edge: usize,
+        key: &'src str,
+    },
+    /// A map of all properties of a node.
+    PropertiesOfNode {
+        node: usize,
+    },
+    /// A map of all properties of an edge.
+    PropertiesOfEdge {
+        edge: usize,
+    },
+    /// The id of the node from which an edge originates.
+    OriginOfEdge {
+        edge: usize,
+    },
+    /// The id of the node at which an edge terminates.
+    TargetOfEdge {
+        edge: usize,
+    },
+    /// The ids of the nodes along a path.
+    NodesOfPath {
+        path: usize,
+    },
+    /// The ids of the edges along a path.
+    EdgesOfPath {
+        path: usize,
+    },
+    /// The number of edges in a path.
+    LengthOfPath {
+        path: usize,
+    },
+    Path {
+        path: usize,
+    },
+    Parameter {
+        name: &'src str,
+    },
+    /// A list of the given values.
+    List(Vec<LoadProperty<'src>>),
+    /// A map from the given keys to their values.
+    Map(Vec<(&'src str, LoadProperty<'src>)>),
+    /// Constructs a temporal value from `value`, or the
+    /// current time if there is none.
+    Temporal {
+        function: Temporal,
+        value: Option<Box<LoadProperty<'src>>>,
+    },
+    Binary {
+        op: BinaryOp,
+        lhs: Box<LoadProperty<'src>>,
+        rhs: Box<LoadProperty<'src>>,
+    },
+    /// The result of the first branch whose condition
+    /// holds, or `default` if there is none.
+    Case {
+        branches: Vec<(Filter<'src>, LoadProperty<'src>)>,
+        default: Option<Box<LoadProperty<'src>>>,
+    },
+    /// The first of the values which is not `NULL`.
+    Coalesce(Vec<LoadProperty<'src>>),
+    /// Calls the built-in or user function `name`.
+    Function {
+        name: &'src str,
+        arguments: Vec<LoadProperty<'src>>,
+    },
+    /// A value bound by an earlier part of the query.
+    Value {
+        name: usize,
+    },
+    /// Aggregates `value` over all matches which share the values
+    /// of the non-aggregate returns. Only valid as a return or sort
+    /// key.
+    Aggregate {
+        function: Aggregate,
+        value: Option<Box<LoadProperty<'src>>>,
+    },
+    /// Aggregates `arguments` using the user aggregate
+    /// `name`, like `Aggregate`.
+    UserAggregate {
+        name: &'src str,
+        arguments: Vec<LoadProperty<'src>>,
+    },
+}
+
+impl<'src> LoadProperty<'src> {
+    pub fn is_aggregate(&self) -> bool {
+        matches!(self, Self::Aggregate { .. } | Self::UserAggregate { .. })
+    }
+}
+
+/// A key by which the returned rows are sorted. Keys
+/// are applied in order, later keys break ties between
+/// rows which compare equal for earlier ones.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SortKey<'src> {
+    pub value: LoadProperty<'src>,
+    pub descending: bool,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) enum UpdateStep<'src> {
     CreateNode {
         name: usize,
-        label: &'src str,
+        labels: Vec<&'src str>,
         properties: Vec<(&'src str, LoadProperty<'src>)>,
     },
     CreateEdge {
@@ -92,6 +308,29 @@ pub(crate) enum UpdateStep<'src> {
         target: usize,
         properties: Vec<(&'src str, LoadProperty<'src>)>,
     },
+    /// Load every node having all `labels` and matching
+    /// `properties`, or create one if there is no match.
+    /// The `on_create` and `on_match` updates only set
+    /// properties and labels.
+    MergeNode {
+        name: usize,
+        labels: Vec<&'src str>,
+        properties: Vec<(&'src str, LoadProperty<'src>)>,
+        on_create: Vec<UpdateStep<'src>>,
+        on_match: Vec<UpdateStep<'src>>,
+    },
+    /// Load every edge from `origin` to `target` matching
+    /// `label` and `properties`, or create one if there is
+    /// no match.
+    MergeEdge {
+        name: usize,
+        label: &'src str,
+        origin: usize,
+        target: usize,
+        properties: Vec<(&'src str, LoadProperty<'src>)>,
+        on_create: Vec<UpdateStep<'src>>,
+        on_match: Vec<UpdateStep<'src>>,
+    },
     SetNodeProperty {
         node: usize,
         key: &'src str,
@@ -102,60 +341,75 @@ pub(crate) enum UpdateStep<'src> {
         key: &'src str,
         value: LoadProperty<'src>,
     },
+    /// Set the properties of `node` to the entries of the
+    /// map `value`. If `replace` is set, all other properties
+    /// are removed.
+    SetNodeProperties {
+        node: usize,
+        value: LoadProperty<'src>,
+        replace: bool,
+    },
+    SetEdgeProperties {
+        edge: usize,
+        value: LoadProperty<'src>,
+        replace: bool,
+    },
+    RemoveNodeProperty {
+        node: usize,
+        key: &'src str,
+    },
+    RemoveEdgeProperty {
+        edge: usize,
+        key: &'src str,
+    },
+    SetNodeLabels {
+        node: usize,
+        labels: Vec<&'src str>,
+    },
+    RemoveNodeLabels {
+        node: usize,
+        labels: Vec<&'src str>,
+    },
+    /// Delete `node`, and all edges connected to it
+    /// if `detach` is set.
     DeleteNode {
         node: usize,
+        detach: bool,
     },
     DeleteEdge {
         edge: usize,
     },
 }
 
-impl<'src> PartialOrd for UpdateStep<'src> {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+impl<'src> UpdateStep<'src> {
+    /// Updates are performed in the order: nodes are created, edges
+    /// are created, properties and labels are updated, edges are
+    /// deleted, and nodes are deleted.
+    fn rank(&self) -> u8 {
         use UpdateStep::*;
-        match (self, other) {
-            (CreateNode { .. }, CreateNode { .. }) => Some(Ordering::Equal),
-            (CreateEdge { .. }, CreateEdge { .. }) => Some(Ordering::Equal),
-            (
-                SetNodeProperty { .. } | SetEdgeProperty { .. },
-                SetNodeProperty { .. } | SetEdgeProperty { .. },
-            ) => Some(Ordering::Equal),
-            (DeleteNode { .. }, DeleteNode { .. }) => Some(Ordering::Equal),
-            (DeleteEdge { .. }, DeleteEdge { .. }) => Some(Ordering::Equal),
-
-            (CreateNode { .. }, CreateEdge { .. }) => Some(Ordering::Less),
-            (CreateEdge { .. }, CreateNode { .. }) => Some(Ordering::Greater),
-
-            (
-                CreateNode { .. } | CreateEdge { .. },
-                SetNodeProperty { .. }
-                | SetEdgeProperty { .. }
-                | DeleteNode { .. }
-                | DeleteEdge { .. },
-            ) => Some(Ordering::Less),
-            (
-                SetNodeProperty { .. }
-                | SetEdgeProperty { .. }
-                | DeleteNode { .. }
-                | DeleteEdge { .. },
-                CreateNode { .. } | CreateEdge { .. },
-            ) => Some(Ordering::Greater),
-
-            (
-                SetNodeProperty { .. } | SetEdgeProperty { .. },
-                DeleteNode { .. } | DeleteEdge { .. },
-            ) => Some(Ordering::Less),
-            (
-                DeleteNode { .. } | DeleteEdge { .. },
-                SetNodeProperty { .. } | SetEdgeProperty { .. },
-            ) => Some(Ordering::Greater),
-
-            (DeleteEdge { .. }, DeleteNode { .. }) => Some(Ordering::Less),
-            (DeleteNode { .. }, DeleteEdge { .. }) => Some(Ordering::Greater),
+        match self {
+            CreateNode { .. } | MergeNode { .. } => 0,
+            CreateEdge { .. } | MergeEdge { .. } => 1,
+            SetNodeProperty { .. }
+            | SetEdgeProperty { .. }
+            | SetNodeProperties { .. }
+            | SetEdgeProperties { .. }
+            | RemoveNodeProperty { .. }
+            | RemoveEdgeProperty { .. }
+            | SetNodeLabels { .. }
+            | RemoveNodeLabels { .. } => 2,
+            DeleteEdge { .. } => 3,
+            DeleteNode { .. } => 4,
         }
     }
 }
 
+impl<'src> PartialOrd for UpdateStep<'src> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.rank().cmp(&other.rank()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -163,7 +417,10 @@ mod tests {
     #[test]
     fn update_step_order() {
         let mut steps = vec![
-            UpdateStep::DeleteNode { node: 1 },
+            UpdateStep::DeleteNode {
+                node: 1,
+                detach: false,
+            },
             UpdateStep::SetEdgeProperty {
                 edge: 0,
                 key: "test",
@@ -190,7 +447,10 @@ mod tests {
                 value: LoadProperty::Parameter { name: "test" },
             },
             UpdateStep::DeleteEdge { edge: 2 },
-            UpdateStep::DeleteNode { node: 1 },
+            UpdateStep::DeleteNode {
+                node: 1,
+                detach: false,
+            },
         ];
 
         assert_eq!(steps, steps_ord);
@@ -1,5 +1,7 @@
-use crate::store::PropOwned;
+use crate::store::{self, PropOwned};
 use crate::Error;
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, Utc};
+use std::collections::BTreeMap;
 use std::convert::{TryFrom, TryInto};
 
 /// A single property which can be stored on a node or edge.
@@ -12,9 +14,40 @@ pub enum Property {
     Text(String),
     Blob(Vec<u8>),
     Null,
+    List(Vec<Property>),
+    Map(BTreeMap<String, Property>),
+    Path(Path),
+    Date(NaiveDate),
+    /// A point in time, with the UTC offset it was
+    /// created with.
+    DateTime(DateTime<FixedOffset>),
+    Duration(Duration),
+}
+
+/// A path through the graph, as bound by a named pattern
+/// like `p = (a) -[*]-> (b)`. Consecutive nodes are connected
+/// by the edge at the same position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path {
+    pub nodes: Vec<u64>,
+    pub edges: Vec<u64>,
+}
+
+impl Path {
+    /// The number of edges in the path.
+    pub fn len(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Returns `true` if the path consists of a
+    /// single node.
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
 }
 
 impl PropOwned {
+    #[allow(clippy::wrong_self_convention)]
     pub(crate) fn to_external(self) -> Property {
         match self {
             Self::Id(id) => Property::Id(id),
@@ -24,11 +57,37 @@ impl PropOwned {
             Self::Text(text) => Property::Text(text),
             Self::Blob(bytes) => Property::Blob(bytes),
             Self::Null => Property::Null,
+            Self::List(list) => {
+                Property::List(list.into_iter().map(PropOwned::to_external).collect())
+            }
+            Self::Map(map) => Property::Map(
+                map.into_iter()
+                    .map(|(key, prop)| (key, prop.to_external()))
+                    .collect(),
+            ),
+            Self::Path(path) => {
+                let ids = |ids: Vec<PropOwned>| {
+                    ids.into_iter()
+                        .filter_map(|id| match id {
+                            PropOwned::Id(id) => Some(id),
+                            _ => None,
+                        })
+                        .collect()
+                };
+                Property::Path(Path {
+                    nodes: ids(path.nodes),
+                    edges: ids(path.edges),
+                })
+            }
+            Self::Date(date) => Property::Date(date),
+            Self::DateTime(datetime) => Property::DateTime(datetime),
+            Self::Duration(duration) => Property::Duration(duration),
         }
     }
 }
 
 impl Property {
+    #[allow(clippy::wrong_self_convention)]
     pub(crate) fn to_internal(self) -> PropOwned {
         match self {
             Self::Id(id) => PropOwned::Id(id),
@@ -38,6 +97,21 @@ impl Property {
             Self::Text(text) => PropOwned::Text(text),
             Self::Blob(bytes) => PropOwned::Blob(bytes),
             Self::Null => PropOwned::Null,
+            Self::List(list) => {
+                PropOwned::List(list.into_iter().map(Property::to_internal).collect())
+            }
+            Self::Map(map) => PropOwned::Map(
+                map.into_iter()
+                    .map(|(key, prop)| (key, prop.to_internal()))
+                    .collect(),
+            ),
+            Self::Path(path) => PropOwned::Path(store::Path {
+                nodes: path.nodes.into_iter().map(PropOwned::Id).collect(),
+                edges: path.edges.into_iter().map(PropOwned::Id).collect(),
+            }),
+            Self::Date(date) => PropOwned::Date(date),
+            Self::DateTime(datetime) => PropOwned::DateTime(datetime),
+            Self::Duration(duration) => PropOwned::Duration(duration),
         }
     }
 }
@@ -102,10 +176,17 @@ try_from!(f64, Real);
 try_from!(bool, Boolean);
 try_from!(String, Text);
 try_from!(Vec<u8>, Blob);
+try_from!(Vec<Property>, List);
+try_from!(BTreeMap<String, Property>, Map);
+try_from!(Path, Path);
+try_from!(NaiveDate, Date);
+try_from!(DateTime<FixedOffset>, DateTime);
+try_from!(Duration, Duration);
 
 from!(i32, Integer);
 from!(&str, Text);
 from!(&[u8], Blob);
+from!(DateTime<Utc>, DateTime);
 
 impl<const N: usize> From<[u8; N]> for Property {
     fn from(value: [u8; N]) -> Self {
@@ -0,0 +1,148 @@
+use crate::parser::ast::Aggregate;
+use crate::store::{PropOwned, PropRef};
+use crate::Error;
+use std::any::Any;
+use std::cmp::Ordering;
+use std::fmt;
+use std::sync::Arc;
+
+/// An aggregate function registered by the user. The state
+/// of each group is created by `init`, and is opaque to the VM.
+pub(crate) trait UserAggregate: Send + Sync {
+    fn init(&self) -> Box<dyn Any>;
+    fn step(&self, state: &mut dyn Any, arguments: &[PropOwned]) -> Result<(), Error>;
+    fn finish(&self, state: Box<dyn Any>) -> Result<PropOwned, Error>;
+}
+
+/// An aggregate function, which is either built-in
+/// or registered by the user.
+#[derive(Clone)]
+pub(crate) enum Aggregator {
+    Builtin(Aggregate),
+    User(Arc<dyn UserAggregate>),
+}
+
+impl fmt::Debug for Aggregator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Builtin(function) => function.fmt(f),
+            Self::User(aggregate) => write!(f, "User({:p})", Arc::as_ptr(aggregate)),
+        }
+    }
+}
+
+impl PartialEq for Aggregator {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Builtin(a), Self::Builtin(b)) => a == b,
+            (Self::User(a), Self::User(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Aggregator {}
+
+/// The running state of an aggregate function over
+/// the matches of a single group.
+pub(crate) enum Accumulator {
+    Count(i64),
+    Sum(PropOwned),
+    Avg { sum: f64, count: i64 },
+    Min(PropOwned),
+    Max(PropOwned),
+    Collect(Vec<PropOwned>),
+    User(Arc<dyn UserAggregate>, Box<dyn Any>),
+}
+
+impl Accumulator {
+    pub fn new(function: &Aggregator) -> Self {
+        match function {
+            Aggregator::Builtin(Aggregate::Count) => Self::Count(0),
+            Aggregator::Builtin(Aggregate::Sum) => Self::Sum(PropOwned::Integer(0)),
+            Aggregator::Builtin(Aggregate::Avg) => Self::Avg { sum: 0.0, count: 0 },
+            Aggregator::Builtin(Aggregate::Min) => Self::Min(PropOwned::Null),
+            Aggregator::Builtin(Aggregate::Max) => Self::Max(PropOwned::Null),
+            Aggregator::Builtin(Aggregate::Collect) => Self::Collect(Vec::new()),
+            Aggregator::User(aggregate) => Self::User(aggregate.clone(), aggregate.init()),
+        }
+    }
+
+    /// Add the arguments of a match to the aggregate. Built-in
+    /// aggregates take a single argument, or none for `count(*)`,
+    /// and ignore `NULL` values. User aggregates are passed all
+    /// arguments as they are.
+    pub fn step(&mut self, arguments: &[PropOwned]) -> Result<(), Error> {
+        if let Self::User(aggregate, state) = self {
+            return aggregate.step(state.as_mut(), arguments);
+        }
+        let value = match arguments.first().map(PropOwned::to_ref) {
+            Some(PropRef::Null) => return Ok(()),
+            Some(value) => value,
+            None => {
+                return match self {
+                    Self::Count(count) => {
+                        *count += 1;
+                        Ok(())
+                    }
+                    _ => Err(Error::Internal),
+                }
+            }
+        };
+        match self {
+            Self::Count(count) => *count += 1,
+            Self::Sum(sum) => {
+                *sum = match (&*sum, value) {
+                    (PropOwned::Integer(lhs), PropRef::Integer(rhs)) => lhs
+                        .checked_add(rhs)
+                        .map(PropOwned::Integer)
+                        .ok_or(Error::TypeMismatch)?,
+                    (PropOwned::Integer(lhs), PropRef::Real(rhs)) => {
+                        PropOwned::Real(*lhs as f64 + rhs)
+                    }
+                    (PropOwned::Real(lhs), PropRef::Integer(rhs)) => {
+                        PropOwned::Real(lhs + rhs as f64)
+                    }
+                    (PropOwned::Real(lhs), PropRef::Real(rhs)) => PropOwned::Real(lhs + rhs),
+                    _ => return Err(Error::TypeMismatch),
+                };
+            }
+            Self::Avg { sum, count } => {
+                *sum += match value {
+                    PropRef::Integer(num) => num as f64,
+                    PropRef::Real(num) => num,
+                    _ => return Err(Error::TypeMismatch),
+                };
+                *count += 1;
+            }
+            Self::Min(min) => {
+                if *min == PropOwned::Null || value.order(&min.to_ref()) == Ordering::Less {
+                    *min = value.to_owned();
+                }
+            }
+            Self::Max(max) => {
+                if *max == PropOwned::Null || value.order(&max.to_ref()) == Ordering::Greater {
+                    *max = value.to_owned();
+                }
+            }
+            Self::Collect(list) => list.push(value.to_owned()),
+            Self::User(_, _) => return Err(Error::Internal),
+        }
+        Ok(())
+    }
+
+    /// The value of the aggregate after all matches
+    /// have been added.
+    pub fn finish(self) -> Result<PropOwned, Error> {
+        Ok(match self {
+            Self::Count(count) => PropOwned::Integer(count),
+            Self::Sum(sum) => sum,
+            Self::Avg { count: 0, .. } => PropOwned::Null,
+            Self::Avg { sum, count } => PropOwned::Real(sum / count as f64),
+            Self::Min(min) => min,
+            Self::Max(max) => max,
+            Self::Collect(list) => PropOwned::List(list),
+            Self::User(aggregate, state) => aggregate.finish(state)?,
+        })
+    }
+}
@@ -0,0 +1,199 @@
+use super::functions::Function;
+use super::temporal;
+use super::Access;
+use crate::parser::ast::{BinaryOp, Temporal};
+use crate::store::{PropOwned, PropRef};
+use crate::Error;
+use regex::Regex;
+
+/// A single step when evaluating an expression. Expressions
+/// are compiled to a sequence of steps in postfix order, which
+/// pass values to each other using a stack.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Eval {
+    /// Push the value of a plain access.
+    Load(Access),
+    /// Pop `len` values and push them as a list.
+    List(usize),
+    /// Pop a value for each key and push them as a map.
+    Map(Vec<String>),
+    /// Pop the argument, if there is one, and push the
+    /// constructed temporal value.
+    Temporal(Temporal, bool),
+    /// Pop the right and left hand side and push the
+    /// result of the operation.
+    Binary(BinaryOp),
+    /// Pop `len` values and push the first which is
+    /// not null.
+    Coalesce(usize),
+    /// Pop `len` arguments and push the result of
+    /// calling the function.
+    Call(Function, usize),
+    /// Pop the right and left hand side and push
+    /// whether the comparison holds.
+    Compare(Comparison),
+    /// Pop a value and push whether it is null.
+    IsNull,
+    /// Pop a value and push whether it is truthy.
+    Truthy,
+    /// Pop a value and push whether it is not truthy.
+    Not,
+    /// Pop two values and push whether both are truthy.
+    And,
+    /// Pop two values and push whether either is truthy.
+    Or,
+    /// Continue at step `jump`.
+    Jump(usize),
+    /// Pop a value and continue at step `jump` if it
+    /// is not truthy.
+    JumpUnless(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Comparison {
+    Eq,
+    Lt,
+    Gt,
+    StartsWith,
+    EndsWith,
+    Contains,
+    /// The left hand side is an element of the list
+    /// on the right.
+    In,
+    /// The lists on both sides have no element
+    /// in common.
+    Disjoint,
+    /// The left hand side fully matches the regular
+    /// expression on the right.
+    Matches,
+}
+
+/// Evaluate compiled expression steps, where `load` provides
+/// the values of plain accesses.
+pub(crate) fn evaluate<F>(code: &[Eval], mut load: F) -> Result<PropOwned, Error>
+where
+    F: FnMut(&Access) -> Result<PropOwned, Error>,
+{
+    let mut stack: Vec<PropOwned> = Vec::new();
+    let mut current = 0;
+    while let Some(step) = code.get(current) {
+        current += 1;
+        let value = match step {
+            Eval::Load(access) => load(access)?,
+            Eval::List(len) => {
+                let start = stack.len().checked_sub(*len).ok_or(Error::Internal)?;
+                PropOwned::List(stack.split_off(start))
+            }
+            Eval::Map(keys) => {
+                let start = stack.len().checked_sub(keys.len()).ok_or(Error::Internal)?;
+                PropOwned::Map(keys.iter().cloned().zip(stack.split_off(start)).collect())
+            }
+            Eval::Temporal(function, has_argument) => {
+                let argument = if *has_argument {
+                    Some(stack.pop().ok_or(Error::Internal)?)
+                } else {
+                    None
+                };
+                temporal::construct(*function, argument.as_ref().map(PropOwned::to_ref))?
+            }
+            Eval::Binary(op) => {
+                let rhs = stack.pop().ok_or(Error::Internal)?;
+                let lhs = stack.pop().ok_or(Error::Internal)?;
+                let (lhs, rhs) = (lhs.to_ref(), rhs.to_ref());
+                match op {
+                    BinaryOp::Add => lhs.add(&rhs)?,
+                    BinaryOp::Sub => lhs.sub(&rhs)?,
+                    BinaryOp::Mul => lhs.mul(&rhs)?,
+                    BinaryOp::Div => lhs.div(&rhs)?,
+                    BinaryOp::Mod => lhs.rem(&rhs)?,
+                    BinaryOp::Pow => lhs.pow(&rhs)?,
+                }
+            }
+            Eval::Coalesce(len) => {
+                let start = stack.len().checked_sub(*len).ok_or(Error::Internal)?;
+                stack
+                    .split_off(start)
+                    .into_iter()
+                    .find(|value| !matches!(value, PropOwned::Null))
+                    .unwrap_or(PropOwned::Null)
+            }
+            Eval::Call(function, len) => {
+                let start = stack.len().checked_sub(*len).ok_or(Error::Internal)?;
+                let arguments = stack.split_off(start);
+                function.call(&arguments)?
+            }
+            Eval::Compare(comparison) => {
+                let rhs = stack.pop().ok_or(Error::Internal)?;
+                let lhs = stack.pop().ok_or(Error::Internal)?;
+                PropOwned::Boolean(compare(*comparison, &lhs.to_ref(), &rhs.to_ref())?)
+            }
+            Eval::IsNull => {
+                let value = stack.pop().ok_or(Error::Internal)?;
+                PropOwned::Boolean(matches!(value, PropOwned::Null))
+            }
+            Eval::Truthy => {
+                let value = stack.pop().ok_or(Error::Internal)?;
+                PropOwned::Boolean(value.to_ref().is_truthy())
+            }
+            Eval::Not => {
+                let value = stack.pop().ok_or(Error::Internal)?;
+                PropOwned::Boolean(!value.to_ref().is_truthy())
+            }
+            Eval::And => {
+                let rhs = stack.pop().ok_or(Error::Internal)?;
+                let lhs = stack.pop().ok_or(Error::Internal)?;
+                PropOwned::Boolean(lhs.to_ref().is_truthy() && rhs.to_ref().is_truthy())
+            }
+            Eval::Or => {
+                let rhs = stack.pop().ok_or(Error::Internal)?;
+                let lhs = stack.pop().ok_or(Error::Internal)?;
+                PropOwned::Boolean(lhs.to_ref().is_truthy() || rhs.to_ref().is_truthy())
+            }
+            Eval::Jump(jump) => {
+                current = *jump;
+                continue;
+            }
+            Eval::JumpUnless(jump) => {
+                if !stack.pop().ok_or(Error::Internal)?.to_ref().is_truthy() {
+                    current = *jump;
+                }
+                continue;
+            }
+        };
+        stack.push(value);
+    }
+    match (stack.pop(), stack.is_empty()) {
+        (Some(value), true) => Ok(value),
+        _ => Err(Error::Internal),
+    }
+}
+
+/// Compile the pattern of a `=~` predicate, which
+/// must match the whole text.
+pub(crate) fn regex(pattern: &str) -> Result<Regex, Error> {
+    Regex::new(&format!("^(?:{})$", pattern)).map_err(|err| Error::InvalidRegex(err.to_string()))
+}
+
+/// Check if the comparison holds. This matches the
+/// conditional checks of the virtual machine.
+fn compare(comparison: Comparison, lhs: &PropRef, rhs: &PropRef) -> Result<bool, Error> {
+    use std::cmp::Ordering;
+    Ok(match (comparison, lhs, rhs) {
+        (Comparison::Eq, lhs, rhs) => lhs.loosely_equals(rhs),
+        (Comparison::Lt, lhs, rhs) => lhs.loosely_compare(rhs) == Some(Ordering::Less),
+        (Comparison::Gt, lhs, rhs) => lhs.loosely_compare(rhs) == Some(Ordering::Greater),
+        (Comparison::StartsWith, PropRef::Text(lhs), PropRef::Text(rhs)) => lhs.starts_with(rhs),
+        (Comparison::EndsWith, PropRef::Text(lhs), PropRef::Text(rhs)) => lhs.ends_with(rhs),
+        (Comparison::Contains, PropRef::Text(lhs), PropRef::Text(rhs)) => lhs.contains(rhs),
+        (Comparison::In, value, PropRef::List(list)) => {
+            list.iter().any(|item| value.loosely_equals(&item.to_ref()))
+        }
+        (Comparison::Disjoint, PropRef::List(lhs), PropRef::List(rhs)) => !lhs
+            .iter()
+            .any(|a| rhs.iter().any(|b| a.to_ref().loosely_equals(&b.to_ref()))),
+        (Comparison::Matches, PropRef::Text(value), PropRef::Text(pattern)) => {
+            regex(pattern)?.is_match(value)
+        }
+        _ => false,
+    })
+}
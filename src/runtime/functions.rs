@@ -0,0 +1,332 @@
+use super::aggregate::{Aggregator, UserAggregate};
+use super::temporal;
+use crate::store::PropOwned;
+use crate::Error;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// A built-in scalar function, which maps its arguments to a
+/// single value. Functions are looked up by their name, ignoring
+/// case, such that new functions only need an entry in `BUILTINS`.
+pub(crate) struct Builtin {
+    pub name: &'static str,
+    pub min_args: usize,
+    pub max_args: usize,
+    pub call: fn(&[PropOwned]) -> Result<PropOwned, Error>,
+}
+
+impl fmt::Debug for Builtin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl PartialEq for Builtin {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Builtin {
+    const fn new(
+        name: &'static str,
+        min_args: usize,
+        max_args: usize,
+        call: fn(&[PropOwned]) -> Result<PropOwned, Error>,
+    ) -> Self {
+        Self {
+            name,
+            min_args,
+            max_args,
+            call,
+        }
+    }
+}
+
+const BUILTINS: &[Builtin] = &[
+    Builtin::new("toUpper", 1, 1, |args| text(&args[0], |t| t.to_uppercase())),
+    Builtin::new("toLower", 1, 1, |args| text(&args[0], |t| t.to_lowercase())),
+    Builtin::new("trim", 1, 1, |args| {
+        text(&args[0], |t| t.trim().to_string())
+    }),
+    Builtin::new("substring", 2, 3, substring),
+    Builtin::new("size", 1, 1, size),
+    Builtin::new("abs", 1, 1, abs),
+    Builtin::new("round", 1, 1, |args| real(&args[0], f64::round)),
+    Builtin::new("floor", 1, 1, |args| real(&args[0], f64::floor)),
+    Builtin::new("ceil", 1, 1, |args| real(&args[0], f64::ceil)),
+    Builtin::new("sqrt", 1, 1, |args| real(&args[0], f64::sqrt)),
+    Builtin::new("toInteger", 1, 1, to_integer),
+    Builtin::new("toFloat", 1, 1, to_float),
+    Builtin::new("toString", 1, 1, to_string),
+    Builtin::new("toBoolean", 1, 1, to_boolean),
+    Builtin::new("keys", 1, 1, keys),
+    Builtin::new("properties", 1, 1, properties),
+];
+
+/// Look up the built-in function with the given name.
+pub(crate) fn builtin(name: &str) -> Option<&'static Builtin> {
+    BUILTINS
+        .iter()
+        .find(|builtin| builtin.name.eq_ignore_ascii_case(name))
+}
+
+/// The implementation of a function registered by the user.
+pub(crate) type Callback = dyn Fn(&[PropOwned]) -> Result<PropOwned, Error> + Send + Sync;
+
+/// A function which can be called from a compiled expression.
+#[derive(Clone)]
+pub(crate) enum Function {
+    Builtin(&'static Builtin),
+    User(Arc<Callback>),
+}
+
+impl Function {
+    pub fn call(&self, arguments: &[PropOwned]) -> Result<PropOwned, Error> {
+        match self {
+            Self::Builtin(builtin) => (builtin.call)(arguments),
+            Self::User(callback) => callback(arguments),
+        }
+    }
+
+    /// Calls to built-in functions only depend on their
+    /// arguments and may be evaluated when compiling.
+    pub fn is_pure(&self) -> bool {
+        matches!(self, Self::Builtin(_))
+    }
+}
+
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Builtin(builtin) => builtin.fmt(f),
+            Self::User(callback) => write!(f, "User({:p})", Arc::as_ptr(callback)),
+        }
+    }
+}
+
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Builtin(a), Self::Builtin(b)) => a == b,
+            (Self::User(a), Self::User(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+/// The functions registered with a graph. User functions
+/// take precedence over built-in functions of the same name.
+#[derive(Clone, Default)]
+pub(crate) struct Functions {
+    user: HashMap<String, (usize, Arc<Callback>)>,
+    aggregates: HashMap<String, (usize, Arc<dyn UserAggregate>)>,
+}
+
+impl Functions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a function, replacing any previously
+    /// registered function with the same name.
+    pub fn register(&mut self, name: &str, arity: usize, callback: Arc<Callback>) {
+        let name = name.to_lowercase();
+        self.aggregates.remove(&name);
+        self.user.insert(name, (arity, callback));
+    }
+
+    /// Register an aggregate function, replacing any previously
+    /// registered function with the same name.
+    pub fn register_aggregate(
+        &mut self,
+        name: &str,
+        arity: usize,
+        aggregate: Arc<dyn UserAggregate>,
+    ) {
+        let name = name.to_lowercase();
+        self.user.remove(&name);
+        self.aggregates.insert(name, (arity, aggregate));
+    }
+
+    pub fn is_aggregate(&self, name: &str) -> bool {
+        self.aggregates.contains_key(&name.to_lowercase())
+    }
+
+    /// Resolve the aggregate function which is called
+    /// with `len` arguments.
+    pub fn resolve_aggregate(&self, name: &str, len: usize) -> Result<Aggregator, Error> {
+        match self.aggregates.get(&name.to_lowercase()) {
+            Some((arity, aggregate)) if *arity == len => Ok(Aggregator::User(aggregate.clone())),
+            Some(_) => Err(Error::TypeMismatch),
+            None => Err(Error::UnknownIdentifier(name.to_string())),
+        }
+    }
+
+    /// Resolve the function which is called with `len`
+    /// arguments.
+    pub fn resolve(&self, name: &str, len: usize) -> Result<Function, Error> {
+        if let Some((arity, callback)) = self.user.get(&name.to_lowercase()) {
+            if len != *arity {
+                return Err(Error::TypeMismatch);
+            }
+            Ok(Function::User(callback.clone()))
+        } else {
+            let builtin =
+                builtin(name).ok_or_else(|| Error::UnknownIdentifier(name.to_string()))?;
+            if len < builtin.min_args || len > builtin.max_args {
+                return Err(Error::TypeMismatch);
+            }
+            Ok(Function::Builtin(builtin))
+        }
+    }
+}
+
+fn text(arg: &PropOwned, function: fn(&str) -> String) -> Result<PropOwned, Error> {
+    match arg {
+        PropOwned::Text(text) => Ok(PropOwned::Text(function(text))),
+        PropOwned::Null => Ok(PropOwned::Null),
+        _ => Err(Error::TypeMismatch),
+    }
+}
+
+fn real(arg: &PropOwned, function: fn(f64) -> f64) -> Result<PropOwned, Error> {
+    match *arg {
+        PropOwned::Integer(num) => Ok(PropOwned::Real(function(num as f64))),
+        PropOwned::Real(num) => Ok(PropOwned::Real(function(num))),
+        PropOwned::Null => Ok(PropOwned::Null),
+        _ => Err(Error::TypeMismatch),
+    }
+}
+
+/// e.g. `substring('hello', 1, 3)` gives 'ell', where the
+/// start and length count characters.
+fn substring(args: &[PropOwned]) -> Result<PropOwned, Error> {
+    let count = |arg: Option<&PropOwned>| match arg {
+        None => Ok(None),
+        Some(&PropOwned::Integer(num)) if num >= 0 => Ok(Some(num as usize)),
+        Some(_) => Err(Error::TypeMismatch),
+    };
+    match &args[0] {
+        PropOwned::Text(text) => {
+            let start = count(args.get(1))?.unwrap_or(0);
+            let length = count(args.get(2))?.unwrap_or(usize::MAX);
+            Ok(PropOwned::Text(
+                text.chars().skip(start).take(length).collect(),
+            ))
+        }
+        PropOwned::Null => Ok(PropOwned::Null),
+        _ => Err(Error::TypeMismatch),
+    }
+}
+
+fn size(args: &[PropOwned]) -> Result<PropOwned, Error> {
+    match &args[0] {
+        PropOwned::Text(text) => Ok(PropOwned::Integer(text.chars().count() as i64)),
+        PropOwned::List(list) => Ok(PropOwned::Integer(list.len() as i64)),
+        PropOwned::Map(map) => Ok(PropOwned::Integer(map.len() as i64)),
+        PropOwned::Null => Ok(PropOwned::Null),
+        _ => Err(Error::TypeMismatch),
+    }
+}
+
+fn abs(args: &[PropOwned]) -> Result<PropOwned, Error> {
+    match args[0] {
+        PropOwned::Integer(num) => Ok(num
+            .checked_abs()
+            .map_or(PropOwned::Real((num as f64).abs()), PropOwned::Integer)),
+        PropOwned::Real(num) => Ok(PropOwned::Real(num.abs())),
+        PropOwned::Null => Ok(PropOwned::Null),
+        _ => Err(Error::TypeMismatch),
+    }
+}
+
+/// Converts numbers, text, and booleans to integers. Text
+/// which is not a number, and reals which are out of range,
+/// give `NULL`.
+fn to_integer(args: &[PropOwned]) -> Result<PropOwned, Error> {
+    let truncate = |num: f64| {
+        if num.is_finite() && num >= i64::MIN as f64 && num <= i64::MAX as f64 {
+            PropOwned::Integer(num as i64)
+        } else {
+            PropOwned::Null
+        }
+    };
+    match &args[0] {
+        PropOwned::Integer(num) => Ok(PropOwned::Integer(*num)),
+        PropOwned::Real(num) => Ok(truncate(*num)),
+        PropOwned::Boolean(b) => Ok(PropOwned::Integer(*b as i64)),
+        PropOwned::Text(text) => Ok(match text.trim().parse::<i64>() {
+            Ok(num) => PropOwned::Integer(num),
+            Err(_) => text.trim().parse().map_or(PropOwned::Null, truncate),
+        }),
+        PropOwned::Null => Ok(PropOwned::Null),
+        _ => Err(Error::TypeMismatch),
+    }
+}
+
+/// Converts numbers and text to reals. Text which is
+/// not a number gives `NULL`.
+fn to_float(args: &[PropOwned]) -> Result<PropOwned, Error> {
+    match &args[0] {
+        PropOwned::Integer(num) => Ok(PropOwned::Real(*num as f64)),
+        PropOwned::Real(num) => Ok(PropOwned::Real(*num)),
+        PropOwned::Text(text) => Ok(text.trim().parse().map_or(PropOwned::Null, PropOwned::Real)),
+        PropOwned::Null => Ok(PropOwned::Null),
+        _ => Err(Error::TypeMismatch),
+    }
+}
+
+fn to_string(args: &[PropOwned]) -> Result<PropOwned, Error> {
+    let text = match &args[0] {
+        PropOwned::Integer(num) => num.to_string(),
+        PropOwned::Real(num) => format!("{:?}", num),
+        PropOwned::Boolean(b) => b.to_string(),
+        PropOwned::Text(text) => text.clone(),
+        PropOwned::Date(date) => date.to_string(),
+        PropOwned::DateTime(datetime) => datetime.to_rfc3339(),
+        PropOwned::Duration(duration) => temporal::format_duration(*duration),
+        PropOwned::Null => return Ok(PropOwned::Null),
+        _ => return Err(Error::TypeMismatch),
+    };
+    Ok(PropOwned::Text(text))
+}
+
+/// Converts booleans, integers, and the text 'true' or 'false'
+/// to booleans. Other text gives `NULL`.
+fn to_boolean(args: &[PropOwned]) -> Result<PropOwned, Error> {
+    match &args[0] {
+        PropOwned::Boolean(b) => Ok(PropOwned::Boolean(*b)),
+        PropOwned::Integer(num) => Ok(PropOwned::Boolean(*num != 0)),
+        PropOwned::Text(text) => Ok(match text.trim() {
+            t if t.eq_ignore_ascii_case("true") => PropOwned::Boolean(true),
+            t if t.eq_ignore_ascii_case("false") => PropOwned::Boolean(false),
+            _ => PropOwned::Null,
+        }),
+        PropOwned::Null => Ok(PropOwned::Null),
+        _ => Err(Error::TypeMismatch),
+    }
+}
+
+/// The keys of a map, in order. Nodes and edges are given
+/// as the map of their properties.
+fn keys(args: &[PropOwned]) -> Result<PropOwned, Error> {
+    match &args[0] {
+        PropOwned::Map(map) => Ok(PropOwned::List(
+            map.keys().cloned().map(PropOwned::Text).collect(),
+        )),
+        PropOwned::Null => Ok(PropOwned::Null),
+        _ => Err(Error::TypeMismatch),
+    }
+}
+
+/// The properties of a node or edge, which are given as
+/// a map, or a map itself.
+fn properties(args: &[PropOwned]) -> Result<PropOwned, Error> {
+    match &args[0] {
+        PropOwned::Map(map) => Ok(PropOwned::Map(map.clone())),
+        PropOwned::Null => Ok(PropOwned::Null),
+        _ => Err(Error::TypeMismatch),
+    }
+}
@@ -1,18 +1,28 @@
+mod aggregate;
+mod eval;
+mod functions;
 mod program;
+mod temporal;
 mod vm;
 
+pub(crate) use aggregate::UserAggregate;
+pub(crate) use functions::Functions;
 pub(crate) use program::Program;
 pub(crate) use vm::{Access, Instruction, Status, VirtualMachine};
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::planner::{MatchStep, QueryPlan};
+    use crate::parser::ast::{BinaryOp, Temporal};
+    use crate::planner::{Filter, LoadProperty, MatchStep, QueryPlan};
+    use crate::store::{PropOwned, PropRef};
+    use eval::{Comparison, Eval};
     use vm::*;
 
     #[test]
     fn compile_a_to_b() {
         let plan = QueryPlan {
+            parts: vec![],
             steps: vec![
                 MatchStep::LoadAnyNode { name: 0 },
                 MatchStep::LoadOriginEdge { name: 1, node: 0 },
@@ -20,6 +30,10 @@ mod tests {
             ],
             updates: vec![],
             returns: vec![],
+            distinct: false,
+            order_by: vec![],
+            skip: None,
+            limit: None,
         };
 
         let code = {
@@ -40,6 +54,110 @@ mod tests {
             ]
         };
 
-        assert_eq!(code, Program::new(&plan).unwrap().instructions);
+        assert_eq!(
+            code,
+            Program::new(&plan, &Functions::new()).unwrap().instructions
+        );
+    }
+
+    #[test]
+    fn compile_expressions() {
+        let plan = QueryPlan {
+            parts: vec![],
+            steps: vec![MatchStep::LoadAnyNode { name: 0 }],
+            updates: vec![],
+            returns: vec![
+                LoadProperty::Binary {
+                    op: BinaryOp::Mul,
+                    lhs: Box::new(LoadProperty::Constant(PropRef::Integer(6))),
+                    rhs: Box::new(LoadProperty::Constant(PropRef::Integer(7))),
+                },
+                LoadProperty::List(vec![
+                    LoadProperty::PropertyOfNode {
+                        node: 0,
+                        key: "age",
+                    },
+                    LoadProperty::Temporal {
+                        function: Temporal::DateTime,
+                        value: None,
+                    },
+                ]),
+            ],
+            distinct: false,
+            order_by: vec![],
+            skip: None,
+            limit: None,
+        };
+
+        // constant expressions are evaluated once
+        let returns = vec![
+            Access::Constant(PropOwned::Integer(42)),
+            Access::Expression(vec![
+                Eval::Load(Access::NodeProperty(0, "age".to_string())),
+                Eval::Temporal(Temporal::DateTime, false),
+                Eval::List(2),
+            ]),
+        ];
+
+        assert_eq!(
+            returns,
+            Program::new(&plan, &Functions::new()).unwrap().returns
+        );
+    }
+
+    #[test]
+    fn compile_case_expressions() {
+        let plan = QueryPlan {
+            parts: vec![],
+            steps: vec![MatchStep::LoadAnyNode { name: 0 }],
+            updates: vec![],
+            returns: vec![LoadProperty::Case {
+                branches: vec![
+                    (
+                        Filter::IsNull(LoadProperty::PropertyOfNode {
+                            node: 0,
+                            key: "age",
+                        }),
+                        LoadProperty::Constant(PropRef::Integer(0)),
+                    ),
+                    (
+                        Filter::Lt(
+                            LoadProperty::PropertyOfNode {
+                                node: 0,
+                                key: "age",
+                            },
+                            LoadProperty::Constant(PropRef::Integer(18)),
+                        ),
+                        LoadProperty::Constant(PropRef::Integer(1)),
+                    ),
+                ],
+                default: None,
+            }],
+            distinct: false,
+            order_by: vec![],
+            skip: None,
+            limit: None,
+        };
+
+        let age = || Eval::Load(Access::NodeProperty(0, "age".to_string()));
+        let returns = vec![Access::Expression(vec![
+            age(),
+            Eval::IsNull,
+            Eval::JumpUnless(5),
+            Eval::Load(Access::Constant(PropOwned::Integer(0))),
+            Eval::Jump(12),
+            age(),
+            Eval::Load(Access::Constant(PropOwned::Integer(18))),
+            Eval::Compare(Comparison::Lt),
+            Eval::JumpUnless(11),
+            Eval::Load(Access::Constant(PropOwned::Integer(1))),
+            Eval::Jump(12),
+            Eval::Load(Access::Constant(PropOwned::Null)),
+        ])];
+
+        assert_eq!(
+            returns,
+            Program::new(&plan, &Functions::new()).unwrap().returns
+        );
     }
 }
@@ -1,5 +1,11 @@
-use crate::planner::{Filter, LoadProperty, MatchStep, QueryPlan, UpdateStep};
+use super::aggregate::Aggregator;
+use super::eval::{self, Comparison, Eval};
+use super::functions::Functions;
+use crate::planner::{
+    Filter, LoadProperty, MatchStep, PathSegment, QueryPart, QueryPlan, UpdateStep,
+};
 use crate::runtime::{Access, Instruction};
+use crate::store::{PropOwned, PropRef};
 use crate::Error;
 use std::collections::HashMap;
 
@@ -10,24 +16,38 @@ pub(crate) struct Program {
     pub instructions: Vec<Instruction>,
     pub accesses: Vec<Access>,
     pub returns: Vec<Access>,
+    /// Programs which run one after the other once this
+    /// one halts, as for the later queries of a `UNION`.
+    pub union: Vec<Program>,
+    /// Only returns the first of any equal rows produced
+    /// by this program and its union.
+    pub distinct: bool,
 }
 
-struct CompileEnv {
+struct CompileEnv<'f> {
+    functions: &'f Functions,
     names: HashMap<usize, usize>, // map names to stack position
     node_stack_len: usize,
     edge_stack_len: usize,
+    path_stack_len: usize,
+    value_stack_len: usize,
+    part: usize, // index of the query part being compiled
 
     instructions: Vec<Instruction>,
     accesses: Vec<Access>,
     returns: Vec<Access>,
 }
 
-impl CompileEnv {
-    pub fn new() -> Self {
+impl<'f> CompileEnv<'f> {
+    pub fn new(functions: &'f Functions) -> Self {
         Self {
+            functions,
             names: HashMap::new(),
             node_stack_len: 0,
             edge_stack_len: 0,
+            path_stack_len: 0,
+            value_stack_len: 0,
+            part: 0,
 
             instructions: Vec::new(),
             accesses: Vec::new(),
@@ -55,6 +75,26 @@ impl CompileEnv {
         self.edge_stack_len -= 1;
     }
 
+    fn push_path(&mut self, name: usize) {
+        self.names.insert(name, self.path_stack_len);
+        self.path_stack_len += 1;
+    }
+
+    fn pop_path(&mut self, name: usize) {
+        self.names.remove(&name);
+        self.path_stack_len -= 1;
+    }
+
+    fn push_value(&mut self, name: usize) {
+        self.names.insert(name, self.value_stack_len);
+        self.value_stack_len += 1;
+    }
+
+    fn pop_value(&mut self, name: usize) {
+        self.names.remove(&name);
+        self.value_stack_len -= 1;
+    }
+
     fn get_stack_idx(&self, name: usize) -> Result<usize, Error> {
         self.names.get(&name).copied().ok_or(Error::Internal)
     }
@@ -66,6 +106,11 @@ impl CompileEnv {
                 Jump { jump }
                 | LoadNextNode { jump }
                 | LoadNextEdge { jump }
+                | LoadNextPath { jump }
+                | LoadNextShortestPath { jump, .. }
+                | LoadNextMergedNode { jump }
+                | LoadNextMergedEdge { jump }
+                | CheckCreated { jump }
                 | LoadExactNode { jump, .. }
                 | CheckIsOrigin { jump, .. }
                 | CheckIsTarget { jump, .. }
@@ -76,7 +121,20 @@ impl CompileEnv {
                 | CheckTrue { jump, .. }
                 | CheckEq { jump, .. }
                 | CheckLt { jump, .. }
-                | CheckGt { jump, .. } => {
+                | CheckGt { jump, .. }
+                | CheckStartsWith { jump, .. }
+                | CheckEndsWith { jump, .. }
+                | CheckContains { jump, .. }
+                | CheckMatches { jump, .. }
+                | CheckIn { jump, .. }
+                | CheckDisjoint { jump, .. }
+                | CountRow { jump, .. }
+                | CheckRowLimit { jump, .. }
+                | CheckNull { jump, .. }
+                | CheckDistinct { jump, .. }
+                | LoadNextValue { jump }
+                | EndOptional { jump }
+                | LoadNextRow { jump } => {
                     if *jump == from {
                         *jump = to;
                     }
@@ -86,18 +144,47 @@ impl CompileEnv {
                 | Halt
                 | IterNodes
                 | IterLabeledNodes { .. }
+                | IterExactNodes { .. }
                 | IterOriginEdges { .. }
                 | IterTargetEdges { .. }
                 | IterBothEdges { .. }
+                | IterPaths { .. }
+                | IterShortestPaths { .. }
+                | LoadNamedPath { .. }
                 | LoadOriginNode { .. }
                 | LoadTargetNode { .. }
                 | LoadOtherNode { .. }
+                | LoadNullNode
+                | LoadNullEdge
+                | LoadNullPath
+                | BeginOptional
+                | SetMatched
                 | PopNode
                 | PopEdge
+                | PopPath
+                | PushValue { .. }
+                | PopValue
+                | BeginPart
+                | EndPart
+                | IterList { .. }
+                | PushRow { .. }
+                | SortRows { .. }
+                | SkipRows { .. }
+                | LimitRows { .. }
+                | AggregateRow { .. }
+                | FlushGroups { .. }
                 | CreateNode { .. }
                 | CreateEdge { .. }
+                | MergeNode { .. }
+                | MergeEdge { .. }
                 | SetNodeProperty { .. }
                 | SetEdgeProperty { .. }
+                | SetNodeProperties { .. }
+                | SetEdgeProperties { .. }
+                | RemoveNodeProperty { .. }
+                | RemoveEdgeProperty { .. }
+                | SetNodeLabels { .. }
+                | RemoveNodeLabels { .. }
                 | DeleteNode { .. }
                 | DeleteEdge { .. } => (),
             }
@@ -123,6 +210,10 @@ impl CompileEnv {
                 let edge = self.get_stack_idx(*edge)?;
                 Access::EdgeLabel(edge)
             }
+            LoadProperty::LabelsOfNode { node } => {
+                let node = self.get_stack_idx(*node)?;
+                Access::NodeLabels(node)
+            }
             LoadProperty::PropertyOfNode { node, key } => {
                 let node = self.get_stack_idx(*node)?;
                 Access::NodeProperty(node, key.to_string())
@@ -131,10 +222,204 @@ impl CompileEnv {
                 let edge = self.get_stack_idx(*edge)?;
                 Access::EdgeProperty(edge, key.to_string())
             }
+            LoadProperty::PropertiesOfNode { node } => {
+                Access::NodeProperties(self.get_stack_idx(*node)?)
+            }
+            LoadProperty::PropertiesOfEdge { edge } => {
+                Access::EdgeProperties(self.get_stack_idx(*edge)?)
+            }
+            LoadProperty::OriginOfEdge { edge } => Access::EdgeOrigin(self.get_stack_idx(*edge)?),
+            LoadProperty::TargetOfEdge { edge } => Access::EdgeTarget(self.get_stack_idx(*edge)?),
             LoadProperty::Parameter { name } => Access::Parameter(name.to_string()),
+            LoadProperty::List(_)
+            | LoadProperty::Map(_)
+            | LoadProperty::Temporal { .. }
+            | LoadProperty::Binary { .. }
+            | LoadProperty::Case { .. }
+            | LoadProperty::Coalesce(_)
+            | LoadProperty::Function { .. } => self.compile_expression(load)?,
+            LoadProperty::NodesOfPath { path } => Access::PathNodes(self.get_stack_idx(*path)?),
+            LoadProperty::EdgesOfPath { path } => Access::PathEdges(self.get_stack_idx(*path)?),
+            LoadProperty::LengthOfPath { path } => Access::PathLength(self.get_stack_idx(*path)?),
+            LoadProperty::Path { path } => Access::Path(self.get_stack_idx(*path)?),
+            LoadProperty::Value { name } => Access::Value(self.get_stack_idx(*name)?),
+            LoadProperty::Aggregate { .. } | LoadProperty::UserAggregate { .. } => {
+                return Err(Error::Internal)
+            }
         })
     }
 
+    /// Compiles a computed value to expression steps. Expressions
+    /// which only depend on constants are evaluated once, here.
+    fn compile_expression(&self, load: &LoadProperty) -> Result<Access, Error> {
+        let mut code = Vec::new();
+        self.compile_eval(load, &mut code)?;
+        let is_constant = code.iter().all(|step| match step {
+            Eval::Load(access) => matches!(access, Access::Constant(_)),
+            // without an argument, the current time is used
+            Eval::Temporal(_, has_argument) => *has_argument,
+            Eval::Call(function, _) => function.is_pure(),
+            Eval::List(_)
+            | Eval::Map(_)
+            | Eval::Binary(_)
+            | Eval::Coalesce(_)
+            | Eval::Compare(_)
+            | Eval::IsNull
+            | Eval::Truthy
+            | Eval::Not
+            | Eval::And
+            | Eval::Or
+            | Eval::Jump(_)
+            | Eval::JumpUnless(_) => true,
+        });
+        if is_constant {
+            // errors are reported once the expression is evaluated
+            let value = eval::evaluate(&code, |access| match access {
+                Access::Constant(val) => Ok(val.clone()),
+                _ => Err(Error::Internal),
+            });
+            if let Ok(value) = value {
+                return Ok(Access::Constant(value));
+            }
+        }
+        Ok(Access::Expression(code))
+    }
+
+    fn compile_eval(&self, load: &LoadProperty, code: &mut Vec<Eval>) -> Result<(), Error> {
+        match load {
+            LoadProperty::List(items) => {
+                for item in items {
+                    self.compile_eval(item, code)?;
+                }
+                code.push(Eval::List(items.len()));
+            }
+            LoadProperty::Map(entries) => {
+                for (_, value) in entries {
+                    self.compile_eval(value, code)?;
+                }
+                code.push(Eval::Map(
+                    entries.iter().map(|(key, _)| key.to_string()).collect(),
+                ));
+            }
+            LoadProperty::Temporal { function, value } => {
+                if let Some(value) = value {
+                    self.compile_eval(value, code)?;
+                }
+                code.push(Eval::Temporal(*function, value.is_some()));
+            }
+            LoadProperty::Binary { op, lhs, rhs } => {
+                self.compile_eval(lhs, code)?;
+                self.compile_eval(rhs, code)?;
+                code.push(Eval::Binary(*op));
+            }
+            LoadProperty::Case { branches, default } => {
+                let mut ends = Vec::new();
+                for (condition, value) in branches {
+                    self.compile_predicate(condition, code)?;
+                    let check = code.len();
+                    code.push(Eval::JumpUnless(JUMP_PLACEHOLDER));
+                    self.compile_eval(value, code)?;
+                    ends.push(code.len());
+                    code.push(Eval::Jump(JUMP_PLACEHOLDER));
+                    code[check] = Eval::JumpUnless(code.len());
+                }
+                match default {
+                    Some(default) => self.compile_eval(default, code)?,
+                    None => code.push(Eval::Load(Access::Constant(PropOwned::Null))),
+                }
+                for end in ends {
+                    code[end] = Eval::Jump(code.len());
+                }
+            }
+            LoadProperty::Coalesce(values) => {
+                for value in values {
+                    self.compile_eval(value, code)?;
+                }
+                code.push(Eval::Coalesce(values.len()));
+            }
+            LoadProperty::Function { name, arguments } => {
+                let function = self.functions.resolve(name, arguments.len())?;
+                for argument in arguments {
+                    self.compile_eval(argument, code)?;
+                }
+                code.push(Eval::Call(function, arguments.len()));
+            }
+            _ => code.push(Eval::Load(self.compile_access_raw(load)?)),
+        }
+        Ok(())
+    }
+
+    /// Compiles a filter to expression steps, which
+    /// push whether the filter holds.
+    fn compile_predicate(&self, filter: &Filter, code: &mut Vec<Eval>) -> Result<(), Error> {
+        let (lhs, rhs, comparison) = match filter {
+            Filter::And(a, b) => {
+                self.compile_predicate(a, code)?;
+                self.compile_predicate(b, code)?;
+                code.push(Eval::And);
+                return Ok(());
+            }
+            Filter::Or(a, b) => {
+                self.compile_predicate(a, code)?;
+                self.compile_predicate(b, code)?;
+                code.push(Eval::Or);
+                return Ok(());
+            }
+            Filter::Not(inner) => {
+                self.compile_predicate(inner, code)?;
+                code.push(Eval::Not);
+                return Ok(());
+            }
+            Filter::IsTruthy(value) => {
+                self.compile_eval(value, code)?;
+                code.push(Eval::Truthy);
+                return Ok(());
+            }
+            Filter::IsNull(value) => {
+                self.compile_eval(value, code)?;
+                code.push(Eval::IsNull);
+                return Ok(());
+            }
+
+            Filter::NodeHasLabel { node, label } => (
+                LoadProperty::Constant(PropRef::Text(label)),
+                LoadProperty::LabelsOfNode { node: *node },
+                Comparison::In,
+            ),
+            Filter::EdgeHasLabel { edge, label } => (
+                LoadProperty::LabelOfEdge { edge: *edge },
+                LoadProperty::Constant(PropRef::Text(label)),
+                Comparison::Eq,
+            ),
+            Filter::NodeHasId { node, id } => (
+                LoadProperty::IdOfNode { node: *node },
+                id.clone(),
+                Comparison::Eq,
+            ),
+            Filter::EdgeHasId { edge, id } => (
+                LoadProperty::IdOfEdge { edge: *edge },
+                id.clone(),
+                Comparison::Eq,
+            ),
+
+            Filter::Eq(lhs, rhs) => (lhs.clone(), rhs.clone(), Comparison::Eq),
+            Filter::Lt(lhs, rhs) => (lhs.clone(), rhs.clone(), Comparison::Lt),
+            Filter::Gt(lhs, rhs) => (lhs.clone(), rhs.clone(), Comparison::Gt),
+            Filter::StartsWith(lhs, rhs) => (lhs.clone(), rhs.clone(), Comparison::StartsWith),
+            Filter::EndsWith(lhs, rhs) => (lhs.clone(), rhs.clone(), Comparison::EndsWith),
+            Filter::Contains(lhs, rhs) => (lhs.clone(), rhs.clone(), Comparison::Contains),
+            Filter::In(lhs, rhs) => (lhs.clone(), rhs.clone(), Comparison::In),
+            Filter::Disjoint(lhs, rhs) => (lhs.clone(), rhs.clone(), Comparison::Disjoint),
+            Filter::Matches(lhs, rhs) => (lhs.clone(), rhs.clone(), Comparison::Matches),
+
+            Filter::IsOrigin { .. } | Filter::IsTarget { .. } => return Err(Error::Internal),
+        };
+        self.compile_eval(&lhs, code)?;
+        self.compile_eval(&rhs, code)?;
+        code.push(Eval::Compare(comparison));
+        Ok(())
+    }
+
     fn compile_access(&mut self, load: &LoadProperty) -> Result<usize, Error> {
         let access = self.compile_access_raw(load)?;
         if let Some(idx) =
@@ -152,15 +437,15 @@ impl CompileEnv {
 
     /// Uses `JUMP_PLACEHOLDER` as a place-holder for the failed condition jump to
     /// be replaced after the position is known.
-    fn compile_filter(&mut self, plan: &QueryPlan, filter: &Filter) -> Result<(), Error> {
+    fn compile_filter(&mut self, filter: &Filter) -> Result<(), Error> {
         match filter {
             Filter::And(a, b) => {
-                self.compile_filter(plan, a)?;
-                self.compile_filter(plan, b)?;
+                self.compile_filter(a)?;
+                self.compile_filter(b)?;
             }
             Filter::Or(a, b) => {
                 let start = self.instructions.len();
-                self.compile_filter(plan, a)?;
+                self.compile_filter(a)?;
                 let inner_jmp = self.instructions.len();
                 Self::adjust_jumps(
                     &mut self.instructions[start..],
@@ -168,14 +453,14 @@ impl CompileEnv {
                     inner_jmp + 1,
                 );
                 self.instructions.push(Instruction::NoOp);
-                self.compile_filter(plan, b)?;
+                self.compile_filter(b)?;
                 self.instructions[inner_jmp] = Instruction::Jump {
                     jump: self.instructions.len(),
                 };
             }
             Filter::Not(inner) => {
                 let start = self.instructions.len();
-                self.compile_filter(plan, inner)?;
+                self.compile_filter(inner)?;
                 let end = self.instructions.len();
                 Self::adjust_jumps(&mut self.instructions[start..], JUMP_PLACEHOLDER, end + 1);
                 self.instructions.push(Instruction::Jump {
@@ -273,20 +558,82 @@ impl CompileEnv {
                     rhs,
                 });
             }
+
+            Filter::StartsWith(lhs, rhs) => {
+                let lhs = self.compile_access(lhs)?;
+                let rhs = self.compile_access(rhs)?;
+                self.instructions.push(Instruction::CheckStartsWith {
+                    jump: JUMP_PLACEHOLDER,
+                    lhs,
+                    rhs,
+                });
+            }
+            Filter::EndsWith(lhs, rhs) => {
+                let lhs = self.compile_access(lhs)?;
+                let rhs = self.compile_access(rhs)?;
+                self.instructions.push(Instruction::CheckEndsWith {
+                    jump: JUMP_PLACEHOLDER,
+                    lhs,
+                    rhs,
+                });
+            }
+            Filter::Contains(lhs, rhs) => {
+                let lhs = self.compile_access(lhs)?;
+                let rhs = self.compile_access(rhs)?;
+                self.instructions.push(Instruction::CheckContains {
+                    jump: JUMP_PLACEHOLDER,
+                    lhs,
+                    rhs,
+                });
+            }
+            Filter::In(value, list) => {
+                let value = self.compile_access(value)?;
+                let list = self.compile_access(list)?;
+                self.instructions.push(Instruction::CheckIn {
+                    jump: JUMP_PLACEHOLDER,
+                    value,
+                    list,
+                });
+            }
+            Filter::Disjoint(lhs, rhs) => {
+                let lhs = self.compile_access(lhs)?;
+                let rhs = self.compile_access(rhs)?;
+                self.instructions.push(Instruction::CheckDisjoint {
+                    jump: JUMP_PLACEHOLDER,
+                    lhs,
+                    rhs,
+                });
+            }
+            Filter::IsNull(value) => {
+                let value = self.compile_access(value)?;
+                self.instructions.push(Instruction::CheckNull {
+                    jump: JUMP_PLACEHOLDER,
+                    value,
+                });
+            }
+            Filter::Matches(value, pattern) => {
+                let value = self.compile_access(value)?;
+                let pattern = self.compile_access(pattern)?;
+                self.instructions.push(Instruction::CheckMatches {
+                    jump: JUMP_PLACEHOLDER,
+                    value,
+                    pattern,
+                });
+            }
         }
         Ok(())
     }
 
     fn compile_update(&mut self, plan: &QueryPlan, updates: &[UpdateStep]) -> Result<(), Error> {
-        if let Some(update) = updates.get(0) {
+        if let Some(update) = updates.first() {
             match update {
                 UpdateStep::CreateNode {
                     name,
-                    label,
+                    labels,
                     properties,
                 } => {
                     let create_node = Instruction::CreateNode {
-                        label: label.to_string(),
+                        labels: labels.iter().map(|label| label.to_string()).collect(),
                         properties: properties
                             .iter()
                             .map(|(key, load)| -> Result<_, Error> {
@@ -326,29 +673,89 @@ impl CompileEnv {
                     self.instructions.push(Instruction::PopEdge);
                     Ok(())
                 }
-                UpdateStep::SetNodeProperty { node, key, value } => {
-                    let node = self.get_stack_idx(*node)?;
-                    let value = self.compile_access(value)?;
-                    self.instructions.push(Instruction::SetNodeProperty {
-                        node,
-                        key: key.to_string(),
-                        value,
-                    });
-                    self.compile_update(plan, &updates[1..])
+                UpdateStep::MergeNode {
+                    name,
+                    labels,
+                    properties,
+                    on_create,
+                    on_match,
+                } => {
+                    let merge_node = Instruction::MergeNode {
+                        labels: labels.iter().map(|label| label.to_string()).collect(),
+                        properties: properties
+                            .iter()
+                            .map(|(key, load)| -> Result<_, Error> {
+                                Ok((key.to_string(), self.compile_access(load)?))
+                            })
+                            .collect::<Result<_, Error>>()?,
+                    };
+                    let start = self.instructions.len();
+                    self.instructions.push(merge_node);
+                    self.instructions.push(Instruction::NoOp); // set after to calc jump
+                    self.push_node(*name);
+                    self.compile_merge_sets(on_create, on_match)?;
+                    self.compile_update(plan, &updates[1..])?;
+                    self.pop_node(*name);
+                    self.instructions.push(Instruction::PopNode);
+                    self.instructions
+                        .push(Instruction::Jump { jump: start + 1 });
+                    self.instructions[start + 1] = Instruction::LoadNextMergedNode {
+                        jump: self.instructions.len(),
+                    };
+                    Ok(())
                 }
-                UpdateStep::SetEdgeProperty { edge, key, value } => {
-                    let edge = self.get_stack_idx(*edge)?;
-                    let value = self.compile_access(value)?;
-                    self.instructions.push(Instruction::SetEdgeProperty {
-                        edge,
-                        key: key.to_string(),
-                        value,
-                    });
+                UpdateStep::MergeEdge {
+                    name,
+                    label,
+                    origin,
+                    target,
+                    properties,
+                    on_create,
+                    on_match,
+                } => {
+                    let merge_edge = Instruction::MergeEdge {
+                        label: label.to_string(),
+                        origin: self.get_stack_idx(*origin)?,
+                        target: self.get_stack_idx(*target)?,
+                        properties: properties
+                            .iter()
+                            .map(|(key, load)| -> Result<_, Error> {
+                                Ok((key.to_string(), self.compile_access(load)?))
+                            })
+                            .collect::<Result<_, Error>>()?,
+                    };
+                    let start = self.instructions.len();
+                    self.instructions.push(merge_edge);
+                    self.instructions.push(Instruction::NoOp); // set after to calc jump
+                    self.push_edge(*name);
+                    self.compile_merge_sets(on_create, on_match)?;
+                    self.compile_update(plan, &updates[1..])?;
+                    self.pop_edge(*name);
+                    self.instructions.push(Instruction::PopEdge);
+                    self.instructions
+                        .push(Instruction::Jump { jump: start + 1 });
+                    self.instructions[start + 1] = Instruction::LoadNextMergedEdge {
+                        jump: self.instructions.len(),
+                    };
+                    Ok(())
+                }
+                UpdateStep::SetNodeProperty { .. }
+                | UpdateStep::SetEdgeProperty { .. }
+                | UpdateStep::SetNodeProperties { .. }
+                | UpdateStep::SetEdgeProperties { .. }
+                | UpdateStep::RemoveNodeProperty { .. }
+                | UpdateStep::RemoveEdgeProperty { .. }
+                | UpdateStep::SetNodeLabels { .. }
+                | UpdateStep::RemoveNodeLabels { .. } => {
+                    self.compile_set(update)?;
                     self.compile_update(plan, &updates[1..])
                 }
-                UpdateStep::DeleteNode { node } => {
+                UpdateStep::DeleteNode { node, detach } => {
                     let node = self.get_stack_idx(*node)?;
-                    self.instructions.push(Instruction::DeleteNode { node });
+                    self.instructions.push(Instruction::DeleteNode {
+                        node,
+                        detach: *detach,
+                    });
                     self.compile_update(plan, &updates[1..])
                 }
                 UpdateStep::DeleteEdge { edge } => {
@@ -357,26 +764,365 @@ impl CompileEnv {
                     self.compile_update(plan, &updates[1..])
                 }
             }
+        } else if let Some(part) = plan.parts.get(self.part) {
+            self.compile_with(plan, part)
+        } else if Self::is_aggregate(plan) {
+            let mut keys = Vec::new();
+            let mut aggregates = Vec::new();
+            for column in Self::row_columns(plan) {
+                match column {
+                    LoadProperty::Aggregate { function, value } => {
+                        let value = value
+                            .iter()
+                            .map(|value| self.compile_access(value))
+                            .collect::<Result<_, _>>()?;
+                        aggregates.push((Aggregator::Builtin(*function), value));
+                    }
+                    LoadProperty::UserAggregate { name, arguments } => {
+                        let function = self.functions.resolve_aggregate(name, arguments.len())?;
+                        let arguments = arguments
+                            .iter()
+                            .map(|argument| self.compile_access(argument))
+                            .collect::<Result<_, _>>()?;
+                        aggregates.push((function, arguments));
+                    }
+                    _ => keys.push(self.compile_access(column)?),
+                }
+            }
+            self.instructions
+                .push(Instruction::AggregateRow { keys, aggregates });
+            Ok(())
         } else {
-            self.instructions.push(Instruction::Yield);
-            if self.returns.is_empty() {
-                for load in &plan.returns {
-                    self.returns.push(self.compile_access_raw(load)?);
+            // aggregated rows are distinct by their grouping keys
+            let start = self.instructions.len();
+            if plan.distinct {
+                let values = plan
+                    .returns
+                    .iter()
+                    .map(|value| self.compile_access(value))
+                    .collect::<Result<_, _>>()?;
+                self.instructions.push(Instruction::CheckDistinct {
+                    jump: JUMP_PLACEHOLDER,
+                    values,
+                });
+            }
+
+            if Self::is_buffered(plan) {
+                let values = Self::row_columns(plan)
+                    .into_iter()
+                    .map(|column| self.compile_access(column))
+                    .collect::<Result<_, _>>()?;
+                self.instructions.push(Instruction::PushRow { values });
+            } else {
+                let skip = plan
+                    .skip
+                    .as_ref()
+                    .map(|skip| self.compile_access(skip))
+                    .transpose()?;
+                if Self::is_paged(plan) {
+                    self.instructions.push(Instruction::CountRow {
+                        jump: JUMP_PLACEHOLDER,
+                        skip,
+                    });
+                }
+                self.instructions.push(Instruction::Yield);
+                if let Some(limit) = &plan.limit {
+                    let limit = self.compile_access(limit)?;
+                    self.instructions.push(Instruction::CheckRowLimit {
+                        jump: self.instructions.len() + 2,
+                        skip,
+                        limit,
+                    });
+                    self.instructions.push(Instruction::Halt);
+                }
+                if self.returns.is_empty() {
+                    for load in &plan.returns {
+                        self.returns.push(self.compile_access_raw(load)?);
+                    }
                 }
             }
+
+            let end = self.instructions.len();
+            Self::adjust_jumps(&mut self.instructions[start..], JUMP_PLACEHOLDER, end);
             Ok(())
         }
     }
 
-    fn compile_step(&mut self, plan: &QueryPlan, steps: &[MatchStep]) -> Result<(), Error> {
-        if let Some(step) = steps.get(0) {
+    fn compile_set(&mut self, update: &UpdateStep) -> Result<(), Error> {
+        let set = match update {
+            UpdateStep::SetNodeProperty { node, key, value } => Instruction::SetNodeProperty {
+                node: self.get_stack_idx(*node)?,
+                key: key.to_string(),
+                value: self.compile_access(value)?,
+            },
+            UpdateStep::SetEdgeProperty { edge, key, value } => Instruction::SetEdgeProperty {
+                edge: self.get_stack_idx(*edge)?,
+                key: key.to_string(),
+                value: self.compile_access(value)?,
+            },
+            UpdateStep::SetNodeProperties {
+                node,
+                value,
+                replace,
+            } => Instruction::SetNodeProperties {
+                node: self.get_stack_idx(*node)?,
+                value: self.compile_access(value)?,
+                replace: *replace,
+            },
+            UpdateStep::SetEdgeProperties {
+                edge,
+                value,
+                replace,
+            } => Instruction::SetEdgeProperties {
+                edge: self.get_stack_idx(*edge)?,
+                value: self.compile_access(value)?,
+                replace: *replace,
+            },
+            UpdateStep::RemoveNodeProperty { node, key } => Instruction::RemoveNodeProperty {
+                node: self.get_stack_idx(*node)?,
+                key: key.to_string(),
+            },
+            UpdateStep::RemoveEdgeProperty { edge, key } => Instruction::RemoveEdgeProperty {
+                edge: self.get_stack_idx(*edge)?,
+                key: key.to_string(),
+            },
+            UpdateStep::SetNodeLabels { node, labels } => Instruction::SetNodeLabels {
+                node: self.get_stack_idx(*node)?,
+                labels: labels.iter().map(|label| label.to_string()).collect(),
+            },
+            UpdateStep::RemoveNodeLabels { node, labels } => Instruction::RemoveNodeLabels {
+                node: self.get_stack_idx(*node)?,
+                labels: labels.iter().map(|label| label.to_string()).collect(),
+            },
+            _ => unreachable!("only property and label updates are compiled as sets"),
+        };
+        self.instructions.push(set);
+        Ok(())
+    }
+
+    /// Apply the `on_create` sets if the innermost merge created
+    /// its pattern, and the `on_match` sets otherwise.
+    fn compile_merge_sets(
+        &mut self,
+        on_create: &[UpdateStep],
+        on_match: &[UpdateStep],
+    ) -> Result<(), Error> {
+        let check_created = self.instructions.len();
+        self.instructions.push(Instruction::NoOp); // set after to calc jump
+        for update in on_create {
+            self.compile_set(update)?;
+        }
+        let skip_match = self.instructions.len();
+        self.instructions.push(Instruction::NoOp); // set after to calc jump
+        self.instructions[check_created] = Instruction::CheckCreated {
+            jump: self.instructions.len(),
+        };
+        for update in on_match {
+            self.compile_set(update)?;
+        }
+        self.instructions[skip_match] = Instruction::Jump {
+            jump: self.instructions.len(),
+        };
+        Ok(())
+    }
+
+    /// Bind the values of `part` and continue with the steps
+    /// of the next part.
+    fn compile_with(&mut self, plan: &QueryPlan, part: &QueryPart) -> Result<(), Error> {
+        for (name, value) in &part.values {
+            let value = self.compile_access(value)?;
+            self.instructions.push(Instruction::PushValue { value });
+            self.push_value(*name);
+        }
+
+        let unwind_start = self.instructions.len();
+        if let Some((name, list)) = &part.unwind {
+            let list = self.compile_access(list)?;
+            self.instructions.push(Instruction::IterList { list });
+            self.instructions.push(Instruction::NoOp); // set after to calc jump
+            self.push_value(*name);
+        }
+
+        let distinct_start = self.instructions.len();
+        if let Some(values) = &part.distinct {
+            let values = values
+                .iter()
+                .map(|value| self.compile_access(value))
+                .collect::<Result<_, _>>()?;
+            self.instructions.push(Instruction::CheckDistinct {
+                jump: JUMP_PLACEHOLDER,
+                values,
+            });
+        }
+
+        self.instructions.push(Instruction::BeginPart);
+        self.part += 1;
+        let steps = match plan.parts.get(self.part) {
+            Some(part) => &part.steps,
+            None => &plan.steps,
+        };
+        self.compile_step(plan, steps, &[])?;
+        self.part -= 1;
+        self.instructions.push(Instruction::EndPart);
+
+        if part.distinct.is_some() {
+            let end = self.instructions.len();
+            Self::adjust_jumps(
+                &mut self.instructions[distinct_start..=distinct_start],
+                JUMP_PLACEHOLDER,
+                end,
+            );
+        }
+
+        if let Some((name, _)) = &part.unwind {
+            self.pop_value(*name);
+            self.instructions.push(Instruction::PopValue);
+            self.instructions.push(Instruction::Jump {
+                jump: unwind_start + 1,
+            });
+            self.instructions[unwind_start + 1] = Instruction::LoadNextValue {
+                jump: self.instructions.len(),
+            };
+        }
+
+        for (name, _) in part.values.iter().rev() {
+            self.pop_value(*name);
+            self.instructions.push(Instruction::PopValue);
+        }
+        Ok(())
+    }
+
+    fn is_aggregate(plan: &QueryPlan) -> bool {
+        plan.returns
+            .iter()
+            .chain(plan.order_by.iter().map(|key| &key.value))
+            .any(LoadProperty::is_aggregate)
+    }
+
+    /// Queries which need to observe all matches before
+    /// returning the first one buffer their returned values
+    /// as rows.
+    /// Updates in any part of a query with `SKIP` or `LIMIT`
+    /// still apply to every match.
+    fn is_buffered(plan: &QueryPlan) -> bool {
+        Self::is_aggregate(plan)
+            || !plan.order_by.is_empty()
+            || (Self::has_slice(plan) && Self::has_updates(plan))
+    }
+
+    fn has_updates(plan: &QueryPlan) -> bool {
+        !plan.updates.is_empty() || plan.parts.iter().any(|part| !part.updates.is_empty())
+    }
+
+    fn has_slice(plan: &QueryPlan) -> bool {
+        plan.skip.is_some() || plan.limit.is_some()
+    }
+
+    /// Queries with `SKIP` or `LIMIT` which need not be buffered
+    /// count their rows as they are returned, and halt once the
+    /// limit is reached.
+    fn is_paged(plan: &QueryPlan) -> bool {
+        Self::has_slice(plan) && !Self::is_buffered(plan)
+    }
+
+    /// The distinct values which make up a buffered row. These are
+    /// all returns and sort keys, where grouping keys come before
+    /// aggregates.
+    fn row_columns<'a, 'src>(plan: &'a QueryPlan<'src>) -> Vec<&'a LoadProperty<'src>> {
+        let mut columns: Vec<&LoadProperty> = Vec::new();
+        for value in plan
+            .returns
+            .iter()
+            .chain(plan.order_by.iter().map(|key| &key.value))
+        {
+            if !columns.contains(&value) {
+                columns.push(value);
+            }
+        }
+        columns.sort_by_key(|column| column.is_aggregate());
+        columns
+    }
+
+    /// Aggregate, sort, and truncate the buffered rows, then
+    /// yield them one after the other. Columns are laid out as
+    /// given by `row_columns`.
+    fn compile_rows(&mut self, plan: &QueryPlan) -> Result<(), Error> {
+        let columns = Self::row_columns(plan);
+        let column_of = |value: &LoadProperty| {
+            columns
+                .iter()
+                .position(|column| *column == value)
+                .ok_or(Error::Internal)
+        };
+
+        if Self::is_aggregate(plan) {
+            // without grouping keys, there is exactly one group
+            let default = columns
+                .iter()
+                .map(|column| match column {
+                    LoadProperty::Aggregate { function, .. } => {
+                        Ok(Some(Aggregator::Builtin(*function)))
+                    }
+                    LoadProperty::UserAggregate { name, arguments } => self
+                        .functions
+                        .resolve_aggregate(name, arguments.len())
+                        .map(Some),
+                    _ => Ok(None),
+                })
+                .collect::<Result<_, Error>>()?;
+            self.instructions.push(Instruction::FlushGroups { default });
+        }
+
+        let keys = plan
+            .order_by
+            .iter()
+            .map(|key| Ok((column_of(&key.value)?, key.descending)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        if !keys.is_empty() {
+            self.instructions.push(Instruction::SortRows { keys });
+        }
+        if let Some(skip) = &plan.skip {
+            let count = self.compile_access(skip)?;
+            self.instructions.push(Instruction::SkipRows { count });
+        }
+        if let Some(limit) = &plan.limit {
+            let count = self.compile_access(limit)?;
+            self.instructions.push(Instruction::LimitRows { count });
+        }
+
+        let start = self.instructions.len();
+        self.instructions.push(Instruction::NoOp); // set after to calc jump
+        self.instructions.push(Instruction::Yield);
+        self.instructions.push(Instruction::Jump { jump: start });
+        self.instructions[start] = Instruction::LoadNextRow {
+            jump: self.instructions.len(),
+        };
+        self.returns = plan
+            .returns
+            .iter()
+            .map(|value| Ok(Access::Column(column_of(value)?)))
+            .collect::<Result<_, Error>>()?;
+        Ok(())
+    }
+
+    /// Compile the nested loops for `steps`. Once all steps are
+    /// matched, compilation continues with the remaining steps
+    /// of any enclosing optional matches in `rest` (innermost
+    /// last), and finally with the updates and returns.
+    fn compile_step(
+        &mut self,
+        plan: &QueryPlan,
+        steps: &[MatchStep],
+        rest: &[&[MatchStep]],
+    ) -> Result<(), Error> {
+        if let Some(step) = steps.first() {
             let start = self.instructions.len();
             match step {
                 MatchStep::LoadAnyNode { name } => {
                     self.instructions.push(Instruction::IterNodes);
                     self.instructions.push(Instruction::NoOp); // set after to calc jump
                     self.push_node(*name);
-                    self.compile_step(plan, &steps[1..])?;
+                    self.compile_step(plan, &steps[1..], rest)?;
                     self.pop_node(*name);
                     self.instructions.push(Instruction::PopNode);
                     self.instructions
@@ -385,13 +1131,13 @@ impl CompileEnv {
                         jump: self.instructions.len(),
                     };
                 }
-                MatchStep::LoadLabeledNode { name, label } => {
+                MatchStep::LoadLabeledNode { name, labels } => {
                     self.instructions.push(Instruction::IterLabeledNodes {
-                        label: label.to_string(),
+                        labels: labels.iter().map(|label| label.to_string()).collect(),
                     });
                     self.instructions.push(Instruction::NoOp); // set after to calc jump
                     self.push_node(*name);
-                    self.compile_step(plan, &steps[1..])?;
+                    self.compile_step(plan, &steps[1..], rest)?;
                     self.pop_node(*name);
                     self.instructions.push(Instruction::PopNode);
                     self.instructions
@@ -407,7 +1153,7 @@ impl CompileEnv {
                         id,
                     });
                     self.push_node(*name);
-                    self.compile_step(plan, &steps[1..])?;
+                    self.compile_step(plan, &steps[1..], rest)?;
                     self.pop_node(*name);
                     self.instructions.push(Instruction::PopNode);
                     let end = self.instructions.len();
@@ -417,12 +1163,26 @@ impl CompileEnv {
                         end,
                     );
                 }
+                MatchStep::LoadExactNodes { name, ids } => {
+                    let ids = self.compile_access(ids)?;
+                    self.instructions.push(Instruction::IterExactNodes { ids });
+                    self.instructions.push(Instruction::NoOp); // set after to calc jump
+                    self.push_node(*name);
+                    self.compile_step(plan, &steps[1..], rest)?;
+                    self.pop_node(*name);
+                    self.instructions.push(Instruction::PopNode);
+                    self.instructions
+                        .push(Instruction::Jump { jump: start + 1 });
+                    self.instructions[start + 1] = Instruction::LoadNextNode {
+                        jump: self.instructions.len(),
+                    };
+                }
                 MatchStep::LoadOriginNode { name, edge } => {
                     self.instructions.push(Instruction::LoadOriginNode {
                         edge: self.get_stack_idx(*edge)?,
                     });
                     self.push_node(*name);
-                    self.compile_step(plan, &steps[1..])?;
+                    self.compile_step(plan, &steps[1..], rest)?;
                     self.pop_node(*name);
                     self.instructions.push(Instruction::PopNode);
                 }
@@ -431,7 +1191,7 @@ impl CompileEnv {
                         edge: self.get_stack_idx(*edge)?,
                     });
                     self.push_node(*name);
-                    self.compile_step(plan, &steps[1..])?;
+                    self.compile_step(plan, &steps[1..], rest)?;
                     self.pop_node(*name);
                     self.instructions.push(Instruction::PopNode);
                 }
@@ -441,7 +1201,7 @@ impl CompileEnv {
                         edge: self.get_stack_idx(*edge)?,
                     });
                     self.push_node(*name);
-                    self.compile_step(plan, &steps[1..])?;
+                    self.compile_step(plan, &steps[1..], rest)?;
                     self.pop_node(*name);
                     self.instructions.push(Instruction::PopNode);
                 }
@@ -452,7 +1212,7 @@ impl CompileEnv {
                     });
                     self.instructions.push(Instruction::NoOp); // set after to calc jump
                     self.push_edge(*name);
-                    self.compile_step(plan, &steps[1..])?;
+                    self.compile_step(plan, &steps[1..], rest)?;
                     self.pop_edge(*name);
                     self.instructions.push(Instruction::PopEdge);
                     self.instructions
@@ -467,7 +1227,7 @@ impl CompileEnv {
                     });
                     self.instructions.push(Instruction::NoOp); // set after to calc jump
                     self.push_edge(*name);
-                    self.compile_step(plan, &steps[1..])?;
+                    self.compile_step(plan, &steps[1..], rest)?;
                     self.pop_edge(*name);
                     self.instructions.push(Instruction::PopEdge);
                     self.instructions
@@ -482,7 +1242,7 @@ impl CompileEnv {
                     });
                     self.instructions.push(Instruction::NoOp); // set after to calc jump
                     self.push_edge(*name);
-                    self.compile_step(plan, &steps[1..])?;
+                    self.compile_step(plan, &steps[1..], rest)?;
                     self.pop_edge(*name);
                     self.instructions.push(Instruction::PopEdge);
                     self.instructions
@@ -491,11 +1251,127 @@ impl CompileEnv {
                         jump: self.instructions.len(),
                     };
                 }
+                MatchStep::LoadPath {
+                    name,
+                    node,
+                    end,
+                    direction,
+                    label,
+                    properties,
+                    min,
+                    max,
+                } => {
+                    let properties = properties
+                        .iter()
+                        .map(|(key, value)| Ok((key.to_string(), self.compile_access(value)?)))
+                        .collect::<Result<_, Error>>()?;
+                    let start = self.instructions.len();
+                    self.instructions.push(Instruction::IterPaths {
+                        node: self.get_stack_idx(*node)?,
+                        direction: *direction,
+                        label: label.map(str::to_string),
+                        properties,
+                        min: *min,
+                        max: *max,
+                    });
+                    self.instructions.push(Instruction::NoOp); // set after to calc jump
+                    self.push_path(*name);
+                    self.push_node(*end);
+                    self.compile_step(plan, &steps[1..], rest)?;
+                    self.pop_node(*end);
+                    self.instructions.push(Instruction::PopNode);
+                    self.pop_path(*name);
+                    self.instructions.push(Instruction::PopPath);
+                    self.instructions
+                        .push(Instruction::Jump { jump: start + 1 });
+                    self.instructions[start + 1] = Instruction::LoadNextPath {
+                        jump: self.instructions.len(),
+                    };
+                }
+                MatchStep::LoadShortestPath {
+                    name,
+                    node,
+                    end,
+                    load_end,
+                    direction,
+                    label,
+                    properties,
+                    min,
+                    max,
+                    all,
+                } => {
+                    let properties = properties
+                        .iter()
+                        .map(|(key, value)| Ok((key.to_string(), self.compile_access(value)?)))
+                        .collect::<Result<_, Error>>()?;
+                    let start = self.instructions.len();
+                    let end_idx = if *load_end {
+                        None
+                    } else {
+                        Some(self.get_stack_idx(*end)?)
+                    };
+                    self.instructions.push(Instruction::IterShortestPaths {
+                        node: self.get_stack_idx(*node)?,
+                        end: end_idx,
+                        direction: *direction,
+                        label: label.map(str::to_string),
+                        properties,
+                        min: *min,
+                        max: *max,
+                        all: *all,
+                    });
+                    self.instructions.push(Instruction::NoOp); // set after to calc jump
+                    self.push_path(*name);
+                    if *load_end {
+                        self.push_node(*end);
+                    }
+                    self.compile_step(plan, &steps[1..], rest)?;
+                    if *load_end {
+                        self.pop_node(*end);
+                        self.instructions.push(Instruction::PopNode);
+                    }
+                    self.pop_path(*name);
+                    self.instructions.push(Instruction::PopPath);
+                    self.instructions
+                        .push(Instruction::Jump { jump: start + 1 });
+                    self.instructions[start + 1] = Instruction::LoadNextShortestPath {
+                        jump: self.instructions.len(),
+                        load_end: *load_end,
+                    };
+                }
+                MatchStep::LoadNamedPath {
+                    name,
+                    start,
+                    segments,
+                } => {
+                    let segments = segments
+                        .iter()
+                        .map(|segment| {
+                            Ok(match *segment {
+                                PathSegment::Edge { edge, node } => PathSegment::Edge {
+                                    edge: self.get_stack_idx(edge)?,
+                                    node: self.get_stack_idx(node)?,
+                                },
+                                PathSegment::Path { path } => PathSegment::Path {
+                                    path: self.get_stack_idx(path)?,
+                                },
+                            })
+                        })
+                        .collect::<Result<_, Error>>()?;
+                    self.instructions.push(Instruction::LoadNamedPath {
+                        start: self.get_stack_idx(*start)?,
+                        segments,
+                    });
+                    self.push_path(*name);
+                    self.compile_step(plan, &steps[1..], rest)?;
+                    self.pop_path(*name);
+                    self.instructions.push(Instruction::PopPath);
+                }
 
                 MatchStep::Filter(filter) => {
-                    self.compile_filter(plan, filter)?;
+                    self.compile_filter(filter)?;
                     let filter_end = self.instructions.len();
-                    self.compile_step(plan, &steps[1..])?;
+                    self.compile_step(plan, &steps[1..], rest)?;
                     let end = self.instructions.len();
                     Self::adjust_jumps(
                         &mut self.instructions[start..filter_end],
@@ -503,24 +1379,139 @@ impl CompileEnv {
                         end,
                     );
                 }
+
+                MatchStep::Optional(optional) => {
+                    self.instructions.push(Instruction::BeginOptional);
+                    let mut outer = rest.to_vec();
+                    outer.push(&steps[1..]);
+                    self.compile_step(plan, optional, &outer)?;
+                    let end_optional = self.instructions.len();
+                    self.instructions.push(Instruction::NoOp); // set after to calc jump
+
+                    // no match was found, continue once with all names set to null
+                    let mut loaded = Vec::new();
+                    for step in optional {
+                        match step {
+                            MatchStep::LoadAnyNode { name }
+                            | MatchStep::LoadExactNode { name, .. }
+                            | MatchStep::LoadExactNodes { name, .. }
+                            | MatchStep::LoadLabeledNode { name, .. }
+                            | MatchStep::LoadOriginNode { name, .. }
+                            | MatchStep::LoadTargetNode { name, .. }
+                            | MatchStep::LoadOtherNode { name, .. } => {
+                                self.instructions.push(Instruction::LoadNullNode);
+                                self.push_node(*name);
+                                loaded.push((*name, Instruction::PopNode));
+                            }
+                            MatchStep::LoadOriginEdge { name, .. }
+                            | MatchStep::LoadTargetEdge { name, .. }
+                            | MatchStep::LoadEitherEdge { name, .. } => {
+                                self.instructions.push(Instruction::LoadNullEdge);
+                                self.push_edge(*name);
+                                loaded.push((*name, Instruction::PopEdge));
+                            }
+                            MatchStep::LoadShortestPath {
+                                name,
+                                end,
+                                load_end: true,
+                                ..
+                            } => {
+                                self.instructions.push(Instruction::LoadNullPath);
+                                self.push_path(*name);
+                                loaded.push((*name, Instruction::PopPath));
+                                self.instructions.push(Instruction::LoadNullNode);
+                                self.push_node(*end);
+                                loaded.push((*end, Instruction::PopNode));
+                            }
+                            MatchStep::LoadShortestPath { name, .. }
+                            | MatchStep::LoadNamedPath { name, .. } => {
+                                self.instructions.push(Instruction::LoadNullPath);
+                                self.push_path(*name);
+                                loaded.push((*name, Instruction::PopPath));
+                            }
+                            MatchStep::LoadPath { name, end, .. } => {
+                                self.instructions.push(Instruction::LoadNullPath);
+                                self.push_path(*name);
+                                loaded.push((*name, Instruction::PopPath));
+                                self.instructions.push(Instruction::LoadNullNode);
+                                self.push_node(*end);
+                                loaded.push((*end, Instruction::PopNode));
+                            }
+                            MatchStep::Filter(_) | MatchStep::Optional(_) => (),
+                        }
+                    }
+                    self.compile_step(plan, &steps[1..], rest)?;
+                    for (name, pop) in loaded.into_iter().rev() {
+                        match pop {
+                            Instruction::PopNode => self.pop_node(name),
+                            Instruction::PopEdge => self.pop_edge(name),
+                            _ => self.pop_path(name),
+                        }
+                        self.instructions.push(pop);
+                    }
+                    self.instructions[end_optional] = Instruction::EndOptional {
+                        jump: self.instructions.len(),
+                    };
+                }
             }
             Ok(())
+        } else if let Some((next, outer)) = rest.split_last() {
+            self.instructions.push(Instruction::SetMatched);
+            self.compile_step(plan, next, outer)
         } else {
-            self.compile_update(plan, &plan.updates)
+            let updates = match plan.parts.get(self.part) {
+                Some(part) => &part.updates,
+                None => &plan.updates,
+            };
+            self.compile_update(plan, updates)
         }
     }
 }
 
 impl Program {
-    /// Compile a `QueryPlan` into a `Program`.
-    pub fn new(plan: &QueryPlan) -> Result<Program, Error> {
-        let mut env = CompileEnv::new();
-        env.compile_step(plan, &plan.steps)?;
+    /// Compile a `QueryPlan` into a `Program`, resolving
+    /// function calls against `functions`.
+    pub fn new(plan: &QueryPlan, functions: &Functions) -> Result<Program, Error> {
+        let mut env = CompileEnv::new(functions);
+        let steps = match plan.parts.first() {
+            Some(part) => &part.steps,
+            None => &plan.steps,
+        };
+        if let (true, Some(limit)) = (CompileEnv::is_paged(plan), &plan.limit) {
+            // don't match anything for a limit of zero
+            let skip = plan
+                .skip
+                .as_ref()
+                .map(|skip| env.compile_access(skip))
+                .transpose()?;
+            let limit = env.compile_access(limit)?;
+            env.instructions.push(Instruction::CheckRowLimit {
+                jump: 2,
+                skip,
+                limit,
+            });
+            env.instructions.push(Instruction::Halt);
+        }
+        env.compile_step(plan, steps, &[])?;
+        if CompileEnv::is_buffered(plan) {
+            env.compile_rows(plan)?;
+        }
         env.instructions.push(Instruction::Halt);
         Ok(Program {
             instructions: env.instructions,
             accesses: env.accesses,
             returns: env.returns,
+            union: Vec::new(),
+            distinct: false,
         })
     }
+
+    /// Combine the programs of the queries in a `UNION` into
+    /// a single program, which runs them one after the other.
+    pub fn union(mut programs: Vec<Program>, distinct: bool) -> Program {
+        let mut program = programs.remove(0);
+        program.distinct = distinct && !programs.is_empty();
+        program.union = programs;
+        program
+    }
 }
@@ -0,0 +1,216 @@
+use crate::parser::ast::Temporal;
+use crate::store::{PropOwned, PropRef};
+use crate::Error;
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, NaiveDateTime, Utc};
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+
+/// Construct a temporal value from `value`, which may be a string,
+/// a map of components, or another temporal value. Without a value,
+/// dates and datetimes are taken from the current time.
+pub(crate) fn construct(function: Temporal, value: Option<PropRef>) -> Result<PropOwned, Error> {
+    match (function, value) {
+        (_, Some(PropRef::Null)) => Ok(PropOwned::Null),
+
+        (Temporal::Date, None) => Ok(PropOwned::Date(Utc::now().date_naive())),
+        (Temporal::Date, Some(PropRef::Date(date))) => Ok(PropOwned::Date(date)),
+        (Temporal::Date, Some(PropRef::DateTime(datetime))) => {
+            Ok(PropOwned::Date(datetime.date_naive()))
+        }
+        (Temporal::Date, Some(PropRef::Text(text))) => parse_date(text).map(PropOwned::Date),
+        (Temporal::Date, Some(PropRef::Map(map))) => NaiveDate::from_ymd_opt(
+            component(map, "year")?.ok_or(Error::TypeMismatch)? as i32,
+            component(map, "month")?.unwrap_or(1) as u32,
+            component(map, "day")?.unwrap_or(1) as u32,
+        )
+        .map(PropOwned::Date)
+        .ok_or(Error::TypeMismatch),
+
+        (Temporal::DateTime, None) => Ok(PropOwned::DateTime(Utc::now().into())),
+        (Temporal::DateTime, Some(PropRef::DateTime(datetime))) => {
+            Ok(PropOwned::DateTime(datetime))
+        }
+        (Temporal::DateTime, Some(PropRef::Date(date))) => {
+            Ok(PropOwned::DateTime(utc(date.and_hms_opt(0, 0, 0).unwrap())))
+        }
+        (Temporal::DateTime, Some(PropRef::Text(text))) => {
+            parse_datetime(text).map(PropOwned::DateTime)
+        }
+        (Temporal::DateTime, Some(PropRef::Map(map))) => {
+            datetime_from_map(map).map(PropOwned::DateTime)
+        }
+
+        (Temporal::Duration, Some(PropRef::Duration(duration))) => {
+            Ok(PropOwned::Duration(duration))
+        }
+        (Temporal::Duration, Some(PropRef::Text(text))) => {
+            parse_duration(text).map(PropOwned::Duration)
+        }
+        (Temporal::Duration, Some(PropRef::Map(map))) => {
+            duration_from_map(map).map(PropOwned::Duration)
+        }
+
+        _ => Err(Error::TypeMismatch),
+    }
+}
+
+fn utc(datetime: NaiveDateTime) -> DateTime<FixedOffset> {
+    datetime.and_utc().into()
+}
+
+/// Get an integer component like `year` from a map, if present.
+fn component(map: &BTreeMap<String, PropOwned>, key: &str) -> Result<Option<i64>, Error> {
+    match map.get(key) {
+        None | Some(PropOwned::Null) => Ok(None),
+        Some(PropOwned::Integer(num)) => Ok(Some(*num)),
+        Some(_) => Err(Error::TypeMismatch),
+    }
+}
+
+/// e.g. '2024-01-31', or the date of a datetime
+/// like '2024-01-31T12:30:00+02:00'.
+fn parse_date(text: &str) -> Result<NaiveDate, Error> {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .or_else(|_| parse_datetime(text).map(|datetime| datetime.date_naive()))
+}
+
+/// e.g. '2024-01-31T12:30:00+02:00', '2024-01-31T12:30:00' or '2024-01-31',
+/// where times without an offset are taken to be UTC.
+fn parse_datetime(text: &str) -> Result<DateTime<FixedOffset>, Error> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(text) {
+        Ok(datetime)
+    } else if let Ok(datetime) = NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f") {
+        Ok(utc(datetime))
+    } else {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d")
+            .map(|date| utc(date.and_hms_opt(0, 0, 0).unwrap()))
+            .map_err(|_| Error::TypeMismatch)
+    }
+}
+
+/// e.g. '{ epochMillis: 1706704200000 }' or '{ year: 2024, month: 1, day: 31, hour: 12 }',
+/// where datetimes given by their components are taken to be UTC.
+fn datetime_from_map(map: &BTreeMap<String, PropOwned>) -> Result<DateTime<FixedOffset>, Error> {
+    let time = |key| -> Result<u32, Error> {
+        component(map, key)?
+            .unwrap_or(0)
+            .try_into()
+            .map_err(|_| Error::TypeMismatch)
+    };
+    let datetime = if let Some(millis) = component(map, "epochMillis")? {
+        DateTime::from_timestamp_millis(millis)
+    } else if let Some(seconds) = component(map, "epochSeconds")? {
+        DateTime::from_timestamp(seconds, 0)
+    } else {
+        let (hour, minute, second, milli) = (
+            time("hour")?,
+            time("minute")?,
+            time("second")?,
+            time("millisecond")?,
+        );
+        NaiveDate::from_ymd_opt(
+            component(map, "year")?.ok_or(Error::TypeMismatch)? as i32,
+            component(map, "month")?.unwrap_or(1) as u32,
+            component(map, "day")?.unwrap_or(1) as u32,
+        )
+        .and_then(|date| date.and_hms_milli_opt(hour, minute, second, milli))
+        .map(|datetime| datetime.and_utc())
+    };
+    datetime.map(Into::into).ok_or(Error::TypeMismatch)
+}
+
+/// e.g. 'P2W', 'P1DT12H', 'PT1.5S' or '-PT30M'. Years and months are
+/// not supported, since they do not have a fixed length.
+fn parse_duration(text: &str) -> Result<Duration, Error> {
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(text) => (true, text),
+        None => (false, text),
+    };
+    let text = text.strip_prefix('P').ok_or(Error::TypeMismatch)?;
+    let (date, time) = match text.split_once('T') {
+        Some((date, time)) if !time.is_empty() => (date, time),
+        Some(_) => return Err(Error::TypeMismatch),
+        None => (text, ""),
+    };
+    if date.is_empty() && time.is_empty() {
+        return Err(Error::TypeMismatch);
+    }
+
+    let mut duration = Duration::zero();
+    for (part, time) in [(date, false), (time, true)] {
+        let mut rest = part;
+        while !rest.is_empty() {
+            let end = rest
+                .find(|c: char| !c.is_ascii_digit() && c != '.')
+                .ok_or(Error::TypeMismatch)?;
+            let (num, unit) = rest.split_at(end);
+            let num: f64 = num.parse().map_err(|_| Error::TypeMismatch)?;
+            let seconds = match (time, unit.as_bytes()[0]) {
+                (false, b'W') => 7.0 * 24.0 * 3600.0,
+                (false, b'D') => 24.0 * 3600.0,
+                (true, b'H') => 3600.0,
+                (true, b'M') => 60.0,
+                (true, b'S') => 1.0,
+                _ => return Err(Error::TypeMismatch),
+            };
+            let part = Duration::try_milliseconds((num * seconds * 1000.0).round() as i64)
+                .ok_or(Error::TypeMismatch)?;
+            duration = duration.checked_add(&part).ok_or(Error::TypeMismatch)?;
+            rest = &unit[1..];
+        }
+    }
+    Ok(if negative { -duration } else { duration })
+}
+
+/// e.g. '{ days: 1, hours: 12 }'
+fn duration_from_map(map: &BTreeMap<String, PropOwned>) -> Result<Duration, Error> {
+    let units = [
+        ("weeks", Duration::try_weeks as fn(i64) -> Option<Duration>),
+        ("days", Duration::try_days),
+        ("hours", Duration::try_hours),
+        ("minutes", Duration::try_minutes),
+        ("seconds", Duration::try_seconds),
+        ("milliseconds", Duration::try_milliseconds),
+    ];
+    let mut duration = Duration::zero();
+    for (key, unit) in units {
+        if let Some(num) = component(map, key)? {
+            let part = unit(num).ok_or(Error::TypeMismatch)?;
+            duration = duration.checked_add(&part).ok_or(Error::TypeMismatch)?;
+        }
+    }
+    Ok(duration)
+}
+
+/// Format a duration like 'P1DT2H30M' or '-PT1.5S', such
+/// that it can be parsed again.
+pub(crate) fn format_duration(duration: Duration) -> String {
+    let sign = if duration < Duration::zero() { "-" } else { "" };
+    let duration = duration.abs();
+    let days = duration.num_days();
+    let hours = duration.num_hours() % 24;
+    let minutes = duration.num_minutes() % 60;
+    let seconds = duration.num_seconds() % 60;
+    let nanos = duration.subsec_nanos();
+
+    let mut text = format!("{}P", sign);
+    if days != 0 {
+        text += &format!("{}D", days);
+    }
+    if hours != 0 || minutes != 0 || seconds != 0 || nanos != 0 || days == 0 {
+        text.push('T');
+        if hours != 0 {
+            text += &format!("{}H", hours);
+        }
+        if minutes != 0 {
+            text += &format!("{}M", minutes);
+        }
+        if nanos != 0 {
+            let fraction = format!("{:09}", nanos);
+            text += &format!("{}.{}S", seconds, fraction.trim_end_matches('0'));
+        } else if seconds != 0 || (days == 0 && hours == 0 && minutes == 0) {
+            text += &format!("{}S", seconds);
+        }
+    }
+    text
+}
@@ -1,8 +1,22 @@
+use super::aggregate::{Accumulator, Aggregator};
+use super::eval::{self, regex, Eval};
 use super::Program;
-use crate::store::{Edge, EdgeIter, Node, NodeIter, PropOwned, PropRef, StoreTxn, Update};
+use crate::parser::ast::Direction;
+use crate::planner::PathSegment;
+use crate::store::{
+    shortest_paths, shortest_paths_from, Edge, EdgeIter, Node, NodeIter, Path, PathIter, PropKey,
+    PropOwned, PropRef, StoreTxn, Update,
+};
 use crate::Error;
+use regex::Regex;
+use std::cell::RefCell;
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::convert::TryInto;
+
+/// The number of compiled regular expressions a
+/// virtual machine keeps around.
+const MAX_CACHED_REGEXES: usize = 64;
 
 /// Runtime to execute a compiled query program. Note that the
 /// transaction takes an immutable borrow, but expects to be the
@@ -14,13 +28,46 @@ pub(crate) struct VirtualMachine<'env, 'txn, 'prog> {
     instructions: &'prog [Instruction],
     accesses: &'prog [Access],
     returns: &'prog [Access],
+    union: &'prog [Program],
     parameters: HashMap<String, PropOwned>,
     current_inst: usize,
 
-    node_stack: Vec<Node>,
-    edge_stack: Vec<Edge>,
+    // unmatched optional nodes, edges, and paths are `None`
+    node_stack: Vec<Option<Node>>,
+    edge_stack: Vec<Option<Edge>>,
+    path_stack: Vec<Option<Path>>,
+    value_stack: Vec<PropOwned>,
     node_iters: Vec<NodeIter<'txn>>,
     edge_iters: Vec<EdgeIter<'txn>>,
+    path_iters: Vec<PathIter<'txn>>,
+    shortest_paths: Vec<std::vec::IntoIter<Path>>,
+    merged_nodes: Vec<std::vec::IntoIter<Node>>,
+    merged_edges: Vec<std::vec::IntoIter<Edge>>,
+    value_iters: Vec<std::vec::IntoIter<PropOwned>>,
+    optional_matched: Vec<bool>,
+    merge_created: Vec<bool>,
+    // the current part, and the part which created each queued
+    // node or edge; later parts match what earlier ones created
+    part: usize,
+    created_parts: HashMap<u64, usize>,
+
+    rows: VecDeque<Vec<PropOwned>>,
+    row: Vec<PropOwned>,
+    // returned values of the last yielded row
+    returned: Vec<PropOwned>,
+    // rows counted by `CountRow`
+    counted_rows: usize,
+
+    groups: Vec<(Vec<PropOwned>, Vec<Accumulator>)>,
+    group_index: HashMap<PropKey, usize>,
+
+    // values seen by each `CheckDistinct`, by instruction
+    distinct: HashMap<usize, HashSet<PropKey>>,
+    // rows returned so far, if a `UNION` is distinct
+    union_rows: Option<HashSet<PropKey>>,
+
+    // compiled regular expressions by their pattern
+    regexes: RefCell<HashMap<String, Regex>>,
 }
 
 /// TODO: Consider to do a Cranelift JIT
@@ -42,10 +89,10 @@ pub(crate) enum Instruction {
 
     /// Create an iterator over all nodes.
     IterNodes,
-    /// Create an iterator over nodes with an
-    /// exact label.
+    /// Create an iterator over nodes which
+    /// have all of the given labels.
     IterLabeledNodes {
-        label: String,
+        labels: Vec<String>,
     },
 
     /// Iterate edges originating from `node`.
@@ -72,12 +119,67 @@ pub(crate) enum Instruction {
         jump: usize,
     },
 
+    /// Iterate all paths of `min` to `max` edges which
+    /// start at `node` and follow `direction`. The edges
+    /// must match `label` and `properties`, which are
+    /// evaluated once.
+    IterPaths {
+        node: usize,
+        direction: Direction,
+        label: Option<String>,
+        properties: Vec<(String, usize)>,
+        min: usize,
+        max: Option<usize>,
+    },
+    /// Load the next path and the node at which it ends from
+    /// the top iterator or pop the iterator and jump.
+    LoadNextPath {
+        jump: usize,
+    },
+
+    /// Search the shortest paths of `min` to `max` edges
+    /// from `node` to `end`, which follow `direction` and
+    /// have edges matching `label` and `properties`. If
+    /// `all` is false, at most one path is found. Without
+    /// `end`, paths to every reachable node are found.
+    IterShortestPaths {
+        node: usize,
+        end: Option<usize>,
+        direction: Direction,
+        label: Option<String>,
+        properties: Vec<(String, usize)>,
+        min: usize,
+        max: Option<usize>,
+        all: bool,
+    },
+    /// Load the next path from the top list of shortest
+    /// paths or pop the list and jump. If `load_end` is
+    /// set, the node at which the path ends is loaded.
+    LoadNextShortestPath {
+        jump: usize,
+        load_end: bool,
+    },
+
+    /// Join the node `start` and the given edges, nodes,
+    /// and paths into a single path. The path is `NULL` if
+    /// any part of it is `NULL`.
+    LoadNamedPath {
+        start: usize,
+        segments: Vec<PathSegment>,
+    },
+
     /// Load the node with `id = access[id]` of jump.
     LoadExactNode {
         jump: usize,
         id: usize,
     },
 
+    /// Iterate the nodes with ids in the list `access[ids]`,
+    /// which is evaluated once.
+    IterExactNodes {
+        ids: usize,
+    },
+
     /// Load the node from which `edge` originates.
     LoadOriginNode {
         edge: usize,
@@ -93,8 +195,55 @@ pub(crate) enum Instruction {
         edge: usize,
     },
 
+    /// Push a `NULL` node, standing in for an unmatched
+    /// optional node.
+    LoadNullNode,
+    /// Push a `NULL` edge, standing in for an unmatched
+    /// optional edge.
+    LoadNullEdge,
+    /// Push a `NULL` path, standing in for an unmatched
+    /// optional path.
+    LoadNullPath,
+
     PopNode,
     PopEdge,
+    PopPath,
+
+    /// Evaluate `value` and push the result onto the
+    /// value stack.
+    PushValue {
+        value: usize,
+    },
+    PopValue,
+
+    /// Continue with the next part of the query, which
+    /// matches the nodes and edges created before it.
+    BeginPart,
+    /// Return to the previous part of the query.
+    EndPart,
+
+    /// Iterate the elements of the list `access[list]`, which
+    /// is evaluated once. `NULL` has no elements, and any other
+    /// value is the only element.
+    IterList {
+        list: usize,
+    },
+    /// Push the next element from the top list iterator onto
+    /// the value stack or pop the iterator and jump.
+    LoadNextValue {
+        jump: usize,
+    },
+
+    /// Start an optional match, which is not yet matched.
+    BeginOptional,
+    /// Mark the innermost optional match as matched.
+    SetMatched,
+    /// Finish the innermost optional match and jump if it
+    /// was matched. Otherwise continue with the unmatched
+    /// case.
+    EndOptional {
+        jump: usize,
+    },
 
     /// Perform a conditional jump if `node` is not
     /// the origin of `edge`.
@@ -111,8 +260,8 @@ pub(crate) enum Instruction {
         edge: usize,
     },
 
-    /// Perform a conditional jump if the label of
-    /// `node` is different from `label`.
+    /// Perform a conditional jump if `node`
+    /// does not have `label`.
     CheckNodeLabel {
         jump: usize,
         node: usize,
@@ -166,15 +315,125 @@ pub(crate) enum Instruction {
         lhs: usize,
         rhs: usize,
     },
+    /// Perform a conditional jump if `lhs` is not a
+    /// text starting with the text `rhs`.
+    CheckStartsWith {
+        jump: usize,
+        lhs: usize,
+        rhs: usize,
+    },
+    /// Perform a conditional jump if `lhs` is not a
+    /// text ending with the text `rhs`.
+    CheckEndsWith {
+        jump: usize,
+        lhs: usize,
+        rhs: usize,
+    },
+    /// Perform a conditional jump if `lhs` is not a
+    /// text containing the text `rhs`.
+    CheckContains {
+        jump: usize,
+        lhs: usize,
+        rhs: usize,
+    },
+    /// Perform a conditional jump if `value` is not
+    /// an element of the list `list`.
+    CheckIn {
+        jump: usize,
+        value: usize,
+        list: usize,
+    },
+    /// Perform a conditional jump if the lists `lhs`
+    /// and `rhs` have an element in common.
+    CheckDisjoint {
+        jump: usize,
+        lhs: usize,
+        rhs: usize,
+    },
+    /// Perform a conditional jump if `value` is not null.
+    CheckNull {
+        jump: usize,
+        value: usize,
+    },
+    /// Perform a conditional jump if `value` is not a text
+    /// which fully matches the regular expression `pattern`.
+    CheckMatches {
+        jump: usize,
+        value: usize,
+        pattern: usize,
+    },
+
+    /// Perform a conditional jump if this instruction has
+    /// seen equal `values` before.
+    CheckDistinct {
+        jump: usize,
+        values: Vec<usize>,
+    },
+
+    /// Evaluate `values` and append the results as a
+    /// new row to the row buffer.
+    PushRow {
+        values: Vec<usize>,
+    },
+    /// Sort the rows in the row buffer. Each key is a
+    /// column and a flag indicating descending order.
+    SortRows {
+        keys: Vec<(usize, bool)>,
+    },
+    /// Discard the first `access[count]` rows from the
+    /// row buffer.
+    SkipRows {
+        count: usize,
+    },
+    /// Discard all but the first `access[count]` rows
+    /// from the row buffer.
+    LimitRows {
+        count: usize,
+    },
+    /// Count a returned row, and perform a conditional
+    /// jump while no more than `access[skip]` rows were
+    /// counted.
+    CountRow {
+        jump: usize,
+        skip: Option<usize>,
+    },
+    /// Perform a conditional jump if fewer than `access[skip]`
+    /// plus `access[limit]` rows were counted.
+    CheckRowLimit {
+        jump: usize,
+        skip: Option<usize>,
+        limit: usize,
+    },
+    /// Load the next row from the row buffer or jump
+    /// if the buffer is empty.
+    LoadNextRow {
+        jump: usize,
+    },
+
+    /// Add the current match to the group identified by
+    /// the values of `keys`, creating the group if it does
+    /// not exist. Each aggregate is fed the values of its
+    /// argument accesses, which are empty for `count(*)`.
+    AggregateRow {
+        keys: Vec<usize>,
+        aggregates: Vec<(Aggregator, Vec<usize>)>,
+    },
+    /// Append one row per group to the row buffer, consisting
+    /// of the keys followed by the aggregates. If no group was
+    /// formed and `default` is given, a single row of empty
+    /// aggregates is appended instead.
+    FlushGroups {
+        default: Option<Vec<Aggregator>>,
+    },
 
     /// Queue an update that creates a new node with
-    /// the given label and the set of properties
+    /// the given labels and the set of properties
     /// assembled using the `Vec` of accesses.
     ///
     /// The created node is also pushed to the node
     /// stack.
     CreateNode {
-        label: String,
+        labels: Vec<String>,
         properties: Vec<(String, usize)>,
     },
     /// Queue an update that creates a new edge with
@@ -190,6 +449,41 @@ pub(crate) enum Instruction {
         target: usize,
         properties: Vec<(String, usize)>,
     },
+    /// Find all nodes with the given labels and the
+    /// properties assembled using the `Vec` of accesses,
+    /// including nodes queued to be created. If there
+    /// are none, queue an update that creates the node.
+    MergeNode {
+        labels: Vec<String>,
+        properties: Vec<(String, usize)>,
+    },
+    /// Find all edges with the given label, origin,
+    /// target, and the properties assembled using the
+    /// `Vec` of accesses, including edges queued to be
+    /// created. If there are none, queue an update that
+    /// creates the edge.
+    MergeEdge {
+        label: String,
+        origin: usize,
+        target: usize,
+        properties: Vec<(String, usize)>,
+    },
+    /// Load the next node found by the innermost merge
+    /// or pop the merge and jump.
+    LoadNextMergedNode {
+        jump: usize,
+    },
+    /// Load the next edge found by the innermost merge
+    /// or pop the merge and jump.
+    LoadNextMergedEdge {
+        jump: usize,
+    },
+    /// Perform a conditional jump if the innermost merge
+    /// found existing nodes or edges instead of creating
+    /// a new one.
+    CheckCreated {
+        jump: usize,
+    },
     /// Queue an update that sets property `key` of
     /// `node` to `access[value]`.
     SetNodeProperty {
@@ -204,9 +498,52 @@ pub(crate) enum Instruction {
         key: String,
         value: usize,
     },
+    /// Queue an update that sets the properties of
+    /// `node` to the entries of the map `access[value]`.
+    /// If `replace` is set, all other properties are
+    /// removed.
+    SetNodeProperties {
+        node: usize,
+        value: usize,
+        replace: bool,
+    },
+    /// Queue an update that sets the properties of
+    /// `edge` to the entries of the map `access[value]`.
+    /// If `replace` is set, all other properties are
+    /// removed.
+    SetEdgeProperties {
+        edge: usize,
+        value: usize,
+        replace: bool,
+    },
+    /// Queue an update that removes property `key`
+    /// from `node`.
+    RemoveNodeProperty {
+        node: usize,
+        key: String,
+    },
+    /// Queue an update that removes property `key`
+    /// from `edge`.
+    RemoveEdgeProperty {
+        edge: usize,
+        key: String,
+    },
+    /// Queue updates that add `labels` to `node`.
+    SetNodeLabels {
+        node: usize,
+        labels: Vec<String>,
+    },
+    /// Queue updates that remove `labels` from `node`.
+    RemoveNodeLabels {
+        node: usize,
+        labels: Vec<String>,
+    },
     /// Queue an update that deletes the given `node`.
+    /// If `detach` is set, all edges connected to the
+    /// node are deleted as well.
     DeleteNode {
         node: usize,
+        detach: bool,
     },
     /// Queue an update that deletes the given `edge`.
     DeleteEdge {
@@ -221,9 +558,46 @@ pub(crate) enum Access {
     EdgeId(usize),
     NodeLabel(usize),
     EdgeLabel(usize),
+    NodeLabels(usize),
     NodeProperty(usize, String),
     EdgeProperty(usize, String),
+    NodeProperties(usize),
+    EdgeProperties(usize),
+    EdgeOrigin(usize),
+    EdgeTarget(usize),
     Parameter(String),
+    Path(usize),
+    PathNodes(usize),
+    PathEdges(usize),
+    PathLength(usize),
+    Value(usize),
+    Column(usize),
+    /// A computed value, like `a.age + 1`.
+    Expression(Vec<Eval>),
+}
+
+/// A property which is either borrowed from the machine
+/// state, or was computed when it was accessed.
+#[derive(Debug, Clone)]
+enum Accessed<'a> {
+    Borrowed(PropRef<'a>),
+    Computed(PropOwned),
+}
+
+impl<'a> Accessed<'a> {
+    fn as_ref(&self) -> PropRef<'_> {
+        match self {
+            Self::Borrowed(prop) => prop.clone(),
+            Self::Computed(prop) => prop.to_ref(),
+        }
+    }
+
+    fn into_owned(self) -> PropOwned {
+        match self {
+            Self::Borrowed(prop) => prop.to_owned(),
+            Self::Computed(prop) => prop,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -243,64 +617,350 @@ impl<'env, 'txn, 'prog> VirtualMachine<'env, 'txn, 'prog> {
             instructions: &program.instructions,
             accesses: &program.accesses,
             returns: &program.returns,
+            union: &program.union,
             current_inst: 0,
 
             parameters,
 
             node_stack: Vec::new(),
             edge_stack: Vec::new(),
+            path_stack: Vec::new(),
+            value_stack: Vec::new(),
             node_iters: Vec::new(),
             edge_iters: Vec::new(),
+            path_iters: Vec::new(),
+            shortest_paths: Vec::new(),
+            merged_nodes: Vec::new(),
+            merged_edges: Vec::new(),
+            value_iters: Vec::new(),
+            optional_matched: Vec::new(),
+            merge_created: Vec::new(),
+            part: 0,
+            created_parts: HashMap::new(),
+
+            rows: VecDeque::new(),
+            row: Vec::new(),
+            returned: Vec::new(),
+            counted_rows: 0,
+
+            groups: Vec::new(),
+            group_index: HashMap::new(),
+
+            distinct: HashMap::new(),
+            union_rows: if program.distinct {
+                Some(HashSet::new())
+            } else {
+                None
+            },
+
+            regexes: RefCell::new(HashMap::new()),
         }
     }
 
-    fn access_property(&self, access: usize) -> Result<PropRef, Error> {
-        match &self.accesses[access] {
-            Access::Constant(val) => Ok(val.to_ref()),
-            Access::NodeId(node) => Ok(PropRef::Id(self.node_stack[*node].id())),
-            Access::EdgeId(edge) => Ok(PropRef::Id(self.edge_stack[*edge].id())),
-            Access::NodeLabel(node) => Ok(PropRef::Text(self.node_stack[*node].label())),
-            Access::EdgeLabel(edge) => Ok(PropRef::Text(self.edge_stack[*edge].label())),
-            Access::NodeProperty(node, key) => Ok(self.node_stack[*node].property(key).to_ref()),
-            Access::EdgeProperty(edge, key) => Ok(self.edge_stack[*edge].property(key).to_ref()),
-            Access::Parameter(name) => Ok(self
+    /// Check if a queued node or edge was created by
+    /// an earlier part, such that it can be matched.
+    fn is_visible(&self, id: u64) -> bool {
+        matches!(self.created_parts.get(&id), Some(&part) if part < self.part)
+    }
+
+    /// The nodes queued to be created by earlier
+    /// parts, which have all of `labels`.
+    fn queued_nodes(&self, labels: &[String]) -> Result<Vec<Node>, Error> {
+        if self.part == 0 {
+            return Ok(vec![]);
+        }
+        let mut nodes = Vec::new();
+        for node in self.txn.queued_nodes()? {
+            if self.is_visible(node.id) {
+                let updated = self.txn.get_updated_labels(&node)?;
+                if labels.iter().all(|label| updated.contains(label)) {
+                    nodes.push(node);
+                }
+            }
+        }
+        Ok(nodes)
+    }
+
+    /// The edges queued to be created by earlier parts.
+    fn queued_edges(&self) -> Result<Vec<Edge>, Error> {
+        if self.part == 0 {
+            return Ok(vec![]);
+        }
+        let mut edges = self.txn.queued_edges()?;
+        edges.retain(|edge| self.is_visible(edge.id));
+        Ok(edges)
+    }
+
+    /// The ids of edges queued to be created by earlier
+    /// parts, which leave or enter `node` in `direction`.
+    fn queued_edge_ids(&self, node: u64, direction: Direction) -> Result<Vec<u64>, Error> {
+        let edges = self.queued_edges()?;
+        let origins = edges.iter().filter(|edge| edge.origin == node);
+        let targets = edges.iter().filter(|edge| edge.target == node);
+        let ids = match direction {
+            Direction::Right => origins.map(|edge| edge.id).collect(),
+            Direction::Left => targets.map(|edge| edge.id).collect(),
+            Direction::Either => origins.chain(targets).map(|edge| edge.id).collect(),
+        };
+        Ok(ids)
+    }
+
+    /// Load a stored node, or one queued to
+    /// be created by an earlier part.
+    fn load_node(&self, id: u64) -> Result<Option<Node>, Error> {
+        match self.txn.load_node(id)? {
+            Some(node) => Ok(Some(node)),
+            None => Ok(self
+                .queued_nodes(&[])?
+                .into_iter()
+                .find(|node| node.id == id)),
+        }
+    }
+
+    /// Load a stored edge, or one queued to
+    /// be created by an earlier part.
+    fn load_edge(&self, id: u64) -> Result<Option<Edge>, Error> {
+        match self.txn.load_edge(id)? {
+            Some(edge) => Ok(Some(edge)),
+            None => Ok(self.queued_edges()?.into_iter().find(|edge| edge.id == id)),
+        }
+    }
+
+    fn access_property(&self, access: usize) -> Result<Accessed<'_>, Error> {
+        self.access_ref(&self.accesses[access])
+    }
+
+    fn access_ref<'a>(&'a self, access: &'a Access) -> Result<Accessed<'a>, Error> {
+        let prop = match access {
+            Access::Constant(val) => val.to_ref(),
+            Access::NodeId(node) => self.node_stack[*node]
+                .as_ref()
+                .map_or(PropRef::Null, |node| PropRef::Id(node.id())),
+            Access::EdgeId(edge) => self.edge_stack[*edge]
+                .as_ref()
+                .map_or(PropRef::Null, |edge| PropRef::Id(edge.id())),
+            Access::NodeLabel(node) => self.node_stack[*node]
+                .as_ref()
+                .and_then(|node| node.labels().first())
+                .map_or(PropRef::Null, |label| PropRef::Text(label)),
+            Access::EdgeLabel(edge) => self.edge_stack[*edge]
+                .as_ref()
+                .map_or(PropRef::Null, |edge| PropRef::Text(edge.label())),
+            Access::NodeProperty(node, key) => self.node_stack[*node]
+                .as_ref()
+                .map_or(PropRef::Null, |node| node.property(key).to_ref()),
+            Access::EdgeProperty(edge, key) => self.edge_stack[*edge]
+                .as_ref()
+                .map_or(PropRef::Null, |edge| edge.property(key).to_ref()),
+            Access::NodeLabels(node) => {
+                return Ok(Accessed::Computed(self.node_stack[*node].as_ref().map_or(
+                    PropOwned::Null,
+                    |node| {
+                        PropOwned::List(
+                            node.labels().iter().cloned().map(PropOwned::Text).collect(),
+                        )
+                    },
+                )));
+            }
+            Access::NodeProperties(node) => {
+                return Ok(Accessed::Computed(
+                    self.node_stack[*node]
+                        .as_ref()
+                        .map_or(PropOwned::Null, |node| {
+                            PropOwned::Map(node.properties.clone().into_iter().collect())
+                        }),
+                ));
+            }
+            Access::EdgeProperties(edge) => {
+                return Ok(Accessed::Computed(
+                    self.edge_stack[*edge]
+                        .as_ref()
+                        .map_or(PropOwned::Null, |edge| {
+                            PropOwned::Map(edge.properties.clone().into_iter().collect())
+                        }),
+                ));
+            }
+            Access::EdgeOrigin(edge) => self.edge_stack[*edge]
+                .as_ref()
+                .map_or(PropRef::Null, |edge| PropRef::Id(edge.origin)),
+            Access::EdgeTarget(edge) => self.edge_stack[*edge]
+                .as_ref()
+                .map_or(PropRef::Null, |edge| PropRef::Id(edge.target)),
+            Access::Parameter(name) => self
                 .parameters
                 .get(name)
                 .map(PropOwned::to_ref)
-                .unwrap_or(PropRef::Null)),
-        }
+                .unwrap_or(PropRef::Null),
+            Access::Path(path) => self.path_stack[*path]
+                .as_ref()
+                .map_or(PropRef::Null, PropRef::Path),
+            Access::PathNodes(path) => self.path_stack[*path]
+                .as_ref()
+                .map_or(PropRef::Null, |path| PropRef::List(&path.nodes)),
+            Access::PathEdges(path) => self.path_stack[*path]
+                .as_ref()
+                .map_or(PropRef::Null, |path| PropRef::List(&path.edges)),
+            Access::PathLength(path) => self.path_stack[*path]
+                .as_ref()
+                .map_or(PropRef::Null, |path| {
+                    PropRef::Integer(path.edges.len() as i64)
+                }),
+            Access::Value(value) => self.value_stack[*value].to_ref(),
+            Access::Column(column) => self.row[*column].to_ref(),
+            Access::Expression(code) => {
+                return Ok(Accessed::Computed(eval::evaluate(code, |access| {
+                    Ok(self.access_ref(access)?.into_owned())
+                })?));
+            }
+        };
+        Ok(Accessed::Borrowed(prop))
     }
 
-    pub fn access_return(&self, access: usize) -> Result<PropOwned, Error> {
-        match self.returns.get(access).ok_or(Error::IndexOutOfBounds)? {
+    pub fn access_return(&self, access: usize) -> Result<&PropOwned, Error> {
+        self.returned.get(access).ok_or(Error::IndexOutOfBounds)
+    }
+
+    /// Like `access_property`, but observes updates which were queued
+    /// by the running program.
+    fn access_owned(&self, access: &Access) -> Result<PropOwned, Error> {
+        match access {
             Access::Constant(val) => Ok(val.clone()),
-            Access::NodeId(node) => Ok(PropOwned::Id(self.node_stack[*node].id())),
-            Access::EdgeId(edge) => Ok(PropOwned::Id(self.edge_stack[*edge].id())),
-            Access::NodeLabel(node) => {
-                Ok(PropOwned::Text(self.node_stack[*node].label().to_string()))
-            }
-            Access::EdgeLabel(edge) => {
-                Ok(PropOwned::Text(self.edge_stack[*edge].label().to_string()))
-            }
-            Access::NodeProperty(node, key) => {
-                let node = &self.node_stack[*node];
-                Ok(self
+            Access::NodeProperty(node, key) => match &self.node_stack[*node] {
+                Some(node) => Ok(self
                     .txn
                     .get_updated_property(node.id(), key)?
-                    .unwrap_or_else(|| node.property(key).clone()))
-            }
-            Access::EdgeProperty(edge, key) => {
-                let edge = &self.edge_stack[*edge];
-                Ok(self
+                    .unwrap_or_else(|| node.property(key).clone())),
+                None => Ok(PropOwned::Null),
+            },
+            Access::EdgeProperty(edge, key) => match &self.edge_stack[*edge] {
+                Some(edge) => Ok(self
                     .txn
                     .get_updated_property(edge.id(), key)?
-                    .unwrap_or_else(|| edge.property(key).clone()))
-            }
+                    .unwrap_or_else(|| edge.property(key).clone())),
+                None => Ok(PropOwned::Null),
+            },
+            Access::NodeProperties(node) => match &self.node_stack[*node] {
+                Some(node) => {
+                    let mut properties = node.properties.clone().into_iter().collect();
+                    self.txn
+                        .apply_property_updates(node.id(), &mut properties)?;
+                    Ok(PropOwned::Map(properties))
+                }
+                None => Ok(PropOwned::Null),
+            },
+            Access::EdgeProperties(edge) => match &self.edge_stack[*edge] {
+                Some(edge) => {
+                    let mut properties = edge.properties.clone().into_iter().collect();
+                    self.txn
+                        .apply_property_updates(edge.id(), &mut properties)?;
+                    Ok(PropOwned::Map(properties))
+                }
+                None => Ok(PropOwned::Null),
+            },
+            Access::NodeLabel(node) => match &self.node_stack[*node] {
+                Some(node) => Ok(self
+                    .txn
+                    .get_updated_labels(node)?
+                    .into_iter()
+                    .next()
+                    .map_or(PropOwned::Null, PropOwned::Text)),
+                None => Ok(PropOwned::Null),
+            },
+            Access::NodeLabels(node) => match &self.node_stack[*node] {
+                Some(node) => Ok(PropOwned::List(
+                    self.txn
+                        .get_updated_labels(node)?
+                        .into_iter()
+                        .map(PropOwned::Text)
+                        .collect(),
+                )),
+                None => Ok(PropOwned::Null),
+            },
+            Access::NodeId(_)
+            | Access::EdgeId(_)
+            | Access::EdgeOrigin(_)
+            | Access::EdgeTarget(_)
+            | Access::EdgeLabel(_)
+            | Access::Path(_)
+            | Access::PathNodes(_)
+            | Access::PathEdges(_)
+            | Access::PathLength(_) => Ok(self.access_ref(access)?.into_owned()),
             Access::Parameter(name) => Ok(self
                 .parameters
                 .get(name)
-                .map(Clone::clone)
+                .cloned()
                 .unwrap_or(PropOwned::Null)),
+            Access::Value(value) => Ok(self.value_stack[*value].clone()),
+            Access::Column(column) => Ok(self.row[*column].clone()),
+            Access::Expression(code) => eval::evaluate(code, |access| self.access_owned(access)),
+        }
+    }
+
+    fn join_path(&self, start: usize, segments: &[PathSegment]) -> Option<Path> {
+        let mut path = Path {
+            nodes: vec![PropOwned::Id(self.node_stack[start].as_ref()?.id)],
+            edges: vec![],
+        };
+        for segment in segments {
+            match *segment {
+                PathSegment::Edge { edge, node } => {
+                    let edge = self.edge_stack[edge].as_ref()?;
+                    let node = self.node_stack[node].as_ref()?;
+                    path.edges.push(PropOwned::Id(edge.id));
+                    path.nodes.push(PropOwned::Id(node.id));
+                }
+                PathSegment::Path { path: part } => {
+                    let part = self.path_stack[part].as_ref()?;
+                    path.edges.extend_from_slice(&part.edges);
+                    path.nodes.extend_from_slice(&part.nodes[1..]);
+                }
+            }
+        }
+        Some(path)
+    }
+
+    fn access_count(&self, access: usize) -> Result<usize, Error> {
+        self.access_property(access)?
+            .as_ref()
+            .cast_to_id()?
+            .try_into()
+            .map_err(|_| Error::TypeMismatch)
+    }
+
+    /// Check if `value` fully matches the regular expression
+    /// `pattern`, which is compiled once and then reused.
+    fn regex_matches(&self, value: &str, pattern: &str) -> Result<bool, Error> {
+        let mut regexes = self.regexes.borrow_mut();
+        if !regexes.contains_key(pattern) {
+            // patterns may come from the matched data
+            if regexes.len() >= MAX_CACHED_REGEXES {
+                regexes.clear();
+            }
+            regexes.insert(pattern.to_string(), regex(pattern)?);
+        }
+        Ok(regexes[pattern].is_match(value))
+    }
+
+    /// Check if the returned row was not returned before by a
+    /// distinct `UNION`, and remember it. Rows are always new if
+    /// duplicates are kept.
+    fn is_new_row(&mut self) -> Result<bool, Error> {
+        if self.union_rows.is_none() {
+            return Ok(true);
+        }
+        let rows = self.union_rows.as_mut().ok_or(Error::Internal)?;
+        Ok(rows.insert(PropKey(self.returned.clone())))
+    }
+
+    /// A `MERGE` with a `NULL` property could never match,
+    /// and would create a new node or edge each time.
+    fn check_merge_properties(properties: &[(String, PropOwned)]) -> Result<(), Error> {
+        match properties
+            .iter()
+            .find(|(_, value)| matches!(value, PropOwned::Null))
+        {
+            Some((key, _)) => Err(Error::MergeNullProperty(key.clone())),
+            None => Ok(()),
         }
     }
 
@@ -318,40 +978,96 @@ impl<'env, 'txn, 'prog> VirtualMachine<'env, 'txn, 'prog> {
                 Instruction::Jump { jump } => self.current_inst = *jump,
                 Instruction::Yield => {
                     self.current_inst += 1;
-                    return Ok(Status::Yield);
+                    // evaluate the row once, such that errors are
+                    // reported when stepping the query
+                    self.returned = self
+                        .returns
+                        .iter()
+                        .map(|value| self.access_owned(value))
+                        .collect::<Result<_, _>>()?;
+                    if self.is_new_row()? {
+                        return Ok(Status::Yield);
+                    }
                 }
-                Instruction::Halt => return Ok(Status::Halt),
+                Instruction::Halt => match self.union.split_first() {
+                    Some((program, union)) => {
+                        self.instructions = &program.instructions;
+                        self.accesses = &program.accesses;
+                        self.returns = &program.returns;
+                        self.union = union;
+                        self.current_inst = 0;
+
+                        // the previous program may halt early, e.g.
+                        // once a `LIMIT` is reached
+                        self.node_stack.clear();
+                        self.edge_stack.clear();
+                        self.path_stack.clear();
+                        self.value_stack.clear();
+                        self.node_iters.clear();
+                        self.edge_iters.clear();
+                        self.path_iters.clear();
+                        self.shortest_paths.clear();
+                        self.merged_nodes.clear();
+                        self.merged_edges.clear();
+                        self.value_iters.clear();
+                        self.optional_matched.clear();
+                        self.merge_created.clear();
+                        self.part = 0;
+                        self.created_parts.clear();
+                        self.rows.clear();
+                        self.row.clear();
+                        self.counted_rows = 0;
+                        self.groups.clear();
+                        self.group_index.clear();
+                        self.distinct.clear();
+                    }
+                    None => return Ok(Status::Halt),
+                },
 
                 Instruction::IterNodes => {
-                    self.node_iters.push(NodeIter::all(self.txn)?);
+                    let iter = NodeIter::all(self.txn)?.with_queued(self.queued_nodes(&[])?);
+                    self.node_iters.push(iter);
                     self.current_inst += 1;
                 }
-                Instruction::IterLabeledNodes { label } => {
-                    self.node_iters
-                        .push(NodeIter::with_label(self.txn, label.clone())?);
+                Instruction::IterLabeledNodes { labels } => {
+                    let iter = NodeIter::with_labels(self.txn, labels.clone())?
+                        .with_queued(self.queued_nodes(labels)?);
+                    self.node_iters.push(iter);
                     self.current_inst += 1;
                 }
 
                 Instruction::IterOriginEdges { node } => {
-                    let node = &self.node_stack[*node];
-                    self.edge_iters.push(EdgeIter::origins(self.txn, node.id)?);
+                    let iter = match &self.node_stack[*node] {
+                        Some(node) => EdgeIter::origins(self.txn, node.id)?
+                            .with_queued(self.queued_edge_ids(node.id, Direction::Right)?),
+                        None => EdgeIter::Empty,
+                    };
+                    self.edge_iters.push(iter);
                     self.current_inst += 1;
                 }
                 Instruction::IterTargetEdges { node } => {
-                    let node = &self.node_stack[*node];
-                    self.edge_iters.push(EdgeIter::targets(self.txn, node.id)?);
+                    let iter = match &self.node_stack[*node] {
+                        Some(node) => EdgeIter::targets(self.txn, node.id)?
+                            .with_queued(self.queued_edge_ids(node.id, Direction::Left)?),
+                        None => EdgeIter::Empty,
+                    };
+                    self.edge_iters.push(iter);
                     self.current_inst += 1;
                 }
                 Instruction::IterBothEdges { node } => {
-                    let node = &self.node_stack[*node];
-                    self.edge_iters.push(EdgeIter::both(self.txn, node.id)?);
+                    let iter = match &self.node_stack[*node] {
+                        Some(node) => EdgeIter::both(self.txn, node.id)?
+                            .with_queued(self.queued_edge_ids(node.id, Direction::Either)?),
+                        None => EdgeIter::Empty,
+                    };
+                    self.edge_iters.push(iter);
                     self.current_inst += 1;
                 }
 
                 Instruction::LoadNextNode { jump } => {
                     let iter = self.node_iters.last_mut().unwrap();
                     if let Some(entry) = iter.next() {
-                        self.node_stack.push(entry?);
+                        self.node_stack.push(Some(entry?));
                         self.current_inst += 1;
                     } else {
                         self.node_iters.pop();
@@ -362,7 +1078,7 @@ impl<'env, 'txn, 'prog> VirtualMachine<'env, 'txn, 'prog> {
                     let iter = self.edge_iters.last_mut().unwrap();
                     if let Some(edge_id) = iter.next() {
                         self.edge_stack
-                            .push(self.txn.load_edge(edge_id?)?.ok_or(Error::MissingEdge)?);
+                            .push(Some(self.load_edge(edge_id?)?.ok_or(Error::MissingEdge)?));
                         self.current_inst += 1;
                     } else {
                         self.edge_iters.pop();
@@ -370,43 +1086,186 @@ impl<'env, 'txn, 'prog> VirtualMachine<'env, 'txn, 'prog> {
                     }
                 }
 
+                Instruction::IterPaths {
+                    node,
+                    direction,
+                    label,
+                    properties,
+                    min,
+                    max,
+                } => {
+                    let iter = match &self.node_stack[*node] {
+                        Some(node) => {
+                            let properties = properties
+                                .iter()
+                                .map(|(key, value)| {
+                                    Ok((key.clone(), self.access_owned(&self.accesses[*value])?))
+                                })
+                                .collect::<Result<_, Error>>()?;
+                            PathIter::new(
+                                self.txn,
+                                *direction,
+                                node.id,
+                                label.clone(),
+                                properties,
+                                *min,
+                                *max,
+                            )?
+                        }
+                        None => PathIter::empty(self.txn),
+                    };
+                    self.path_iters.push(iter);
+                    self.current_inst += 1;
+                }
+                Instruction::LoadNextPath { jump } => {
+                    let iter = self.path_iters.last_mut().unwrap();
+                    if let Some(path) = iter.next() {
+                        let path = path?;
+                        let end = match path.nodes.last() {
+                            Some(&PropOwned::Id(id)) => id,
+                            _ => return Err(Error::Internal),
+                        };
+                        self.node_stack
+                            .push(Some(self.txn.load_node(end)?.ok_or(Error::MissingNode)?));
+                        self.path_stack.push(Some(path));
+                        self.current_inst += 1;
+                    } else {
+                        self.path_iters.pop();
+                        self.current_inst = *jump;
+                    }
+                }
+
+                Instruction::IterShortestPaths {
+                    node,
+                    end,
+                    direction,
+                    label,
+                    properties,
+                    min,
+                    max,
+                    all,
+                } => {
+                    let properties = properties
+                        .iter()
+                        .map(|(key, value)| {
+                            Ok((key.clone(), self.access_owned(&self.accesses[*value])?))
+                        })
+                        .collect::<Result<Vec<_>, Error>>()?;
+                    let is_match = |edge: &Edge| edge.matches(label.as_deref(), &properties);
+                    let end = end.map(|end| self.node_stack[end].as_ref());
+                    let mut paths = match (&self.node_stack[*node], end) {
+                        (Some(node), Some(Some(end))) => shortest_paths(
+                            self.txn, node.id, end.id, *direction, is_match, *max, *all,
+                        )?,
+                        (Some(node), None) => shortest_paths_from(
+                            self.txn, node.id, *direction, is_match, *max, *all,
+                        )?,
+                        _ => vec![],
+                    };
+                    paths.retain(|path| path.edges.len() >= *min);
+                    self.shortest_paths.push(paths.into_iter());
+                    self.current_inst += 1;
+                }
+                Instruction::LoadNextShortestPath { jump, load_end } => {
+                    let iter = self.shortest_paths.last_mut().unwrap();
+                    if let Some(path) = iter.next() {
+                        if *load_end {
+                            let end = match path.nodes.last() {
+                                Some(&PropOwned::Id(id)) => id,
+                                _ => return Err(Error::Internal),
+                            };
+                            self.node_stack
+                                .push(Some(self.txn.load_node(end)?.ok_or(Error::MissingNode)?));
+                        }
+                        self.path_stack.push(Some(path));
+                        self.current_inst += 1;
+                    } else {
+                        self.shortest_paths.pop();
+                        self.current_inst = *jump;
+                    }
+                }
+
+                Instruction::LoadNamedPath { start, segments } => {
+                    self.path_stack.push(self.join_path(*start, segments));
+                    self.current_inst += 1;
+                }
+
                 Instruction::LoadExactNode { jump, id } => {
-                    let id = self.access_property(*id)?.cast_to_id().ok();
+                    let id = self.access_property(*id)?.as_ref().cast_to_id().ok();
                     if let Some(node) = id
-                        .map(|id| self.txn.load_node(id).transpose())
-                        .flatten()
+                        .and_then(|id| self.load_node(id).transpose())
                         .transpose()?
                     {
-                        self.node_stack.push(node);
+                        self.node_stack.push(Some(node));
                         self.current_inst += 1;
                     } else {
                         self.current_inst = *jump;
                     }
                 }
 
+                Instruction::IterExactNodes { ids } => {
+                    let mut ids = match self.access_property(*ids)?.as_ref() {
+                        PropRef::List(ids) => ids
+                            .iter()
+                            .filter_map(|id| id.to_ref().cast_to_id().ok())
+                            .collect(),
+                        _ => vec![],
+                    };
+                    ids.sort_unstable();
+                    ids.dedup();
+                    let queued = self
+                        .queued_nodes(&[])?
+                        .into_iter()
+                        .filter(|node| ids.binary_search(&node.id).is_ok())
+                        .collect();
+                    self.node_iters
+                        .push(NodeIter::exact(self.txn, ids).with_queued(queued));
+                    self.current_inst += 1;
+                }
+
                 Instruction::LoadOriginNode { edge } => {
-                    let edge = &self.edge_stack[*edge];
-                    let node = self.txn.load_node(edge.origin)?.ok_or(Error::MissingNode)?;
+                    let node = match &self.edge_stack[*edge] {
+                        Some(edge) => Some(self.load_node(edge.origin)?.ok_or(Error::MissingNode)?),
+                        None => None,
+                    };
                     self.node_stack.push(node);
                     self.current_inst += 1;
                 }
                 Instruction::LoadTargetNode { edge } => {
-                    let edge = &self.edge_stack[*edge];
-                    let node = self.txn.load_node(edge.target)?.ok_or(Error::MissingNode)?;
+                    let node = match &self.edge_stack[*edge] {
+                        Some(edge) => Some(self.load_node(edge.target)?.ok_or(Error::MissingNode)?),
+                        None => None,
+                    };
                     self.node_stack.push(node);
                     self.current_inst += 1;
                 }
                 Instruction::LoadOtherNode { node, edge } => {
-                    let node = &self.node_stack[*node];
-                    let edge = &self.edge_stack[*edge];
-                    let other = if edge.target == node.id {
-                        self.txn.load_node(edge.origin)?.ok_or(Error::MissingNode)?
-                    } else {
-                        self.txn.load_node(edge.target)?.ok_or(Error::MissingNode)?
+                    let other = match (&self.node_stack[*node], &self.edge_stack[*edge]) {
+                        (Some(node), Some(edge)) => {
+                            let other = if edge.target == node.id {
+                                edge.origin
+                            } else {
+                                edge.target
+                            };
+                            Some(self.load_node(other)?.ok_or(Error::MissingNode)?)
+                        }
+                        _ => None,
                     };
                     self.node_stack.push(other);
                     self.current_inst += 1;
                 }
+                Instruction::LoadNullNode => {
+                    self.node_stack.push(None);
+                    self.current_inst += 1;
+                }
+                Instruction::LoadNullEdge => {
+                    self.edge_stack.push(None);
+                    self.current_inst += 1;
+                }
+                Instruction::LoadNullPath => {
+                    self.path_stack.push(None);
+                    self.current_inst += 1;
+                }
 
                 Instruction::PopNode => {
                     self.node_stack.pop();
@@ -416,61 +1275,113 @@ impl<'env, 'txn, 'prog> VirtualMachine<'env, 'txn, 'prog> {
                     self.edge_stack.pop();
                     self.current_inst += 1;
                 }
+                Instruction::PopPath => {
+                    self.path_stack.pop();
+                    self.current_inst += 1;
+                }
 
-                Instruction::CheckIsOrigin { jump, node, edge } => {
-                    let node = &self.node_stack[*node];
-                    let edge = &self.edge_stack[*edge];
-                    if node.id == edge.origin {
+                Instruction::PushValue { value } => {
+                    let value = self.access_owned(&self.accesses[*value])?;
+                    self.value_stack.push(value);
+                    self.current_inst += 1;
+                }
+                Instruction::PopValue => {
+                    self.value_stack.pop();
+                    self.current_inst += 1;
+                }
+
+                Instruction::BeginPart => {
+                    self.part += 1;
+                    self.current_inst += 1;
+                }
+                Instruction::EndPart => {
+                    self.part -= 1;
+                    self.current_inst += 1;
+                }
+                Instruction::IterList { list } => {
+                    let elements = match self.access_owned(&self.accesses[*list])? {
+                        PropOwned::List(elements) => elements,
+                        PropOwned::Null => vec![],
+                        element => vec![element],
+                    };
+                    self.value_iters.push(elements.into_iter());
+                    self.current_inst += 1;
+                }
+                Instruction::LoadNextValue { jump } => {
+                    let iter = self.value_iters.last_mut().unwrap();
+                    if let Some(element) = iter.next() {
+                        self.value_stack.push(element);
                         self.current_inst += 1;
                     } else {
+                        self.value_iters.pop();
                         self.current_inst = *jump;
                     }
                 }
-                Instruction::CheckIsTarget { jump, node, edge } => {
-                    let node = &self.node_stack[*node];
-                    let edge = &self.edge_stack[*edge];
-                    if node.id == edge.target {
-                        self.current_inst += 1;
-                    } else {
+
+                Instruction::BeginOptional => {
+                    self.optional_matched.push(false);
+                    self.current_inst += 1;
+                }
+                Instruction::SetMatched => {
+                    *self.optional_matched.last_mut().unwrap() = true;
+                    self.current_inst += 1;
+                }
+                Instruction::EndOptional { jump } => {
+                    if self.optional_matched.pop().unwrap() {
                         self.current_inst = *jump;
+                    } else {
+                        self.current_inst += 1;
+                    }
+                }
+
+                Instruction::CheckIsOrigin { jump, node, edge } => {
+                    match (&self.node_stack[*node], &self.edge_stack[*edge]) {
+                        (Some(node), Some(edge)) if node.id == edge.origin => {
+                            self.current_inst += 1
+                        }
+                        _ => self.current_inst = *jump,
+                    }
+                }
+                Instruction::CheckIsTarget { jump, node, edge } => {
+                    match (&self.node_stack[*node], &self.edge_stack[*edge]) {
+                        (Some(node), Some(edge)) if node.id == edge.target => {
+                            self.current_inst += 1
+                        }
+                        _ => self.current_inst = *jump,
                     }
                 }
 
                 Instruction::CheckNodeLabel { jump, node, label } => {
-                    let node = &self.node_stack[*node];
-                    if node.label() == label.as_str() {
-                        self.current_inst += 1;
-                    } else {
-                        self.current_inst = *jump;
+                    match &self.node_stack[*node] {
+                        Some(node) if node.has_label(label) => self.current_inst += 1,
+                        _ => self.current_inst = *jump,
                     }
                 }
                 Instruction::CheckEdgeLabel { jump, edge, label } => {
-                    let edge = &self.edge_stack[*edge];
-                    if edge.label() == label.as_str() {
-                        self.current_inst += 1;
-                    } else {
-                        self.current_inst = *jump;
+                    match &self.edge_stack[*edge] {
+                        Some(edge) if edge.label() == label.as_str() => self.current_inst += 1,
+                        _ => self.current_inst = *jump,
                     }
                 }
 
                 Instruction::CheckNodeId { jump, node, id } => {
-                    let node = &self.node_stack[*node];
-                    match self.access_property(*id)?.cast_to_id() {
-                        Ok(id) if id == node.id => self.current_inst += 1,
-                        Ok(_) | Err(_) => self.current_inst = *jump,
+                    let id = self.access_property(*id)?.as_ref().cast_to_id();
+                    match (&self.node_stack[*node], id) {
+                        (Some(node), Ok(id)) if id == node.id => self.current_inst += 1,
+                        _ => self.current_inst = *jump,
                     }
                 }
                 Instruction::CheckEdgeId { jump, edge, id } => {
-                    let edge = &self.edge_stack[*edge];
-                    match self.access_property(*id)?.cast_to_id() {
-                        Ok(id) if id == edge.id => self.current_inst += 1,
-                        Ok(_) | Err(_) => self.current_inst = *jump,
+                    let id = self.access_property(*id)?.as_ref().cast_to_id();
+                    match (&self.edge_stack[*edge], id) {
+                        (Some(edge), Ok(id)) if id == edge.id => self.current_inst += 1,
+                        _ => self.current_inst = *jump,
                     }
                 }
 
                 Instruction::CheckTrue { jump, value } => {
                     let value = self.access_property(*value)?;
-                    if value.is_truthy() {
+                    if value.as_ref().is_truthy() {
                         self.current_inst += 1;
                     } else {
                         self.current_inst = *jump;
@@ -479,7 +1390,7 @@ impl<'env, 'txn, 'prog> VirtualMachine<'env, 'txn, 'prog> {
                 Instruction::CheckEq { jump, lhs, rhs } => {
                     let lhs = self.access_property(*lhs)?;
                     let rhs = self.access_property(*rhs)?;
-                    if lhs.loosely_equals(&rhs) {
+                    if lhs.as_ref().loosely_equals(&rhs.as_ref()) {
                         self.current_inst += 1;
                     } else {
                         self.current_inst = *jump;
@@ -488,7 +1399,7 @@ impl<'env, 'txn, 'prog> VirtualMachine<'env, 'txn, 'prog> {
                 Instruction::CheckLt { jump, lhs, rhs } => {
                     let lhs = self.access_property(*lhs)?;
                     let rhs = self.access_property(*rhs)?;
-                    if let Some(Ordering::Less) = lhs.loosely_compare(&rhs) {
+                    if let Some(Ordering::Less) = lhs.as_ref().loosely_compare(&rhs.as_ref()) {
                         self.current_inst += 1;
                     } else {
                         self.current_inst = *jump;
@@ -497,27 +1408,234 @@ impl<'env, 'txn, 'prog> VirtualMachine<'env, 'txn, 'prog> {
                 Instruction::CheckGt { jump, lhs, rhs } => {
                     let lhs = self.access_property(*lhs)?;
                     let rhs = self.access_property(*rhs)?;
-                    if let Some(Ordering::Greater) = lhs.loosely_compare(&rhs) {
+                    if let Some(Ordering::Greater) = lhs.as_ref().loosely_compare(&rhs.as_ref()) {
+                        self.current_inst += 1;
+                    } else {
+                        self.current_inst = *jump;
+                    }
+                }
+                Instruction::CheckStartsWith { jump, lhs, rhs } => {
+                    let lhs = self.access_property(*lhs)?;
+                    let rhs = self.access_property(*rhs)?;
+                    match (lhs.as_ref(), rhs.as_ref()) {
+                        (PropRef::Text(lhs), PropRef::Text(rhs)) if lhs.starts_with(rhs) => {
+                            self.current_inst += 1
+                        }
+                        _ => self.current_inst = *jump,
+                    }
+                }
+                Instruction::CheckEndsWith { jump, lhs, rhs } => {
+                    let lhs = self.access_property(*lhs)?;
+                    let rhs = self.access_property(*rhs)?;
+                    match (lhs.as_ref(), rhs.as_ref()) {
+                        (PropRef::Text(lhs), PropRef::Text(rhs)) if lhs.ends_with(rhs) => {
+                            self.current_inst += 1
+                        }
+                        _ => self.current_inst = *jump,
+                    }
+                }
+                Instruction::CheckContains { jump, lhs, rhs } => {
+                    let lhs = self.access_property(*lhs)?;
+                    let rhs = self.access_property(*rhs)?;
+                    match (lhs.as_ref(), rhs.as_ref()) {
+                        (PropRef::Text(lhs), PropRef::Text(rhs)) if lhs.contains(rhs) => {
+                            self.current_inst += 1
+                        }
+                        _ => self.current_inst = *jump,
+                    }
+                }
+                Instruction::CheckIn { jump, value, list } => {
+                    let value = self.access_property(*value)?;
+                    let list = self.access_property(*list)?;
+                    match list.as_ref() {
+                        PropRef::List(list)
+                            if list
+                                .iter()
+                                .any(|item| value.as_ref().loosely_equals(&item.to_ref())) =>
+                        {
+                            self.current_inst += 1
+                        }
+                        _ => self.current_inst = *jump,
+                    }
+                }
+                Instruction::CheckDisjoint { jump, lhs, rhs } => {
+                    let lhs = self.access_property(*lhs)?;
+                    let rhs = self.access_property(*rhs)?;
+                    match (lhs.as_ref(), rhs.as_ref()) {
+                        (PropRef::List(lhs), PropRef::List(rhs))
+                            if !lhs.iter().any(|a| {
+                                rhs.iter().any(|b| a.to_ref().loosely_equals(&b.to_ref()))
+                            }) =>
+                        {
+                            self.current_inst += 1
+                        }
+                        _ => self.current_inst = *jump,
+                    }
+                }
+                Instruction::CheckNull { jump, value } => {
+                    if let PropRef::Null = self.access_property(*value)?.as_ref() {
+                        self.current_inst += 1;
+                    } else {
+                        self.current_inst = *jump;
+                    }
+                }
+                Instruction::CheckMatches {
+                    jump,
+                    value,
+                    pattern,
+                } => {
+                    let value = self.access_property(*value)?;
+                    let pattern = self.access_property(*pattern)?;
+                    match (value.as_ref(), pattern.as_ref()) {
+                        (PropRef::Text(value), PropRef::Text(pattern))
+                            if self.regex_matches(value, pattern)? =>
+                        {
+                            self.current_inst += 1
+                        }
+                        _ => self.current_inst = *jump,
+                    }
+                }
+
+                Instruction::PushRow { values } => {
+                    let row = values
+                        .iter()
+                        .map(|value| self.access_owned(&self.accesses[*value]))
+                        .collect::<Result<_, Error>>()?;
+                    self.rows.push_back(row);
+                    self.current_inst += 1;
+                }
+                Instruction::SortRows { keys } => {
+                    self.rows.make_contiguous().sort_by(|a, b| {
+                        keys.iter()
+                            .map(|&(column, descending)| {
+                                let ord = a[column].to_ref().order(&b[column].to_ref());
+                                if descending {
+                                    ord.reverse()
+                                } else {
+                                    ord
+                                }
+                            })
+                            .find(|ord| *ord != Ordering::Equal)
+                            .unwrap_or(Ordering::Equal)
+                    });
+                    self.current_inst += 1;
+                }
+                Instruction::SkipRows { count } => {
+                    let count = self.access_count(*count)?.min(self.rows.len());
+                    self.rows.drain(..count);
+                    self.current_inst += 1;
+                }
+                Instruction::LimitRows { count } => {
+                    let count = self.access_count(*count)?;
+                    self.rows.truncate(count);
+                    self.current_inst += 1;
+                }
+                Instruction::CountRow { jump, skip } => {
+                    let skip = skip.map(|skip| self.access_count(skip)).transpose()?;
+                    self.counted_rows += 1;
+                    if self.counted_rows > skip.unwrap_or(0) {
+                        self.current_inst += 1;
+                    } else {
+                        self.current_inst = *jump;
+                    }
+                }
+                Instruction::CheckRowLimit { jump, skip, limit } => {
+                    let skip = skip.map(|skip| self.access_count(skip)).transpose()?;
+                    let limit = self.access_count(*limit)?;
+                    if self.counted_rows >= skip.unwrap_or(0).saturating_add(limit) {
+                        self.current_inst += 1;
+                    } else {
+                        self.current_inst = *jump;
+                    }
+                }
+                Instruction::CheckDistinct { jump, values } => {
+                    let key = values
+                        .iter()
+                        .map(|value| self.access_owned(&self.accesses[*value]))
+                        .collect::<Result<Vec<_>, Error>>()?;
+                    if self
+                        .distinct
+                        .entry(self.current_inst)
+                        .or_default()
+                        .insert(PropKey(key))
+                    {
+                        self.current_inst += 1;
+                    } else {
+                        self.current_inst = *jump;
+                    }
+                }
+
+                Instruction::LoadNextRow { jump } => {
+                    if let Some(row) = self.rows.pop_front() {
+                        self.row = row;
                         self.current_inst += 1;
                     } else {
                         self.current_inst = *jump;
                     }
                 }
 
-                Instruction::CreateNode { label, properties } => {
+                Instruction::AggregateRow { keys, aggregates } => {
+                    let key = keys
+                        .iter()
+                        .map(|key| self.access_owned(&self.accesses[*key]))
+                        .collect::<Result<Vec<_>, Error>>()?;
+                    let key = PropKey(key);
+                    let group = match self.group_index.get(&key) {
+                        Some(group) => *group,
+                        None => {
+                            self.groups.push((
+                                key.0.clone(),
+                                aggregates
+                                    .iter()
+                                    .map(|(function, _)| Accumulator::new(function))
+                                    .collect(),
+                            ));
+                            self.group_index.insert(key, self.groups.len() - 1);
+                            self.groups.len() - 1
+                        }
+                    };
+                    for (idx, (_, arguments)) in aggregates.iter().enumerate() {
+                        let arguments = arguments
+                            .iter()
+                            .map(|argument| self.access_owned(&self.accesses[*argument]))
+                            .collect::<Result<Vec<_>, Error>>()?;
+                        self.groups[group].1[idx].step(&arguments)?;
+                    }
+                    self.current_inst += 1;
+                }
+                Instruction::FlushGroups { default } => {
+                    if let (true, Some(default)) = (self.groups.is_empty(), default) {
+                        let row = default
+                            .iter()
+                            .map(|function| Accumulator::new(function).finish())
+                            .collect::<Result<_, Error>>()?;
+                        self.rows.push_back(row);
+                    }
+                    self.group_index.clear();
+                    for (mut row, aggregates) in std::mem::take(&mut self.groups) {
+                        for aggregate in aggregates {
+                            row.push(aggregate.finish()?);
+                        }
+                        self.rows.push_back(row);
+                    }
+                    self.current_inst += 1;
+                }
+
+                Instruction::CreateNode { labels, properties } => {
                     let node = Node {
                         id: self.txn.id_seq(),
-                        label: label.clone(),
+                        labels: labels.clone(),
                         properties: properties
                             .iter()
                             .map(|(key, access)| -> Result<_, Error> {
-                                Ok((key.clone(), self.access_property(*access)?.to_owned()))
+                                Ok((key.clone(), self.access_property(*access)?.into_owned()))
                             })
                             .filter(|prop| !matches!(prop, Ok((_, PropOwned::Null))))
                             .collect::<Result<_, Error>>()?,
                     };
                     self.txn.queue_update(Update::CreateNode(node.clone()))?;
-                    self.node_stack.push(node);
+                    self.created_parts.insert(node.id, self.part);
+                    self.node_stack.push(Some(node));
                     self.current_inst += 1;
                 }
                 Instruction::CreateEdge {
@@ -526,8 +1644,15 @@ impl<'env, 'txn, 'prog> VirtualMachine<'env, 'txn, 'prog> {
                     target,
                     properties,
                 } => {
-                    let origin = self.node_stack[*origin].id();
-                    let target = self.node_stack[*target].id();
+                    // edges can not be created between unmatched optional nodes
+                    let origin = self.node_stack[*origin]
+                        .as_ref()
+                        .ok_or(Error::MissingNode)?
+                        .id();
+                    let target = self.node_stack[*target]
+                        .as_ref()
+                        .ok_or(Error::MissingNode)?
+                        .id();
                     let edge = Edge {
                         id: self.txn.id_seq(),
                         label: label.clone(),
@@ -536,43 +1661,217 @@ impl<'env, 'txn, 'prog> VirtualMachine<'env, 'txn, 'prog> {
                         properties: properties
                             .iter()
                             .map(|(key, access)| -> Result<_, Error> {
-                                Ok((key.clone(), self.access_property(*access)?.to_owned()))
+                                Ok((key.clone(), self.access_property(*access)?.into_owned()))
                             })
                             .filter(|prop| !matches!(prop, Ok((_, PropOwned::Null))))
                             .collect::<Result<_, Error>>()?,
                     };
                     self.txn.queue_update(Update::CreateEdge(edge.clone()))?;
-                    self.edge_stack.push(edge);
+                    self.created_parts.insert(edge.id, self.part);
+                    self.edge_stack.push(Some(edge));
+                    self.current_inst += 1;
+                }
+                Instruction::MergeNode { labels, properties } => {
+                    let properties = properties
+                        .iter()
+                        .map(|(key, access)| -> Result<_, Error> {
+                            Ok((key.clone(), self.access_property(*access)?.into_owned()))
+                        })
+                        .collect::<Result<Vec<_>, Error>>()?;
+                    Self::check_merge_properties(&properties)?;
+                    let mut nodes = self.txn.find_nodes(labels, &properties)?;
+                    let created = nodes.is_empty();
+                    if created {
+                        let node = Node {
+                            id: self.txn.id_seq(),
+                            labels: labels.clone(),
+                            properties: properties.into_iter().collect(),
+                        };
+                        self.txn.queue_update(Update::CreateNode(node.clone()))?;
+                        self.created_parts.insert(node.id, self.part);
+                        nodes.push(node);
+                    }
+                    self.merged_nodes.push(nodes.into_iter());
+                    self.merge_created.push(created);
+                    self.current_inst += 1;
+                }
+                Instruction::MergeEdge {
+                    label,
+                    origin,
+                    target,
+                    properties,
+                } => {
+                    // edges can not be merged between unmatched optional nodes
+                    let origin = self.node_stack[*origin]
+                        .as_ref()
+                        .ok_or(Error::MissingNode)?
+                        .id();
+                    let target = self.node_stack[*target]
+                        .as_ref()
+                        .ok_or(Error::MissingNode)?
+                        .id();
+                    let properties = properties
+                        .iter()
+                        .map(|(key, access)| -> Result<_, Error> {
+                            Ok((key.clone(), self.access_property(*access)?.into_owned()))
+                        })
+                        .collect::<Result<Vec<_>, Error>>()?;
+                    Self::check_merge_properties(&properties)?;
+                    let mut edges = self.txn.find_edges(label, origin, target, &properties)?;
+                    let created = edges.is_empty();
+                    if created {
+                        let edge = Edge {
+                            id: self.txn.id_seq(),
+                            label: label.clone(),
+                            origin,
+                            target,
+                            properties: properties.into_iter().collect(),
+                        };
+                        self.txn.queue_update(Update::CreateEdge(edge.clone()))?;
+                        self.created_parts.insert(edge.id, self.part);
+                        edges.push(edge);
+                    }
+                    self.merged_edges.push(edges.into_iter());
+                    self.merge_created.push(created);
                     self.current_inst += 1;
                 }
+                Instruction::LoadNextMergedNode { jump } => {
+                    let iter = self.merged_nodes.last_mut().unwrap();
+                    if let Some(node) = iter.next() {
+                        self.node_stack.push(Some(node));
+                        self.current_inst += 1;
+                    } else {
+                        self.merged_nodes.pop();
+                        self.merge_created.pop();
+                        self.current_inst = *jump;
+                    }
+                }
+                Instruction::LoadNextMergedEdge { jump } => {
+                    let iter = self.merged_edges.last_mut().unwrap();
+                    if let Some(edge) = iter.next() {
+                        self.edge_stack.push(Some(edge));
+                        self.current_inst += 1;
+                    } else {
+                        self.merged_edges.pop();
+                        self.merge_created.pop();
+                        self.current_inst = *jump;
+                    }
+                }
+                Instruction::CheckCreated { jump } => {
+                    if *self.merge_created.last().unwrap() {
+                        self.current_inst += 1;
+                    } else {
+                        self.current_inst = *jump;
+                    }
+                }
+                // updates to unmatched optional nodes and edges are ignored
                 Instruction::SetNodeProperty { node, key, value } => {
-                    let node = &self.node_stack[*node];
-                    let value = self.access_property(*value)?.to_owned();
-                    self.txn.queue_update(Update::SetNodeProperty(
-                        node.id,
-                        key.to_string(),
-                        value,
-                    ))?;
+                    if let Some(node) = &self.node_stack[*node] {
+                        let value = self.access_property(*value)?.into_owned();
+                        self.txn.queue_update(Update::SetNodeProperty(
+                            node.id,
+                            key.to_string(),
+                            value,
+                        ))?;
+                    }
                     self.current_inst += 1;
                 }
                 Instruction::SetEdgeProperty { edge, key, value } => {
-                    let edge = &self.node_stack[*edge];
-                    let value = self.access_property(*value)?.to_owned();
-                    self.txn.queue_update(Update::SetEdgeProperty(
-                        edge.id,
-                        key.to_string(),
-                        value,
-                    ))?;
+                    if let Some(edge) = &self.edge_stack[*edge] {
+                        let value = self.access_property(*value)?.into_owned();
+                        self.txn.queue_update(Update::SetEdgeProperty(
+                            edge.id,
+                            key.to_string(),
+                            value,
+                        ))?;
+                    }
+                    self.current_inst += 1;
+                }
+                Instruction::SetNodeProperties {
+                    node,
+                    value,
+                    replace,
+                } => {
+                    if let Some(node) = &self.node_stack[*node] {
+                        let properties = match self.access_property(*value)?.into_owned() {
+                            PropOwned::Map(map) => map,
+                            _ => return Err(Error::TypeMismatch),
+                        };
+                        let update = if *replace {
+                            Update::ReplaceNodeProperties(node.id, properties)
+                        } else {
+                            Update::MergeNodeProperties(node.id, properties)
+                        };
+                        self.txn.queue_update(update)?;
+                    }
+                    self.current_inst += 1;
+                }
+                Instruction::SetEdgeProperties {
+                    edge,
+                    value,
+                    replace,
+                } => {
+                    if let Some(edge) = &self.edge_stack[*edge] {
+                        let properties = match self.access_property(*value)?.into_owned() {
+                            PropOwned::Map(map) => map,
+                            _ => return Err(Error::TypeMismatch),
+                        };
+                        let update = if *replace {
+                            Update::ReplaceEdgeProperties(edge.id, properties)
+                        } else {
+                            Update::MergeEdgeProperties(edge.id, properties)
+                        };
+                        self.txn.queue_update(update)?;
+                    }
+                    self.current_inst += 1;
+                }
+                Instruction::RemoveNodeProperty { node, key } => {
+                    if let Some(node) = &self.node_stack[*node] {
+                        self.txn
+                            .queue_update(Update::RemoveNodeProperty(node.id, key.to_string()))?;
+                    }
+                    self.current_inst += 1;
+                }
+                Instruction::RemoveEdgeProperty { edge, key } => {
+                    if let Some(edge) = &self.edge_stack[*edge] {
+                        self.txn
+                            .queue_update(Update::RemoveEdgeProperty(edge.id, key.to_string()))?;
+                    }
+                    self.current_inst += 1;
+                }
+                Instruction::SetNodeLabels { node, labels } => {
+                    if let Some(node) = &self.node_stack[*node] {
+                        for label in labels {
+                            self.txn
+                                .queue_update(Update::SetNodeLabel(node.id, label.clone()))?;
+                        }
+                    }
+                    self.current_inst += 1;
+                }
+                Instruction::RemoveNodeLabels { node, labels } => {
+                    if let Some(node) = &self.node_stack[*node] {
+                        for label in labels {
+                            self.txn
+                                .queue_update(Update::RemoveNodeLabel(node.id, label.clone()))?;
+                        }
+                    }
                     self.current_inst += 1;
                 }
-                Instruction::DeleteNode { node } => {
-                    let node = &self.node_stack[*node];
-                    self.txn.queue_update(Update::DeleteNode(node.id))?;
+                Instruction::DeleteNode { node, detach } => {
+                    if let Some(node) = &self.node_stack[*node] {
+                        let update = if *detach {
+                            Update::DetachDeleteNode(node.id)
+                        } else {
+                            Update::DeleteNode(node.id)
+                        };
+                        self.txn.queue_update(update)?;
+                    }
                     self.current_inst += 1;
                 }
                 Instruction::DeleteEdge { edge } => {
-                    let edge = &self.edge_stack[*edge];
-                    self.txn.queue_update(Update::DeleteEdge(edge.id))?;
+                    if let Some(edge) = &self.edge_stack[*edge] {
+                        self.txn.queue_update(Update::DeleteEdge(edge.id))?;
+                    }
                     self.current_inst += 1;
                 }
             }
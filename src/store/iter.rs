@@ -1,4 +1,5 @@
-use super::{DynTxn, Node, StoreTxn};
+use super::{DynTxn, Node, Path, PropOwned, StoreTxn};
+use crate::parser::ast::Direction;
 use crate::Error;
 use sanakirja::{btree, Env, UnsizedStorable};
 use serde::Deserialize;
@@ -12,10 +13,31 @@ type BytesIter<'txn, K, V> =
 pub(crate) type IndexIter<'txn> =
     btree::Iter<'txn, DynTxn<&'txn Env>, u64, u64, btree::page::Page<u64, u64>>;
 
+#[allow(clippy::large_enum_variant)]
 pub(crate) enum EdgeIter<'txn> {
     Directed(u64, IndexIter<'txn>),
     // TODO(dyedgreen): Fix this; its a bit iffy ...
     Undirected(u64, Option<IndexIter<'txn>>, IndexIter<'txn>),
+    Empty,
+    /// Stored edges, followed by the ids of edges
+    /// which are queued to be created.
+    Queued(Box<EdgeIter<'txn>>, std::vec::IntoIter<u64>),
+}
+
+/// Iterates all paths which start at a given node and consist
+/// of `min` to `max` edges, where no edge occurs twice. Every
+/// edge must have `label` and `properties`, if given.
+pub(crate) struct PathIter<'txn> {
+    txn: &'txn StoreTxn<'txn>,
+    direction: Direction,
+    label: Option<String>,
+    properties: Vec<(String, PropOwned)>,
+    min: usize,
+    max: Option<usize>,
+
+    path: Path,
+    frames: Vec<EdgeIter<'txn>>,
+    yield_start: bool,
 }
 
 pub(crate) struct DeserializeIter<'txn, K, I>
@@ -29,7 +51,18 @@ where
 
 pub(crate) enum NodeIter<'txn> {
     All(DeserializeIter<'txn, u64, Node>),
-    WithLabel(String, &'txn StoreTxn<'txn>, BytesCursor<[u8], u64>),
+    /// Nodes from the index of the first label, which
+    /// also have all other labels.
+    WithLabels(
+        String,
+        Vec<String>,
+        &'txn StoreTxn<'txn>,
+        BytesCursor<[u8], u64>,
+    ),
+    Exact(&'txn StoreTxn<'txn>, std::vec::IntoIter<u64>),
+    /// Stored nodes, followed by nodes which are
+    /// queued to be created.
+    Queued(Box<NodeIter<'txn>>, std::vec::IntoIter<Node>),
 }
 
 impl<'txn> EdgeIter<'txn> {
@@ -48,6 +81,132 @@ impl<'txn> EdgeIter<'txn> {
         let iter_targ = btree::iter(&txn.txn, &txn.targets, Some((&node, None)))?;
         Ok(Self::Undirected(node, Some(iter_orig), iter_targ))
     }
+
+    /// Also iterate the `queued` edge ids, after the stored ones.
+    pub fn with_queued(self, queued: Vec<u64>) -> Self {
+        if queued.is_empty() {
+            self
+        } else {
+            Self::Queued(Box::new(self), queued.into_iter())
+        }
+    }
+}
+
+impl<'txn> PathIter<'txn> {
+    /// Create an iterator over paths from `start`, which follow
+    /// edges in the given `direction`.
+    pub fn new(
+        txn: &'txn StoreTxn<'txn>,
+        direction: Direction,
+        start: u64,
+        label: Option<String>,
+        properties: Vec<(String, PropOwned)>,
+        min: usize,
+        max: Option<usize>,
+    ) -> Result<Self, Error> {
+        let frame = if max == Some(0) {
+            EdgeIter::Empty
+        } else {
+            Self::expand(txn, direction, start)?
+        };
+        Ok(Self {
+            txn,
+            direction,
+            label,
+            properties,
+            min,
+            max,
+            path: Path {
+                nodes: vec![PropOwned::Id(start)],
+                edges: vec![],
+            },
+            frames: vec![frame],
+            yield_start: min == 0,
+        })
+    }
+
+    pub fn empty(txn: &'txn StoreTxn<'txn>) -> Self {
+        Self {
+            txn,
+            direction: Direction::Right,
+            label: None,
+            properties: vec![],
+            min: 0,
+            max: None,
+            path: Path {
+                nodes: vec![],
+                edges: vec![],
+            },
+            frames: vec![],
+            yield_start: false,
+        }
+    }
+
+    fn expand(
+        txn: &'txn StoreTxn<'txn>,
+        direction: Direction,
+        node: u64,
+    ) -> Result<EdgeIter<'txn>, Error> {
+        match direction {
+            Direction::Right => EdgeIter::origins(txn, node),
+            Direction::Left => EdgeIter::targets(txn, node),
+            Direction::Either => EdgeIter::both(txn, node),
+        }
+    }
+
+    fn next_path(&mut self) -> Result<Option<Path>, Error> {
+        if self.yield_start {
+            self.yield_start = false;
+            return Ok(Some(self.path.clone()));
+        }
+        while let Some(frame) = self.frames.last_mut() {
+            let edge_id = match frame.next().transpose()? {
+                Some(edge_id) => edge_id,
+                None => {
+                    self.frames.pop();
+                    if self.path.edges.pop().is_some() {
+                        self.path.nodes.pop();
+                    }
+                    continue;
+                }
+            };
+            if self.path.edges.contains(&PropOwned::Id(edge_id)) {
+                continue;
+            }
+            let edge = self.txn.load_edge(edge_id)?.ok_or(Error::MissingEdge)?;
+            if !edge.matches(self.label.as_deref(), &self.properties) {
+                continue;
+            }
+
+            let next = match self.path.nodes.last() {
+                Some(&PropOwned::Id(node)) if node == edge.origin => edge.target,
+                Some(_) => edge.origin,
+                None => return Err(Error::Internal),
+            };
+            self.path.edges.push(PropOwned::Id(edge_id));
+            self.path.nodes.push(PropOwned::Id(next));
+            let len = self.path.edges.len();
+            // `Option::is_none_or` needs a recent compiler
+            #[allow(clippy::unnecessary_map_or)]
+            self.frames.push(if self.max.map_or(true, |max| len < max) {
+                Self::expand(self.txn, self.direction, next)?
+            } else {
+                EdgeIter::Empty
+            });
+            if len >= self.min {
+                return Ok(Some(self.path.clone()));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<'txn> Iterator for PathIter<'txn> {
+    type Item = Result<Path, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_path().transpose()
+    }
 }
 
 impl<'txn> Iterator for EdgeIter<'txn> {
@@ -77,6 +236,8 @@ impl<'txn> Iterator for EdgeIter<'txn> {
                     *iter_opt = None;
                     iter_target.next().and_then(filter(*id))
                 }),
+            Self::Empty => None,
+            Self::Queued(stored, queued) => stored.next().or_else(|| queued.next().map(Ok)),
         }
     }
 }
@@ -108,7 +269,7 @@ where
 
     fn next(&mut self) -> Option<Self::Item> {
         self.inner.next().map(|res| {
-            res.map_err(|err| err).and_then(|(key, bytes)| {
+            res.and_then(|(key, bytes)| {
                 let item = bincode::deserialize(bytes)?;
                 Ok((key, item))
             })
@@ -121,10 +282,59 @@ impl<'txn> NodeIter<'txn> {
         Ok(Self::All(DeserializeIter::new(txn, &txn.nodes, None)?))
     }
 
-    pub(crate) fn with_label(txn: &'txn StoreTxn<'txn>, label: String) -> Result<Self, Error> {
+    /// Iterate the nodes which have all of `labels`. Nodes are
+    /// found using the index of the most selective label.
+    pub(crate) fn with_labels(
+        txn: &'txn StoreTxn<'txn>,
+        mut labels: Vec<String>,
+    ) -> Result<Self, Error> {
+        if labels.is_empty() {
+            return Self::all(txn);
+        }
+        let label = labels.swap_remove(Self::most_selective(txn, &labels)?);
         let mut cursor = BytesCursor::new(&txn.txn, &txn.labels)?;
         cursor.set(&txn.txn, label.as_bytes(), None)?;
-        Ok(Self::WithLabel(label, txn, cursor))
+        Ok(Self::WithLabels(label, labels, txn, cursor))
+    }
+
+    /// Find the label with the fewest nodes. The index entries
+    /// of all labels are counted in lockstep, such that only as
+    /// many entries are visited as the smallest label has.
+    fn most_selective(txn: &'txn StoreTxn<'txn>, labels: &[String]) -> Result<usize, Error> {
+        if labels.len() < 2 {
+            return Ok(0);
+        }
+        let mut cursors = labels
+            .iter()
+            .map(|label| {
+                let mut cursor = BytesCursor::new(&txn.txn, &txn.labels)?;
+                cursor.set(&txn.txn, label.as_bytes(), None)?;
+                Ok(cursor)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        loop {
+            for (idx, (label, cursor)) in labels.iter().zip(&mut cursors).enumerate() {
+                match cursor.next(&txn.txn)? {
+                    Some((key, _)) if key == label.as_bytes() => (),
+                    _ => return Ok(idx),
+                }
+            }
+        }
+    }
+
+    /// Iterate the nodes with the given ids, skipping
+    /// ids which do not exist.
+    pub(crate) fn exact(txn: &'txn StoreTxn<'txn>, ids: Vec<u64>) -> Self {
+        Self::Exact(txn, ids.into_iter())
+    }
+
+    /// Also iterate the `queued` nodes, after the stored ones.
+    pub(crate) fn with_queued(self, queued: Vec<Node>) -> Self {
+        if queued.is_empty() {
+            self
+        } else {
+            Self::Queued(Box::new(self), queued.into_iter())
+        }
     }
 }
 
@@ -136,19 +346,22 @@ impl<'txn> Iterator for NodeIter<'txn> {
             Self::All(iter) => iter
                 .next()
                 .map(|maybe_result| maybe_result.map(|(_, node)| node)),
-            Self::WithLabel(label, txn, cursor) => match cursor.next(&txn.txn).transpose() {
-                Some(result) => result
-                    .map(|(key, node_id)| {
-                        if key == label.as_bytes() {
-                            Some(node_id)
-                        } else {
-                            None
-                        }
-                    })
-                    .transpose()
-                    .and_then(|result| result.and_then(|&id| txn.load_node(id)).transpose()),
-                None => None,
+            Self::WithLabels(label, others, txn, cursor) => loop {
+                let id = match cursor.next(&txn.txn) {
+                    Ok(Some((key, &id))) if key == label.as_bytes() => id,
+                    Ok(_) => return None,
+                    Err(err) => return Some(Err(err)),
+                };
+                match txn.load_node(id) {
+                    Ok(Some(node)) if others.iter().all(|label| node.has_label(label)) => {
+                        return Some(Ok(node))
+                    }
+                    Ok(_) => continue,
+                    Err(err) => return Some(Err(err)),
+                }
             },
+            Self::Exact(txn, ids) => ids.find_map(|id| txn.load_node(id).transpose()),
+            Self::Queued(stored, queued) => stored.next().or_else(|| queued.next().map(Ok)),
         }
     }
 }
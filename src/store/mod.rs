@@ -1,20 +1,24 @@
 use crate::Error;
 use sanakirja::btree::{Db, UDb};
 use sanakirja::{btree, Env, MutTxn, RootDb, Storable, UnsizedStorable};
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::RwLock;
 use txn::DynTxn;
 
 mod iter;
+mod search;
 mod txn;
 mod types;
 
 #[cfg(test)]
 mod tests;
 
-pub(crate) use iter::{EdgeIter, NodeIter};
+pub(crate) use iter::{EdgeIter, NodeIter, PathIter};
+pub(crate) use search::{shortest_paths, shortest_paths_from};
 pub use types::{Edge, Node, PropOwned, PropRef};
+pub(crate) use types::{LegacyNode, Path, PropKey};
 
 const ID_SQUENCE: usize = 0;
 const DB_NODES: usize = 1;
@@ -22,6 +26,11 @@ const DB_EDGES: usize = 2;
 const DB_ORIGINS: usize = 3;
 const DB_TARGETS: usize = 4;
 const DB_LABELS: usize = 5;
+const FORMAT_VERSION: usize = 6;
+
+/// The current storage format. Stores written before
+/// the format was versioned hold a single label per node.
+const STORE_FORMAT: u64 = 1;
 
 pub(crate) struct Store {
     pub env: Env,
@@ -47,17 +56,28 @@ pub(crate) enum Update {
     CreateEdge(Edge),
     SetNodeProperty(u64, String, PropOwned),
     SetEdgeProperty(u64, String, PropOwned),
+    MergeNodeProperties(u64, BTreeMap<String, PropOwned>),
+    MergeEdgeProperties(u64, BTreeMap<String, PropOwned>),
+    ReplaceNodeProperties(u64, BTreeMap<String, PropOwned>),
+    ReplaceEdgeProperties(u64, BTreeMap<String, PropOwned>),
+    RemoveNodeProperty(u64, String),
+    RemoveEdgeProperty(u64, String),
+    SetNodeLabel(u64, String),
+    RemoveNodeLabel(u64, String),
     DeleteNode(u64),
+    DetachDeleteNode(u64),
     DeleteEdge(u64),
 }
 
 impl Store {
-    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+    pub fn open<P: AsRef<path::Path>>(path: P) -> Result<Self, Error> {
         // TODO: How small can the thing be initially,
         // how many version do we want to allow?
         let env = Env::new(path, 4096 * 4, 2)?;
         let store = Self { env };
-        store.mut_txn()?.commit()?;
+        let mut txn = store.mut_txn()?;
+        txn.migrate()?;
+        txn.commit()?;
         Ok(store)
     }
 
@@ -69,7 +89,7 @@ impl Store {
         Ok(store)
     }
 
-    pub fn txn(&self) -> Result<StoreTxn, Error> {
+    pub fn txn(&self) -> Result<StoreTxn<'_>, Error> {
         let txn = Env::txn_begin(&self.env)?;
         let id_seq = AtomicU64::new(txn.root(ID_SQUENCE));
         let nodes = txn.root_db(DB_NODES).ok_or(Error::Corruption)?;
@@ -89,7 +109,7 @@ impl Store {
         })
     }
 
-    pub fn mut_txn(&self) -> Result<StoreTxn, Error> {
+    pub fn mut_txn(&self) -> Result<StoreTxn<'_>, Error> {
         let mut txn = Env::mut_txn_begin(&self.env)?;
         let id_seq = AtomicU64::new(txn.root(ID_SQUENCE).unwrap_or(0));
         let nodes = Self::get_buffer_db(&mut txn, DB_NODES)?;
@@ -172,12 +192,9 @@ impl<'e> StoreTxn<'e> {
     pub fn unchecked_create_node(&mut self, node: Node) -> Result<Node, Error> {
         let bytes = bincode::serialize(&node)?;
         btree::put(&mut self.txn, &mut self.nodes, &node.id, bytes.as_ref())?;
-        btree::put(
-            &mut self.txn,
-            &mut self.labels,
-            node.label().as_bytes(),
-            &node.id,
-        )?;
+        for label in &node.labels {
+            btree::put(&mut self.txn, &mut self.labels, label.as_bytes(), &node.id)?;
+        }
         Ok(node)
     }
 
@@ -194,6 +211,54 @@ impl<'e> StoreTxn<'e> {
         Ok(())
     }
 
+    /// Replace all properties of `node`, skipping
+    /// any properties which are `NULL`.
+    pub fn replace_node_properties(
+        &mut self,
+        node: u64,
+        properties: BTreeMap<String, PropOwned>,
+    ) -> Result<(), Error> {
+        let mut node = self.load_node(node)?.ok_or(Error::MissingNode)?;
+        node.properties = properties
+            .into_iter()
+            .filter(|(_, value)| *value != PropOwned::Null)
+            .collect();
+        let bytes = bincode::serialize(&node)?;
+        btree::del(&mut self.txn, &mut self.nodes, &node.id, None)?;
+        btree::put(&mut self.txn, &mut self.nodes, &node.id, bytes.as_ref())?;
+        Ok(())
+    }
+
+    /// Add `label` to `node`, unless the node
+    /// already has the label.
+    pub fn add_node_label(&mut self, node: u64, label: &str) -> Result<(), Error> {
+        let mut node = self.load_node(node)?.ok_or(Error::MissingNode)?;
+        if node.has_label(label) {
+            return Ok(());
+        }
+        node.labels.push(label.to_string());
+        let bytes = bincode::serialize(&node)?;
+        btree::del(&mut self.txn, &mut self.nodes, &node.id, None)?;
+        btree::put(&mut self.txn, &mut self.nodes, &node.id, bytes.as_ref())?;
+        btree::put(&mut self.txn, &mut self.labels, label.as_bytes(), &node.id)?;
+        Ok(())
+    }
+
+    /// Remove `label` from `node`, if the
+    /// node has the label.
+    pub fn remove_node_label(&mut self, node: u64, label: &str) -> Result<(), Error> {
+        let mut node = self.load_node(node)?.ok_or(Error::MissingNode)?;
+        if !node.has_label(label) {
+            return Ok(());
+        }
+        node.labels.retain(|other| other != label);
+        let bytes = bincode::serialize(&node)?;
+        btree::del(&mut self.txn, &mut self.nodes, &node.id, None)?;
+        btree::put(&mut self.txn, &mut self.nodes, &node.id, bytes.as_ref())?;
+        btree::del(&mut self.txn, &mut self.labels, label.as_bytes(), Some(&node.id))?;
+        Ok(())
+    }
+
     pub fn delete_node(&mut self, node: u64) -> Result<(), Error> {
         let has_origin = btree::get(&self.txn, &self.origins, &node, None)?
             .map(|(k, _)| *k == node)
@@ -204,21 +269,30 @@ impl<'e> StoreTxn<'e> {
         if has_origin || has_target {
             Err(Error::DeleteConnected)
         } else {
-            self.load_node(node)?
-                .map(|node| {
+            if let Some(node) = self.load_node(node)? {
+                for label in &node.labels {
                     btree::del(
                         &mut self.txn,
                         &mut self.labels,
-                        node.label.as_bytes(),
+                        label.as_bytes(),
                         Some(&node.id),
                     )?;
-                    btree::del(&mut self.txn, &mut self.nodes, &node.id, None)
-                })
-                .transpose()?;
+                }
+                btree::del(&mut self.txn, &mut self.nodes, &node.id, None)?;
+            }
             Ok(())
         }
     }
 
+    /// Delete `node` together with all edges connected to it.
+    pub fn detach_delete_node(&mut self, node: u64) -> Result<(), Error> {
+        let edges = EdgeIter::both(self, node)?.collect::<Result<Vec<_>, _>>()?;
+        for edge in edges {
+            self.delete_edge(edge)?;
+        }
+        self.delete_node(node)
+    }
+
     pub fn unchecked_create_edge(&mut self, edge: Edge) -> Result<Edge, Error> {
         let bytes = bincode::serialize(&edge)?;
         btree::put(&mut self.txn, &mut self.edges, &edge.id, bytes.as_ref())?;
@@ -240,6 +314,24 @@ impl<'e> StoreTxn<'e> {
         Ok(())
     }
 
+    /// Replace all properties of `edge`, skipping
+    /// any properties which are `NULL`.
+    pub fn replace_edge_properties(
+        &mut self,
+        edge: u64,
+        properties: BTreeMap<String, PropOwned>,
+    ) -> Result<(), Error> {
+        let mut edge = self.load_edge(edge)?.ok_or(Error::MissingEdge)?;
+        edge.properties = properties
+            .into_iter()
+            .filter(|(_, value)| *value != PropOwned::Null)
+            .collect();
+        let bytes = bincode::serialize(&edge)?;
+        btree::del(&mut self.txn, &mut self.edges, &edge.id, None)?;
+        btree::put(&mut self.txn, &mut self.edges, &edge.id, bytes.as_ref())?;
+        Ok(())
+    }
+
     pub fn delete_edge(&mut self, edge: u64) -> Result<(), Error> {
         if let Some(edge) = self.load_edge(edge)? {
             btree::del(
@@ -268,6 +360,48 @@ impl<'e> StoreTxn<'e> {
         }
     }
 
+    /// The nodes which are queued to be created,
+    /// unless they are queued for deletion.
+    pub fn queued_nodes(&self) -> Result<Vec<Node>, Error> {
+        let nodes: Vec<Node> = self
+            .updates
+            .try_read()?
+            .iter()
+            .filter_map(|update| match update {
+                Update::CreateNode(node) => Some(node.clone()),
+                _ => None,
+            })
+            .collect();
+        let mut found = Vec::new();
+        for node in nodes {
+            if !self.is_queued_for_deletion(node.id)? {
+                found.push(node);
+            }
+        }
+        Ok(found)
+    }
+
+    /// The edges which are queued to be created,
+    /// unless they are queued for deletion.
+    pub fn queued_edges(&self) -> Result<Vec<Edge>, Error> {
+        let edges: Vec<Edge> = self
+            .updates
+            .try_read()?
+            .iter()
+            .filter_map(|update| match update {
+                Update::CreateEdge(edge) => Some(edge.clone()),
+                _ => None,
+            })
+            .collect();
+        let mut found = Vec::new();
+        for edge in edges {
+            if !self.is_queued_for_deletion(edge.id)? {
+                found.push(edge);
+            }
+        }
+        Ok(found)
+    }
+
     pub fn get_updated_property(
         &self,
         node_or_edge_id: u64,
@@ -307,11 +441,217 @@ impl<'e> StoreTxn<'e> {
                         None
                     }
                 }
+                Update::MergeNodeProperties(id, properties)
+                | Update::MergeEdgeProperties(id, properties) => {
+                    if *id == node_or_edge_id {
+                        properties.get(property).cloned()
+                    } else {
+                        None
+                    }
+                }
+                Update::ReplaceNodeProperties(id, properties)
+                | Update::ReplaceEdgeProperties(id, properties) => {
+                    if *id == node_or_edge_id {
+                        Some(properties.get(property).cloned().unwrap_or(PropOwned::Null))
+                    } else {
+                        None
+                    }
+                }
+                Update::RemoveNodeProperty(id, key) | Update::RemoveEdgeProperty(id, key) => {
+                    if *id == node_or_edge_id && key == property {
+                        Some(PropOwned::Null)
+                    } else {
+                        None
+                    }
+                }
+                Update::SetNodeLabel(_, _) => None,
+                Update::RemoveNodeLabel(_, _) => None,
                 Update::DeleteNode(_) => None,
+                Update::DetachDeleteNode(_) => None,
                 Update::DeleteEdge(_) => None,
             }))
     }
 
+    /// Apply the property updates queued for the given node or
+    /// edge to `properties`, in the order they were queued.
+    pub fn apply_property_updates(
+        &self,
+        node_or_edge_id: u64,
+        properties: &mut BTreeMap<String, PropOwned>,
+    ) -> Result<(), Error> {
+        fn set(properties: &mut BTreeMap<String, PropOwned>, key: &str, value: &PropOwned) {
+            if *value == PropOwned::Null {
+                properties.remove(key);
+            } else {
+                properties.insert(key.to_string(), value.clone());
+            }
+        }
+        for update in self.updates.try_read()?.iter() {
+            match update {
+                Update::SetNodeProperty(id, key, value)
+                | Update::SetEdgeProperty(id, key, value) => {
+                    if *id == node_or_edge_id {
+                        set(properties, key, value);
+                    }
+                }
+                Update::MergeNodeProperties(id, updates)
+                | Update::MergeEdgeProperties(id, updates) => {
+                    if *id == node_or_edge_id {
+                        for (key, value) in updates {
+                            set(properties, key, value);
+                        }
+                    }
+                }
+                Update::ReplaceNodeProperties(id, updates)
+                | Update::ReplaceEdgeProperties(id, updates) => {
+                    if *id == node_or_edge_id {
+                        properties.clear();
+                        for (key, value) in updates {
+                            set(properties, key, value);
+                        }
+                    }
+                }
+                Update::RemoveNodeProperty(id, key) | Update::RemoveEdgeProperty(id, key) => {
+                    if *id == node_or_edge_id {
+                        properties.remove(key);
+                    }
+                }
+                Update::CreateNode(_)
+                | Update::CreateEdge(_)
+                | Update::SetNodeLabel(_, _)
+                | Update::RemoveNodeLabel(_, _)
+                | Update::DeleteNode(_)
+                | Update::DetachDeleteNode(_)
+                | Update::DeleteEdge(_) => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// The labels of `node`, after applying the
+    /// queued label updates in order.
+    pub fn get_updated_labels(&self, node: &Node) -> Result<Vec<String>, Error> {
+        let mut labels = node.labels.clone();
+        for update in self.updates.try_read()?.iter() {
+            match update {
+                Update::SetNodeLabel(id, label) if *id == node.id && !labels.contains(label) => {
+                    labels.push(label.clone());
+                }
+                Update::RemoveNodeLabel(id, label) if *id == node.id => {
+                    labels.retain(|other| other != label);
+                }
+                _ => (),
+            }
+        }
+        Ok(labels)
+    }
+
+    fn is_queued_for_deletion(&self, node_or_edge_id: u64) -> Result<bool, Error> {
+        Ok(self.updates.try_read()?.iter().any(|update| match update {
+            Update::DeleteNode(id) | Update::DetachDeleteNode(id) | Update::DeleteEdge(id) => {
+                *id == node_or_edge_id
+            }
+            _ => false,
+        }))
+    }
+
+    fn has_updated_properties<'a, F>(
+        &self,
+        node_or_edge_id: u64,
+        property: F,
+        properties: &[(String, PropOwned)],
+    ) -> Result<bool, Error>
+    where
+        F: Fn(&str) -> &'a PropOwned,
+    {
+        for (key, value) in properties {
+            let current = self.get_updated_property(node_or_edge_id, key)?;
+            let current = current.as_ref().unwrap_or_else(|| property(key));
+            if !current.to_ref().loosely_equals(&value.to_ref()) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Find all nodes with `labels` and `properties`, including the
+    /// nodes which are queued to be created. Queued property updates
+    /// and deletions are taken into account.
+    pub fn find_nodes(
+        &self,
+        labels: &[String],
+        properties: &[(String, PropOwned)],
+    ) -> Result<Vec<Node>, Error> {
+        let mut nodes =
+            NodeIter::with_labels(self, labels.to_vec())?.collect::<Result<Vec<_>, _>>()?;
+        nodes.extend(
+            self.updates
+                .try_read()?
+                .iter()
+                .filter_map(|update| match update {
+                    Update::CreateNode(node)
+                        if labels.iter().all(|label| node.has_label(label)) =>
+                    {
+                        Some(node.clone())
+                    }
+                    _ => None,
+                }),
+        );
+        let mut found = Vec::new();
+        for node in nodes {
+            if !self.is_queued_for_deletion(node.id)?
+                && self.has_updated_properties(node.id, |key| node.property(key), properties)?
+            {
+                found.push(node);
+            }
+        }
+        Ok(found)
+    }
+
+    /// Find all edges from `origin` to `target` with `label` and
+    /// `properties`, including the edges which are queued to be
+    /// created. Queued property updates and deletions are taken
+    /// into account.
+    pub fn find_edges(
+        &self,
+        label: &str,
+        origin: u64,
+        target: u64,
+        properties: &[(String, PropOwned)],
+    ) -> Result<Vec<Edge>, Error> {
+        let mut edges = Vec::new();
+        for id in EdgeIter::origins(self, origin)? {
+            let edge = self.load_edge(id?)?.ok_or(Error::MissingEdge)?;
+            if edge.target == target && edge.label == label {
+                edges.push(edge);
+            }
+        }
+        edges.extend(
+            self.updates
+                .try_read()?
+                .iter()
+                .filter_map(|update| match update {
+                    Update::CreateEdge(edge)
+                        if edge.origin == origin
+                            && edge.target == target
+                            && edge.label == label =>
+                    {
+                        Some(edge.clone())
+                    }
+                    _ => None,
+                }),
+        );
+        let mut found = Vec::new();
+        for edge in edges {
+            if !self.is_queued_for_deletion(edge.id)?
+                && self.has_updated_properties(edge.id, |key| edge.property(key), properties)?
+            {
+                found.push(edge);
+            }
+        }
+        Ok(found)
+    }
+
     pub fn flush(&mut self) -> Result<(), Error> {
         let updates = std::mem::take(&mut *self.updates.try_write()?);
         for update in updates {
@@ -320,13 +660,59 @@ impl<'e> StoreTxn<'e> {
                 Update::CreateEdge(edge) => self.unchecked_create_edge(edge).map(|_| ())?,
                 Update::SetNodeProperty(node, key, value) => self.update_node(node, &key, value)?,
                 Update::SetEdgeProperty(edge, key, value) => self.update_edge(edge, &key, value)?,
+                Update::MergeNodeProperties(node, properties) => {
+                    for (key, value) in properties {
+                        self.update_node(node, &key, value)?;
+                    }
+                }
+                Update::MergeEdgeProperties(edge, properties) => {
+                    for (key, value) in properties {
+                        self.update_edge(edge, &key, value)?;
+                    }
+                }
+                Update::ReplaceNodeProperties(node, properties) => {
+                    self.replace_node_properties(node, properties)?
+                }
+                Update::ReplaceEdgeProperties(edge, properties) => {
+                    self.replace_edge_properties(edge, properties)?
+                }
+                Update::RemoveNodeProperty(node, key) => {
+                    self.update_node(node, &key, PropOwned::Null)?
+                }
+                Update::RemoveEdgeProperty(edge, key) => {
+                    self.update_edge(edge, &key, PropOwned::Null)?
+                }
+                Update::SetNodeLabel(node, label) => self.add_node_label(node, &label)?,
+                Update::RemoveNodeLabel(node, label) => self.remove_node_label(node, &label)?,
                 Update::DeleteNode(node) => self.delete_node(node)?,
+                Update::DetachDeleteNode(node) => self.detach_delete_node(node)?,
                 Update::DeleteEdge(edge) => self.delete_edge(edge)?,
             }
         }
         Ok(())
     }
 
+    /// Rewrite node records stored in an older format,
+    /// the format version is updated on commit.
+    fn migrate(&mut self) -> Result<(), Error> {
+        if self.txn.root(FORMAT_VERSION).is_some() {
+            return Ok(());
+        }
+        let nodes = btree::iter(&self.txn, &self.nodes, None)?
+            .map(|entry| {
+                let (_, bytes) = entry?;
+                let node: LegacyNode = bincode::deserialize(bytes)?;
+                Ok(Node::from(node))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        for node in nodes {
+            let bytes = bincode::serialize(&node)?;
+            btree::del(&mut self.txn, &mut self.nodes, &node.id, None)?;
+            btree::put(&mut self.txn, &mut self.nodes, &node.id, bytes.as_ref())?;
+        }
+        Ok(())
+    }
+
     pub fn commit(mut self) -> Result<(), Error> {
         self.flush()?;
         self.txn.set_root(ID_SQUENCE, self.id_seq.into_inner())?;
@@ -335,6 +721,7 @@ impl<'e> StoreTxn<'e> {
         self.txn.set_root(DB_ORIGINS, self.origins.db)?;
         self.txn.set_root(DB_TARGETS, self.targets.db)?;
         self.txn.set_root(DB_LABELS, self.labels.db)?;
+        self.txn.set_root(FORMAT_VERSION, STORE_FORMAT)?;
         self.txn.commit()
     }
 }
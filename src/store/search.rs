@@ -0,0 +1,207 @@
+use super::{Edge, EdgeIter, Path, PropOwned, StoreTxn};
+use crate::parser::ast::Direction;
+use crate::Error;
+use std::collections::HashMap;
+
+/// One side of a bidirectional breadth first search. Every
+/// visited node remembers its depth and the edges through
+/// which it was reached from the previous depth.
+struct Frontier {
+    direction: Direction,
+    depth: usize,
+    nodes: Vec<u64>,
+    visited: HashMap<u64, (usize, Vec<(u64, u64)>)>,
+}
+
+impl Frontier {
+    fn new(root: u64, direction: Direction) -> Self {
+        let mut visited = HashMap::new();
+        visited.insert(root, (0, vec![]));
+        Self {
+            direction,
+            depth: 0,
+            nodes: vec![root],
+            visited,
+        }
+    }
+
+    /// Visit all nodes one edge further from the root.
+    fn expand<F>(&mut self, txn: &StoreTxn, is_match: &F) -> Result<(), Error>
+    where
+        F: Fn(&Edge) -> bool,
+    {
+        self.depth += 1;
+        let mut next = vec![];
+        for &node in &self.nodes {
+            let edges = match self.direction {
+                Direction::Right => EdgeIter::origins(txn, node)?,
+                Direction::Left => EdgeIter::targets(txn, node)?,
+                Direction::Either => EdgeIter::both(txn, node)?,
+            };
+            for edge_id in edges {
+                let edge = txn.load_edge(edge_id?)?.ok_or(Error::MissingEdge)?;
+                if !is_match(&edge) {
+                    continue;
+                }
+                let other = if edge.origin == node {
+                    edge.target
+                } else {
+                    edge.origin
+                };
+                match self.visited.get_mut(&other) {
+                    Some((depth, parents)) => {
+                        if *depth == self.depth {
+                            parents.push((edge.id, node));
+                        }
+                    }
+                    None => {
+                        self.visited
+                            .insert(other, (self.depth, vec![(edge.id, node)]));
+                        next.push(other);
+                    }
+                }
+            }
+        }
+        self.nodes = next;
+        Ok(())
+    }
+
+    /// Collect the paths from the root to `node`, as lists of node
+    /// and edge ids which start at the root. If `all` is false, only
+    /// the first path is collected.
+    fn paths_to(&self, node: u64, all: bool) -> Vec<(Vec<u64>, Vec<u64>)> {
+        match self.visited.get(&node) {
+            Some((0, _)) => vec![(vec![node], vec![])],
+            Some((_, parents)) => {
+                let mut paths = vec![];
+                for &(edge, parent) in parents {
+                    for (mut nodes, mut edges) in self.paths_to(parent, all) {
+                        nodes.push(node);
+                        edges.push(edge);
+                        paths.push((nodes, edges));
+                        if !all {
+                            return paths;
+                        }
+                    }
+                }
+                paths
+            }
+            None => vec![],
+        }
+    }
+}
+
+/// Find the shortest paths of at most `max` edges from `start` to
+/// `end`, which follow edges in `direction` for which `is_match` holds.
+/// The search runs breadth first from both ends, always expanding the
+/// smaller side. If `all` is false, at most one path is returned.
+pub(crate) fn shortest_paths<F>(
+    txn: &StoreTxn,
+    start: u64,
+    end: u64,
+    direction: Direction,
+    is_match: F,
+    max: Option<usize>,
+    all: bool,
+) -> Result<Vec<Path>, Error>
+where
+    F: Fn(&Edge) -> bool,
+{
+    let path = |nodes: Vec<u64>, edges: Vec<u64>| Path {
+        nodes: nodes.into_iter().map(PropOwned::Id).collect(),
+        edges: edges.into_iter().map(PropOwned::Id).collect(),
+    };
+    if start == end {
+        return Ok(vec![path(vec![start], vec![])]);
+    }
+
+    let reverse = match direction {
+        Direction::Left => Direction::Right,
+        Direction::Right => Direction::Left,
+        Direction::Either => Direction::Either,
+    };
+    let mut forward = Frontier::new(start, direction);
+    let mut backward = Frontier::new(end, reverse);
+    loop {
+        #[allow(clippy::unnecessary_map_or)]
+        if forward.nodes.is_empty()
+            || backward.nodes.is_empty()
+            || max.map_or(false, |max| forward.depth + backward.depth >= max)
+        {
+            return Ok(vec![]);
+        }
+
+        let (expanded, other) = if forward.nodes.len() <= backward.nodes.len() {
+            (&mut forward, &backward)
+        } else {
+            (&mut backward, &forward)
+        };
+        expanded.expand(txn, &is_match)?;
+        let meets: Vec<u64> = expanded
+            .nodes
+            .iter()
+            .copied()
+            .filter(|node| other.visited.contains_key(node))
+            .collect();
+        if meets.is_empty() {
+            continue;
+        }
+
+        let mut paths = vec![];
+        for node in meets {
+            for (head_nodes, head_edges) in forward.paths_to(node, all) {
+                for (tail_nodes, tail_edges) in backward.paths_to(node, all) {
+                    let nodes = head_nodes
+                        .iter()
+                        .copied()
+                        .chain(tail_nodes.into_iter().rev().skip(1))
+                        .collect();
+                    let edges = head_edges
+                        .iter()
+                        .copied()
+                        .chain(tail_edges.into_iter().rev())
+                        .collect();
+                    paths.push(path(nodes, edges));
+                    if !all {
+                        return Ok(paths);
+                    }
+                }
+            }
+        }
+        return Ok(paths);
+    }
+}
+
+/// Find the shortest paths of at most `max` edges from `start` to
+/// every node it reaches, which follow edges in `direction` for which
+/// `is_match` holds. Paths are ordered by their length. If `all` is
+/// false, at most one path is returned for each node.
+pub(crate) fn shortest_paths_from<F>(
+    txn: &StoreTxn,
+    start: u64,
+    direction: Direction,
+    is_match: F,
+    max: Option<usize>,
+    all: bool,
+) -> Result<Vec<Path>, Error>
+where
+    F: Fn(&Edge) -> bool,
+{
+    let mut paths = vec![];
+    let mut frontier = Frontier::new(start, direction);
+    loop {
+        for &node in &frontier.nodes {
+            for (nodes, edges) in frontier.paths_to(node, all) {
+                paths.push(Path {
+                    nodes: nodes.into_iter().map(PropOwned::Id).collect(),
+                    edges: edges.into_iter().map(PropOwned::Id).collect(),
+                });
+            }
+        }
+        #[allow(clippy::unnecessary_map_or)]
+        if frontier.nodes.is_empty() || max.map_or(false, |max| frontier.depth >= max) {
+            return Ok(paths);
+        }
+        frontier.expand(txn, &is_match)?;
+    }
+}
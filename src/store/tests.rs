@@ -24,14 +24,14 @@ fn create_nodes_and_edges() {
     let node1 = txn
         .unchecked_create_node(Node {
             id: txn.id_seq(),
-            label: "PERSON".to_string(),
+            labels: vec!["PERSON".to_string()],
             properties: Default::default(),
         })
         .unwrap();
     let node2 = txn
         .unchecked_create_node(Node {
             id: txn.id_seq(),
-            label: "PERSON".to_string(),
+            labels: vec!["PERSON".to_string()],
             properties: Default::default(),
         })
         .unwrap();
@@ -51,8 +51,8 @@ fn create_nodes_and_edges() {
     let node2 = txn.load_node(node2.id()).unwrap().unwrap();
     let edge = txn.load_edge(edge.id()).unwrap().unwrap();
 
-    assert_eq!(node1.label(), "PERSON");
-    assert_eq!(node2.label(), "PERSON");
+    assert_eq!(node1.labels(), ["PERSON"]);
+    assert_eq!(node2.labels(), ["PERSON"]);
     assert_eq!(edge.label(), "KNOWS");
 }
 
@@ -63,7 +63,7 @@ fn update_nodes_and_edges() {
     let node = txn
         .unchecked_create_node(Node {
             id: txn.id_seq(),
-            label: "PERSON".to_string(),
+            labels: vec!["PERSON".to_string()],
             properties: Default::default(),
         })
         .unwrap();
@@ -93,6 +93,92 @@ fn update_nodes_and_edges() {
     assert_eq!(edge.property("test"), &PropOwned::Real(42.0));
 }
 
+#[test]
+fn update_node_labels() {
+    let store = Store::open_anon().unwrap();
+    let mut txn = store.mut_txn().unwrap();
+    let admin = txn
+        .unchecked_create_node(Node {
+            id: txn.id_seq(),
+            labels: vec!["PERSON".to_string(), "ADMIN".to_string()],
+            properties: Default::default(),
+        })
+        .unwrap();
+    let person = txn
+        .unchecked_create_node(Node {
+            id: txn.id_seq(),
+            labels: vec!["PERSON".to_string()],
+            properties: Default::default(),
+        })
+        .unwrap();
+    txn.commit().unwrap();
+
+    let labeled = |txn: &StoreTxn, labels: &[&str]| -> Vec<u64> {
+        let labels = labels.iter().map(|label| label.to_string()).collect();
+        NodeIter::with_labels(txn, labels)
+            .unwrap()
+            .map(|node| node.unwrap().id())
+            .collect()
+    };
+    let txn = store.txn().unwrap();
+    assert_eq!(labeled(&txn, &["PERSON"]), vec![admin.id(), person.id()]);
+    assert_eq!(labeled(&txn, &["PERSON", "ADMIN"]), vec![admin.id()]);
+    assert_eq!(labeled(&txn, &["ADMIN", "PERSON"]), vec![admin.id()]);
+    assert_eq!(labeled(&txn, &["ADMIN", "OTHER"]), vec![]);
+    drop(txn);
+
+    let mut txn = store.mut_txn().unwrap();
+    txn.add_node_label(person.id(), "ADMIN").unwrap();
+    txn.add_node_label(person.id(), "ADMIN").unwrap();
+    txn.remove_node_label(admin.id(), "PERSON").unwrap();
+    txn.commit().unwrap();
+
+    let txn = store.txn().unwrap();
+    let admin = txn.load_node(admin.id()).unwrap().unwrap();
+    let person = txn.load_node(person.id()).unwrap().unwrap();
+    assert_eq!(admin.labels(), ["ADMIN"]);
+    assert_eq!(person.labels(), ["PERSON", "ADMIN"]);
+    assert_eq!(labeled(&txn, &["PERSON"]), vec![person.id()]);
+    assert_eq!(labeled(&txn, &["ADMIN"]), vec![admin.id(), person.id()]);
+}
+
+#[test]
+fn store_list_and_map_properties() {
+    let store = Store::open_anon().unwrap();
+    let mut txn = store.mut_txn().unwrap();
+    let node = txn
+        .unchecked_create_node(Node {
+            id: txn.id_seq(),
+            labels: vec!["PERSON".to_string()],
+            properties: Default::default(),
+        })
+        .unwrap();
+    txn.commit().unwrap();
+
+    let list = PropOwned::List(vec![
+        PropOwned::Integer(1),
+        PropOwned::Text("two".to_string()),
+        PropOwned::List(vec![PropOwned::Null]),
+    ]);
+    let map = PropOwned::Map(
+        vec![
+            ("city".to_string(), PropOwned::Text("Bern".to_string())),
+            ("zip".to_string(), PropOwned::Integer(3000)),
+        ]
+        .into_iter()
+        .collect(),
+    );
+    let mut txn = store.mut_txn().unwrap();
+    txn.update_node(node.id(), "list", list.clone()).unwrap();
+    txn.update_node(node.id(), "map", map.clone()).unwrap();
+    txn.commit().unwrap();
+
+    let txn = store.txn().unwrap();
+    let node = txn.load_node(node.id()).unwrap().unwrap();
+    assert_eq!(node.property("list"), &list);
+    assert_eq!(node.property("map"), &map);
+}
+
 #[test]
 fn delete_nodes_and_edges() {
     let store = Store::open_anon().unwrap();
@@ -100,7 +186,7 @@ fn delete_nodes_and_edges() {
     let node = txn
         .unchecked_create_node(Node {
             id: txn.id_seq(),
-            label: "PERSON".to_string(),
+            labels: vec!["PERSON".to_string()],
             properties: Default::default(),
         })
         .unwrap();
@@ -127,3 +213,103 @@ fn delete_nodes_and_edges() {
     assert!(txn.load_node(node.id()).unwrap().is_none());
     assert!(txn.load_edge(edge.id()).unwrap().is_none());
 }
+
+#[test]
+fn shortest_paths_search_both_directions() {
+    use crate::parser::ast::Direction;
+
+    let store = Store::open_anon().unwrap();
+    let mut txn = store.mut_txn().unwrap();
+    let nodes: Vec<u64> = (0..5)
+        .map(|_| {
+            txn.unchecked_create_node(Node {
+                id: txn.id_seq(),
+                labels: vec!["STOP".to_string()],
+                properties: Default::default(),
+            })
+            .unwrap()
+            .id()
+        })
+        .collect();
+    // 0 -> 1 -> 2 -> 4, 0 -> 1 -> 4 and 0 -> 3 -> 4
+    for (origin, target) in [(0, 1), (1, 2), (2, 4), (1, 4), (0, 3), (3, 4)] {
+        txn.unchecked_create_edge(Edge {
+            id: txn.id_seq(),
+            label: "NEXT".to_string(),
+            origin: nodes[origin],
+            target: nodes[target],
+            properties: Default::default(),
+        })
+        .unwrap();
+    }
+    txn.commit().unwrap();
+
+    let txn = store.txn().unwrap();
+    let ids = |path: &Path| -> Vec<u64> {
+        path.nodes
+            .iter()
+            .map(|id| match id {
+                PropOwned::Id(id) => *id,
+                _ => panic!(),
+            })
+            .collect()
+    };
+    let any = |_: &Edge| true;
+    let search = |start: usize, end: usize, direction, max, all| {
+        shortest_paths(&txn, nodes[start], nodes[end], direction, any, max, all).unwrap()
+    };
+
+    let mut paths: Vec<_> = search(0, 4, Direction::Right, None, true)
+        .iter()
+        .map(ids)
+        .collect();
+    paths.sort();
+    assert_eq!(
+        paths,
+        vec![
+            vec![nodes[0], nodes[1], nodes[4]],
+            vec![nodes[0], nodes[3], nodes[4]],
+        ]
+    );
+    assert_eq!(search(0, 4, Direction::Right, None, false).len(), 1);
+
+    assert!(search(4, 0, Direction::Right, None, true).is_empty());
+    assert_eq!(search(4, 0, Direction::Left, None, true).len(), 2);
+
+    let paths = search(2, 3, Direction::Either, None, true);
+    assert_eq!(paths.len(), 1);
+    assert_eq!(ids(&paths[0]), vec![nodes[2], nodes[4], nodes[3]]);
+    assert_eq!(paths[0].edges.len(), 2);
+    assert!(search(2, 3, Direction::Either, Some(1), true).is_empty());
+
+    let paths = search(2, 2, Direction::Right, None, true);
+    assert_eq!(ids(&paths[0]), vec![nodes[2]]);
+
+    let search_from = |start: usize, direction, max, all| -> Vec<Vec<u64>> {
+        shortest_paths_from(&txn, nodes[start], direction, any, max, all)
+            .unwrap()
+            .iter()
+            .map(ids)
+            .collect()
+    };
+    assert_eq!(
+        search_from(0, Direction::Right, None, false),
+        vec![
+            vec![nodes[0]],
+            vec![nodes[0], nodes[1]],
+            vec![nodes[0], nodes[3]],
+            vec![nodes[0], nodes[1], nodes[2]],
+            vec![nodes[0], nodes[1], nodes[4]],
+        ]
+    );
+    assert_eq!(search_from(0, Direction::Right, None, true).len(), 6);
+    assert_eq!(search_from(0, Direction::Right, Some(1), true).len(), 3);
+    assert_eq!(
+        search_from(2, Direction::Left, None, true),
+        vec![
+            vec![nodes[2]],
+            vec![nodes[2], nodes[1]],
+            vec![nodes[2], nodes[1], nodes[0]],
+        ]
+    );
+}
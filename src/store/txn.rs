@@ -18,6 +18,13 @@ impl<E: Borrow<Env>> DynTxn<E> {
         }
     }
 
+    pub fn root(&self, n: usize) -> Option<u64> {
+        match self {
+            DynTxn::Txn(txn) => Some(txn.root(n)).filter(|&value| value != 0),
+            DynTxn::MutTxn(txn) => txn.root(n),
+        }
+    }
+
     pub fn commit(self) -> Result<(), Error> {
         match self {
             DynTxn::Txn(_) => Err(Error::ReadOnlyWrite),
@@ -1,7 +1,10 @@
 use crate::Error;
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate};
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
 use std::convert::TryInto;
-use std::{cmp::Ordering, collections::HashMap};
+use std::hash::{Hash, Hasher};
 
 // Some general notes
 //
@@ -39,7 +42,7 @@ use std::{cmp::Ordering, collections::HashMap};
 // - UpdateNode / UpdateEdge    (takes reference to new key-value pair)
 // - Flush                      (ensures writes are propagated to underlying store)
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub enum PropRef<'a> {
     Id(u64),
     Integer(i64),
@@ -48,6 +51,12 @@ pub enum PropRef<'a> {
     Text(&'a str),
     Blob(&'a [u8]),
     Null,
+    List(&'a [PropOwned]),
+    Map(&'a BTreeMap<String, PropOwned>),
+    Path(&'a Path),
+    Date(NaiveDate),
+    DateTime(DateTime<FixedOffset>),
+    Duration(Duration),
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -59,15 +68,36 @@ pub enum PropOwned {
     Text(String),
     Blob(Vec<u8>),
     Null,
+    List(Vec<PropOwned>),
+    Map(BTreeMap<String, PropOwned>),
+    Path(Path),
+    Date(NaiveDate),
+    DateTime(DateTime<FixedOffset>),
+    Duration(Duration),
 }
 
+/// A tuple of properties which can be used as a key when
+/// hashing, e.g. to group rows. Keys are equal if they hold
+/// identical values, in particular `NaN` is equal to itself.
+#[derive(Debug, Clone)]
+pub(crate) struct PropKey(pub Vec<PropOwned>);
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Node {
     pub(crate) id: u64,
-    pub(crate) label: String,
+    pub(crate) labels: Vec<String>,
     pub(crate) properties: HashMap<String, PropOwned>,
 }
 
+/// A node as stored before nodes could have multiple
+/// labels, which is read when migrating older stores.
+#[derive(Debug, Deserialize)]
+pub(crate) struct LegacyNode {
+    id: u64,
+    label: String,
+    properties: HashMap<String, PropOwned>,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Edge {
     pub(crate) id: u64,
@@ -77,13 +107,36 @@ pub struct Edge {
     pub(crate) target: u64,
 }
 
+/// A path through the graph, where consecutive nodes are
+/// connected by the edge at the same position. Ids are held
+/// as `PropOwned::Id`, such that they can be read as lists.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Path {
+    pub nodes: Vec<PropOwned>,
+    pub edges: Vec<PropOwned>,
+}
+
+impl From<LegacyNode> for Node {
+    fn from(node: LegacyNode) -> Self {
+        Self {
+            id: node.id,
+            labels: vec![node.label],
+            properties: node.properties,
+        }
+    }
+}
+
 impl Node {
     pub fn id(&self) -> u64 {
         self.id
     }
 
-    pub fn label(&self) -> &str {
-        self.label.as_str()
+    pub fn labels(&self) -> &[String] {
+        self.labels.as_slice()
+    }
+
+    pub fn has_label(&self, label: &str) -> bool {
+        self.labels.iter().any(|other| other == label)
     }
 
     pub fn property(&self, key: &str) -> &PropOwned {
@@ -103,10 +156,19 @@ impl Edge {
     pub fn property(&self, key: &str) -> &PropOwned {
         self.properties.get(key).unwrap_or(&PropOwned::Null)
     }
+
+    /// Check if the edge has `label` and `properties`, if given.
+    #[allow(clippy::unnecessary_map_or)]
+    pub(crate) fn matches(&self, label: Option<&str>, properties: &[(String, PropOwned)]) -> bool {
+        label.map_or(true, |label| self.label == label)
+            && properties
+                .iter()
+                .all(|(key, value)| self.property(key).to_ref().loosely_equals(&value.to_ref()))
+    }
 }
 
 impl PropOwned {
-    pub(crate) fn to_ref(&self) -> PropRef {
+    pub(crate) fn to_ref(&self) -> PropRef<'_> {
         match self {
             Self::Id(id) => PropRef::Id(*id),
             Self::Integer(num) => PropRef::Integer(*num),
@@ -115,6 +177,91 @@ impl PropOwned {
             Self::Text(text) => PropRef::Text(text.as_str()),
             Self::Blob(bytes) => PropRef::Blob(bytes.as_slice()),
             Self::Null => PropRef::Null,
+            Self::List(list) => PropRef::List(list.as_slice()),
+            Self::Map(map) => PropRef::Map(map),
+            Self::Path(path) => PropRef::Path(path),
+            Self::Date(date) => PropRef::Date(*date),
+            Self::DateTime(datetime) => PropRef::DateTime(*datetime),
+            Self::Duration(duration) => PropRef::Duration(*duration),
+        }
+    }
+}
+
+impl PropKey {
+    fn identical(lhs: &PropOwned, rhs: &PropOwned) -> bool {
+        match (lhs, rhs) {
+            (PropOwned::Real(lhs), PropOwned::Real(rhs)) => lhs.to_bits() == rhs.to_bits(),
+            (PropOwned::List(lhs), PropOwned::List(rhs)) => {
+                lhs.len() == rhs.len()
+                    && lhs
+                        .iter()
+                        .zip(rhs)
+                        .all(|(lhs, rhs)| Self::identical(lhs, rhs))
+            }
+            (PropOwned::Map(lhs), PropOwned::Map(rhs)) => {
+                lhs.len() == rhs.len()
+                    && lhs.iter().zip(rhs).all(|((lhs_key, lhs), (rhs_key, rhs))| {
+                        lhs_key == rhs_key && Self::identical(lhs, rhs)
+                    })
+            }
+            _ => lhs == rhs,
+        }
+    }
+
+    fn hash_prop<H: Hasher>(prop: &PropOwned, state: &mut H) {
+        std::mem::discriminant(prop).hash(state);
+        match prop {
+            PropOwned::Id(id) => id.hash(state),
+            PropOwned::Integer(num) => num.hash(state),
+            PropOwned::Real(num) => num.to_bits().hash(state),
+            PropOwned::Boolean(val) => val.hash(state),
+            PropOwned::Text(text) => text.hash(state),
+            PropOwned::Blob(bytes) => bytes.hash(state),
+            PropOwned::Null => (),
+            PropOwned::List(list) => {
+                list.len().hash(state);
+                for prop in list {
+                    Self::hash_prop(prop, state);
+                }
+            }
+            PropOwned::Map(map) => {
+                map.len().hash(state);
+                for (key, prop) in map {
+                    key.hash(state);
+                    Self::hash_prop(prop, state);
+                }
+            }
+            PropOwned::Path(path) => {
+                path.nodes.len().hash(state);
+                for prop in path.nodes.iter().chain(&path.edges) {
+                    Self::hash_prop(prop, state);
+                }
+            }
+            PropOwned::Date(date) => date.hash(state),
+            PropOwned::DateTime(datetime) => datetime.hash(state),
+            PropOwned::Duration(duration) => duration.hash(state),
+        }
+    }
+}
+
+impl PartialEq for PropKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len()
+            && self
+                .0
+                .iter()
+                .zip(&other.0)
+                .all(|(lhs, rhs)| Self::identical(lhs, rhs))
+    }
+}
+
+impl Eq for PropKey {}
+
+impl Hash for PropKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.len().hash(state);
+        for prop in &self.0 {
+            Self::hash_prop(prop, state);
         }
     }
 }
@@ -129,6 +276,12 @@ impl<'a> PropRef<'a> {
             Self::Text(text) => PropOwned::Text(text.to_string()),
             Self::Blob(bytes) => PropOwned::Blob(bytes.to_vec()),
             Self::Null => PropOwned::Null,
+            Self::List(list) => PropOwned::List(list.to_vec()),
+            Self::Map(map) => PropOwned::Map((*map).clone()),
+            Self::Path(path) => PropOwned::Path((*path).clone()),
+            Self::Date(date) => PropOwned::Date(*date),
+            Self::DateTime(datetime) => PropOwned::DateTime(*datetime),
+            Self::Duration(duration) => PropOwned::Duration(*duration),
         }
     }
 
@@ -136,6 +289,7 @@ impl<'a> PropRef<'a> {
         fn eq(lhs: &PropRef, rhs: &PropRef) -> bool {
             match (lhs, rhs) {
                 (PropRef::Integer(i), PropRef::Real(r)) => *i as f64 == *r,
+                (PropRef::Integer(i), PropRef::Id(id)) => *i >= 0 && *i as u64 == *id,
                 _ => false,
             }
         }
@@ -149,10 +303,62 @@ impl<'a> PropRef<'a> {
             (Self::Real(lhs), Self::Integer(rhs)) => lhs.partial_cmp(&(*rhs as f64)),
             (Self::Integer(lhs), Self::Real(rhs)) => (*lhs as f64).partial_cmp(rhs),
             (Self::Text(lhs), Self::Text(rhs)) => Some(lhs.cmp(rhs)),
+            (Self::Date(lhs), Self::Date(rhs)) => Some(lhs.cmp(rhs)),
+            (Self::DateTime(lhs), Self::DateTime(rhs)) => Some(lhs.cmp(rhs)),
+            (Self::Duration(lhs), Self::Duration(rhs)) => Some(lhs.cmp(rhs)),
             _ => None,
         }
     }
 
+    /// Total order over all properties, as used when sorting
+    /// rows. Properties of different types are ordered by type
+    /// and `NULL` is ordered after all other values.
+    pub(crate) fn order(&self, other: &Self) -> Ordering {
+        fn rank(prop: &PropRef) -> u8 {
+            match prop {
+                PropRef::Id(_) => 0,
+                PropRef::Integer(_) | PropRef::Real(_) => 1,
+                PropRef::Boolean(_) => 2,
+                PropRef::Text(_) => 3,
+                PropRef::Date(_) => 4,
+                PropRef::DateTime(_) => 5,
+                PropRef::Duration(_) => 6,
+                PropRef::List(_) => 7,
+                PropRef::Map(_) => 8,
+                PropRef::Path(_) => 9,
+                PropRef::Blob(_) => 10,
+                PropRef::Null => 11,
+            }
+        }
+        match (self, other) {
+            (Self::Id(lhs), Self::Id(rhs)) => lhs.cmp(rhs),
+            (Self::Boolean(lhs), Self::Boolean(rhs)) => lhs.cmp(rhs),
+            (Self::Blob(lhs), Self::Blob(rhs)) => lhs.cmp(rhs),
+            (Self::List(lhs), Self::List(rhs)) => lhs
+                .iter()
+                .zip(rhs.iter())
+                .map(|(lhs, rhs)| lhs.to_ref().order(&rhs.to_ref()))
+                .find(|ord| *ord != Ordering::Equal)
+                .unwrap_or_else(|| lhs.len().cmp(&rhs.len())),
+            (Self::Map(lhs), Self::Map(rhs)) => lhs
+                .iter()
+                .zip(rhs.iter())
+                .map(|((lhs_key, lhs), (rhs_key, rhs))| {
+                    lhs_key
+                        .cmp(rhs_key)
+                        .then_with(|| lhs.to_ref().order(&rhs.to_ref()))
+                })
+                .find(|ord| *ord != Ordering::Equal)
+                .unwrap_or_else(|| lhs.len().cmp(&rhs.len())),
+            (Self::Path(lhs), Self::Path(rhs)) => PropRef::List(&lhs.nodes)
+                .order(&PropRef::List(&rhs.nodes))
+                .then_with(|| PropRef::List(&lhs.edges).order(&PropRef::List(&rhs.edges))),
+            _ => self
+                .loosely_compare(other)
+                .unwrap_or_else(|| rank(self).cmp(&rank(other))),
+        }
+    }
+
     pub(crate) fn is_truthy(&self) -> bool {
         match self {
             Self::Id(_) => true,
@@ -162,6 +368,147 @@ impl<'a> PropRef<'a> {
             Self::Text(_) => true,
             Self::Blob(_) => true,
             Self::Null => false,
+            Self::List(_) => true,
+            Self::Map(_) => true,
+            Self::Path(_) => true,
+            Self::Date(_) => true,
+            Self::DateTime(_) => true,
+            Self::Duration(_) => true,
+        }
+    }
+
+    /// Applies a numeric operation. Integers are promoted to reals if
+    /// the other side is a real, and integer operations which overflow
+    /// fail.
+    fn numeric(
+        &self,
+        other: &Self,
+        int: fn(i64, i64) -> Option<i64>,
+        real: fn(f64, f64) -> f64,
+    ) -> Option<Result<PropOwned, Error>> {
+        match (self, other) {
+            (&Self::Integer(lhs), &Self::Integer(rhs)) => Some(
+                int(lhs, rhs)
+                    .map(PropOwned::Integer)
+                    .ok_or(Error::TypeMismatch),
+            ),
+            (&Self::Integer(lhs), &Self::Real(rhs)) => {
+                Some(Ok(PropOwned::Real(real(lhs as f64, rhs))))
+            }
+            (&Self::Real(lhs), &Self::Integer(rhs)) => {
+                Some(Ok(PropOwned::Real(real(lhs, rhs as f64))))
+            }
+            (&Self::Real(lhs), &Self::Real(rhs)) => Some(Ok(PropOwned::Real(real(lhs, rhs)))),
+            _ => None,
+        }
+    }
+
+    /// Adds two properties. Numbers are added, text and lists are
+    /// concatenated, and durations can be added to each other and to
+    /// dates or datetimes. Adding `NULL` gives `NULL`.
+    pub(crate) fn add(&self, other: &Self) -> Result<PropOwned, Error> {
+        if let Some(num) = self.numeric(other, i64::checked_add, |lhs, rhs| lhs + rhs) {
+            return num;
+        }
+        match (self, other) {
+            (Self::Null, _) | (_, Self::Null) => Ok(PropOwned::Null),
+            (Self::Text(lhs), Self::Text(rhs)) => Ok(PropOwned::Text(format!("{}{}", lhs, rhs))),
+            (Self::Text(lhs), Self::Integer(rhs)) => Ok(PropOwned::Text(format!("{}{}", lhs, rhs))),
+            (Self::Text(lhs), Self::Real(rhs)) => Ok(PropOwned::Text(format!("{}{}", lhs, rhs))),
+            (Self::Integer(lhs), Self::Text(rhs)) => Ok(PropOwned::Text(format!("{}{}", lhs, rhs))),
+            (Self::Real(lhs), Self::Text(rhs)) => Ok(PropOwned::Text(format!("{}{}", lhs, rhs))),
+            (Self::List(lhs), Self::List(rhs)) => Ok(PropOwned::List([*lhs, *rhs].concat())),
+            (Self::List(lhs), rhs) => Ok(PropOwned::List([*lhs, &[rhs.to_owned()]].concat())),
+            (lhs, Self::List(rhs)) => Ok(PropOwned::List([&[lhs.to_owned()], *rhs].concat())),
+            (Self::Duration(lhs), Self::Duration(rhs)) => lhs
+                .checked_add(rhs)
+                .map(PropOwned::Duration)
+                .ok_or(Error::TypeMismatch),
+            (Self::Date(date), Self::Duration(duration))
+            | (Self::Duration(duration), Self::Date(date)) => date
+                .checked_add_signed(Duration::days(duration.num_days()))
+                .map(PropOwned::Date)
+                .ok_or(Error::TypeMismatch),
+            (Self::DateTime(datetime), Self::Duration(duration))
+            | (Self::Duration(duration), Self::DateTime(datetime)) => datetime
+                .checked_add_signed(*duration)
+                .map(PropOwned::DateTime)
+                .ok_or(Error::TypeMismatch),
+            _ => Err(Error::TypeMismatch),
+        }
+    }
+
+    /// Subtracts two properties. Subtracting two dates or two
+    /// datetimes gives the duration between them.
+    pub(crate) fn sub(&self, other: &Self) -> Result<PropOwned, Error> {
+        if let Some(num) = self.numeric(other, i64::checked_sub, |lhs, rhs| lhs - rhs) {
+            return num;
+        }
+        match (self, other) {
+            (Self::Null, _) | (_, Self::Null) => Ok(PropOwned::Null),
+            (Self::Duration(lhs), Self::Duration(rhs)) => lhs
+                .checked_sub(rhs)
+                .map(PropOwned::Duration)
+                .ok_or(Error::TypeMismatch),
+            (Self::Date(date), Self::Duration(duration)) => date
+                .checked_sub_signed(Duration::days(duration.num_days()))
+                .map(PropOwned::Date)
+                .ok_or(Error::TypeMismatch),
+            (Self::DateTime(datetime), Self::Duration(duration)) => datetime
+                .checked_sub_signed(*duration)
+                .map(PropOwned::DateTime)
+                .ok_or(Error::TypeMismatch),
+            (Self::Date(lhs), Self::Date(rhs)) => Ok(PropOwned::Duration(*lhs - *rhs)),
+            (Self::DateTime(lhs), Self::DateTime(rhs)) => {
+                Ok(PropOwned::Duration(lhs.signed_duration_since(*rhs)))
+            }
+            _ => Err(Error::TypeMismatch),
+        }
+    }
+
+    pub(crate) fn mul(&self, other: &Self) -> Result<PropOwned, Error> {
+        match (self, other) {
+            (Self::Null, _) | (_, Self::Null) => Ok(PropOwned::Null),
+            _ => self
+                .numeric(other, i64::checked_mul, |lhs, rhs| lhs * rhs)
+                .unwrap_or(Err(Error::TypeMismatch)),
+        }
+    }
+
+    /// Divides two numbers, where dividing integers truncates the result.
+    /// Dividing an integer by zero gives `NULL`.
+    pub(crate) fn div(&self, other: &Self) -> Result<PropOwned, Error> {
+        match (self, other) {
+            (Self::Null, _) | (_, Self::Null) => Ok(PropOwned::Null),
+            (Self::Integer(_), Self::Integer(0)) => Ok(PropOwned::Null),
+            _ => self
+                .numeric(other, i64::checked_div, |lhs, rhs| lhs / rhs)
+                .unwrap_or(Err(Error::TypeMismatch)),
+        }
+    }
+
+    /// The remainder of dividing two numbers, which has the sign
+    /// of the dividend.
+    pub(crate) fn rem(&self, other: &Self) -> Result<PropOwned, Error> {
+        match (self, other) {
+            (Self::Null, _) | (_, Self::Null) => Ok(PropOwned::Null),
+            (Self::Integer(_), Self::Integer(0)) => Ok(PropOwned::Null),
+            _ => self
+                .numeric(other, i64::checked_rem, |lhs, rhs| lhs % rhs)
+                .unwrap_or(Err(Error::TypeMismatch)),
+        }
+    }
+
+    /// Raises a number to a power, which always gives a real.
+    pub(crate) fn pow(&self, other: &Self) -> Result<PropOwned, Error> {
+        match (self, other) {
+            (Self::Null, _) | (_, Self::Null) => Ok(PropOwned::Null),
+            (&Self::Integer(lhs), &Self::Integer(rhs)) => {
+                Ok(PropOwned::Real((lhs as f64).powf(rhs as f64)))
+            }
+            _ => self
+                .numeric(other, |_, _| None, f64::powf)
+                .unwrap_or(Err(Error::TypeMismatch)),
         }
     }
 
@@ -0,0 +1,226 @@
+use cqlite::{Error, Graph, Property};
+
+#[macro_use]
+mod common;
+
+fn create_people(graph: &Graph) {
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare(
+            "
+            CREATE (:PERSON { name: 'Peter', city: 'New York', age: 21 })
+            CREATE (:PERSON { name: 'Clark', city: 'Metropolis', age: 42 })
+            CREATE (:PERSON { name: 'Lois', city: 'Metropolis', age: 35.5 })
+            CREATE (:PERSON { name: 'Bruce', city: 'Gotham' })
+            CREATE (:CITY { name: 'Metropolis' })
+            ",
+        )
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+}
+
+#[test]
+fn count_matches() {
+    let graph = Graph::open_anon().unwrap();
+    create_people(&graph);
+
+    let mut txn = graph.txn().unwrap();
+    let counts: Vec<(i64, i64)> = graph
+        .prepare("MATCH (p:PERSON) RETURN count(*), count(p.age)")
+        .unwrap()
+        .query_map(&mut txn, (), |m| Ok((m.get(0)?, m.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(counts, vec![(4, 3)]);
+
+    let count: i64 = graph
+        .prepare("MATCH (a) RETURN COUNT(*)")
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(count, 5);
+
+    // aggregating no matches without grouping keys still returns a row
+    let empty: Vec<(i64, i64, Option<f64>, Vec<Property>)> = graph
+        .prepare("MATCH (a:MISSING) RETURN count(*), sum(a.age), avg(a.age), collect(a.name)")
+        .unwrap()
+        .query_map(&mut txn, (), |m| {
+            Ok((m.get(0)?, m.get(1)?, m.get(2)?, m.get(3)?))
+        })
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(empty, vec![(0, 0, None, vec![])]);
+
+    let grouped = graph
+        .prepare("MATCH (a:MISSING) RETURN a.name, count(*)")
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get::<i64, _>(1))
+        .unwrap()
+        .count();
+    assert_eq!(grouped, 0);
+}
+
+#[test]
+fn aggregate_functions() {
+    let graph = Graph::open_anon().unwrap();
+    create_people(&graph);
+
+    let mut txn = graph.txn().unwrap();
+    let stmt = graph
+        .prepare(
+            "
+            MATCH (p:PERSON)
+            RETURN sum(p.age), avg(p.age), min(p.age), max(p.age), min(p.name), max(p.name)
+            ",
+        )
+        .unwrap();
+    let mut query = stmt.query(&mut txn, ()).unwrap();
+    let m = query.step().unwrap().unwrap();
+    assert_eq!(m.get::<f64, _>(0).unwrap(), 98.5);
+    assert!((m.get::<f64, _>(1).unwrap() - 98.5 / 3.0).abs() < 1e-9);
+    assert_eq!(m.get::<i64, _>(2).unwrap(), 21);
+    assert_eq!(m.get::<i64, _>(3).unwrap(), 42);
+    assert_eq!(m.get::<String, _>(4).unwrap(), "Bruce");
+    assert_eq!(m.get::<String, _>(5).unwrap(), "Peter");
+    assert!(query.step().unwrap().is_none());
+
+    let sum: i64 = graph
+        .prepare("MATCH (p:PERSON) WHERE p.name <> 'Lois' RETURN sum(p.age)")
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(sum, 63);
+
+    let names: Vec<Property> = graph
+        .prepare("MATCH (p:PERSON) WHERE p.city = 'Metropolis' RETURN collect(p.name)")
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(names.len(), 2);
+    assert!(names.contains(&Property::Text("Clark".into())));
+    assert!(names.contains(&Property::Text("Lois".into())));
+
+    assert_err!(
+        graph
+            .prepare("MATCH (p:PERSON) RETURN sum(p.name)")
+            .unwrap()
+            .query_map(&mut txn, (), |m| m.get::<i64, _>(0))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>(),
+        Error::TypeMismatch
+    );
+    assert_err!(
+        graph
+            .prepare("UNWIND [9223372036854775807, 1] AS n RETURN sum(n)")
+            .unwrap()
+            .query_map(&mut txn, (), |m| m.get::<Property, _>(0))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>(),
+        Error::TypeMismatch
+    );
+}
+
+#[test]
+fn implicit_grouping() {
+    let graph = Graph::open_anon().unwrap();
+    create_people(&graph);
+
+    let mut txn = graph.txn().unwrap();
+    let cities: Vec<(String, i64, Option<f64>)> = graph
+        .prepare(
+            "
+            MATCH (p:PERSON)
+            RETURN p.city, count(*), avg(p.age)
+            ORDER BY count(*) DESC, p.city
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| Ok((m.get(0)?, m.get(1)?, m.get(2)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        cities,
+        vec![
+            ("Metropolis".into(), 2, Some(38.75)),
+            ("Gotham".into(), 1, None),
+            ("New York".into(), 1, Some(21.0)),
+        ]
+    );
+
+    // aggregates may appear anywhere among the returns
+    let cities: Vec<(i64, String)> = graph
+        .prepare("MATCH (p:PERSON) RETURN count(*), p.city ORDER BY p.city LIMIT 2")
+        .unwrap()
+        .query_map(&mut txn, (), |m| Ok((m.get(0)?, m.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(cities, vec![(1, "Gotham".into()), (2, "Metropolis".into())]);
+
+    // sort by an aggregate which is not returned
+    let cities: Vec<String> = graph
+        .prepare("MATCH (p:PERSON) RETURN p.city ORDER BY max(p.age) DESC, p.city")
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(cities, vec!["Gotham", "Metropolis", "New York"]);
+
+    let people: Vec<(String, i64)> = graph
+        .prepare(
+            "
+            MATCH (p:PERSON) -> (c:CITY)
+            RETURN c.name, count(*)
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| Ok((m.get(0)?, m.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert!(people.is_empty());
+}
+
+#[test]
+fn aggregate_sort_keys_must_be_returned() {
+    let graph = Graph::open_anon().unwrap();
+    assert_err!(
+        graph.prepare("MATCH (p) RETURN p.city, count(*) ORDER BY p.name"),
+        Error::UnknownIdentifier(_)
+    );
+    assert!(graph
+        .prepare("MATCH (p) RETURN p.city, count(*) ORDER BY p.city, 42")
+        .is_ok());
+}
+
+#[test]
+fn aggregate_after_update() {
+    let graph = Graph::open_anon().unwrap();
+    create_people(&graph);
+
+    let mut txn = graph.mut_txn().unwrap();
+    let total: i64 = graph
+        .prepare("MATCH (p:PERSON) SET p.age = 1 RETURN sum(p.age)")
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(total, 4);
+}
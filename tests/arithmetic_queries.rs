@@ -0,0 +1,153 @@
+use cqlite::{Error, Graph, Property};
+
+#[macro_use]
+mod common;
+
+fn eval(graph: &Graph, query: &str) -> Result<Property, Error> {
+    graph
+        .prepare(query)?
+        .query_map(&mut graph.txn()?, (), |m| m.get(0))?
+        .next()
+        .unwrap()
+}
+
+#[test]
+fn arithmetic_precedence() {
+    let graph = Graph::open_anon().unwrap();
+    let cases = [
+        ("RETURN 1 + 2 * 3", Property::Integer(7)),
+        ("RETURN (1 + 2) * 3", Property::Integer(9)),
+        ("RETURN 10 - 4 - 3", Property::Integer(3)),
+        ("RETURN 2 * 3 ^ 2", Property::Real(18.0)),
+        ("RETURN 2 ^ 3 ^ 2", Property::Real(64.0)),
+        ("RETURN 7 / 2", Property::Integer(3)),
+        ("RETURN -7 / 2", Property::Integer(-3)),
+        ("RETURN 7 % 3 + 1", Property::Integer(2)),
+        ("RETURN -7 % 3", Property::Integer(-1)),
+        ("RETURN 10 - -2", Property::Integer(12)),
+        ("RETURN 1 + 0.5", Property::Real(1.5)),
+        ("RETURN 7 / 2.0", Property::Real(3.5)),
+        ("RETURN 7.5 % 2", Property::Real(1.5)),
+        ("RETURN 1 / 0", Property::Null),
+        ("RETURN 1 % 0", Property::Null),
+        ("RETURN 1 + NULL", Property::Null),
+        (
+            "RETURN 9223372036854775807 + 1.0",
+            Property::Real(9223372036854775808.0),
+        ),
+    ];
+    for (query, expected) in cases {
+        assert_eq!(eval(&graph, query).unwrap(), expected, "{}", query);
+    }
+}
+
+#[test]
+fn integer_overflow() {
+    let graph = Graph::open_anon().unwrap();
+    for query in [
+        "RETURN 9223372036854775807 + 1",
+        "RETURN -9223372036854775807 - 2",
+        "RETURN 4611686018427387904 * 2",
+    ] {
+        assert_err!(eval(&graph, query), Error::TypeMismatch);
+    }
+
+    let mut txn = graph.txn().unwrap();
+    let stmt = graph.prepare("RETURN $a + 1").unwrap();
+    let mut query = stmt.query(&mut txn, ("a", i64::MAX)).unwrap();
+    assert!(matches!(query.step(), Err(Error::TypeMismatch)));
+    let mut query = stmt.query(&mut txn, ("a", i64::MAX - 1)).unwrap();
+    let sum: i64 = query.step().unwrap().unwrap().get(0).unwrap();
+    assert_eq!(sum, i64::MAX);
+}
+
+#[test]
+fn string_and_list_concatenation() {
+    let graph = Graph::open_anon().unwrap();
+    let text = |text: &str| Property::Text(text.to_string());
+    assert_eq!(
+        eval(&graph, "RETURN 'Hello' + ', ' + 'World'").unwrap(),
+        text("Hello, World")
+    );
+    assert_eq!(eval(&graph, "RETURN 'v' + 2").unwrap(), text("v2"));
+    assert_eq!(eval(&graph, "RETURN 1.5 + 'x'").unwrap(), text("1.5x"));
+    assert_eq!(
+        eval(&graph, "RETURN [1] + [2, 3] + 4").unwrap(),
+        Property::List(vec![
+            Property::Integer(1),
+            Property::Integer(2),
+            Property::Integer(3),
+            Property::Integer(4),
+        ])
+    );
+    assert_err!(eval(&graph, "RETURN 'a' * 2"), Error::TypeMismatch);
+    assert_err!(eval(&graph, "RETURN TRUE + 1"), Error::TypeMismatch);
+}
+
+#[test]
+fn arithmetic_in_where_set_and_return() {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare(
+            "
+            CREATE (a:PERSON { first: 'Ada', last: 'Lovelace', age: 36 })
+            CREATE (b:PERSON { first: 'Alan', last: 'Turing', age: 41 })
+            ",
+        )
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    graph
+        .prepare("MATCH (p:PERSON) SET p.age = p.age + $years SET p.months = (p.age + $years) * 12")
+        .unwrap()
+        .execute(&mut txn, ("years", 1))
+        .unwrap();
+    txn.commit().unwrap();
+
+    let rows = graph
+        .prepare(
+            "
+            MATCH (p:PERSON)
+            WHERE p.age * 2 - 10 > 70
+            RETURN p.first + ' ' + p.last, p.age, p.months / 12
+            ",
+        )
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| {
+            Ok((m.get(0)?, m.get(1)?, m.get(2)?))
+        })
+        .unwrap()
+        .collect::<Result<Vec<(String, i64, i64)>, _>>()
+        .unwrap();
+    assert_eq!(rows, vec![("Alan Turing".to_string(), 42, 42)]);
+
+    let names = graph
+        .prepare("MATCH (p:PERSON) RETURN p.first ORDER BY 0 - p.age")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<Vec<String>, _>>()
+        .unwrap();
+    assert_eq!(names, vec!["Alan", "Ada"]);
+
+    assert_err!(
+        graph.prepare("MATCH (p:PERSON) RETURN count(p) ORDER BY p.age + 1"),
+        Error::UnknownIdentifier(_)
+    );
+    assert_err!(
+        graph.prepare("MATCH (p:PERSON) RETURN p.age + q.age"),
+        Error::UnknownIdentifier(_)
+    );
+}
+
+#[test]
+fn return_errors_fail_step() {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.txn().unwrap();
+    for (query, param) in [("RETURN $a * 2", "a"), ("RETURN date($a)", "2020-13-01")] {
+        let stmt = graph.prepare(query).unwrap();
+        let mut query = stmt.query(&mut txn, ("a", param)).unwrap();
+        assert!(matches!(query.step(), Err(Error::TypeMismatch)));
+    }
+}
@@ -0,0 +1,145 @@
+use cqlite::Graph;
+
+fn people() -> Graph {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare("CREATE (:PERSON { name: 'Alice', nick: 'Ali', age: 42, rank: 1 })")
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    graph
+        .prepare("CREATE (:PERSON { name: 'Bob', age: 12, rank: 2 })")
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    graph
+        .prepare("CREATE (:PERSON { name: 'Carol', rank: 3 })")
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+    graph
+}
+
+fn strings(graph: &Graph, query: &str) -> Vec<Option<String>> {
+    graph
+        .prepare(query)
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap()
+}
+
+#[test]
+fn searched_case() {
+    let graph = people();
+    assert_eq!(
+        strings(
+            &graph,
+            "
+            MATCH (n:PERSON)
+            RETURN CASE
+                WHEN n.age IS NULL THEN 'unknown'
+                WHEN n.age < 18 THEN 'minor'
+                ELSE 'adult'
+            END
+            ORDER BY n.name
+            "
+        ),
+        vec![
+            Some("adult".into()),
+            Some("minor".into()),
+            Some("unknown".into())
+        ]
+    );
+
+    // without a default, unmatched rows are null
+    assert_eq!(
+        strings(
+            &graph,
+            "MATCH (n:PERSON) RETURN CASE WHEN n.name STARTS WITH 'A' OR n.age > 100 THEN n.name END ORDER BY n.name"
+        ),
+        vec![Some("Alice".into()), None, None]
+    );
+}
+
+#[test]
+fn simple_case() {
+    let graph = people();
+    assert_eq!(
+        strings(
+            &graph,
+            "MATCH (n:PERSON) RETURN CASE n.rank WHEN 1 THEN 'gold' WHEN 2 THEN 'silver' ELSE 'none' END ORDER BY n.rank"
+        ),
+        vec![
+            Some("gold".into()),
+            Some("silver".into()),
+            Some("none".into())
+        ]
+    );
+
+    // the first matching branch is used
+    let ranks = graph
+        .prepare("RETURN CASE 2.0 WHEN 1 THEN 'a' WHEN 2 THEN 'b' WHEN 2.0 THEN 'c' END")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<Vec<String>, _>>()
+        .unwrap();
+    assert_eq!(ranks, vec!["b"]);
+}
+
+#[test]
+fn case_in_where_and_order_by() {
+    let graph = people();
+    assert_eq!(
+        strings(
+            &graph,
+            "MATCH (n:PERSON) WHERE CASE WHEN n.age IS NULL THEN false ELSE true END RETURN n.name ORDER BY CASE n.name WHEN 'Bob' THEN 0 ELSE 1 END"
+        ),
+        vec![Some("Bob".into()), Some("Alice".into())]
+    );
+}
+
+#[test]
+fn untaken_branches_are_not_evaluated() {
+    let graph = Graph::open_anon().unwrap();
+    let values = graph
+        .prepare("RETURN CASE WHEN $safe THEN 'ok' ELSE date('not a date') END")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), ("safe", true), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<Vec<String>, _>>()
+        .unwrap();
+    assert_eq!(values, vec!["ok"]);
+}
+
+#[test]
+fn coalesce_values() {
+    let graph = people();
+    assert_eq!(
+        strings(
+            &graph,
+            "MATCH (n:PERSON) RETURN coalesce(n.nick, n.name) ORDER BY n.name"
+        ),
+        vec![Some("Ali".into()), Some("Bob".into()), Some("Carol".into())]
+    );
+    assert_eq!(
+        strings(
+            &graph,
+            "MATCH (n:PERSON) RETURN coalesce(n.missing, $missing) ORDER BY n.name"
+        ),
+        vec![None, None, None]
+    );
+
+    let ages = graph
+        .prepare("MATCH (n:PERSON) RETURN coalesce(n.age, -1) ORDER BY n.name")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<Vec<i64>, _>>()
+        .unwrap();
+    assert_eq!(ages, vec![42, 12, -1]);
+}
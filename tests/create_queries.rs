@@ -1,4 +1,7 @@
-use cqlite::Graph;
+use cqlite::{Error, Graph};
+
+#[macro_use]
+mod common;
 
 #[test]
 fn create_label_only() {
@@ -101,3 +104,125 @@ fn create_edges_with_label() {
         .unwrap();
     assert_eq!(labels, [("NODE_A".into(), "NODE_B".into(), "EDGE".into())]);
 }
+
+#[test]
+fn create_paths_with_new_nodes() {
+    let graph = Graph::open_anon().unwrap();
+
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare(
+            "
+            CREATE (a:PERSON { name: 'Alice' }) -[:KNOWS { since: 2010 }]-> (b:PERSON { name: 'Bob' })
+                <-[:LIKES]- (:ROBOT { name: 'Marvin' }) -[:LIKES]-> (a)
+            ",
+        )
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+
+    let edges = graph
+        .prepare("MATCH (a) -[e]-> (b) RETURN a.name, LABEL(e), b.name ORDER BY a.name, b.name")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| {
+            Ok((m.get(0)?, m.get(1)?, m.get(2)?))
+        })
+        .unwrap()
+        .collect::<Result<Vec<(String, String, String)>, _>>()
+        .unwrap();
+    assert_eq!(
+        edges,
+        [
+            ("Alice".into(), "KNOWS".into(), "Bob".into()),
+            ("Marvin".into(), "LIKES".into(), "Alice".into()),
+            ("Marvin".into(), "LIKES".into(), "Bob".into()),
+        ]
+    );
+
+    let since: i64 = graph
+        .prepare("MATCH (:PERSON) -[e:KNOWS]-> (:PERSON) RETURN e.since")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(since, 2010);
+}
+
+#[test]
+fn create_comma_separated_patterns() {
+    let graph = Graph::open_anon().unwrap();
+
+    let mut txn = graph.mut_txn().unwrap();
+    let names = graph
+        .prepare(
+            "
+            CREATE (a:PERSON { name: 'Alice' }), (b:PERSON { name: 'Bob' }), (a) -[e:KNOWS]-> (b)
+            RETURN a.name, b.name, LABEL(e)
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| Ok((m.get(0)?, m.get(1)?, m.get(2)?)))
+        .unwrap()
+        .collect::<Result<Vec<(String, String, String)>, _>>()
+        .unwrap();
+    assert_eq!(names, [("Alice".into(), "Bob".into(), "KNOWS".into())]);
+    txn.commit().unwrap();
+
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare("MATCH (a { name: 'Alice' }) CREATE (a) <-[:KNOWS]- (:PERSON { name: 'Carol' }), (:ROBOT)")
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+
+    let names = graph
+        .prepare("MATCH (a) -[:KNOWS]-> (b) RETURN a.name, b.name ORDER BY a.name")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| {
+            Ok((m.get(0)?, m.get(1)?))
+        })
+        .unwrap()
+        .collect::<Result<Vec<(String, String)>, _>>()
+        .unwrap();
+    assert_eq!(
+        names,
+        [
+            ("Alice".into(), "Bob".into()),
+            ("Carol".into(), "Alice".into())
+        ]
+    );
+    let count: i64 = graph
+        .prepare("MATCH (n) RETURN count(n)")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(count, 4);
+}
+
+#[test]
+fn create_path_errors() {
+    let graph = Graph::open_anon().unwrap();
+    assert_err!(
+        graph.prepare("CREATE (a:PERSON) -[:KNOWS]-> (b)"),
+        Error::UnknownIdentifier(_)
+    );
+    assert_err!(
+        graph.prepare("CREATE (a:PERSON) -[:KNOWS]-> (a:PERSON)"),
+        Error::IdentifierExists(_)
+    );
+    assert_err!(
+        graph.prepare("CREATE (a:PERSON) -[a:KNOWS]-> (b:PERSON)"),
+        Error::IdentifierExists(_)
+    );
+    assert_err!(
+        graph.prepare("CREATE (a:PERSON), (a) -[:KNOWS]-> (a:PERSON)"),
+        Error::IdentifierExists(_)
+    );
+}
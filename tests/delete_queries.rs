@@ -163,3 +163,91 @@ fn connected_delete_fails() {
     nodes.sort_unstable();
     assert_eq!(nodes, [0, 1]);
 }
+
+#[test]
+fn detach_delete_node() {
+    let graph = Graph::open_anon().unwrap();
+
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare(
+            "
+            CREATE (a:TEST { name: 'a' })
+            CREATE (b:TEST { name: 'b' })
+            CREATE (c:TEST { name: 'c' })
+            CREATE (a) -[:CONNECTED]-> (b)
+            CREATE (c) -[:CONNECTED]-> (a)
+            CREATE (a) -[:CONNECTED]-> (a)
+            CREATE (b) -[:CONNECTED]-> (c)
+            ",
+        )
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare("MATCH (n:TEST { name: 'a' }) DETACH DELETE n")
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+
+    let mut nodes = graph
+        .prepare("MATCH (n) RETURN n.name")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<Vec<String>, _>>()
+        .unwrap();
+    nodes.sort_unstable();
+    assert_eq!(nodes, ["b", "c"]);
+
+    let edges = graph
+        .prepare("MATCH (a) -> (b) RETURN a.name, b.name")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| {
+            Ok((m.get(0)?, m.get(1)?))
+        })
+        .unwrap()
+        .collect::<Result<Vec<(String, String)>, _>>()
+        .unwrap();
+    assert_eq!(edges, [("b".into(), "c".into())]);
+}
+
+#[test]
+fn detach_delete_pending_edges() {
+    let graph = Graph::open_anon().unwrap();
+
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare("CREATE (:TEST)")
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    graph
+        .prepare("MATCH (a) CREATE (b:TEST) CREATE (a) -[:CONNECTED]-> (b) DETACH DELETE a")
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+
+    let nodes = graph
+        .prepare("MATCH (n) RETURN ID(n)")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<Vec<u64>, _>>()
+        .unwrap();
+    assert_eq!(nodes, [1]);
+
+    let edges = graph
+        .prepare("MATCH () -[e]- () RETURN ID(e)")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<Vec<u64>, _>>()
+        .unwrap();
+    assert_eq!(edges, []);
+}
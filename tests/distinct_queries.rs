@@ -0,0 +1,146 @@
+use cqlite::{Error, Graph};
+
+#[macro_use]
+mod common;
+
+fn create_test_graph() -> Graph {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare(
+            "
+            CREATE (peter:PERSON { name: 'Peter', age: 21, city: 'New York' })
+            CREATE (clark:PERSON { name: 'Clark', age: 42, city: 'Metropolis' })
+            CREATE (lois:PERSON { name: 'Lois', age: 35, city: 'Metropolis' })
+            CREATE (jimmy:PERSON { name: 'Jimmy', age: 21, city: 'Metropolis' })
+
+            CREATE (peter) -[:KNOWS]-> (clark)
+            CREATE (peter) -[:KNOWS]-> (lois)
+            CREATE (jimmy) -[:KNOWS]-> (lois)
+            ",
+        )
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+    graph
+}
+
+#[test]
+fn return_distinct_rows() {
+    let graph = create_test_graph();
+
+    let mut txn = graph.txn().unwrap();
+    let cities: Vec<String> = graph
+        .prepare("MATCH (p:PERSON) RETURN DISTINCT p.city ORDER BY p.city")
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(cities, vec!["Metropolis", "New York"]);
+
+    let rows: Vec<(String, i64)> = graph
+        .prepare("MATCH (p:PERSON) RETURN DISTINCT p.city AS city, p.age AS age ORDER BY age, city")
+        .unwrap()
+        .query_map(&mut txn, (), |m| Ok((m.get(0)?, m.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            ("Metropolis".into(), 21),
+            ("New York".into(), 21),
+            ("Metropolis".into(), 35),
+            ("Metropolis".into(), 42),
+        ]
+    );
+
+    // without buffering, rows are returned as they are matched
+    let mut ages: Vec<i64> = graph
+        .prepare("MATCH (p:PERSON) RETURN DISTINCT p.age")
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    ages.sort_unstable();
+    assert_eq!(ages, vec![21, 35, 42]);
+
+    let known: Vec<String> = graph
+        .prepare("MATCH (a:PERSON) -[:KNOWS]-> (b:PERSON) RETURN DISTINCT b.name ORDER BY b.name LIMIT 5")
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(known, vec!["Clark", "Lois"]);
+}
+
+#[test]
+fn return_distinct_with_aggregates() {
+    let graph = create_test_graph();
+
+    let mut txn = graph.txn().unwrap();
+    let rows: Vec<(String, i64)> = graph
+        .prepare("MATCH (p:PERSON) RETURN DISTINCT p.city, count(*) ORDER BY p.city")
+        .unwrap()
+        .query_map(&mut txn, (), |m| Ok((m.get(0)?, m.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(rows, vec![("Metropolis".into(), 3), ("New York".into(), 1)]);
+}
+
+#[test]
+fn with_distinct_rows() {
+    let graph = create_test_graph();
+
+    let mut txn = graph.txn().unwrap();
+    let count: i64 = graph
+        .prepare(
+            "
+            MATCH (a:PERSON) -[:KNOWS]-> (b:PERSON)
+            WITH DISTINCT b
+            RETURN count(*)
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(count, 2);
+
+    let rows: Vec<(String, i64)> = graph
+        .prepare(
+            "
+            MATCH (p:PERSON)
+            WITH DISTINCT p.city AS city WHERE city <> 'Gotham'
+            MATCH (q:PERSON) WHERE q.city = city
+            RETURN city, count(*)
+            ORDER BY city
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| Ok((m.get(0)?, m.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(rows, vec![("Metropolis".into(), 3), ("New York".into(), 1)]);
+}
+
+#[test]
+fn distinct_order_by_must_be_returned() {
+    let graph = create_test_graph();
+
+    assert_err!(
+        graph.prepare("MATCH (p:PERSON) RETURN DISTINCT p.city ORDER BY p.age"),
+        Error::UnknownIdentifier(_)
+    );
+    assert!(graph
+        .prepare("MATCH (p:PERSON) RETURN DISTINCT p.city AS city ORDER BY city")
+        .is_ok());
+}
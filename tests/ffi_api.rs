@@ -73,3 +73,85 @@ fn create_node_read_node() {
     })
     .success();
 }
+
+#[test]
+fn create_function() {
+    (assert_c! {
+        #include <stdio.h>
+        #include <assert.h>
+        #include "cqlite.h"
+
+        CQLiteStatus scale(CQLiteContext *ctx, void *user_data) {
+            if (cqlite_arg_type(ctx, 0) != CQLITE_INTEGER) {
+                return CQLITE_TYPE_MISMATCH;
+            }
+            int64_t factor = *(int64_t *)user_data;
+            cqlite_result_integer(ctx, factor * cqlite_arg_integer(ctx, 0));
+            return CQLITE_OK;
+        }
+
+        int main() {
+            CQLiteGraph *graph;
+            assert(cqlite_open_anon(&graph) == CQLITE_OK);
+
+            int64_t factor = 3;
+            assert(cqlite_create_function(graph, "scale", 1, scale, &factor) == CQLITE_OK);
+
+            CQLiteStatement *stmt;
+            CQLiteTxn *txn;
+
+            assert(cqlite_prepare(graph, "RETURN scale(2, 3)", &stmt) == CQLITE_TYPE_MISMATCH);
+
+            assert(cqlite_prepare(graph, "RETURN scale($value)", &stmt) == CQLITE_OK);
+            assert(cqlite_txn(graph, &txn) == CQLITE_OK);
+            assert(cqlite_create_function(graph, "scale", 1, scale, &factor) == CQLITE_OPEN_TRANSACTION);
+
+            assert(cqlite_bind_integer(stmt, "value", 14) == CQLITE_OK);
+            assert(cqlite_start(stmt, txn) == CQLITE_OK);
+            assert(cqlite_step(stmt) == CQLITE_MATCH);
+            assert(cqlite_return_type(stmt, 0) == CQLITE_INTEGER);
+            assert(cqlite_return_integer(stmt, 0) == 42);
+            assert(cqlite_step(stmt) == CQLITE_DONE);
+
+            assert(cqlite_bind_text(stmt, "value", "14") == CQLITE_OK);
+            assert(cqlite_start(stmt, txn) == CQLITE_OK);
+            assert(cqlite_step(stmt) == CQLITE_TYPE_MISMATCH);
+
+            assert(cqlite_drop(txn) == CQLITE_OK);
+            assert(cqlite_create_function(graph, "scale", 1, scale, &factor) == CQLITE_OPEN_STATEMENT);
+            assert(cqlite_finalize(stmt) == CQLITE_OK);
+            assert(cqlite_close(graph) == CQLITE_OK);
+
+            return 0;
+        }
+    })
+    .success();
+}
+
+#[test]
+fn return_names() {
+    (assert_c! {
+        #include <stdio.h>
+        #include <assert.h>
+        #include <string.h>
+        #include "cqlite.h"
+
+        int main() {
+            CQLiteGraph *graph;
+            assert(cqlite_open_anon(&graph) == CQLITE_OK);
+
+            CQLiteStatement *stmt;
+            assert(cqlite_prepare(graph, "MATCH (a) RETURN ID(a), a.name AS name", &stmt) == CQLITE_OK);
+            assert(cqlite_return_count(stmt) == 2);
+            assert(strcmp(cqlite_return_name(stmt, 0), "ID(a)") == 0);
+            assert(strcmp(cqlite_return_name(stmt, 1), "name") == 0);
+            assert(cqlite_return_name(stmt, 2) == NULL);
+            assert(cqlite_finalize(stmt) == CQLITE_OK);
+
+            assert(cqlite_close(graph) == CQLITE_OK);
+
+            return 0;
+        }
+    })
+    .success();
+}
@@ -0,0 +1,241 @@
+use cqlite::{Error, Graph, Property};
+use std::collections::BTreeMap;
+
+#[macro_use]
+mod common;
+
+fn texts(texts: &[&str]) -> Property {
+    Property::List(
+        texts
+            .iter()
+            .map(|t| Property::Text(t.to_string()))
+            .collect(),
+    )
+}
+
+fn value(graph: &Graph, query: &str) -> Property {
+    let mut values = graph
+        .prepare(query)
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<Vec<Property>, _>>()
+        .unwrap();
+    assert_eq!(values.len(), 1);
+    values.pop().unwrap()
+}
+
+#[test]
+fn string_functions() {
+    let graph = Graph::open_anon().unwrap();
+    let text = |t: &str| Property::Text(t.into());
+    assert_eq!(value(&graph, "RETURN toUpper('Hello')"), text("HELLO"));
+    assert_eq!(value(&graph, "RETURN TOLOWER('Hello')"), text("hello"));
+    assert_eq!(value(&graph, "RETURN trim('  hi ')"), text("hi"));
+    assert_eq!(value(&graph, "RETURN substring('hello', 1)"), text("ello"));
+    assert_eq!(
+        value(&graph, "RETURN substring('hello', 1, 3)"),
+        text("ell")
+    );
+    assert_eq!(value(&graph, "RETURN substring('hello', 10, 3)"), text(""));
+    assert_eq!(value(&graph, "RETURN size('héllo')"), Property::Integer(5));
+    assert_eq!(
+        value(&graph, "RETURN size([1, 2, 3])"),
+        Property::Integer(3)
+    );
+    assert_eq!(value(&graph, "RETURN toUpper(NULL)"), Property::Null);
+}
+
+#[test]
+fn numeric_functions() {
+    let graph = Graph::open_anon().unwrap();
+    assert_eq!(value(&graph, "RETURN abs(-3)"), Property::Integer(3));
+    assert_eq!(value(&graph, "RETURN abs(-2.5)"), Property::Real(2.5));
+    assert_eq!(value(&graph, "RETURN round(2.5)"), Property::Real(3.0));
+    assert_eq!(value(&graph, "RETURN floor(2.7)"), Property::Real(2.0));
+    assert_eq!(value(&graph, "RETURN ceil(2)"), Property::Real(2.0));
+    assert_eq!(value(&graph, "RETURN sqrt(16)"), Property::Real(4.0));
+    assert_eq!(value(&graph, "RETURN abs(NULL)"), Property::Null);
+}
+
+#[test]
+fn conversion_functions() {
+    let graph = Graph::open_anon().unwrap();
+    assert_eq!(
+        value(&graph, "RETURN toInteger('42')"),
+        Property::Integer(42)
+    );
+    assert_eq!(
+        value(&graph, "RETURN toInteger('4.7')"),
+        Property::Integer(4)
+    );
+    assert_eq!(
+        value(&graph, "RETURN toInteger(-4.7)"),
+        Property::Integer(-4)
+    );
+    assert_eq!(value(&graph, "RETURN toInteger('four')"), Property::Null);
+    assert_eq!(value(&graph, "RETURN toFloat(3)"), Property::Real(3.0));
+    assert_eq!(value(&graph, "RETURN toFloat('0.5')"), Property::Real(0.5));
+    assert_eq!(value(&graph, "RETURN toFloat('half')"), Property::Null);
+    assert_eq!(
+        value(&graph, "RETURN toString(42)"),
+        Property::Text("42".into())
+    );
+    assert_eq!(
+        value(&graph, "RETURN toString(1.0)"),
+        Property::Text("1.0".into())
+    );
+    assert_eq!(
+        value(&graph, "RETURN toString(true)"),
+        Property::Text("true".into())
+    );
+    assert_eq!(
+        value(&graph, "RETURN toString(date('2024-01-31'))"),
+        Property::Text("2024-01-31".into())
+    );
+    assert_eq!(
+        value(&graph, "RETURN toString(duration('P1DT2H30M'))"),
+        Property::Text("P1DT2H30M".into())
+    );
+    assert_eq!(
+        value(&graph, "RETURN toString(duration('-PT1.5S'))"),
+        Property::Text("-PT1.5S".into())
+    );
+    assert_eq!(
+        value(&graph, "RETURN toBoolean('TRUE')"),
+        Property::Boolean(true)
+    );
+    assert_eq!(
+        value(&graph, "RETURN toBoolean(0)"),
+        Property::Boolean(false)
+    );
+    assert_eq!(value(&graph, "RETURN toBoolean('yes')"), Property::Null);
+}
+
+#[test]
+fn graph_functions() {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare(
+            "
+            CREATE (a:PERSON { name: 'Alice', age: 42 })
+            CREATE (b:PERSON { name: 'Bob' })
+            CREATE (a) -[:KNOWS { since: 2020 }]-> (b)
+            ",
+        )
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+
+    let rows = graph
+        .prepare("MATCH (a) -[e]-> (b) RETURN keys(a), type(e), labels(b), properties(e)")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| {
+            Ok((m.get(0)?, m.get(1)?, m.get(2)?, m.get(3)?))
+        })
+        .unwrap()
+        .collect::<Result<Vec<(Property, String, Property, Property)>, _>>()
+        .unwrap();
+    let mut since = BTreeMap::new();
+    since.insert("since".to_string(), Property::Integer(2020));
+    assert_eq!(
+        rows,
+        vec![(
+            texts(&["age", "name"]),
+            "KNOWS".into(),
+            texts(&["PERSON"]),
+            Property::Map(since),
+        )]
+    );
+
+    let ends = graph
+        .prepare(
+            "MATCH (a) -[e]-> (b) WHERE startNode(e) = ID(a) AND endNode(e) = ID(b) RETURN a.name",
+        )
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<Vec<String>, _>>()
+        .unwrap();
+    assert_eq!(ends, vec!["Alice"]);
+
+    // maps work as well
+    assert_eq!(
+        value(&graph, "RETURN keys({ b: 1, a: 2 })"),
+        texts(&["a", "b"])
+    );
+}
+
+#[test]
+fn properties_observe_updates() {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    let keys = graph
+        .prepare("CREATE (n:PERSON { name: 'Alice', age: 42 }) SET n.admin = true REMOVE n.age RETURN keys(n)")
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<Vec<Property>, _>>()
+        .unwrap();
+    assert_eq!(keys, vec![texts(&["admin", "name"])]);
+}
+
+#[test]
+fn functions_in_where() {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    for name in ["alice", "Bob", "ALFRED"] {
+        graph
+            .prepare("CREATE (:PERSON { name: $name })")
+            .unwrap()
+            .execute(&mut txn, ("name", name))
+            .unwrap();
+    }
+    txn.commit().unwrap();
+
+    let names = graph
+        .prepare(
+            "MATCH (n:PERSON) WHERE toLower(n.name) STARTS WITH 'al' RETURN n.name ORDER BY n.name",
+        )
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<Vec<String>, _>>()
+        .unwrap();
+    assert_eq!(names, vec!["ALFRED", "alice"]);
+}
+
+#[test]
+fn function_errors() {
+    let graph = Graph::open_anon().unwrap();
+    assert_err!(
+        graph.prepare("RETURN noSuchFunction(1)"),
+        Error::UnknownIdentifier(_)
+    );
+    assert_err!(
+        graph.prepare("RETURN toUpper('a', 'b')"),
+        Error::TypeMismatch
+    );
+    assert_err!(graph.prepare("RETURN substring('a')"), Error::TypeMismatch);
+    assert_err!(
+        graph.prepare("MATCH (n) RETURN type(n)"),
+        Error::IdentifierIsNotEdge(_)
+    );
+    assert_err!(
+        graph.prepare("MATCH (n) -[e]-> (m) RETURN labels(e)"),
+        Error::IdentifierIsNotNode(_)
+    );
+    assert_err!(graph.prepare("RETURN type(e)"), Error::UnknownIdentifier(_));
+    assert_err!(
+        graph
+            .prepare("RETURN toUpper($value)")
+            .unwrap()
+            .query_map(&mut graph.txn().unwrap(), ("value", 42), |m| m
+                .get::<Property, _>(0))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>(),
+        Error::TypeMismatch
+    );
+}
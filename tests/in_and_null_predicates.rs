@@ -0,0 +1,135 @@
+use cqlite::{Graph, Property};
+
+fn people() -> Graph {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    for (name, age) in [("Alice", 42), ("Bob", 0), ("Carol", 23)] {
+        graph
+            .prepare("CREATE (:PERSON { name: $name, age: $age })")
+            .unwrap()
+            .execute(&mut txn, (("name", name), ("age", age)))
+            .unwrap();
+    }
+    graph
+        .prepare("CREATE (:PERSON { name: 'Dave', admin: false })")
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+    graph
+}
+
+fn names(graph: &Graph, condition: &str) -> Vec<String> {
+    graph
+        .prepare(&format!(
+            "MATCH (n:PERSON) WHERE {} RETURN n.name ORDER BY n.name",
+            condition
+        ))
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<Vec<String>, _>>()
+        .unwrap()
+}
+
+#[test]
+fn in_list_literal() {
+    let graph = people();
+    assert_eq!(names(&graph, "n.age IN [0, 23]"), vec!["Bob", "Carol"]);
+    assert_eq!(names(&graph, "n.age IN [42.0]"), vec!["Alice"]);
+    assert_eq!(names(&graph, "n.name IN ['Dave', 'Eve']"), vec!["Dave"]);
+    assert_eq!(names(&graph, "n.age IN []"), Vec::<String>::new());
+    assert_eq!(
+        names(&graph, "NOT n.name IN ['Alice', 'Bob']"),
+        vec!["Carol", "Dave"]
+    );
+    // the right hand side has to be a list
+    assert_eq!(names(&graph, "n.age IN 42"), Vec::<String>::new());
+}
+
+#[test]
+fn in_list_parameter() {
+    let graph = people();
+    let names = graph
+        .prepare("MATCH (n:PERSON) WHERE n.name IN $names RETURN n.age")
+        .unwrap()
+        .query_map(
+            &mut graph.txn().unwrap(),
+            (
+                "names",
+                vec![Property::from("Carol"), Property::from("Alice")],
+            ),
+            |m| m.get(0),
+        )
+        .unwrap()
+        .collect::<Result<Vec<i64>, _>>()
+        .unwrap();
+    assert_eq!(names.len(), 2);
+    assert!(names.contains(&42) && names.contains(&23));
+}
+
+#[test]
+fn is_null_and_is_not_null() {
+    let graph = people();
+    assert_eq!(names(&graph, "n.age IS NULL"), vec!["Dave"]);
+    assert_eq!(
+        names(&graph, "n.age IS NOT NULL"),
+        vec!["Alice", "Bob", "Carol"]
+    );
+    assert_eq!(names(&graph, "n.admin IS NOT NULL"), vec!["Dave"]);
+    // unlike truthiness, zero and false are not null
+    assert_eq!(names(&graph, "n.age"), vec!["Alice", "Carol"]);
+    assert_eq!(
+        names(&graph, "NOT n.age IS NULL AND n.age < 30"),
+        vec!["Bob", "Carol"]
+    );
+}
+
+#[test]
+fn id_in_list() {
+    let graph = people();
+    let ids = graph
+        .prepare("MATCH (n:PERSON) WHERE n.age IS NOT NULL RETURN ID(n) ORDER BY n.name")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<Vec<u64>, _>>()
+        .unwrap();
+
+    let stmt = graph
+        .prepare("MATCH (n) WHERE ID(n) IN $ids RETURN n.name ORDER BY n.name")
+        .unwrap();
+    let names = |ids: Vec<Property>| {
+        stmt.query_map(&mut graph.txn().unwrap(), ("ids", ids), |m| m.get(0))
+            .unwrap()
+            .collect::<Result<Vec<String>, _>>()
+            .unwrap()
+    };
+    assert_eq!(
+        names(vec![Property::Id(ids[2]), Property::Id(ids[0])]),
+        vec!["Alice", "Carol"]
+    );
+    // duplicates, integers, missing nodes, and other values
+    assert_eq!(
+        names(vec![
+            Property::Id(ids[1]),
+            Property::Integer(ids[1] as i64),
+            Property::Id(1000),
+            Property::Text("Alice".into()),
+        ]),
+        vec!["Bob"]
+    );
+    assert_eq!(names(vec![]), Vec::<String>::new());
+
+    // ids are checked again when they depend on other nodes
+    let names = graph
+        .prepare(
+            "MATCH (a:PERSON) MATCH (b) WHERE a.name = 'Alice' AND ID(b) IN [ID(a)] RETURN b.name",
+        )
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<Vec<String>, _>>()
+        .unwrap();
+    assert_eq!(names, vec!["Alice"]);
+}
@@ -0,0 +1,197 @@
+use cqlite::{Error, Graph, Property};
+
+#[macro_use]
+mod common;
+
+fn graph_with_people() -> Graph {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare(
+            "
+            CREATE (:PERSON:ADMIN { name: 'Alice' })
+            CREATE (:PERSON { name: 'Bob' })
+            CREATE (:ROBOT:ADMIN { name: 'Marvin' })
+            CREATE (:ROBOT { name: 'R2D2' })
+            ",
+        )
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+    graph
+}
+
+fn names(graph: &Graph, query: &str) -> Vec<String> {
+    graph
+        .prepare(query)
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap()
+}
+
+#[test]
+fn match_label_conjunction() {
+    let graph = graph_with_people();
+    assert_eq!(
+        names(&graph, "MATCH (n:PERSON:ADMIN) RETURN n.name"),
+        vec!["Alice"]
+    );
+    assert_eq!(
+        names(&graph, "MATCH (n:ADMIN) RETURN n.name ORDER BY n.name"),
+        vec!["Alice", "Marvin"]
+    );
+    assert_eq!(
+        names(&graph, "MATCH (n:PERSON:ROBOT) RETURN n.name"),
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        names(
+            &graph,
+            "MATCH (n) WHERE 'ADMIN' IN labels(n) RETURN n.name ORDER BY n.name"
+        ),
+        vec!["Alice", "Marvin"]
+    );
+}
+
+#[test]
+fn match_label_disjunction() {
+    let graph = graph_with_people();
+    assert_eq!(
+        names(
+            &graph,
+            "MATCH (n:PERSON|ROBOT) RETURN n.name ORDER BY n.name"
+        ),
+        vec!["Alice", "Bob", "Marvin", "R2D2"]
+    );
+    assert_eq!(
+        names(
+            &graph,
+            "MATCH (n:PERSON|ADMIN { name: 'Marvin' }) RETURN n.name"
+        ),
+        vec!["Marvin"]
+    );
+}
+
+#[test]
+fn return_labels() {
+    let graph = graph_with_people();
+    let labels: Vec<Property> = graph
+        .prepare("MATCH (n { name: 'Alice' }) RETURN labels(n)")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        labels,
+        vec![Property::List(vec![
+            Property::Text("PERSON".into()),
+            Property::Text("ADMIN".into()),
+        ])]
+    );
+}
+
+#[test]
+fn set_and_remove_labels() {
+    let graph = graph_with_people();
+    let mut txn = graph.mut_txn().unwrap();
+    let labels: Vec<Property> = graph
+        .prepare(
+            "MATCH (n:PERSON { name: 'Bob' }) SET n:ADMIN:OWNER REMOVE n:PERSON RETURN labels(n)",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        labels,
+        vec![Property::List(vec![
+            Property::Text("ADMIN".into()),
+            Property::Text("OWNER".into()),
+        ])]
+    );
+    graph
+        .prepare("MATCH (n:ROBOT:ADMIN) SET n:ADMIN REMOVE n:ROBOT:MISSING")
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+
+    assert_eq!(
+        names(&graph, "MATCH (n:ADMIN) RETURN n.name ORDER BY n.name"),
+        vec!["Alice", "Bob", "Marvin"]
+    );
+    assert_eq!(names(&graph, "MATCH (n:OWNER) RETURN n.name"), vec!["Bob"]);
+    assert_eq!(
+        names(&graph, "MATCH (n:PERSON) RETURN n.name"),
+        vec!["Alice"]
+    );
+    assert_eq!(names(&graph, "MATCH (n:ROBOT) RETURN n.name"), vec!["R2D2"]);
+}
+
+#[test]
+fn merge_multiple_labels() {
+    let graph = graph_with_people();
+    let mut txn = graph.mut_txn().unwrap();
+    let stmt = graph
+        .prepare("MERGE (n:ADMIN:PERSON { name: $name }) ON CREATE SET n:NEW RETURN n.name")
+        .unwrap();
+    stmt.execute(&mut txn, ("name", "Alice")).unwrap();
+    stmt.execute(&mut txn, ("name", "Bob")).unwrap();
+    txn.commit().unwrap();
+
+    assert_eq!(
+        names(
+            &graph,
+            "MATCH (n:PERSON:ADMIN) RETURN n.name ORDER BY n.name"
+        ),
+        vec!["Alice", "Bob"]
+    );
+    assert_eq!(names(&graph, "MATCH (n:NEW) RETURN n.name"), vec!["Bob"]);
+    assert_eq!(
+        names(&graph, "MATCH (n:PERSON) RETURN n.name ORDER BY n.name"),
+        vec!["Alice", "Bob", "Bob"]
+    );
+}
+
+#[test]
+fn delete_node_with_labels() {
+    let graph = graph_with_people();
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare("MATCH (n:ADMIN) DELETE n")
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+
+    assert_eq!(
+        names(
+            &graph,
+            "MATCH (n:PERSON|ROBOT) RETURN n.name ORDER BY n.name"
+        ),
+        vec!["Bob", "R2D2"]
+    );
+    assert_eq!(
+        names(&graph, "MATCH (n:ADMIN) RETURN n.name"),
+        Vec::<String>::new()
+    );
+}
+
+#[test]
+fn label_errors() {
+    let graph = graph_with_people();
+    assert_err!(
+        graph.prepare("MATCH (a) -[e]-> (b) SET e:LABEL"),
+        Error::IdentifierIsNotNode(_)
+    );
+    assert_err!(
+        graph.prepare("MATCH (a) REMOVE b:LABEL"),
+        Error::UnknownIdentifier(_)
+    );
+    assert_err!(graph.prepare("CREATE (a:A|B)"), Error::Syntax { .. });
+}
@@ -0,0 +1,154 @@
+use cqlite::{Graph, Property};
+use std::collections::BTreeMap;
+
+#[test]
+fn create_and_return_lists() {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare("CREATE (a:PERSON { name: 'Alice', nicknames: ['Al', 'Ally'], scores: [] })")
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+
+    let rows = graph
+        .prepare("MATCH (a:PERSON) RETURN a.nicknames, a.scores, [a.name, 42, [true]]")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| {
+            Ok((m.get(0)?, m.get(1)?, m.get(2)?))
+        })
+        .unwrap()
+        .collect::<Result<Vec<(Vec<Property>, Vec<Property>, Vec<Property>)>, _>>()
+        .unwrap();
+    assert_eq!(
+        rows,
+        vec![(
+            vec![
+                Property::Text("Al".to_string()),
+                Property::Text("Ally".to_string()),
+            ],
+            vec![],
+            vec![
+                Property::Text("Alice".to_string()),
+                Property::Integer(42),
+                Property::List(vec![Property::Boolean(true)]),
+            ],
+        )]
+    );
+}
+
+#[test]
+fn create_and_return_maps() {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare("CREATE (a:PERSON { name: 'Alice', address: { city: 'Bern', zip: 3000 } })")
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+
+    let rows = graph
+        .prepare("MATCH (a:PERSON) RETURN a.address, { name: a.name, tags: ['x'] }, {}")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| {
+            Ok((m.get(0)?, m.get(1)?, m.get(2)?))
+        })
+        .unwrap()
+        .collect::<Result<Vec<(BTreeMap<String, Property>, _, BTreeMap<String, Property>)>, _>>()
+        .unwrap();
+
+    let mut address = BTreeMap::new();
+    address.insert("city".to_string(), Property::Text("Bern".to_string()));
+    address.insert("zip".to_string(), Property::Integer(3000));
+    let mut person = BTreeMap::new();
+    person.insert("name".to_string(), Property::Text("Alice".to_string()));
+    person.insert(
+        "tags".to_string(),
+        Property::List(vec![Property::Text("x".to_string())]),
+    );
+    assert_eq!(rows, vec![(address, person, BTreeMap::new())]);
+}
+
+#[test]
+fn bind_lists_and_maps_as_parameters() {
+    let graph = Graph::open_anon().unwrap();
+    let tags = vec![Property::Integer(1), Property::Text("two".to_string())];
+    let mut meta = BTreeMap::new();
+    meta.insert("active".to_string(), Property::Boolean(true));
+
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare("CREATE (a:PERSON { tags: $tags, meta: $meta })")
+        .unwrap()
+        .execute(&mut txn, (("tags", tags.clone()), ("meta", meta.clone())))
+        .unwrap();
+    graph
+        .prepare("MATCH (a:PERSON) SET a.both = [$tags, $meta]")
+        .unwrap()
+        .execute(&mut txn, (("tags", tags.clone()), ("meta", meta.clone())))
+        .unwrap();
+    txn.commit().unwrap();
+
+    let rows = graph
+        .prepare("MATCH (a:PERSON) RETURN a.tags, a.meta, a.both")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| {
+            Ok((m.get(0)?, m.get(1)?, m.get(2)?))
+        })
+        .unwrap()
+        .collect::<Result<Vec<(Vec<Property>, BTreeMap<String, Property>, Vec<Property>)>, _>>()
+        .unwrap();
+    assert_eq!(
+        rows,
+        vec![(
+            tags.clone(),
+            meta.clone(),
+            vec![Property::List(tags), Property::Map(meta)],
+        )]
+    );
+}
+
+#[test]
+fn compare_lists_and_maps() {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare(
+            "
+            CREATE (a:PERSON { name: 'Alice', langs: ['de', 'en'], pet: { kind: 'cat' } })
+            CREATE (b:PERSON { name: 'Bob', langs: ['en'], pet: { kind: 'dog' } })
+            ",
+        )
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+
+    let names = |query: &str| {
+        graph
+            .prepare(query)
+            .unwrap()
+            .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+            .unwrap()
+            .collect::<Result<Vec<String>, _>>()
+            .unwrap()
+    };
+    assert_eq!(
+        names("MATCH (p:PERSON) WHERE p.langs = ['de', 'en'] RETURN p.name"),
+        vec!["Alice"]
+    );
+    assert_eq!(
+        names("MATCH (p:PERSON) WHERE p.pet = { kind: 'dog' } RETURN p.name"),
+        vec!["Bob"]
+    );
+    assert_eq!(
+        names("MATCH (p:PERSON { langs: ['en'] }) RETURN p.name"),
+        vec!["Bob"]
+    );
+    assert_eq!(
+        names("MATCH (p:PERSON) WHERE [p.name] = ['Alice'] RETURN p.name"),
+        vec!["Alice"]
+    );
+}
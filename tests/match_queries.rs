@@ -191,7 +191,8 @@ fn match_long_path() {
         .collect::<Result<_, _>>()
         .unwrap();
     nodes.sort_unstable();
-    assert_eq!(nodes, vec![(1, 0, 1, 3), (2, 0, 1, 3)]);
+    // the edge from 0 to 1 can not be matched twice
+    assert_eq!(nodes, vec![(2, 0, 1, 3)]);
 }
 
 #[test]
@@ -330,7 +330,8 @@ fn match_long_path_with_id_constraint() {
         .collect::<Result<_, _>>()
         .unwrap();
     paths.sort_unstable();
-    assert_eq!(paths, [(0, 3, 2), (2, 0, 2), (2, 3, 2)]);
+    // edges are not matched twice, so `a` and `c` always differ
+    assert_eq!(paths, [(0, 3, 2)]);
 
     let paths: Vec<(u64, u64, u64)> = graph
         .prepare(
@@ -0,0 +1,310 @@
+use cqlite::{Error, Graph, Property};
+
+#[macro_use]
+mod common;
+
+fn count(graph: &Graph, query: &str) -> i64 {
+    let mut txn = graph.txn().unwrap();
+    graph
+        .prepare(query)
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+}
+
+#[test]
+fn merge_creates_missing_node_once() {
+    let graph = Graph::open_anon().unwrap();
+    let stmt = graph
+        .prepare("MERGE (c:CITY { name: 'Zurich' }) RETURN c.name")
+        .unwrap();
+
+    for _ in 0..2 {
+        let mut txn = graph.mut_txn().unwrap();
+        let names = stmt
+            .query_map(&mut txn, (), |m| m.get(0))
+            .unwrap()
+            .collect::<Result<Vec<String>, _>>()
+            .unwrap();
+        assert_eq!(names, vec!["Zurich"]);
+        txn.commit().unwrap();
+    }
+
+    assert_eq!(count(&graph, "MATCH (c:CITY) RETURN count(*)"), 1);
+}
+
+#[test]
+fn merge_on_create_and_on_match() {
+    let graph = Graph::open_anon().unwrap();
+    let stmt = graph
+        .prepare(
+            "
+            MERGE (c:CITY { name: $name })
+            ON CREATE SET c.visits = 1
+            ON MATCH SET c.visits = 2, c.seen = true
+            RETURN c.visits, c.seen
+            ",
+        )
+        .unwrap();
+
+    let mut txn = graph.mut_txn().unwrap();
+    let rows = stmt
+        .query_map(&mut txn, ("name", "Zurich"), |m| Ok((m.get(0)?, m.get(1)?)))
+        .unwrap()
+        .collect::<Result<Vec<(i64, Option<bool>)>, _>>()
+        .unwrap();
+    assert_eq!(rows, vec![(1, None)]);
+
+    let rows = stmt
+        .query_map(&mut txn, ("name", "Zurich"), |m| Ok((m.get(0)?, m.get(1)?)))
+        .unwrap()
+        .collect::<Result<Vec<(i64, Option<bool>)>, _>>()
+        .unwrap();
+    assert_eq!(rows, vec![(2, Some(true))]);
+    txn.commit().unwrap();
+}
+
+#[test]
+fn merge_sees_pending_creations() {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare(
+            "
+            CREATE (a:PERSON { name: 'Alice', city: 'Zurich' })
+            CREATE (b:PERSON { name: 'Bob', city: 'Bern' })
+            CREATE (c:PERSON { name: 'Carol', city: 'Zurich' })
+            ",
+        )
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    graph
+        .prepare(
+            "
+            MATCH (p:PERSON)
+            MERGE (c:CITY { name: p.city }) ON CREATE SET c.founded = 'today'
+            MERGE (p) -[:LIVES_IN]-> (c)
+            ",
+        )
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+
+    // the same pattern merged twice in one query is only created once
+    let ids = graph
+        .prepare(
+            "
+            MERGE (a:TAG { name: 'x' })
+            MERGE (b:TAG { name: 'x' })
+            RETURN ID(a), ID(b)
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| Ok((m.get(0)?, m.get(1)?)))
+        .unwrap()
+        .collect::<Result<Vec<(u64, u64)>, _>>()
+        .unwrap();
+    assert_eq!(ids.len(), 1);
+    assert_eq!(ids[0].0, ids[0].1);
+    txn.commit().unwrap();
+
+    assert_eq!(count(&graph, "MATCH (c:CITY) RETURN count(*)"), 2);
+    assert_eq!(
+        count(
+            &graph,
+            "MATCH (c:CITY { founded: 'today' }) RETURN count(*)"
+        ),
+        2
+    );
+    assert_eq!(
+        count(
+            &graph,
+            "MATCH (:PERSON) -[:LIVES_IN]-> (:CITY) RETURN count(*)"
+        ),
+        3
+    );
+    assert_eq!(
+        count(
+            &graph,
+            "MATCH (p:PERSON) -[:LIVES_IN]-> (c:CITY { name: 'Zurich' }) RETURN count(p)"
+        ),
+        2
+    );
+    assert_eq!(count(&graph, "MATCH (t:TAG) RETURN count(*)"), 1);
+}
+
+#[test]
+fn merge_edges() {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare("CREATE (a:PERSON { name: 'Alice' }) CREATE (b:PERSON { name: 'Bob' })")
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+
+    let stmt = graph
+        .prepare(
+            "
+            MATCH (a:PERSON { name: 'Alice' })
+            MATCH (b:PERSON { name: 'Bob' })
+            MERGE (a) -[e:KNOWS { since: 2020 }]-> (b)
+            ON CREATE SET e.created = true
+            ON MATCH SET e.created = false
+            RETURN e.created
+            ",
+        )
+        .unwrap();
+    for created in [true, false, false] {
+        let mut txn = graph.mut_txn().unwrap();
+        let rows = stmt
+            .query_map(&mut txn, (), |m| m.get(0))
+            .unwrap()
+            .collect::<Result<Vec<bool>, _>>()
+            .unwrap();
+        assert_eq!(rows, vec![created]);
+        txn.commit().unwrap();
+    }
+
+    // the direction and properties are part of the pattern
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare(
+            "
+            MATCH (a:PERSON { name: 'Alice' })
+            MATCH (b:PERSON { name: 'Bob' })
+            MERGE (a) <-[:KNOWS { since: 2020 }]- (b)
+            MERGE (a) -[:KNOWS { since: 2021 }]-> (b)
+            ",
+        )
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+    assert_eq!(
+        count(&graph, "MATCH (a) -[e:KNOWS]-> (b) RETURN count(e)"),
+        3
+    );
+}
+
+#[test]
+fn merge_matches_every_existing_node() {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare(
+            "
+            CREATE (a:PERSON { name: 'Alice' })
+            CREATE (b:PERSON { name: 'Alice' })
+            CREATE (c:PERSON { name: 'Bob' })
+            ",
+        )
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    let rows = graph
+        .prepare("MERGE (p:PERSON { name: 'Alice' }) ON MATCH SET p.seen = true RETURN p.seen")
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<Vec<bool>, _>>()
+        .unwrap();
+    assert_eq!(rows, vec![true, true]);
+
+    // nodes which are deleted in the same transaction are not matched
+    graph
+        .prepare("MATCH (p:PERSON { name: 'Bob' }) DELETE p")
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    graph
+        .prepare("MERGE (p:PERSON { name: 'Bob' })")
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+
+    assert_eq!(
+        count(&graph, "MATCH (p:PERSON { seen: true }) RETURN count(*)"),
+        2
+    );
+    assert_eq!(
+        count(&graph, "MATCH (p:PERSON { name: 'Bob' }) RETURN count(*)"),
+        1
+    );
+}
+
+#[test]
+fn merge_null_properties() {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare("CREATE (:PERSON { name: 'Alice' }) CREATE (:PERSON { name: 'Bob' })")
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+
+    let mut txn = graph.mut_txn().unwrap();
+    assert_err!(
+        graph
+            .prepare("MERGE (a:PERSON { name: null })")
+            .unwrap()
+            .execute(&mut txn, ()),
+        Error::MergeNullProperty(_)
+    );
+    assert_err!(
+        graph
+            .prepare("MERGE (a:PERSON { name: $name })")
+            .unwrap()
+            .execute(&mut txn, ("name", Property::Null)),
+        Error::MergeNullProperty(_)
+    );
+    assert_err!(
+        graph
+            .prepare(
+                "
+                MATCH (a:PERSON { name: 'Alice' }), (b:PERSON { name: 'Bob' })
+                MERGE (a) -[:KNOWS { since: $since }]-> (b)
+                ",
+            )
+            .unwrap()
+            .execute(&mut txn, ()),
+        Error::MergeNullProperty(_)
+    );
+    txn.commit().unwrap();
+
+    assert_eq!(count(&graph, "MATCH (a:PERSON) RETURN count(*)"), 2);
+    assert_eq!(count(&graph, "MATCH () -[e]-> () RETURN count(*)"), 0);
+}
+
+#[test]
+fn merge_invalid_names() {
+    let graph = Graph::open_anon().unwrap();
+    assert_err!(
+        graph.prepare("MATCH (a) MERGE (a:PERSON)"),
+        Error::IdentifierExists(_)
+    );
+    assert_err!(
+        graph.prepare("MATCH (a) MERGE (a) -[:KNOWS]-> (b)"),
+        Error::UnknownIdentifier(_)
+    );
+    assert_err!(
+        graph.prepare("MERGE (a:PERSON) ON CREATE SET b.name = 'Alice'"),
+        Error::UnknownIdentifier(_)
+    );
+
+    let mut txn = graph.txn().unwrap();
+    assert_err!(
+        graph
+            .prepare("MERGE (a:PERSON)")
+            .unwrap()
+            .execute(&mut txn, ()),
+        Error::ReadOnlyWrite
+    );
+}
@@ -0,0 +1,233 @@
+use cqlite::Graph;
+
+fn create_test_graph() -> Graph {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare(
+            "
+            CREATE (peter:PERSON { name: 'Peter', age: 21 })
+            CREATE (clark:PERSON { name: 'Clark', age: 42 })
+            CREATE (lois:PERSON { name: 'Lois', age: 35 })
+            CREATE (perry:PERSON { name: 'Perry', age: 61 })
+
+            CREATE (clark) -[:REPORTS_TO { since: 1978 }]-> (perry)
+            CREATE (lois) -[:REPORTS_TO { since: 1977 }]-> (perry)
+            CREATE (peter) -[:KNOWS]-> (clark)
+            ",
+        )
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+    graph
+}
+
+#[test]
+fn optional_match_returns_null_for_missing_nodes() {
+    let graph = create_test_graph();
+
+    let mut txn = graph.txn().unwrap();
+    let rows: Vec<(String, Option<String>, Option<i64>)> = graph
+        .prepare(
+            "
+            MATCH (p:PERSON)
+            OPTIONAL MATCH (p) -[e:REPORTS_TO]-> (m)
+            RETURN p.name, m.name, e.since
+            ORDER BY p.name
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| Ok((m.get(0)?, m.get(1)?, m.get(2)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            ("Clark".into(), Some("Perry".into()), Some(1978)),
+            ("Lois".into(), Some("Perry".into()), Some(1977)),
+            ("Perry".into(), None, None),
+            ("Peter".into(), None, None),
+        ]
+    );
+
+    let ids: Vec<Option<u64>> = graph
+        .prepare(
+            "
+            MATCH (p:PERSON)
+            OPTIONAL MATCH (p) -[:REPORTS_TO]-> (m)
+            RETURN ID(m)
+            ORDER BY p.name
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert!(ids[0].is_some() && ids[0] == ids[1]);
+    assert_eq!(&ids[2..], &[None, None]);
+}
+
+#[test]
+fn optional_match_where_does_not_drop_rows() {
+    let graph = create_test_graph();
+
+    let mut txn = graph.txn().unwrap();
+    let rows: Vec<(String, Option<String>)> = graph
+        .prepare(
+            "
+            MATCH (p:PERSON)
+            OPTIONAL MATCH (p) -[:REPORTS_TO]-> (m) WHERE m.age > 60 AND p.age < 40
+            RETURN p.name, m.name
+            ORDER BY p.name
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| Ok((m.get(0)?, m.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            ("Clark".into(), None),
+            ("Lois".into(), Some("Perry".into())),
+            ("Perry".into(), None),
+            ("Peter".into(), None),
+        ]
+    );
+}
+
+#[test]
+fn optional_match_multiple_matches() {
+    let graph = create_test_graph();
+
+    let mut txn = graph.txn().unwrap();
+    let rows: Vec<(String, Option<String>)> = graph
+        .prepare(
+            "
+            MATCH (m:PERSON)
+            OPTIONAL MATCH (p) -[:REPORTS_TO]-> (m)
+            RETURN m.name, p.name
+            ORDER BY m.name, p.name
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| Ok((m.get(0)?, m.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            ("Clark".into(), None),
+            ("Lois".into(), None),
+            ("Perry".into(), Some("Clark".into())),
+            ("Perry".into(), Some("Lois".into())),
+            ("Peter".into(), None),
+        ]
+    );
+}
+
+#[test]
+fn optional_match_chained() {
+    let graph = create_test_graph();
+
+    let mut txn = graph.txn().unwrap();
+    let rows: Vec<(String, Option<String>, Option<String>)> = graph
+        .prepare(
+            "
+            MATCH (p:PERSON)
+            OPTIONAL MATCH (p) -[:KNOWS]-> (f)
+            OPTIONAL MATCH (f) -[:REPORTS_TO]-> (m)
+            RETURN p.name, f.name, m.name
+            ORDER BY p.name
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| Ok((m.get(0)?, m.get(1)?, m.get(2)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            ("Clark".into(), None, None),
+            ("Lois".into(), None, None),
+            ("Perry".into(), None, None),
+            ("Peter".into(), Some("Clark".into()), Some("Perry".into())),
+        ]
+    );
+}
+
+#[test]
+fn optional_match_with_aggregates() {
+    let graph = create_test_graph();
+
+    let mut txn = graph.txn().unwrap();
+    let rows: Vec<(String, i64, i64)> = graph
+        .prepare(
+            "
+            MATCH (m:PERSON)
+            OPTIONAL MATCH (p) -[:REPORTS_TO]-> (m)
+            RETURN m.name, count(p.name), count(*)
+            ORDER BY m.name
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| Ok((m.get(0)?, m.get(1)?, m.get(2)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            ("Clark".into(), 0, 1),
+            ("Lois".into(), 0, 1),
+            ("Perry".into(), 2, 2),
+            ("Peter".into(), 0, 1),
+        ]
+    );
+}
+
+#[test]
+fn optional_match_ignores_updates_to_null() {
+    let graph = create_test_graph();
+
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare(
+            "
+            MATCH (p:PERSON)
+            OPTIONAL MATCH (p) -[e:REPORTS_TO]-> (m)
+            SET m.manager = TRUE
+            SET e.checked = TRUE
+            ",
+        )
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+
+    let mut txn = graph.txn().unwrap();
+    let managers: Vec<String> = graph
+        .prepare("MATCH (m:PERSON) WHERE m.manager RETURN m.name")
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(managers, vec!["Perry".to_string()]);
+
+    let checked: i64 = graph
+        .prepare("MATCH (a) -[e]-> (b) WHERE e.checked RETURN count(*)")
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(checked, 2);
+}
@@ -0,0 +1,216 @@
+use cqlite::{Error, Graph, Path, Property};
+
+#[macro_use]
+mod common;
+
+fn create_test_graph() -> (Graph, Vec<u64>) {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    let ids = graph
+        .prepare(
+            "
+            CREATE (a:STOP { name: 'A' })
+            CREATE (b:STOP { name: 'B' })
+            CREATE (c:STOP { name: 'C' })
+            CREATE (d:STOP { name: 'D' })
+
+            CREATE (a) -[:NEXT]-> (b)
+            CREATE (b) -[:NEXT]-> (c)
+            CREATE (d) -[:NEXT]-> (c)
+            RETURN ID(a), ID(b), ID(c), ID(d)
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| {
+            Ok(vec![m.get(0)?, m.get(1)?, m.get(2)?, m.get(3)?])
+        })
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    txn.commit().unwrap();
+    (graph, ids)
+}
+
+#[test]
+fn named_path_of_fixed_pattern() {
+    let (graph, ids) = create_test_graph();
+
+    let mut txn = graph.txn().unwrap();
+    let paths: Vec<Path> = graph
+        .prepare("MATCH p = (a { name: 'A' }) -> (b) -> (c) RETURN p")
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(paths.len(), 1);
+    assert_eq!(paths[0].nodes, ids[..3]);
+    assert_eq!(paths[0].len(), 2);
+
+    // paths are followed in the direction of the pattern
+    let paths: Vec<Path> = graph
+        .prepare("MATCH p = (c { name: 'C' }) <- (b) <- (a) RETURN p")
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(paths.len(), 1);
+    assert_eq!(paths[0].nodes, vec![ids[2], ids[1], ids[0]]);
+
+    let single: Path = graph
+        .prepare("MATCH p = (a { name: 'D' }) RETURN p")
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(single.nodes, vec![ids[3]]);
+    assert!(single.is_empty());
+}
+
+#[test]
+fn named_path_functions() {
+    let (graph, ids) = create_test_graph();
+
+    let mut txn = graph.txn().unwrap();
+    let rows: Vec<(Vec<Property>, Vec<Property>, i64)> = graph
+        .prepare(
+            "
+            MATCH p = (a { name: 'A' }) -[*]- (b)
+            RETURN nodes(p), relationships(p), length(p)
+            ORDER BY length(p)
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| Ok((m.get(0)?, m.get(1)?, m.get(2)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let nodes: Vec<Vec<Property>> = rows.iter().map(|row| row.0.clone()).collect();
+    assert_eq!(
+        nodes,
+        vec![
+            vec![Property::Id(ids[0]), Property::Id(ids[1])],
+            vec![
+                Property::Id(ids[0]),
+                Property::Id(ids[1]),
+                Property::Id(ids[2])
+            ],
+            vec![
+                Property::Id(ids[0]),
+                Property::Id(ids[1]),
+                Property::Id(ids[2]),
+                Property::Id(ids[3])
+            ],
+        ]
+    );
+    for (nodes, edges, length) in rows {
+        assert_eq!(edges.len() as i64, length);
+        assert_eq!(nodes.len() as i64, length + 1);
+    }
+}
+
+#[test]
+fn named_path_joins_segments() {
+    let (graph, ids) = create_test_graph();
+
+    let mut txn = graph.txn().unwrap();
+    let paths: Vec<(Path, Vec<Property>)> = graph
+        .prepare(
+            "
+            MATCH p = (a { name: 'A' }) -[e]-> (b) -[r*]-> (c) <-[f]- (d { name: 'D' })
+            RETURN p, r
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| Ok((m.get(0)?, m.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(paths.len(), 1);
+    let (path, r) = &paths[0];
+    assert_eq!(path.nodes, ids);
+    assert_eq!(path.len(), 3);
+    assert_eq!(r, &vec![Property::Id(path.edges[1])]);
+}
+
+#[test]
+fn named_path_in_where_and_with() {
+    let (graph, _) = create_test_graph();
+
+    let mut txn = graph.txn().unwrap();
+    let names: Vec<String> = graph
+        .prepare(
+            "
+            MATCH p = (a { name: 'A' }) -[*]- (b)
+            WITH p, b
+            WHERE length(p) > 1
+            RETURN b.name
+            ORDER BY b.name
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(names, vec!["C".to_string(), "D".to_string()]);
+}
+
+#[test]
+fn optional_named_path() {
+    let (graph, _) = create_test_graph();
+
+    let mut txn = graph.txn().unwrap();
+    let rows: Vec<(String, Option<Path>, Option<i64>)> = graph
+        .prepare(
+            "
+            MATCH (a:STOP)
+            OPTIONAL MATCH p = (a) -[:NEXT*2]-> (b)
+            RETURN a.name, p, length(p)
+            ORDER BY a.name
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| Ok((m.get(0)?, m.get(1)?, m.get(2)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let rows: Vec<(&str, bool, Option<i64>)> = rows
+        .iter()
+        .map(|(name, path, len)| (name.as_str(), path.is_some(), *len))
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            ("A", true, Some(2)),
+            ("B", false, None),
+            ("C", false, None),
+            ("D", false, None),
+        ]
+    );
+}
+
+#[test]
+fn named_path_invalid_names() {
+    let graph = Graph::open_anon().unwrap();
+    assert_err!(
+        graph.prepare("MATCH p = (a) -> (b) RETURN p.name"),
+        Error::IdentifierIsNotNode(_)
+    );
+    assert_err!(
+        graph.prepare("MATCH p = (a) -> (p) RETURN p"),
+        Error::IdentifierExists(_)
+    );
+    assert_err!(
+        graph.prepare("MATCH p = (a) -[r*]-> (b) RETURN length(r)"),
+        Error::TypeMismatch
+    );
+    assert_err!(
+        graph.prepare("MATCH (a) RETURN nodes(p)"),
+        Error::UnknownIdentifier(_)
+    );
+}
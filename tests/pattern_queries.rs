@@ -0,0 +1,173 @@
+use cqlite::Graph;
+
+fn create_triangle() -> Graph {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare(
+            "
+            CREATE (a:NODE { name: 'A' }) -[:LINK]-> (b:NODE { name: 'B' }),
+                   (a) -[:LINK]-> (c:NODE { name: 'C' }),
+                   (b) -[:LINK]-> (c)
+            ",
+        )
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+    graph
+}
+
+fn name_pairs(graph: &Graph, query: &str) -> Vec<(String, String)> {
+    let mut pairs: Vec<(String, String)> = graph
+        .prepare(query)
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| {
+            Ok((m.get(0)?, m.get(1)?))
+        })
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    pairs.sort();
+    pairs
+}
+
+fn count(graph: &Graph, query: &str) -> i64 {
+    graph
+        .prepare(query)
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+}
+
+fn pair(a: &str, b: &str) -> (String, String) {
+    (a.to_string(), b.to_string())
+}
+
+#[test]
+fn match_comma_separated_patterns() {
+    let graph = create_triangle();
+    assert_eq!(
+        name_pairs(
+            &graph,
+            "MATCH (a { name: 'A' }) -> (x), (a) -> (y) RETURN x.name, y.name"
+        ),
+        vec![pair("B", "C"), pair("C", "B")]
+    );
+    assert_eq!(
+        name_pairs(
+            &graph,
+            "MATCH (a { name: 'A' }), (b { name: 'B' }) RETURN a.name, b.name"
+        ),
+        vec![pair("A", "B")]
+    );
+}
+
+#[test]
+fn match_branching_patterns() {
+    let graph = create_triangle();
+    assert_eq!(
+        name_pairs(
+            &graph,
+            "MATCH (x) -> (y), (x) -> (z), (y) -> (z) RETURN x.name, z.name"
+        ),
+        vec![pair("A", "C")]
+    );
+    assert_eq!(
+        name_pairs(
+            &graph,
+            "MATCH (x) -[e]-> (y), (y) <-[f]- (x) RETURN x.name, y.name"
+        ),
+        vec![]
+    );
+}
+
+#[test]
+fn match_edges_once_per_clause() {
+    let graph = create_triangle();
+    assert_eq!(count(&graph, "MATCH (x) - (y) - (z) RETURN count(*)"), 6);
+    assert_eq!(
+        count(&graph, "MATCH (x) - (y) MATCH (y) - (z) RETURN count(*)"),
+        12
+    );
+    assert_eq!(
+        name_pairs(
+            &graph,
+            "MATCH (a { name: 'A' }) -> (x) MATCH (a) -> (y) RETURN x.name, y.name"
+        ),
+        vec![
+            pair("B", "B"),
+            pair("B", "C"),
+            pair("C", "B"),
+            pair("C", "C")
+        ]
+    );
+    assert_eq!(
+        count(
+            &graph,
+            "MATCH () -[e]-> () MATCH (x) -[e]-> (y), (x) -[f]-> () RETURN count(*)"
+        ),
+        2
+    );
+}
+
+#[test]
+fn match_paths_once_per_clause() {
+    let graph = create_triangle();
+    assert_eq!(
+        name_pairs(
+            &graph,
+            "MATCH (a { name: 'A' }) -[*1..2]-> (x), (a) -> (y) RETURN x.name, y.name"
+        ),
+        vec![pair("B", "C"), pair("C", "B"), pair("C", "C")]
+    );
+    assert_eq!(
+        name_pairs(
+            &graph,
+            "MATCH (a { name: 'A' }) -[*1..2]-> (x), (a) -[*1..2]-> (y) RETURN x.name, y.name"
+        ),
+        vec![
+            pair("B", "C"),
+            pair("C", "B"),
+            pair("C", "C"),
+            pair("C", "C")
+        ]
+    );
+}
+
+#[test]
+fn optional_match_comma_separated_patterns() {
+    let graph = create_triangle();
+    let names: Vec<Option<String>> = graph
+        .prepare(
+            "
+            MATCH (a { name: 'A' })
+            OPTIONAL MATCH (a) -> (x), (x) -> (y)
+            RETURN y.name
+            ",
+        )
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(names, vec![Some("C".to_string())]);
+
+    let names: Vec<Option<String>> = graph
+        .prepare(
+            "
+            MATCH (c { name: 'C' })
+            OPTIONAL MATCH (c) -> (x), (x) -> (y)
+            RETURN y.name
+            ",
+        )
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(names, vec![None]);
+}
@@ -1,4 +1,6 @@
 use cqlite::{Error, Graph, Property};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 #[macro_use]
 mod common;
@@ -201,3 +203,301 @@ fn return_out_of_bounds() {
     assert_eq!(m.get::<i64, _>(1).unwrap(), 42);
     assert_err!(m.get::<Property, _>(2), Error::IndexOutOfBounds);
 }
+
+fn create_people(graph: &Graph) {
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare(
+            "
+            CREATE (:PERSON { name: 'Peter', age: 21 })
+            CREATE (:PERSON { name: 'Clark', age: 42 })
+            CREATE (:PERSON { name: 'Bruce', age: 35 })
+            CREATE (:PERSON { name: 'Diana', age: 35 })
+            CREATE (:PERSON { name: 'Nobody' })
+            ",
+        )
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+}
+
+#[test]
+fn return_order_by() {
+    let graph = Graph::open_anon().unwrap();
+    create_people(&graph);
+
+    let names: Vec<String> = graph
+        .prepare("MATCH (p:PERSON) RETURN p.name ORDER BY p.age, p.name")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(names, vec!["Peter", "Bruce", "Diana", "Clark", "Nobody"]);
+
+    let names: Vec<String> = graph
+        .prepare("MATCH (p:PERSON) RETURN p.name ORDER BY p.age DESC, p.name ASC")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(names, vec!["Nobody", "Clark", "Bruce", "Diana", "Peter"]);
+
+    let ages: Vec<Option<i64>> = graph
+        .prepare("MATCH (p:PERSON) RETURN p.age ORDER BY p.age DESCENDING")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(ages, vec![None, Some(42), Some(35), Some(35), Some(21)]);
+}
+
+#[test]
+fn return_skip_and_limit() {
+    let graph = Graph::open_anon().unwrap();
+    create_people(&graph);
+
+    let stmt = graph
+        .prepare("MATCH (p:PERSON) RETURN p.name ORDER BY p.name SKIP $skip LIMIT $limit")
+        .unwrap();
+    let page = |skip: i64, limit: i64| -> Vec<String> {
+        stmt.query_map(
+            &mut graph.txn().unwrap(),
+            (("skip", skip), ("limit", limit)),
+            |m| m.get(0),
+        )
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap()
+    };
+
+    assert_eq!(page(0, 2), vec!["Bruce", "Clark"]);
+    assert_eq!(page(2, 2), vec!["Diana", "Nobody"]);
+    assert_eq!(page(4, 2), vec!["Peter"]);
+    assert!(page(10, 2).is_empty());
+    assert!(page(0, 0).is_empty());
+
+    let count = graph
+        .prepare("MATCH (p:PERSON) RETURN ID(p) LIMIT 3")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get::<u64, _>(0))
+        .unwrap()
+        .count();
+    assert_eq!(count, 3);
+
+    let mut txn = graph.txn().unwrap();
+    let stmt = graph.prepare("RETURN 1 LIMIT $limit").unwrap();
+    assert_err!(
+        stmt.query_map(&mut txn, ("limit", -1), |m| m.get::<i64, _>(0))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>(),
+        Error::TypeMismatch
+    );
+    assert_err!(
+        graph.prepare("MATCH (p) RETURN p.name LIMIT p.age"),
+        Error::UnknownIdentifier(_)
+    );
+}
+
+#[test]
+fn limit_without_order_by_stops_matching() {
+    let mut graph = Graph::open_anon().unwrap();
+    create_people(&graph);
+    let calls = Arc::new(AtomicUsize::new(0));
+    let seen = calls.clone();
+    graph.create_function("seen", 1, move |_| {
+        seen.fetch_add(1, Ordering::SeqCst);
+        Ok(Property::Boolean(true))
+    });
+
+    let names = |query: &str| -> Vec<Property> {
+        calls.store(0, Ordering::SeqCst);
+        graph
+            .prepare(query)
+            .unwrap()
+            .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    };
+
+    assert_eq!(
+        names("MATCH (p:PERSON) WHERE seen(p) RETURN p.name LIMIT 2").len(),
+        2
+    );
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    assert_eq!(
+        names("MATCH (p:PERSON) WHERE seen(p) RETURN p.name SKIP 1 LIMIT 2").len(),
+        2
+    );
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+    assert!(names("MATCH (p:PERSON) WHERE seen(p) RETURN p.name LIMIT 0").is_empty());
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+    assert_eq!(
+        names("MATCH (p:PERSON) WHERE seen(p) RETURN p.name SKIP 3").len(),
+        2
+    );
+    assert_eq!(calls.load(Ordering::SeqCst), 5);
+    assert_eq!(
+        names("MATCH (p:PERSON) WHERE seen(p) RETURN DISTINCT p.age SKIP 1 LIMIT 2").len(),
+        2
+    );
+    assert_eq!(
+        names("RETURN 'a' AS x UNION ALL RETURN 'b' AS x UNION ALL RETURN 'c' AS x LIMIT 1"),
+        vec![
+            Property::Text("a".into()),
+            Property::Text("b".into()),
+            Property::Text("c".into()),
+        ]
+    );
+}
+
+#[test]
+fn limit_does_not_stop_updates() {
+    let graph = Graph::open_anon().unwrap();
+    create_people(&graph);
+
+    let mut txn = graph.mut_txn().unwrap();
+    let names: Vec<String> = graph
+        .prepare("MATCH (p:PERSON) SET p.seen = true RETURN p.name LIMIT 1")
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(names.len(), 1);
+    let count: i64 = graph
+        .prepare("MATCH (p:PERSON { seen: true }) RETURN count(p)")
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(count, 5);
+}
+
+#[test]
+fn limit_does_not_stop_updates_in_earlier_parts() {
+    for (limit, rows) in [(1, 1), (0, 0)] {
+        let graph = Graph::open_anon().unwrap();
+        let mut txn = graph.mut_txn().unwrap();
+        let values: Vec<i64> = graph
+            .prepare("UNWIND [1, 2, 3] AS x CREATE (:N { x: x }) WITH x RETURN x LIMIT $limit")
+            .unwrap()
+            .query_map(&mut txn, ("limit", limit), |m| m.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(values.len(), rows);
+        txn.commit().unwrap();
+
+        let count: i64 = graph
+            .prepare("MATCH (n:N) RETURN count(n)")
+            .unwrap()
+            .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(count, 3);
+    }
+}
+
+#[test]
+fn order_by_observes_updates() {
+    let graph = Graph::open_anon().unwrap();
+    create_people(&graph);
+
+    let mut txn = graph.mut_txn().unwrap();
+    let names: Vec<String> = graph
+        .prepare("MATCH (p:PERSON) SET p.rank = p.name RETURN p.name ORDER BY p.rank DESC LIMIT 2")
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(names, vec!["Peter", "Nobody"]);
+}
+
+#[test]
+fn return_aliases() {
+    let graph = Graph::open_anon().unwrap();
+    create_people(&graph);
+
+    let stmt = graph
+        .prepare(
+            "
+            MATCH (p:PERSON)
+            WHERE p.age > 30
+            RETURN p.name AS name, p.age AS age, ID(p)
+            ORDER BY age DESC, name
+            ",
+        )
+        .unwrap();
+    assert_eq!(stmt.column_names(), vec!["name", "age", "ID(p)"]);
+
+    let rows: Vec<(String, i64)> = stmt
+        .query_map(&mut graph.txn().unwrap(), (), |m| {
+            Ok((m.get_by_name("name")?, m.get_by_name("age")?))
+        })
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            ("Clark".into(), 42),
+            ("Bruce".into(), 35),
+            ("Diana".into(), 35)
+        ]
+    );
+
+    // aliases may be used to sort by aggregates
+    let counts: Vec<(i64, i64)> = graph
+        .prepare(
+            "MATCH (p:PERSON) RETURN p.age AS age, count(*) AS people ORDER BY people DESC, age",
+        )
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| {
+            Ok((m.get_by_name("people")?, m.get(0)?))
+        })
+        .unwrap()
+        .take(2)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(counts, vec![(2, 35), (1, 21)]);
+}
+
+#[test]
+fn return_column_names() {
+    let graph = Graph::open_anon().unwrap();
+
+    let stmt = graph
+        .prepare("MATCH (a) -[e]-> (b) RETURN a.name, count(*), e.since + 1 AS next")
+        .unwrap();
+    assert_eq!(stmt.column_names(), vec!["a.name", "count(*)", "next"]);
+
+    let stmt = graph
+        .prepare("MATCH (a) WITH a.name AS name RETURN name AS who")
+        .unwrap();
+    assert_eq!(stmt.column_names(), vec!["who"]);
+
+    let stmt = graph.prepare("CREATE (a:PERSON)").unwrap();
+    assert!(stmt.column_names().is_empty());
+
+    let mut txn = graph.txn().unwrap();
+    let stmt = graph.prepare("RETURN 42 AS answer").unwrap();
+    let mut query = stmt.query(&mut txn, ()).unwrap();
+    let m = query.step().unwrap().unwrap();
+    assert_eq!(m.get_by_name::<i64, _>("answer").unwrap(), 42);
+    assert_err!(m.get_by_name::<i64, _>("42"), Error::UnknownIdentifier(_));
+
+    assert_err!(
+        graph.prepare("MATCH (a) RETURN a.name AS x, a.age AS x"),
+        Error::IdentifierExists(_)
+    );
+}
@@ -1,4 +1,8 @@
-use cqlite::Graph;
+use cqlite::{Error, Graph, Property};
+use std::collections::BTreeMap;
+
+#[macro_use]
+mod common;
 
 #[test]
 fn set_once() {
@@ -125,3 +129,150 @@ fn delete_property() {
         .unwrap();
     assert_eq!(names, vec![None]);
 }
+
+fn properties(graph: &Graph) -> Vec<(Option<String>, Option<i64>, Option<bool>)> {
+    graph
+        .prepare("MATCH (n) RETURN n.name, n.age, n.admin")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| {
+            Ok((m.get(0)?, m.get(1)?, m.get(2)?))
+        })
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap()
+}
+
+#[test]
+fn set_properties_from_map() {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare("CREATE (n:PERSON { name: 'Alice', age: 42 })")
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+
+    let mut update = BTreeMap::new();
+    update.insert("age".to_string(), Property::Integer(43));
+    update.insert("admin".to_string(), Property::Boolean(true));
+    let mut txn = graph.mut_txn().unwrap();
+    let rows = graph
+        .prepare("MATCH (n) SET n += $update RETURN n.name, n.age, n.admin")
+        .unwrap()
+        .query_map(&mut txn, ("update", update), |m| {
+            Ok((m.get(0)?, m.get(1)?, m.get(2)?))
+        })
+        .unwrap()
+        .collect::<Result<Vec<(String, i64, bool)>, _>>()
+        .unwrap();
+    assert_eq!(rows, vec![("Alice".into(), 43, true)]);
+    txn.commit().unwrap();
+    assert_eq!(
+        properties(&graph),
+        vec![(Some("Alice".into()), Some(43), Some(true))]
+    );
+
+    // NULL entries remove properties
+    let mut update = BTreeMap::new();
+    update.insert("admin".to_string(), Property::Null);
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare("MATCH (n) SET n += $update")
+        .unwrap()
+        .execute(&mut txn, ("update", update))
+        .unwrap();
+    txn.commit().unwrap();
+    assert_eq!(
+        properties(&graph),
+        vec![(Some("Alice".into()), Some(43), None)]
+    );
+}
+
+#[test]
+fn replace_properties_from_map() {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare("CREATE (a:PERSON { name: 'Alice', age: 42 }) CREATE (a) -[:KNOWS { since: 2020 }]-> (a)")
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+
+    let mut replace = BTreeMap::new();
+    replace.insert("admin".to_string(), Property::Boolean(false));
+    let mut txn = graph.mut_txn().unwrap();
+    let rows = graph
+        .prepare("MATCH (n) -[e]-> (n) SET n = $replace SET e = $replace RETURN n.name, n.admin, e.since, e.admin")
+        .unwrap()
+        .query_map(&mut txn, ("replace", replace), |m| {
+            Ok((m.get(0)?, m.get(1)?, m.get(2)?, m.get(3)?))
+        })
+        .unwrap()
+        .collect::<Result<Vec<(Option<String>, bool, Option<i64>, bool)>, _>>()
+        .unwrap();
+    assert_eq!(rows, vec![(None, false, None, false)]);
+    txn.commit().unwrap();
+    assert_eq!(properties(&graph), vec![(None, None, Some(false))]);
+
+    // a map set after a single property overrides it
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare("MATCH (n) SET n.name = 'Alice' SET n = $replace SET n.age = 7")
+        .unwrap()
+        .execute(&mut txn, ("replace", BTreeMap::new()))
+        .unwrap();
+    txn.commit().unwrap();
+    assert_eq!(properties(&graph), vec![(None, Some(7), None)]);
+}
+
+#[test]
+fn remove_property() {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare("CREATE (n:PERSON { name: 'Alice', age: 42, admin: true })")
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+
+    let mut txn = graph.mut_txn().unwrap();
+    let rows = graph
+        .prepare("MATCH (n) REMOVE n.age REMOVE n.admin RETURN n.name, n.age")
+        .unwrap()
+        .query_map(&mut txn, (), |m| Ok((m.get(0)?, m.get(1)?)))
+        .unwrap()
+        .collect::<Result<Vec<(String, Option<i64>)>, _>>()
+        .unwrap();
+    assert_eq!(rows, vec![("Alice".into(), None)]);
+    txn.commit().unwrap();
+    assert_eq!(properties(&graph), vec![(Some("Alice".into()), None, None)]);
+}
+
+#[test]
+fn set_properties_errors() {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare("CREATE (n:PERSON)")
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    assert_err!(
+        graph
+            .prepare("MATCH (n) SET n += $update")
+            .unwrap()
+            .execute(&mut txn, ("update", 42)),
+        Error::TypeMismatch
+    );
+    assert_err!(
+        graph.prepare("MATCH (n) REMOVE m.name"),
+        Error::UnknownIdentifier(_)
+    );
+    assert_err!(
+        graph.prepare("MATCH p = (n) -[*]-> (m) SET p = $update"),
+        Error::IdentifierIsNotNode(_)
+    );
+}
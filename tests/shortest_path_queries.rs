@@ -0,0 +1,256 @@
+use cqlite::{Graph, Path};
+
+fn create_test_graph() -> Graph {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare(
+            "
+            CREATE (a:STOP { name: 'A' })
+            CREATE (b:STOP { name: 'B' })
+            CREATE (c:STOP { name: 'C' })
+            CREATE (d:STOP { name: 'D' })
+            CREATE (e:STOP { name: 'E' })
+            CREATE (f:STOP { name: 'F' })
+
+            CREATE (a) -[:ROAD { toll: FALSE }]-> (b)
+            CREATE (b) -[:ROAD { toll: FALSE }]-> (c)
+            CREATE (c) -[:ROAD { toll: FALSE }]-> (d)
+            CREATE (a) -[:ROAD { toll: TRUE }]-> (e)
+            CREATE (e) -[:ROAD { toll: TRUE }]-> (d)
+            CREATE (a) -[:RAIL]-> (f)
+            CREATE (f) -[:RAIL]-> (d)
+            ",
+        )
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+    graph
+}
+
+fn route(graph: &Graph, query: &str) -> Vec<Vec<String>> {
+    let mut txn = graph.txn().unwrap();
+    let mut routes: Vec<Vec<String>> = graph
+        .prepare(query)
+        .unwrap()
+        .query_map(&mut txn, (), |m| {
+            let path: Path = m.get(0)?;
+            let mut names = vec![];
+            for id in path.nodes {
+                let name: String = graph
+                    .prepare("MATCH (n) WHERE ID(n) = $id RETURN n.name")?
+                    .query_map(&mut graph.txn()?, ("id", id), |m| m.get(0))?
+                    .next()
+                    .unwrap()?;
+                names.push(name);
+            }
+            Ok(names)
+        })
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    routes.sort();
+    routes
+}
+
+#[test]
+fn shortest_path_between_bound_nodes() {
+    let graph = create_test_graph();
+    assert_eq!(
+        route(
+            &graph,
+            "
+            MATCH (a { name: 'A' })
+            MATCH (d { name: 'D' })
+            MATCH p = shortestPath((a) -[:ROAD*]-> (d))
+            RETURN p
+            "
+        )
+        .len(),
+        1
+    );
+    assert_eq!(
+        route(
+            &graph,
+            "
+            MATCH (a { name: 'A' })
+            MATCH (d { name: 'D' })
+            MATCH p = allShortestPaths((a) -[:ROAD*]-> (d))
+            RETURN p
+            "
+        ),
+        vec![vec!["A", "E", "D"]]
+    );
+    assert_eq!(
+        route(
+            &graph,
+            "
+            MATCH (a { name: 'A' })
+            MATCH (d { name: 'D' })
+            MATCH p = allShortestPaths((a) -[*]-> (d))
+            RETURN p
+            "
+        ),
+        vec![vec!["A", "E", "D"], vec!["A", "F", "D"]]
+    );
+}
+
+#[test]
+fn shortest_path_respects_edge_constraints() {
+    let graph = create_test_graph();
+    assert_eq!(
+        route(
+            &graph,
+            "
+            MATCH (a { name: 'A' })
+            MATCH (d { name: 'D' })
+            MATCH p = allShortestPaths((a) -[:ROAD* { toll: FALSE }]-> (d))
+            RETURN p
+            "
+        ),
+        vec![vec!["A", "B", "C", "D"]]
+    );
+    assert_eq!(
+        route(
+            &graph,
+            "
+            MATCH (a { name: 'A' })
+            MATCH (d { name: 'D' })
+            MATCH p = allShortestPaths((a) -[:ROAD*..2 { toll: FALSE }]-> (d))
+            RETURN p
+            "
+        ),
+        Vec::<Vec<String>>::new()
+    );
+    assert_eq!(
+        route(
+            &graph,
+            "
+            MATCH (a { name: 'A' })
+            MATCH (d { name: 'D' })
+            MATCH p = allShortestPaths((d) <-[:ROAD*]- (a))
+            RETURN p
+            "
+        ),
+        vec![vec!["D", "E", "A"]]
+    );
+}
+
+#[test]
+fn shortest_path_to_every_node() {
+    let graph = create_test_graph();
+
+    let mut txn = graph.txn().unwrap();
+    let rows: Vec<(String, i64)> = graph
+        .prepare(
+            "
+            MATCH (a { name: 'B' })
+            MATCH p = shortestPath((a) -[*]- (b:STOP))
+            RETURN b.name, length(p)
+            ORDER BY b.name
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| Ok((m.get(0)?, m.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            ("A".into(), 1),
+            ("C".into(), 1),
+            ("D".into(), 2),
+            ("E".into(), 2),
+            ("F".into(), 2),
+        ]
+    );
+
+    let zero: Vec<i64> = graph
+        .prepare("MATCH p = shortestPath((a { name: 'B' }) -[*0..]- (a)) RETURN length(p)")
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(zero, vec![0]);
+}
+
+#[test]
+fn all_shortest_paths_to_every_node() {
+    let graph = create_test_graph();
+    assert_eq!(
+        route(
+            &graph,
+            "MATCH p = allShortestPaths((a { name: 'A' }) -[:ROAD*]-> (b)) RETURN p"
+        ),
+        vec![
+            vec!["A", "B"],
+            vec!["A", "B", "C"],
+            vec!["A", "E"],
+            vec!["A", "E", "D"],
+        ]
+    );
+
+    let mut txn = graph.txn().unwrap();
+    let rows: Vec<(String, Option<String>)> = graph
+        .prepare(
+            "
+            MATCH (s:STOP)
+            OPTIONAL MATCH p = shortestPath((s) -[:RAIL*]-> (t))
+            RETURN s.name, t.name
+            ORDER BY s.name, t.name
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| Ok((m.get(0)?, m.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            ("A".into(), Some("D".into())),
+            ("A".into(), Some("F".into())),
+            ("B".into(), None),
+            ("C".into(), None),
+            ("D".into(), None),
+            ("E".into(), None),
+            ("F".into(), Some("D".into())),
+        ]
+    );
+}
+
+#[test]
+fn optional_shortest_path() {
+    let graph = create_test_graph();
+
+    let mut txn = graph.txn().unwrap();
+    let rows: Vec<(String, Option<i64>)> = graph
+        .prepare(
+            "
+            MATCH (d { name: 'D' })
+            MATCH (s:STOP)
+            OPTIONAL MATCH p = shortestPath((s) -[:RAIL*]-> (d))
+            RETURN s.name, length(p)
+            ORDER BY s.name
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| Ok((m.get(0)?, m.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            ("A".into(), Some(2)),
+            ("B".into(), None),
+            ("C".into(), None),
+            ("D".into(), None),
+            ("E".into(), None),
+            ("F".into(), Some(1)),
+        ]
+    );
+}
@@ -0,0 +1,72 @@
+use cqlite::Graph;
+use std::path::PathBuf;
+
+/// Copy the fixture, which was written before nodes
+/// could have multiple labels, to a scratch location.
+fn baseline_graph(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("cqlite-{}-{}.graph", name, std::process::id()));
+    std::fs::copy("tests/fixtures/baseline.graph", &path).unwrap();
+    path
+}
+
+fn names(graph: &Graph, query: &str) -> Vec<String> {
+    let mut names: Vec<String> = graph
+        .prepare(query)
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    names.sort();
+    names
+}
+
+#[test]
+fn open_baseline_format() {
+    let path = baseline_graph("open");
+    let graph = Graph::open(&path).unwrap();
+
+    assert_eq!(
+        names(&graph, "MATCH (n:PERSON) RETURN n.name"),
+        vec!["Paul", "Peter"]
+    );
+    assert_eq!(
+        names(
+            &graph,
+            "MATCH (n:CITY) WHERE size(labels(n)) = 1 RETURN n.name"
+        ),
+        vec!["Berlin"]
+    );
+    assert_eq!(
+        names(
+            &graph,
+            "MATCH (a) -[e:KNOWS]-> (b) WHERE e.since = 2020 AND a.age = 42 RETURN b.name"
+        ),
+        vec!["Paul"]
+    );
+    assert_eq!(
+        names(&graph, "MATCH (:PERSON) -[:LIVES_IN]-> (c) RETURN c.name"),
+        vec!["Berlin"]
+    );
+
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare("MATCH (n { name: 'Peter' }) SET n:ADMIN")
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+    drop(graph);
+
+    let graph = Graph::open(&path).unwrap();
+    assert_eq!(
+        names(&graph, "MATCH (n:PERSON:ADMIN) RETURN n.name"),
+        vec!["Peter"]
+    );
+    assert_eq!(
+        names(&graph, "MATCH (n:PERSON) RETURN n.name"),
+        vec!["Paul", "Peter"]
+    );
+    drop(graph);
+    std::fs::remove_file(path).unwrap();
+}
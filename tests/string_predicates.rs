@@ -0,0 +1,148 @@
+use cqlite::{Error, Graph};
+
+#[macro_use]
+mod common;
+
+fn names(graph: &Graph, condition: &str) -> Vec<String> {
+    graph
+        .prepare(&format!(
+            "MATCH (n:PERSON) WHERE {} RETURN n.name ORDER BY n.name",
+            condition
+        ))
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<Vec<String>, _>>()
+        .unwrap()
+}
+
+fn people() -> Graph {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    for (name, age) in [("Alice", 42), ("Alfred", 23), ("Bob", 31), ("Malice", 12)] {
+        graph
+            .prepare("CREATE (:PERSON { name: $name, age: $age })")
+            .unwrap()
+            .execute(&mut txn, (("name", name), ("age", age)))
+            .unwrap();
+    }
+    graph
+        .prepare("CREATE (:PERSON { age: 7 })")
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+    graph
+}
+
+#[test]
+fn starts_ends_with_and_contains() {
+    let graph = people();
+    assert_eq!(
+        names(&graph, "n.name STARTS WITH 'Al'"),
+        vec!["Alfred", "Alice"]
+    );
+    assert_eq!(
+        names(&graph, "n.name ENDS WITH 'ice'"),
+        vec!["Alice", "Malice"]
+    );
+    assert_eq!(
+        names(&graph, "n.name CONTAINS 'li'"),
+        vec!["Alice", "Malice"]
+    );
+    assert_eq!(
+        names(&graph, "n.name starts with ''"),
+        vec!["Alfred", "Alice", "Bob", "Malice"]
+    );
+    assert_eq!(
+        names(&graph, "n.age > 10 AND NOT n.name STARTS WITH 'Al'"),
+        vec!["Bob", "Malice"]
+    );
+    assert_eq!(
+        names(&graph, "n.name STARTS WITH 'A' AND n.name ENDS WITH 'd'"),
+        vec!["Alfred"]
+    );
+}
+
+#[test]
+fn string_predicates_with_parameters() {
+    let graph = people();
+    let names = graph
+        .prepare("MATCH (n:PERSON) WHERE n.name STARTS WITH $prefix RETURN n.name")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), ("prefix", "Bo"), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<Vec<String>, _>>()
+        .unwrap();
+    assert_eq!(names, vec!["Bob"]);
+}
+
+#[test]
+fn string_predicates_on_other_types() {
+    let graph = people();
+    assert!(names(&graph, "n.age STARTS WITH '4'").is_empty());
+    assert!(names(&graph, "n.name CONTAINS 1").is_empty());
+    assert!(names(&graph, "n.name ENDS WITH NULL").is_empty());
+    assert!(names(&graph, "n.age =~ '.*'").is_empty());
+}
+
+#[test]
+fn regex_matches() {
+    let graph = people();
+    assert_eq!(names(&graph, "n.name =~ 'Al.*'"), vec!["Alfred", "Alice"]);
+    // the whole text has to match
+    assert_eq!(names(&graph, "n.name =~ 'lice'"), Vec::<String>::new());
+    assert_eq!(names(&graph, "n.name =~ '.*lice'"), vec!["Alice", "Malice"]);
+    assert_eq!(names(&graph, "n.name =~ '(?i)bob'"), vec!["Bob"]);
+    assert_eq!(
+        names(&graph, "n.age > 10 AND NOT n.name =~ '[A-L].*'"),
+        vec!["Malice"]
+    );
+}
+
+#[test]
+fn regex_invalid_pattern() {
+    let graph = people();
+    assert_err!(
+        graph
+            .prepare("MATCH (n:PERSON) WHERE n.name =~ '(' RETURN n.name")
+            .unwrap()
+            .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+            .unwrap()
+            .collect::<Result<Vec<String>, _>>(),
+        Error::InvalidRegex(_)
+    );
+    assert_err!(
+        graph
+            .prepare("RETURN CASE WHEN 'a' =~ '[a-' THEN 1 ELSE 0 END")
+            .unwrap()
+            .query_map(&mut graph.txn().unwrap(), (), |m| m.get::<i64, _>(0))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>(),
+        Error::InvalidRegex(_)
+    );
+}
+
+#[test]
+fn regex_patterns_from_data() {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    let counts = (0..200).map(|i| i.to_string()).collect::<Vec<_>>();
+    graph
+        .prepare(&format!(
+            "UNWIND [{}] AS i CREATE (:PATTERN {{ re: 'x{{' + i + '}}' }})",
+            counts.join(", ")
+        ))
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    let count: i64 = graph
+        .prepare("MATCH (p:PATTERN) WHERE 'xxx' =~ p.re RETURN count(*)")
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(count, 1);
+}
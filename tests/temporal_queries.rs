@@ -0,0 +1,275 @@
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, Utc};
+use cqlite::{Error, Graph, Property};
+
+#[macro_use]
+mod common;
+
+fn date(text: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+}
+
+fn datetime(text: &str) -> DateTime<FixedOffset> {
+    DateTime::parse_from_rfc3339(text).unwrap()
+}
+
+#[test]
+fn construct_temporal_values() {
+    let graph = Graph::open_anon().unwrap();
+    let rows = graph
+        .prepare(
+            "
+            RETURN
+                date('2024-02-29'),
+                date({ year: 2024, month: 3 }),
+                date(datetime('2024-01-31T23:30:00-02:00')),
+                datetime('2024-01-31T12:30:00+02:00'),
+                datetime('2024-01-31T12:30:00'),
+                datetime({ epochMillis: 1706704200000 }),
+                duration('P1DT2H30M'),
+                duration({ weeks: 1, seconds: 30 }),
+                duration('-PT1.5S')
+            ",
+        )
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| {
+            Ok((
+                (m.get(0)?, m.get(1)?, m.get(2)?),
+                (m.get(3)?, m.get(4)?, m.get(5)?),
+                (m.get(6)?, m.get(7)?, m.get(8)?),
+            ))
+        })
+        .unwrap()
+        .collect::<Result<
+            Vec<(
+                (NaiveDate, NaiveDate, NaiveDate),
+                (
+                    DateTime<FixedOffset>,
+                    DateTime<FixedOffset>,
+                    DateTime<FixedOffset>,
+                ),
+                (Duration, Duration, Duration),
+            )>,
+            _,
+        >>()
+        .unwrap();
+    assert_eq!(
+        rows,
+        vec![(
+            (date("2024-02-29"), date("2024-03-01"), date("2024-01-31")),
+            (
+                datetime("2024-01-31T12:30:00+02:00"),
+                datetime("2024-01-31T12:30:00Z"),
+                datetime("2024-01-31T12:30:00Z"),
+            ),
+            (
+                Duration::hours(26) + Duration::minutes(30),
+                Duration::weeks(1) + Duration::seconds(30),
+                -Duration::milliseconds(1500),
+            ),
+        )]
+    );
+
+    // the UTC offset is kept
+    let offset = graph
+        .prepare("RETURN datetime('2024-01-31T12:30:00+02:00')")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| {
+            m.get::<DateTime<FixedOffset>, _>(0)
+        })
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .offset()
+        .local_minus_utc();
+    assert_eq!(offset, 2 * 3600);
+
+    let now: DateTime<FixedOffset> = graph
+        .prepare("RETURN datetime()")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert!((Utc::now().signed_duration_since(now)).num_seconds().abs() < 60);
+}
+
+#[test]
+fn store_and_compare_temporal_values() {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    let stmt = graph
+        .prepare("CREATE (e:EVENT { name: $name, at: datetime($at), day: date($at) })")
+        .unwrap();
+    for (name, at) in [
+        ("launch", "2024-01-31T09:00:00+01:00"),
+        ("review", "2024-02-01T12:00:00Z"),
+        ("retro", "2024-02-05T16:00:00-05:00"),
+    ] {
+        stmt.execute(&mut txn, (("name", name), ("at", at)))
+            .unwrap();
+    }
+    txn.commit().unwrap();
+
+    let names = |query: &str, params: (&str, Property)| {
+        graph
+            .prepare(query)
+            .unwrap()
+            .query_map(&mut graph.txn().unwrap(), params, |m| m.get(0))
+            .unwrap()
+            .collect::<Result<Vec<String>, _>>()
+            .unwrap()
+    };
+
+    assert_eq!(
+        names(
+            "MATCH (e:EVENT) WHERE e.at > $since RETURN e.name ORDER BY e.at",
+            ("since", datetime("2024-02-01T10:00:00-01:00").into()),
+        ),
+        vec!["review", "retro"]
+    );
+    assert_eq!(
+        names(
+            "MATCH (e:EVENT) WHERE e.day < date($day) RETURN e.name",
+            ("day", "2024-02-01".into()),
+        ),
+        vec!["launch"]
+    );
+    assert_eq!(
+        names(
+            "MATCH (e:EVENT) RETURN e.name ORDER BY e.at DESC",
+            ("unused", Property::Null),
+        ),
+        vec!["retro", "review", "launch"]
+    );
+    // datetimes are equal if they refer to the same instant
+    assert_eq!(
+        names(
+            "MATCH (e:EVENT) WHERE e.at = datetime('2024-01-31T08:00:00Z') RETURN e.name",
+            ("unused", Property::Null),
+        ),
+        vec!["launch"]
+    );
+}
+
+#[test]
+fn temporal_arithmetic() {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare(
+            "
+            CREATE (e:EVENT { start: $start, end: datetime('2024-02-01T01:30:00+01:00') })
+            ",
+        )
+        .unwrap()
+        .execute(&mut txn, ("start", datetime("2024-01-31T22:00:00+01:00")))
+        .unwrap();
+    graph
+        .prepare("MATCH (e:EVENT) SET e.length = e.end - e.start")
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+
+    let rows = graph
+        .prepare(
+            "
+            MATCH (e:EVENT)
+            RETURN
+                e.length,
+                e.start + duration({ hours: 1 }),
+                duration('PT1H') + e.start,
+                e.end - duration('P1D'),
+                date(e.start) + duration('P1DT12H'),
+                date('2024-03-01') - date('2024-02-01'),
+                e.length - duration('PT30M') + duration('PT1M')
+            ",
+        )
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| {
+            Ok((
+                m.get(0)?,
+                m.get(1)?,
+                m.get(2)?,
+                m.get(3)?,
+                m.get(4)?,
+                m.get(5)?,
+                m.get(6)?,
+            ))
+        })
+        .unwrap()
+        .collect::<Result<
+            Vec<(
+                Duration,
+                DateTime<FixedOffset>,
+                DateTime<FixedOffset>,
+                DateTime<FixedOffset>,
+                NaiveDate,
+                Duration,
+                Duration,
+            )>,
+            _,
+        >>()
+        .unwrap();
+    assert_eq!(
+        rows,
+        vec![(
+            Duration::minutes(210),
+            datetime("2024-01-31T23:00:00+01:00"),
+            datetime("2024-01-31T23:00:00+01:00"),
+            datetime("2024-01-31T01:30:00+01:00"),
+            date("2024-02-01"),
+            Duration::days(29),
+            Duration::minutes(181),
+        )]
+    );
+
+    let events: i64 = graph
+        .prepare(
+            "
+            MATCH (e:EVENT)
+            WHERE e.end - e.start > duration('PT3H')
+            AND e.start + e.length = e.end
+            RETURN count(e)
+            ",
+        )
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(events, 1);
+}
+
+#[test]
+fn temporal_errors() {
+    let graph = Graph::open_anon().unwrap();
+    let run = |query: &str| {
+        graph
+            .prepare(query)
+            .unwrap()
+            .query_map(&mut graph.txn().unwrap(), (), |m| m.get::<Property, _>(0))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+    };
+
+    assert_err!(run("RETURN date('31.01.2024')"), Error::TypeMismatch);
+    assert_err!(
+        run("RETURN date({ year: 2024, month: 13 })"),
+        Error::TypeMismatch
+    );
+    assert_err!(run("RETURN duration('P1M')"), Error::TypeMismatch);
+    assert_err!(run("RETURN duration()"), Error::TypeMismatch);
+    assert_err!(
+        run("RETURN datetime() + date('2024-01-01')"),
+        Error::TypeMismatch
+    );
+    assert_eq!(run("RETURN date(NULL)").unwrap(), vec![Property::Null]);
+    assert_eq!(
+        run("RETURN datetime() - NULL").unwrap(),
+        vec![Property::Null]
+    );
+}
@@ -0,0 +1,176 @@
+use cqlite::{Error, Graph};
+
+#[macro_use]
+mod common;
+
+fn create_test_graph() -> Graph {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare(
+            "
+            CREATE (:PERSON { name: 'Peter', tag: 'hero' })
+            CREATE (:PERSON { name: 'Clark', tag: 'hero' })
+            CREATE (:PERSON { name: 'Lois', tag: 'reporter' })
+            CREATE (:TAG { name: 'hero' })
+            CREATE (:TAG { name: 'Lois' })
+            ",
+        )
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+    graph
+}
+
+fn names(graph: &Graph, query: &str) -> Vec<String> {
+    graph
+        .prepare(query)
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap()
+}
+
+#[test]
+fn union_all_keeps_duplicates() {
+    let graph = create_test_graph();
+
+    let rows = names(
+        &graph,
+        "
+        MATCH (p:PERSON) RETURN p.name AS name ORDER BY name
+        UNION ALL
+        MATCH (t:TAG) RETURN t.name AS name ORDER BY name
+        ",
+    );
+    assert_eq!(rows, vec!["Clark", "Lois", "Peter", "Lois", "hero"]);
+
+    let rows = names(
+        &graph,
+        "
+        MATCH (p:PERSON) RETURN p.tag AS tag ORDER BY tag
+        UNION ALL
+        RETURN 'villain' AS tag
+        UNION ALL
+        RETURN 'hero' AS tag
+        ",
+    );
+    assert_eq!(rows, vec!["hero", "hero", "reporter", "villain", "hero"]);
+}
+
+#[test]
+fn union_removes_duplicates() {
+    let graph = create_test_graph();
+
+    let rows = names(
+        &graph,
+        "
+        MATCH (p:PERSON) RETURN p.name AS name ORDER BY name
+        UNION
+        MATCH (t:TAG) RETURN t.name AS name ORDER BY name
+        ",
+    );
+    assert_eq!(rows, vec!["Clark", "Lois", "Peter", "hero"]);
+
+    // duplicates within a single query are removed as well
+    let rows = names(
+        &graph,
+        "
+        MATCH (p:PERSON) RETURN p.tag AS tag ORDER BY tag
+        UNION
+        RETURN 'villain' AS tag
+        ",
+    );
+    assert_eq!(rows, vec!["hero", "reporter", "villain"]);
+}
+
+#[test]
+fn union_with_parameters_and_updates() {
+    let graph = create_test_graph();
+
+    let stmt = graph
+        .prepare(
+            "
+            MATCH (p:PERSON) WHERE p.name = $name RETURN p.name AS hit
+            UNION
+            MATCH (p:PERSON) WHERE p.tag = $name RETURN p.name AS hit
+            ",
+        )
+        .unwrap();
+    assert_eq!(stmt.column_names(), ["hit"]);
+    let mut hits: Vec<String> = stmt
+        .query_map(&mut graph.txn().unwrap(), ("name", "hero"), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    hits.sort();
+    assert_eq!(hits, vec!["Clark", "Peter"]);
+
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare("CREATE (:TAG { name: 'a' }) UNION CREATE (:TAG { name: 'b' })")
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+    assert_eq!(
+        names(&graph, "MATCH (t:TAG) RETURN t.name ORDER BY t.name"),
+        vec!["Lois", "a", "b", "hero"]
+    );
+}
+
+#[test]
+fn union_after_limit() {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare("CREATE (:X {n:'x1'}), (:X {n:'x2'}), (:Y {n:'y1'}), (:Y {n:'y2'})")
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+
+    let rows = names(
+        &graph,
+        "MATCH (a:X) RETURN a.n AS n LIMIT 1 UNION ALL MATCH (b:Y) RETURN b.n AS n",
+    );
+    assert_eq!(rows, vec!["x1", "y1", "y2"]);
+}
+
+#[test]
+fn union_column_names_must_match() {
+    let graph = create_test_graph();
+
+    assert_err!(
+        graph.prepare("MATCH (p:PERSON) RETURN p.name UNION MATCH (t:TAG) RETURN t.name"),
+        Error::UnionColumnMismatch
+    );
+    assert_err!(
+        graph.prepare("RETURN 1 AS a, 2 AS b UNION ALL RETURN 2 AS b, 1 AS a"),
+        Error::UnionColumnMismatch
+    );
+    assert_err!(
+        graph.prepare("RETURN 1 AS a UNION RETURN 1 AS a UNION RETURN 1 AS b"),
+        Error::UnionColumnMismatch
+    );
+}
+
+#[test]
+fn union_column_counts_must_match() {
+    let graph = create_test_graph();
+
+    assert_err!(
+        graph.prepare("RETURN 1 AS a, 2 AS b UNION RETURN 1 AS a"),
+        Error::UnionColumnMismatch
+    );
+    assert_err!(
+        graph.prepare("RETURN 1 AS a UNION ALL RETURN 1 AS a, 2 AS b"),
+        Error::UnionColumnMismatch
+    );
+    assert_err!(
+        graph.prepare("RETURN 1 AS a UNION RETURN 1 AS a UNION ALL RETURN 1 AS a"),
+        Error::Syntax { .. }
+    );
+}
@@ -0,0 +1,143 @@
+use cqlite::{Error, Graph, Property};
+
+#[macro_use]
+mod common;
+
+fn names(names: &[&str]) -> Property {
+    Property::List(
+        names
+            .iter()
+            .map(|name| Property::Text(name.to_string()))
+            .collect(),
+    )
+}
+
+#[test]
+fn unwind_creates_nodes() {
+    let graph = Graph::open_anon().unwrap();
+
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare("UNWIND $names AS name CREATE (:PERSON { name: name })")
+        .unwrap()
+        .execute(&mut txn, ("names", names(&["Peter", "Clark", "Lois"])))
+        .unwrap();
+    txn.commit().unwrap();
+
+    let mut txn = graph.txn().unwrap();
+    let people: Vec<String> = graph
+        .prepare("MATCH (p:PERSON) RETURN p.name ORDER BY p.name")
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(people, vec!["Clark", "Lois", "Peter"]);
+}
+
+#[test]
+fn unwind_keeps_names_in_scope() {
+    let graph = Graph::open_anon().unwrap();
+
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare("CREATE (:PERSON { name: 'Peter' }) CREATE (:PERSON { name: 'Clark' })")
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+
+    let mut txn = graph.txn().unwrap();
+    let rows: Vec<(String, i64)> = graph
+        .prepare(
+            "
+            MATCH (p:PERSON)
+            UNWIND [1, 2] AS x
+            RETURN p.name, x
+            ORDER BY p.name, x
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| Ok((m.get(0)?, m.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            ("Clark".into(), 1),
+            ("Clark".into(), 2),
+            ("Peter".into(), 1),
+            ("Peter".into(), 2),
+        ]
+    );
+
+    let found: Vec<String> = graph
+        .prepare(
+            "
+            UNWIND $names AS name
+            MATCH (p:PERSON) WHERE p.name = name
+            RETURN p.name
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, ("names", names(&["Peter", "Bruce"])), |m| {
+            m.get(0)
+        })
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(found, vec!["Peter"]);
+}
+
+#[test]
+fn unwind_non_lists() {
+    let graph = Graph::open_anon().unwrap();
+
+    let mut txn = graph.txn().unwrap();
+    let stmt = graph.prepare("UNWIND $list AS x RETURN x").unwrap();
+    let values: Vec<Property> = stmt
+        .query_map(&mut txn, ("list", Property::Null), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(values, vec![]);
+
+    let values: Vec<Property> = stmt
+        .query_map(&mut txn, ("list", Property::List(vec![])), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(values, vec![]);
+
+    let values: Vec<Property> = stmt
+        .query_map(&mut txn, ("list", 42), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(values, vec![Property::Integer(42)]);
+
+    let count: i64 = graph
+        .prepare("UNWIND [1, 2, 2, 3] AS x WITH DISTINCT x RETURN count(*)")
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(count, 3);
+}
+
+#[test]
+fn unwind_name_must_be_new() {
+    let graph = Graph::open_anon().unwrap();
+
+    assert_err!(
+        graph.prepare("MATCH (a:PERSON) UNWIND [1, 2] AS a RETURN a"),
+        Error::IdentifierExists(_)
+    );
+    assert_err!(
+        graph.prepare("UNWIND x AS y RETURN y"),
+        Error::UnknownIdentifier(_)
+    );
+}
@@ -0,0 +1,262 @@
+use cqlite::{Error, Graph, Property};
+
+#[macro_use]
+mod common;
+
+fn create_people(graph: &Graph) {
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare(
+            "
+            CREATE (:PERSON { name: 'Peter', city: 'New York', age: 21, weight: 1 })
+            CREATE (:PERSON { name: 'Clark', city: 'Metropolis', age: 42, weight: 1 })
+            CREATE (:PERSON { name: 'Lois', city: 'Metropolis', age: 35, weight: 3 })
+            CREATE (:PERSON { name: 'Jimmy', city: 'Metropolis', age: 20, weight: 1 })
+            CREATE (:PERSON { name: 'Bruce', city: 'Gotham' })
+            ",
+        )
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+}
+
+fn number(value: &Property) -> Result<Option<i64>, Error> {
+    match value {
+        Property::Integer(num) => Ok(Some(*num)),
+        Property::Null => Ok(None),
+        _ => Err(Error::TypeMismatch),
+    }
+}
+
+/// The value below which at least half of the total weight lies.
+fn create_weighted_median(graph: &mut Graph) {
+    graph.create_aggregate(
+        "weightedMedian",
+        2,
+        Vec::new,
+        |values: &mut Vec<(i64, i64)>, args| {
+            if let (Some(value), Some(weight)) = (number(&args[0])?, number(&args[1])?) {
+                values.push((value, weight));
+            }
+            Ok(())
+        },
+        |mut values| {
+            values.sort_unstable();
+            let total: i64 = values.iter().map(|(_, weight)| weight).sum();
+            let mut seen = 0;
+            for (value, weight) in values {
+                seen += weight;
+                if 2 * seen >= total {
+                    return Ok(Property::Integer(value));
+                }
+            }
+            Ok(Property::Null)
+        },
+    );
+}
+
+#[test]
+fn aggregate_with_multiple_arguments() {
+    let mut graph = Graph::open_anon().unwrap();
+    create_people(&graph);
+    create_weighted_median(&mut graph);
+
+    let median: i64 = graph
+        .prepare("MATCH (p:PERSON) RETURN WEIGHTEDMEDIAN(p.age, p.weight)")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(median, 35);
+}
+
+#[test]
+fn aggregate_groups() {
+    let mut graph = Graph::open_anon().unwrap();
+    create_people(&graph);
+    create_weighted_median(&mut graph);
+    graph.create_aggregate(
+        "names",
+        1,
+        String::new,
+        |names: &mut String, args| {
+            if let Property::Text(name) = &args[0] {
+                if !names.is_empty() {
+                    names.push_str(", ");
+                }
+                names.push_str(name);
+            }
+            Ok(())
+        },
+        |names| Ok(Property::Text(names)),
+    );
+
+    let rows: Vec<(String, String, Option<i64>, i64)> = graph
+        .prepare(
+            "
+            MATCH (p:PERSON)
+            RETURN p.city, names(p.name), weightedMedian(p.age, p.weight), count(*)
+            ORDER BY weightedMedian(p.age, p.weight) DESC, p.city
+            ",
+        )
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| {
+            Ok((m.get(0)?, m.get(1)?, m.get(2)?, m.get(3)?))
+        })
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            ("Gotham".into(), "Bruce".into(), None, 1),
+            (
+                "Metropolis".into(),
+                "Clark, Lois, Jimmy".into(),
+                Some(35),
+                3
+            ),
+            ("New York".into(), "Peter".into(), Some(21), 1),
+        ]
+    );
+}
+
+#[test]
+fn aggregate_without_matches() {
+    let mut graph = Graph::open_anon().unwrap();
+    graph.create_aggregate(
+        "total",
+        1,
+        || 0,
+        |total: &mut i64, args| {
+            *total += number(&args[0])?.unwrap_or(0);
+            Ok(())
+        },
+        |total| Ok(Property::Integer(total)),
+    );
+
+    let totals: Vec<i64> = graph
+        .prepare("MATCH (p:PERSON) RETURN total(p.age)")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(totals, vec![0]);
+
+    let totals: Vec<(String, i64)> = graph
+        .prepare("MATCH (p:PERSON) RETURN p.city, total(p.age)")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| {
+            Ok((m.get(0)?, m.get(1)?))
+        })
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(totals, vec![]);
+}
+
+#[test]
+fn aggregate_errors() {
+    let mut graph = Graph::open_anon().unwrap();
+    create_people(&graph);
+    create_weighted_median(&mut graph);
+    graph.create_aggregate(
+        "fails",
+        1,
+        || (),
+        |_, _| Err(Error::TypeMismatch),
+        |_| Ok(Property::Null),
+    );
+
+    assert_err!(
+        graph.prepare("MATCH (p:PERSON) RETURN weightedMedian(p.age)"),
+        Error::TypeMismatch
+    );
+    assert_err!(
+        graph
+            .prepare("MATCH (p:PERSON) RETURN fails(p.age)")
+            .unwrap()
+            .query_map(&mut graph.txn().unwrap(), (), |m| m.get::<Property, _>(0))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>(),
+        Error::TypeMismatch
+    );
+
+    // aggregates replace scalar functions of the same name
+    graph.create_function("fails", 1, |_| Ok(Property::Null));
+    graph.create_aggregate(
+        "fails",
+        1,
+        || 0,
+        |count: &mut i64, _| {
+            *count += 1;
+            Ok(())
+        },
+        |count| Ok(Property::Integer(count)),
+    );
+    let count: i64 = graph
+        .prepare("MATCH (p:PERSON) RETURN fails(p.age)")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(count, 5);
+}
+
+#[test]
+fn aggregate_outside_of_returned_values() {
+    let mut graph = Graph::open_anon().unwrap();
+    create_people(&graph);
+    create_weighted_median(&mut graph);
+
+    assert_err!(
+        graph.prepare("MATCH (p:PERSON) WITH weightedMedian(p.age, p.weight) AS m RETURN m"),
+        Error::MisplacedAggregate(_)
+    );
+    assert_err!(
+        graph.prepare(
+            "MATCH (p:PERSON) WITH p WHERE weightedMedian(p.age, p.weight) > 1 RETURN p.name"
+        ),
+        Error::MisplacedAggregate(_)
+    );
+    assert_err!(
+        graph.prepare("MATCH (p:PERSON) RETURN weightedMedian(p.age, p.weight) + 1"),
+        Error::MisplacedAggregate(_)
+    );
+    assert_err!(
+        graph.prepare("MATCH (p:PERSON) CREATE (:MEDIAN { age: weightedMedian(p.age, p.weight) })"),
+        Error::MisplacedAggregate(_)
+    );
+    assert_err!(
+        graph.prepare(
+            "MATCH (p:PERSON) RETURN weightedMedian(weightedMedian(p.age, p.weight), p.weight)"
+        ),
+        Error::MisplacedAggregate(_)
+    );
+
+    let rows: Vec<(String, i64)> = graph
+        .prepare(
+            "
+            MATCH (p:PERSON) WHERE p.city <> 'Gotham'
+            RETURN p.city, weightedMedian(p.age, p.weight)
+            ORDER BY weightedMedian(p.age, p.weight)
+            ",
+        )
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| {
+            Ok((m.get(0)?, m.get(1)?))
+        })
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        rows,
+        vec![("New York".into(), 21), ("Metropolis".into(), 35)]
+    );
+}
@@ -0,0 +1,141 @@
+use cqlite::{Error, Graph, Property};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+
+#[macro_use]
+mod common;
+
+fn graph_with_people() -> Graph {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    for (name, age) in [("Alice", 42), ("Bob", 17), ("Carol", 30)] {
+        graph
+            .prepare("CREATE (:PERSON { name: $name, age: $age })")
+            .unwrap()
+            .execute(&mut txn, (("name", name), ("age", age)))
+            .unwrap();
+    }
+    txn.commit().unwrap();
+    graph
+}
+
+fn greet(args: &[Property]) -> Result<Property, Error> {
+    match &args[0] {
+        Property::Text(name) => Ok(Property::Text(format!("Hello {}!", name))),
+        Property::Null => Ok(Property::Null),
+        _ => Err(Error::TypeMismatch),
+    }
+}
+
+#[test]
+fn call_user_function() {
+    let mut graph = graph_with_people();
+    graph.create_function("greet", 1, greet);
+    graph.create_function("score", 2, |args| match (&args[0], &args[1]) {
+        (Property::Integer(age), Property::Integer(weight)) => Ok(Property::Integer(age * weight)),
+        _ => Err(Error::TypeMismatch),
+    });
+
+    let rows = graph
+        .prepare(
+            "
+            MATCH (p:PERSON)
+            WHERE score(p.age, $weight) > 50
+            RETURN GREET(p.name), score(p.age, $weight)
+            ORDER BY p.name
+            ",
+        )
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), ("weight", 2), |m| {
+            Ok((m.get(0)?, m.get(1)?))
+        })
+        .unwrap()
+        .collect::<Result<Vec<(String, i64)>, _>>()
+        .unwrap();
+    assert_eq!(
+        rows,
+        vec![("Hello Alice!".into(), 84), ("Hello Carol!".into(), 60)]
+    );
+}
+
+#[test]
+fn user_function_is_called_for_each_match() {
+    let mut graph = graph_with_people();
+    let counter = Arc::new(AtomicI64::new(0));
+    let calls = counter.clone();
+    graph.create_function("next", 0, move |_| {
+        Ok(Property::Integer(calls.fetch_add(1, Ordering::SeqCst)))
+    });
+
+    let stmt = graph
+        .prepare("MATCH (p:PERSON) RETURN next() ORDER BY p.name")
+        .unwrap();
+    let values = stmt
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<Vec<i64>, _>>()
+        .unwrap();
+    assert_eq!(values, vec![0, 1, 2]);
+    assert_eq!(counter.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn user_function_replaces_builtin() {
+    let mut graph = Graph::open_anon().unwrap();
+    graph.create_function("toUpper", 1, greet);
+    let value: String = graph
+        .prepare("RETURN toUpper('Bob')")
+        .unwrap()
+        .query_map(&mut graph.txn().unwrap(), (), |m| m.get(0))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(value, "Hello Bob!");
+}
+
+#[test]
+fn user_function_errors() {
+    let mut graph = Graph::open_anon().unwrap();
+    graph.create_function("greet", 1, greet);
+
+    assert_err!(graph.prepare("RETURN greet()"), Error::TypeMismatch);
+    assert_err!(graph.prepare("RETURN greet('a', 'b')"), Error::TypeMismatch);
+    assert_err!(
+        graph.prepare("RETURN greeting('a')"),
+        Error::UnknownIdentifier(_)
+    );
+    assert_err!(
+        graph
+            .prepare("RETURN greet($name)")
+            .unwrap()
+            .query_map(&mut graph.txn().unwrap(), ("name", 42), |m| m
+                .get::<Property, _>(0))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>(),
+        Error::TypeMismatch
+    );
+}
+
+#[test]
+fn user_function_errors_fail_step() {
+    let mut graph = Graph::open_anon().unwrap();
+    let counter = Arc::new(AtomicI64::new(0));
+    let calls = counter.clone();
+    graph.create_function("greet", 1, move |args| {
+        calls.fetch_add(1, Ordering::SeqCst);
+        greet(args)
+    });
+
+    let stmt = graph.prepare("RETURN greet($name)").unwrap();
+    let mut txn = graph.txn().unwrap();
+
+    let mut query = stmt.query(&mut txn, ("name", 42)).unwrap();
+    assert!(matches!(query.step(), Err(Error::TypeMismatch)));
+
+    let mut query = stmt.query(&mut txn, ("name", "Bob")).unwrap();
+    let row = query.step().unwrap().unwrap();
+    assert_eq!(row.get::<String, _>(0).unwrap(), "Hello Bob!");
+    assert_eq!(row.get::<String, _>(0).unwrap(), "Hello Bob!");
+    assert_eq!(counter.load(Ordering::SeqCst), 2);
+}
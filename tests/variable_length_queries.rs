@@ -0,0 +1,225 @@
+use cqlite::{Error, Graph, Property};
+
+#[macro_use]
+mod common;
+
+fn create_test_graph() -> Graph {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare(
+            "
+            CREATE (a:PERSON { name: 'Alice' })
+            CREATE (b:PERSON { name: 'Bob' })
+            CREATE (c:PERSON { name: 'Carol' })
+            CREATE (d:PERSON { name: 'Dave' })
+            CREATE (e:PERSON { name: 'Eve' })
+
+            CREATE (a) -[:KNOWS]-> (b)
+            CREATE (b) -[:KNOWS]-> (c)
+            CREATE (c) -[:KNOWS]-> (d)
+            CREATE (d) -[:KNOWS]-> (b)
+            CREATE (a) -[:HATES]-> (e)
+            ",
+        )
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+    graph
+}
+
+fn names(graph: &Graph, query: &str) -> Vec<String> {
+    let mut txn = graph.txn().unwrap();
+    let mut names: Vec<String> = graph
+        .prepare(query)
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    names.sort();
+    names
+}
+
+#[test]
+fn variable_length_reaches_downstream_nodes() {
+    let graph = create_test_graph();
+    assert_eq!(
+        names(
+            &graph,
+            "MATCH (a { name: 'Alice' }) -[:KNOWS*]-> (b) RETURN b.name"
+        ),
+        vec!["Bob", "Bob", "Carol", "Dave"]
+    );
+    assert_eq!(
+        names(
+            &graph,
+            "MATCH (a { name: 'Alice' }) -[*]-> (b) RETURN b.name"
+        ),
+        vec!["Bob", "Bob", "Carol", "Dave", "Eve"]
+    );
+    assert_eq!(
+        names(
+            &graph,
+            "MATCH (a { name: 'Dave' }) <-[:KNOWS*]- (b) RETURN b.name"
+        ),
+        vec!["Alice", "Bob", "Carol", "Dave"]
+    );
+}
+
+#[test]
+fn variable_length_respects_bounds() {
+    let graph = create_test_graph();
+    assert_eq!(
+        names(
+            &graph,
+            "MATCH (a { name: 'Alice' }) -[:KNOWS*2..3]-> (b) RETURN b.name"
+        ),
+        vec!["Carol", "Dave"]
+    );
+    assert_eq!(
+        names(
+            &graph,
+            "MATCH (a { name: 'Alice' }) -[:KNOWS*..2]-> (b) RETURN b.name"
+        ),
+        vec!["Bob", "Carol"]
+    );
+    assert_eq!(
+        names(
+            &graph,
+            "MATCH (a { name: 'Alice' }) -[:KNOWS*3]-> (b) RETURN b.name"
+        ),
+        vec!["Dave"]
+    );
+    assert_eq!(
+        names(
+            &graph,
+            "MATCH (a { name: 'Alice' }) -[:KNOWS*0..1]-> (b) RETURN b.name"
+        ),
+        vec!["Alice", "Bob"]
+    );
+}
+
+#[test]
+fn variable_length_does_not_repeat_edges() {
+    let graph = create_test_graph();
+
+    // b -> c -> d -> b forms a cycle, which is traversed once
+    let mut txn = graph.txn().unwrap();
+    let lengths: Vec<i64> = graph
+        .prepare(
+            "
+            MATCH (a { name: 'Bob' }) -[r:KNOWS*]-> (b { name: 'Bob' })
+            RETURN count(*)
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(lengths, vec![1]);
+
+    assert_eq!(
+        names(&graph, "MATCH (a { name: 'Eve' }) -[*]- (b) RETURN b.name"),
+        vec!["Alice", "Bob", "Bob", "Bob", "Carol", "Carol", "Dave", "Dave"]
+    );
+}
+
+#[test]
+fn variable_length_returns_edge_lists() {
+    let graph = create_test_graph();
+
+    let mut txn = graph.txn().unwrap();
+    let rows: Vec<(Vec<Property>, String)> = graph
+        .prepare(
+            "
+            MATCH (a { name: 'Alice' }) -[r:KNOWS*1..2]-> (b)
+            RETURN r, b.name
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| Ok((m.get(0)?, m.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(rows.len(), 2);
+    for (edges, name) in rows {
+        match name.as_str() {
+            "Bob" => assert_eq!(edges.len(), 1),
+            "Carol" => assert_eq!(edges.len(), 2),
+            _ => panic!("unexpected node {}", name),
+        }
+        assert!(edges.iter().all(|e| matches!(e, Property::Id(_))));
+    }
+}
+
+#[test]
+fn variable_length_with_bound_end_node() {
+    let graph = create_test_graph();
+    assert_eq!(
+        names(
+            &graph,
+            "
+            MATCH (b { name: 'Dave' })
+            MATCH (a:PERSON) -[:KNOWS*2]-> (b)
+            RETURN a.name
+            "
+        ),
+        vec!["Bob"]
+    );
+}
+
+#[test]
+fn optional_variable_length() {
+    let graph = create_test_graph();
+
+    let mut txn = graph.txn().unwrap();
+    let rows: Vec<(String, Option<String>)> = graph
+        .prepare(
+            "
+            MATCH (a:PERSON)
+            OPTIONAL MATCH (a) -[:KNOWS*3]-> (b)
+            RETURN a.name, b.name
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| Ok((m.get(0)?, m.get(1)?)))
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let mut rows = rows;
+    rows.sort();
+    assert_eq!(
+        rows,
+        vec![
+            ("Alice".into(), Some("Dave".into())),
+            ("Bob".into(), Some("Bob".into())),
+            ("Carol".into(), Some("Carol".into())),
+            ("Dave".into(), Some("Dave".into())),
+            ("Eve".into(), None),
+        ]
+    );
+}
+
+#[test]
+fn variable_length_invalid_names() {
+    let graph = Graph::open_anon().unwrap();
+    assert_err!(
+        graph.prepare("MATCH (a) -[r*]-> (b) RETURN r.name"),
+        Error::IdentifierIsNotEdge(_)
+    );
+    assert_err!(
+        graph.prepare("MATCH (a) -[r*]-> (b) RETURN ID(r)"),
+        Error::IdentifierIsNotEdge(_)
+    );
+    assert_err!(
+        graph.prepare("MATCH (a) -[r*]-> (r) RETURN a"),
+        Error::IdentifierIsNotNode(_)
+    );
+    assert_err!(
+        graph.prepare("MATCH (a) -[a*]-> (b) RETURN a"),
+        Error::IdentifierExists(_)
+    );
+}
@@ -0,0 +1,245 @@
+use cqlite::{Error, Graph};
+
+#[macro_use]
+mod common;
+
+fn create_test_graph() -> Graph {
+    let graph = Graph::open_anon().unwrap();
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare(
+            "
+            CREATE (peter:PERSON { name: 'Peter', age: 21, city: 'New York' })
+            CREATE (clark:PERSON { name: 'Clark', age: 42, city: 'Metropolis' })
+            CREATE (lois:PERSON { name: 'Lois', age: 35, city: 'Metropolis' })
+
+            CREATE (peter) -[:KNOWS]-> (clark)
+            CREATE (clark) -[:KNOWS]-> (lois)
+            ",
+        )
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+    graph
+}
+
+#[test]
+fn with_projects_and_filters() {
+    let graph = create_test_graph();
+
+    let mut txn = graph.txn().unwrap();
+    let rows: Vec<(String, i64)> = graph
+        .prepare(
+            "
+            MATCH (p:PERSON)
+            WITH p, p.age AS age WHERE age > 30
+            RETURN p.name, age
+            ORDER BY age
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| Ok((m.get(0)?, m.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(rows, vec![("Lois".into(), 35), ("Clark".into(), 42)]);
+}
+
+#[test]
+fn with_starts_new_match_scope() {
+    let graph = create_test_graph();
+
+    let mut txn = graph.txn().unwrap();
+    let rows: Vec<(String, String)> = graph
+        .prepare(
+            "
+            MATCH (a:PERSON) -[:KNOWS]-> (b:PERSON)
+            WITH b AS friend, a.name AS name
+            MATCH (friend) -[:KNOWS]-> (c)
+            RETURN name, c.name
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| Ok((m.get(0)?, m.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(rows, vec![("Peter".into(), "Lois".into())]);
+
+    let names: Vec<String> = graph
+        .prepare(
+            "
+            MATCH (a:PERSON { name: 'Peter' })
+            WITH a.city AS city
+            MATCH (b:PERSON) WHERE b.city <> city
+            WITH b.name AS name
+            RETURN name
+            ORDER BY name
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(names, vec!["Clark".to_string(), "Lois".to_string()]);
+}
+
+#[test]
+fn with_values_are_evaluated_once() {
+    let graph = create_test_graph();
+
+    let mut txn = graph.mut_txn().unwrap();
+    let rows: Vec<(i64, i64)> = graph
+        .prepare(
+            "
+            MATCH (p:PERSON { name: 'Clark' })
+            WITH p, p.age AS old
+            SET p.age = 43
+            RETURN old, p.age
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| Ok((m.get(0)?, m.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(rows, vec![(42, 43)]);
+}
+
+#[test]
+fn with_allows_match_after_create() {
+    let graph = create_test_graph();
+
+    let mut txn = graph.mut_txn().unwrap();
+    graph
+        .prepare(
+            "
+            CREATE (tony:PERSON { name: 'Tony' })
+            WITH tony
+            MATCH (p:PERSON) WHERE p.city = 'Metropolis'
+            CREATE (tony) -[:KNOWS]-> (p)
+            ",
+        )
+        .unwrap()
+        .execute(&mut txn, ())
+        .unwrap();
+    txn.commit().unwrap();
+
+    let mut txn = graph.txn().unwrap();
+    let mut names: Vec<String> = graph
+        .prepare("MATCH (a { name: 'Tony' }) -[:KNOWS]-> (b) RETURN b.name")
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    names.sort();
+    assert_eq!(names, vec!["Clark".to_string(), "Lois".to_string()]);
+}
+
+#[test]
+fn with_matches_nodes_created_before() {
+    let graph = create_test_graph();
+    let mut txn = graph.mut_txn().unwrap();
+
+    let count: i64 = graph
+        .prepare("CREATE (a:W) WITH a MATCH (b:W) RETURN count(b)")
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(count, 1);
+
+    let ids: Vec<(u64, u64)> = graph
+        .prepare("CREATE (a:V) WITH a MATCH (b) WHERE ID(b) = ID(a) RETURN ID(a), ID(b)")
+        .unwrap()
+        .query_map(&mut txn, (), |m| Ok((m.get(0)?, m.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(ids.len(), 1);
+    assert_eq!(ids[0].0, ids[0].1);
+
+    let names: Vec<String> = graph
+        .prepare(
+            "
+            MATCH (p:PERSON { name: 'Peter' })
+            CREATE (p) -[:OWNS]-> (:PET { name: 'Rex' })
+            WITH p
+            MATCH (p) -[:OWNS]-> (pet)
+            RETURN pet.name
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(names, vec!["Rex"]);
+
+    // nodes created in the same part are not matched
+    let count: i64 = graph
+        .prepare(
+            "
+            UNWIND [1, 2] AS x
+            WITH x
+            MATCH (p:PERSON)
+            CREATE (:PERSON)
+            RETURN count(*)
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| m.get(0))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(count, 6);
+}
+
+#[test]
+fn with_aggregates_over_values() {
+    let graph = create_test_graph();
+
+    let mut txn = graph.txn().unwrap();
+    let rows: Vec<(String, i64)> = graph
+        .prepare(
+            "
+            MATCH (p:PERSON)
+            WITH p.city AS city
+            RETURN city, count(*)
+            ORDER BY city
+            ",
+        )
+        .unwrap()
+        .query_map(&mut txn, (), |m| Ok((m.get(0)?, m.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(rows, vec![("Metropolis".into(), 2), ("New York".into(), 1)]);
+}
+
+#[test]
+fn with_invalid_names() {
+    let graph = Graph::open_anon().unwrap();
+    assert_err!(
+        graph.prepare("MATCH (a) -> (b) WITH a RETURN b.name"),
+        Error::UnknownIdentifier(_)
+    );
+    assert_err!(
+        graph.prepare("MATCH (a) -> (b) WITH a, b AS a RETURN a.name"),
+        Error::IdentifierExists(_)
+    );
+    assert_err!(
+        graph.prepare("MATCH (a) WITH a.name AS name MATCH (name) RETURN ID(name)"),
+        Error::IdentifierIsNotNode(_)
+    );
+    assert_err!(
+        graph.prepare("MATCH (a) WITH a.name AS name RETURN name.length"),
+        Error::IdentifierIsNotNode(_)
+    );
+}